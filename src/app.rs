@@ -1,30 +1,116 @@
 use crate::data::models::*;
 use crate::data::manager::HouseholdManager;
+use crate::data::duplicates::{DuplicateGroup, DuplicateMatch};
+use crate::data::drafts::Draft;
+use crate::data::audit::AuditEntry;
+use crate::data::privacy::SensitiveField;
+use crate::data::error::DataError;
+use crate::data::divisions::{self, DivisionLevel};
+use crate::data::history::UndoHistory;
+use crate::data::rules;
+use crate::config::{self, Settings};
+use crate::ui::address::AddressReview;
+use crate::ui::attachments::AttachmentState;
+use crate::ui::conflict::SaveConflict;
+use crate::ui::dialogs::FormFeedback;
+use crate::ui::drafts::FormTracker;
+use crate::ui::dwellings::OccupancyFilter;
+use crate::ui::notifications::Notifications;
+use crate::ui::shortcuts::{ShortcutAction, Shortcuts};
+use crate::ui::tabs::HouseholdTab;
+#[cfg(feature = "persistence")]
+use crate::ui::tabs::TABS_STORAGE_KEY;
 use eframe::egui;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// 检查其他用户是否修改了数据库的间隔
+const EXTERNAL_CHANGE_INTERVAL: Duration = Duration::from_secs(2);
+
 pub struct HouseholdApp {
     pub household_manager: HouseholdManager,
     pub ui_state: UiState,
+    pub settings: Settings,
+}
+
+/// 左侧导航面板显示的内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NavigationMode {
+    #[default]
+    Households,
+    Dwellings,
 }
 
 #[derive(Default)]
 pub struct UiState {
+    pub navigation: NavigationMode,
     pub selected_household: Option<usize>,
+    /// 中央区域打开的标签页
+    pub tabs: Vec<HouseholdTab>,
+    pub active_tab: Option<usize>,
+    /// 与当前标签页并排比较的标签页
+    pub compare_tab: Option<usize>,
+    /// 当前标签页显示的选中户籍，列表中改选后据此切换标签页
+    pub shown_selection: Option<Uuid>,
+    /// 放弃或保存表单后关闭所在标签页
+    pub close_tab_after_form: bool,
+    /// 当前标签页的表单，其他标签页的表单暂存在各自的 `HouseholdTab` 中
     pub show_add_dialog: bool,
     pub show_edit_dialog: bool,
-    pub show_error_dialog: bool,
-    pub error_message: String,
+    /// 正在编辑的户籍，列表重新加载后选中项可能改变，保存时以此为准
+    pub editing_household: Option<Uuid>,
     pub edit_form: HouseholdForm,
+    pub form_tracker: FormTracker,
+    pub attachments: AttachmentState,
+    /// 已点击显示完整内容的证件号码和电话，本次运行期间有效
+    pub revealed: HashSet<(Uuid, SensitiveField, String)>,
+    /// 打开的操作记录窗口中的记录
+    pub audit_entries: Option<Vec<AuditEntry>>,
+    /// 取消有修改的表单时等待用户确认
+    pub confirm_discard: bool,
+    /// 启动时发现的未保存表单，等待用户恢复或丢弃
+    pub recovered_drafts: Vec<Draft>,
+    pub save_conflict: Option<SaveConflict>,
+    pub last_change_check: Option<Instant>,
+    pub history: UndoHistory,
+    pub shortcuts: Shortcuts,
+    pub show_shortcuts: bool,
+    /// 正在等待用户按下新按键的操作
+    pub capturing_shortcut: Option<ShortcutAction>,
+    /// 下一帧让搜索框获得焦点
+    pub focus_search: bool,
+    /// 下一帧把列表滚动到选中的户籍
+    pub scroll_to_selected: bool,
     pub search_query: String,
     pub filtered_households: Vec<usize>,
+    pub pending_save_warnings: Vec<DuplicateMatch>,
+    pub pending_save_is_add: bool,
+    pub show_duplicate_dialog: bool,
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    pub transfer_form: Option<TransferForm>,
+    pub vital_event_form: Option<VitalEventForm>,
+    pub head_change_form: Option<HeadChangeForm>,
+    pub show_decode_errors: bool,
+    pub notifications: Notifications,
+    pub form_feedback: FormFeedback,
+    pub show_address_review: bool,
+    pub address_reviews: Vec<AddressReview>,
+    pub show_division_stats: bool,
+    pub division_stats_level: DivisionLevel,
+    pub dwelling_form: Option<DwellingForm>,
+    pub show_occupancy_report: bool,
+    pub occupancy_filter: OccupancyFilter,
 }
 
 impl HouseholdApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Result<Self, DataError> {
+        // 打开数据库时的地址迁移会用到区划代码表，须先读取
+        let divisions_loaded = divisions::load(divisions::DIVISIONS_FILE);
         let mut app = Self {
             household_manager: HouseholdManager::new()?,
             ui_state: UiState::default(),
+            settings: Settings::default(),
         };
         
         // 只在数据库为空时添加示例数据
@@ -38,43 +124,165 @@ impl HouseholdApp {
         }
         app.update_filtered_households()?;
         
+        // 校验规则配置有误时沿用默认规则，代码表文件有误时沿用内置代码表
+        match rules::RuleSet::load(rules::RULES_FILE) {
+            Ok(rule_set) => rules::install(rule_set),
+            Err(e) => app.report_warning("校验规则未加载", e),
+        }
+        if let Err(e) = divisions_loaded {
+            app.report_warning("区划代码表未加载", e);
+        }
+
+        match Settings::load(config::SETTINGS_FILE) {
+            Ok(settings) => app.settings = settings,
+            Err(e) => app.report_warning("设置未加载", e),
+        }
+        let (shortcuts, problems) = Shortcuts::from_settings(&app.settings);
+        app.ui_state.shortcuts = shortcuts;
+        if !problems.is_empty() {
+            app.report_warning("快捷键设置有误", problems.join("\n"));
+        }
+        
+        #[cfg(feature = "persistence")]
+        if let Some(saved) = cc.storage.and_then(|storage| eframe::get_value(storage, TABS_STORAGE_KEY)) {
+            app.restore_tabs(saved);
+        }
+        #[cfg(not(feature = "persistence"))]
+        let _ = cc;
+        
+        app.load_recovered_drafts();
+        
         Ok(app)
     }
     
-    pub fn update_filtered_households(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn update_filtered_households(&mut self) -> Result<(), DataError> {
         self.ui_state.filtered_households = self.household_manager.search(&self.ui_state.search_query)?;
         Ok(())
     }
     
-    pub fn get_households(&mut self) -> Result<Vec<Household>, Box<dyn std::error::Error>> {
+    /// 定期检查其他用户是否修改了数据库，有修改时重新加载列表并保持选中同一户
+    pub fn poll_external_changes(&mut self, ctx: &egui::Context) {
+        if self.ui_state.last_change_check.is_some_and(|t| t.elapsed() < EXTERNAL_CHANGE_INTERVAL) {
+            return;
+        }
+        self.ui_state.last_change_check = Some(Instant::now());
+        ctx.request_repaint_after(EXTERNAL_CHANGE_INTERVAL);
+        
+        let selected_id = self.selected_household_id();
+        match self.household_manager.check_external_changes() {
+            Ok(false) => {}
+            Ok(true) => match self.reload_households(selected_id) {
+                Ok(()) => self.notify("数据已被其他用户更新"),
+                Err(e) => self.report_error("重新加载失败", &e),
+            },
+            // 数据库暂时无法读取时下次再检查，不逐次提示
+            Err(_) => {}
+        }
+    }
+    
+    /// 当前选中的户籍，须在使缓存失效的操作之前读取
+    pub fn selected_household_id(&mut self) -> Option<Uuid> {
+        self.ui_state.selected_household
+            .and_then(|i| self.get_household(i).ok().flatten())
+            .map(|h| h.id)
+    }
+    
+    /// 重新加载后列表顺序可能改变，按ID重新定位选中的户籍，已被删除时取消选中
+    pub fn reload_households(&mut self, selected_id: Option<Uuid>) -> Result<(), DataError> {
+        self.update_filtered_households()?;
+        let households = self.get_households()?;
+        self.ui_state.selected_household = selected_id
+            .and_then(|id| households.iter().position(|h| h.id == id));
+        Ok(())
+    }
+    
+    pub fn get_households(&mut self) -> Result<Vec<Household>, DataError> {
         self.household_manager.get_households()
     }
     
-    pub fn get_household(&mut self, index: usize) -> Result<Option<Household>, Box<dyn std::error::Error>> {
+    pub fn get_household(&mut self, index: usize) -> Result<Option<Household>, DataError> {
         self.household_manager.get_household(index)
     }
     
-    pub fn add_household(&mut self, household: Household) -> Result<(), Box<dyn std::error::Error>> {
-        self.household_manager.add_household(household)?;
+    pub fn add_household(&mut self, household: Household) -> Result<(), DataError> {
+        let description = format!("新增户籍 {}", household.head_name);
+        self.record(description, |manager| manager.add_household(household))?;
         self.update_filtered_households()?;
         Ok(())
     }
     
-    pub fn update_household(&mut self, household: Household) -> Result<(), Box<dyn std::error::Error>> {
-        self.household_manager.update_household(household)?;
+    pub fn update_household(&mut self, household: Household) -> Result<(), DataError> {
+        let description = format!("修改户籍 {}", household.head_name);
+        self.record(description, |manager| manager.update_household(household))?;
         self.update_filtered_households()?;
         Ok(())
     }
     
-    pub fn remove_household(&mut self, household_id: &Uuid) -> Result<(), Box<dyn std::error::Error>> {
-        self.household_manager.remove_household(household_id)?;
+    /// 删除户籍，`household` 为读取时的内容，其间被其他用户修改过时返回 `DataError::Conflict`
+    pub fn remove_household(&mut self, household: &Household) -> Result<(), DataError> {
+        self.record(format!("删除户籍 {}", household.head_name), |manager| manager.remove_household(&household.id, household.revision))?;
         self.update_filtered_households()?;
         Ok(())
     }
+    
+    /// 在新标签页中填写新增户籍表单
+    pub fn open_add_dialog(&mut self) {
+        if !self.ensure_editable() {
+            return;
+        }
+        let mut form = HouseholdForm::default();
+        form.clear();
+        if self.open_form_tab(None, form) {
+            self.track_form();
+        }
+    }
+    
+    /// 在选中户籍的标签页中编辑，该户已在编辑时切换过去
+    pub fn open_edit_dialog(&mut self) {
+        if !self.ensure_editable() {
+            return;
+        }
+        if let Some(selected) = self.ui_state.selected_household {
+            if let Ok(Some(household)) = self.get_household(selected) {
+                match self.household_manager.household_form(&household) {
+                    Ok(form) => {
+                        if self.open_form_tab(Some(household.id), form) {
+                            self.track_form();
+                        }
+                    }
+                    Err(e) => self.report_error("读取户籍失败", &e),
+                }
+            }
+        }
+    }
+    
+    pub fn delete_selected_household(&mut self) {
+        if !self.ensure_editable() {
+            return;
+        }
+        if let Some(selected) = self.ui_state.selected_household {
+            if let Ok(Some(household)) = self.get_household(selected) {
+                // 正在编辑的户籍须先保存或关闭表单
+                if self.ui_state.tabs.iter().any(|t| t.editing && t.household_id == Some(household.id)) {
+                    self.report_warning("无法删除", "该户籍正在编辑，请先保存或关闭其标签页");
+                    return;
+                }
+                if let Err(e) = self.remove_household(&household) {
+                    self.report_error("删除失败", &e);
+                }
+            }
+        }
+    }
 }
 
 impl eframe::App for HouseholdApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.render_ui(ctx);
     }
+    
+    /// 退出时保存打开的标签页，下次启动时恢复
+    fn save(&mut self, _storage: &mut dyn eframe::Storage) {
+        #[cfg(feature = "persistence")]
+        eframe::set_value(_storage, TABS_STORAGE_KEY, &self.saved_tabs());
+    }
 }
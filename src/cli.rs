@@ -0,0 +1,859 @@
+//! 命令行工具：不启动界面，直接通过 `HouseholdManager` 读写数据库，供批处理脚本使用
+
+use crate::config::{Settings, SETTINGS_FILE};
+use crate::data::attachments::{Attachment, ExportedAttachment};
+use crate::data::audit::{AuditAction, AuditEntry};
+use crate::data::consistency::{self, Severity};
+use crate::data::divisions::{self, DivisionLevel};
+use crate::data::error::DataError;
+use crate::data::manager::{DivisionCount, HouseholdManager, HouseholdStatistics, DEFAULT_DB_PATH};
+use crate::data::models::*;
+use crate::data::privacy::{self, Role};
+use crate::data::repository::HouseholdRepository;
+use crate::data::rules;
+use crate::data::validation::Validate;
+use crate::utils::csv;
+use crate::utils::export::{AnonymizationOptions, BirthGranularity, ExportUtils};
+use std::process::ExitCode;
+use uuid::Uuid;
+
+const USAGE: &str = "用法: household_cli [--db 数据库文件] [--json] [--masked] <命令> [参数]
+
+命令:
+  list                       列出全部户籍
+  search <关键词>            按户主姓名、证件号码、地址或电话搜索
+  show <户籍ID或证件号码>    显示一户的详细信息
+  add <文件.json>            校验后新增一户
+  import <文件.csv|.json>    批量导入，户籍ID已存在的整体替换
+  export <文件.csv|.json>    导出全部户籍，JSON 文件包含附件（脱敏导出时不含）
+  anonymize <文件.csv> [--k 数量] [--birth year|5|10] [--region 级别] [--key 密钥]
+                             导出匿名化的在册成员数据供统计使用，默认 k 为 5、
+                             出生按 5 年分段、地区到县区；密钥相同时各次导出的人员编号一致，
+                             隐去的记录列在输出的报告中；查看员不能指定密钥
+  stats [--by 级别]          统计信息，级别为 province/city/county/township/village
+  check                      检查数据库完整性、数据校验、重复记录和待核对地址
+  backup <目标文件>          备份数据库
+  audit [数量]               最近的操作记录，默认 100 条
+  serve [--addr 地址] [--token 令牌]
+                             启动 HTTP 接口服务，默认监听 127.0.0.1:8080，
+                             令牌也可由环境变量 HOUSEHOLD_API_TOKEN 指定
+
+选项:
+  --db <文件>   数据库文件，默认为 household_management.db；
+                启用 postgres 功能时也可为 postgres://用户:密码@主机/数据库
+  --json        以JSON格式输出
+  --masked      遮蔽证件号码和电话，用于提供给第三方的导出和打印；
+                设置中角色为查看员时总是遮蔽";
+
+/// 查看员不能执行的命令：修改数据，或输出无法遮蔽的完整数据（数据库备份、接口服务）
+const EDITOR_COMMANDS: &[&str] = &["add", "import", "backup", "serve"];
+
+enum CliError {
+    /// 参数有误，退出码 2
+    Usage(String),
+    /// 执行失败，退出码 1
+    Failed(String),
+}
+
+impl From<DataError> for CliError {
+    fn from(error: DataError) -> Self {
+        CliError::Failed(error.to_string())
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for CliError {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        CliError::Failed(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CliError {
+    fn from(error: serde_json::Error) -> Self {
+        CliError::Failed(format!("JSON 格式错误: {}", error))
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(error: std::io::Error) -> Self {
+        CliError::Failed(DataError::from(error).to_string())
+    }
+}
+
+struct Options {
+    db_path: String,
+    json: bool,
+    masked: bool,
+    /// 写入操作记录的操作员和角色，取自设置
+    operator: String,
+    role: Role,
+    command: String,
+    args: Vec<String>,
+}
+
+/// 执行命令行参数（不含程序名）对应的命令
+pub fn run(args: &[String]) -> ExitCode {
+    let result = parse_options(args).and_then(|mut options| {
+        // 校验规则配置有误时沿用默认规则，代码表文件有误时沿用内置代码表
+        match rules::RuleSet::load(rules::RULES_FILE) {
+            Ok(rule_set) => rules::install(rule_set),
+            Err(e) => eprintln!("警告: 校验规则未加载: {}", e),
+        }
+        if let Err(e) = divisions::load(divisions::DIVISIONS_FILE) {
+            eprintln!("警告: 区划代码表未加载: {}", e);
+        }
+        let settings = Settings::load(SETTINGS_FILE).unwrap_or_else(|e| {
+            eprintln!("警告: 设置未加载: {}", e);
+            Settings::default()
+        });
+        options.apply_settings(&settings);
+        open_and_execute(&options)
+    });
+
+    match result {
+        Ok(code) => code,
+        Err(CliError::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("错误: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+impl Options {
+    // 操作员和角色取自设置，查看员的输出总是遮蔽
+    fn apply_settings(&mut self, settings: &Settings) {
+        self.masked |= !settings.role.can_reveal();
+        self.operator = settings.operator();
+        self.role = settings.role;
+    }
+}
+
+// `--db` 为 postgres:// 地址时连接 PostgreSQL（需启用 postgres 功能），否则打开 SQLite 文件
+fn open_and_execute(options: &Options) -> Result<ExitCode, CliError> {
+    #[cfg(feature = "postgres")]
+    if options.db_path.starts_with("postgres://") || options.db_path.starts_with("postgresql://") {
+        let repository = crate::data::postgres::PostgresRepository::connect(&options.db_path)?;
+        return execute(&mut HouseholdManager::with_repository(repository), options);
+    }
+    execute(&mut HouseholdManager::open(&options.db_path)?, options)
+}
+
+fn parse_options(args: &[String]) -> Result<Options, CliError> {
+    let mut options = Options {
+        db_path: DEFAULT_DB_PATH.to_string(),
+        json: false,
+        masked: false,
+        operator: String::new(),
+        role: Role::default(),
+        command: String::new(),
+        args: Vec::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" => {
+                options.db_path = args.next().ok_or_else(|| CliError::Usage("--db 需要指定文件".into()))?.clone();
+            }
+            "--json" => options.json = true,
+            "--masked" => options.masked = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ if options.command.is_empty() => options.command = arg.clone(),
+            _ => options.args.push(arg.clone()),
+        }
+    }
+    if options.command.is_empty() {
+        return Err(CliError::Usage("缺少命令".into()));
+    }
+    Ok(options)
+}
+
+fn execute<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, options: &Options) -> Result<ExitCode, CliError> {
+    let arg = |name: &str| {
+        options.args.first().cloned().ok_or_else(|| CliError::Usage(format!("{} 需要参数: {}", options.command, name)))
+    };
+
+    if EDITOR_COMMANDS.contains(&options.command.as_str()) && !options.role.can_edit() {
+        return Err(CliError::Failed(format!("当前角色为{}，不能执行 {} 命令", options.role, options.command)));
+    }
+    // 已知密钥即可由证件号码推算人员编号，查看员只能使用每次随机生成的密钥
+    if options.command == "anonymize" && options.args.iter().any(|a| a == "--key") && !options.role.can_edit() {
+        return Err(CliError::Failed(format!("当前角色为{}，不能用 --key 指定密钥", options.role)));
+    }
+
+    match options.command.as_str() {
+        "list" => {
+            let households = manager.get_households()?;
+            let households = prepare_output(manager, options, households, "列出")?;
+            print_households(&households, options.json)?;
+        }
+        "search" => {
+            let query = arg("关键词")?;
+            let households = manager.get_households()?;
+            let matched: Vec<Household> = manager.search(&query)?.into_iter().map(|i| households[i].clone()).collect();
+            let matched = prepare_output(manager, options, matched, &format!("搜索“{}”", query))?;
+            print_households(&matched, options.json)?;
+        }
+        "show" => {
+            let key = arg("户籍ID或证件号码")?;
+            let household = find_household(manager, &key)?;
+            let household = prepare_output(manager, options, vec![household], "查看")?.remove(0);
+            if options.json {
+                println!("{}", serde_json::to_string_pretty(&household)?);
+            } else {
+                print_household_details(&household);
+            }
+        }
+        "add" => {
+            let path = arg("文件")?;
+            let household = read_json_household(&std::fs::read_to_string(&path)?)?;
+            check_household(manager, &household)?;
+            let id = household.id;
+            manager.add_household(household)?;
+            println!("已新增户籍 {}", id);
+        }
+        "import" => {
+            let path = arg("文件")?;
+            let (households, attachments) = if is_json(&path) {
+                read_json_households(&std::fs::read_to_string(&path)?)?
+            } else {
+                (csv::read_households(&path)?, Vec::new())
+            };
+            // 先校验全部户籍，有一户不通过则不导入
+            let mut failures = Vec::new();
+            for household in &households {
+                if let Err(errors) = HouseholdForm::from_household(household).validate() {
+                    failures.push(format!("户主 {} ({}): {}", household.head_name, household.id, errors));
+                }
+            }
+            if !failures.is_empty() {
+                return Err(CliError::Failed(format!("{}户未通过校验，未导入:\n{}", failures.len(), failures.join("\n"))));
+            }
+            let count = households.len();
+            manager.import_households(households)?;
+            manager.import_attachments(&attachments)?;
+            if attachments.is_empty() {
+                println!("已导入 {} 户", count);
+            } else {
+                println!("已导入 {} 户，附件 {} 个", count, attachments.len());
+            }
+        }
+        "export" => {
+            let path = arg("文件")?;
+            let households = manager.get_households()?;
+            if options.masked {
+                // 附件为证件扫描件，脱敏导出时不包含
+                let masked: Vec<Household> = households.iter().map(privacy::mask_household).collect();
+                if is_json(&path) {
+                    std::fs::write(&path, serde_json::to_string_pretty(&masked)?)?;
+                } else {
+                    csv::write_households(&masked, &path)?;
+                }
+            } else if is_json(&path) {
+                std::fs::write(&path, serde_json::to_string_pretty(&manager.export_json()?)?)?;
+            } else {
+                csv::write_households(&households, &path)?;
+            }
+            let action = if options.masked { AuditAction::MaskedExport } else { AuditAction::Export };
+            let detail = format!("命令行导出 {} 户到 {}", households.len(), path);
+            manager.record_audit(&AuditEntry::new(&options.operator, action, None, detail))?;
+            println!("已导出 {} 户到 {}", households.len(), path);
+        }
+        "anonymize" => {
+            let path = arg("文件")?;
+            let anonymization = anonymization_options(options)?;
+            let report = ExportUtils::export_anonymized(&manager.get_households()?, &path, &anonymization)?;
+            let detail = format!("命令行匿名化导出 {} 条到 {}（k = {}，隐去 {} 条）", report.exported, path, report.k, report.suppressed.len());
+            manager.record_audit(&AuditEntry::new(&options.operator, AuditAction::AnonymizedExport, None, detail))?;
+            print!("{}", report);
+        }
+        "stats" => print_statistics(manager, options)?,
+        "check" => return run_checks(manager, options.json),
+        "backup" => {
+            let path = arg("目标文件")?;
+            manager.backup(&path)?;
+            println!("已备份到 {}", path);
+        }
+        "audit" => {
+            let limit = match options.args.first() {
+                Some(count) => count.parse().map_err(|_| CliError::Usage(format!("记录数量无效: {}", count)))?,
+                None => 100,
+            };
+            print_audit(&manager.audit_log(limit)?, options.json)?;
+        }
+        "serve" => serve(manager, options)?,
+        other => return Err(CliError::Usage(format!("未知命令: {}", other))),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(feature = "server")]
+fn serve<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, options: &Options) -> Result<(), CliError> {
+    use crate::server::{Server, DEFAULT_ADDR, TOKEN_ENV};
+
+    let value = |name: &str| -> Result<Option<String>, CliError> {
+        match options.args.iter().position(|a| a == name) {
+            Some(i) => options.args.get(i + 1).cloned().map(Some).ok_or_else(|| CliError::Usage(format!("{} 需要参数", name))),
+            None => Ok(None),
+        }
+    };
+    let addr = value("--addr")?.unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let token = value("--token")?.or_else(|| std::env::var(TOKEN_ENV).ok()).unwrap_or_default();
+
+    let server = Server::bind(manager, &addr, &token).map_err(CliError::Failed)?.with_operator(&options.operator);
+    match server.local_addr() {
+        Some(addr) => eprintln!("接口服务已启动: http://{}/api/openapi.json", addr),
+        None => eprintln!("接口服务已启动"),
+    }
+    server.run();
+    Ok(())
+}
+
+#[cfg(not(feature = "server"))]
+fn serve<R: HouseholdRepository>(_manager: &mut HouseholdManager<R>, _options: &Options) -> Result<(), CliError> {
+    Err(CliError::Failed("此版本未包含接口服务，请使用 cargo build --features server 编译".into()))
+}
+
+fn is_json(path: &str) -> bool {
+    path.to_lowercase().ends_with(".json")
+}
+
+// 按户籍ID、户主或任一在册成员的证件号码查找
+fn find_household<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, key: &str) -> Result<Household, CliError> {
+    let households = manager.get_households()?;
+    let id = Uuid::parse_str(key).ok();
+    let key = key.trim().to_uppercase();
+    households.into_iter()
+        .find(|h| Some(h.id) == id || h.members.iter().any(|m| m.is_active() && m.id_number.trim().to_uppercase() == key))
+        .ok_or_else(|| CliError::Failed(format!("未找到户籍: {}", key)))
+}
+
+fn read_json_household(content: &str) -> Result<Household, CliError> {
+    Ok(Household::from_json(serde_json::from_str(content)?)?)
+}
+
+// 户籍及 `export` 随其导出的附件
+type JsonImport = (Vec<Household>, Vec<(Attachment, Vec<u8>)>);
+
+fn read_json_households(content: &str) -> Result<JsonImport, CliError> {
+    let items = match serde_json::from_str(content)? {
+        serde_json::Value::Array(items) => items,
+        value => vec![value],
+    };
+    let mut households = Vec::with_capacity(items.len());
+    let mut attachments = Vec::new();
+    for mut item in items {
+        let exported: Vec<ExportedAttachment> = match item.as_object_mut().and_then(|o| o.remove("attachments")) {
+            Some(value) => serde_json::from_value(value)?,
+            None => Vec::new(),
+        };
+        let household = Household::from_json(item)?;
+        for attachment in &exported {
+            attachments.push(attachment.decode(household.id)?);
+        }
+        households.push(household);
+    }
+    Ok((households, attachments))
+}
+
+// 疑似重复只提示
+fn check_household<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, household: &Household) -> Result<(), CliError> {
+    for warning in manager.check_household(household, None)? {
+        eprintln!("警告: {}", warning);
+    }
+    Ok(())
+}
+
+// `--masked` 时遮蔽证件号码和电话，否则将这次打印记入操作记录
+fn prepare_output<R: HouseholdRepository>(
+    manager: &mut HouseholdManager<R>,
+    options: &Options,
+    households: Vec<Household>,
+    action: &str,
+) -> Result<Vec<Household>, CliError> {
+    if options.masked {
+        return Ok(households.iter().map(privacy::mask_household).collect());
+    }
+    let (household_id, detail) = match households.as_slice() {
+        [] => return Ok(households),
+        [household] => (Some(household.id), format!("命令行{} {}户", action, household.head_name)),
+        _ => (None, format!("命令行{} {} 户", action, households.len())),
+    };
+    manager.record_audit(&AuditEntry::new(&options.operator, AuditAction::Export, household_id, detail))?;
+    Ok(households)
+}
+
+fn anonymization_options(options: &Options) -> Result<AnonymizationOptions, CliError> {
+    let value = |name: &str| -> Result<Option<&String>, CliError> {
+        match options.args.iter().position(|a| a == name) {
+            Some(i) => options.args.get(i + 1).map(Some).ok_or_else(|| CliError::Usage(format!("{} 需要参数", name))),
+            None => Ok(None),
+        }
+    };
+    let mut anonymization = AnonymizationOptions::default();
+    if let Some(k) = value("--k")? {
+        anonymization.k = k.parse().ok().filter(|k| *k >= 1).ok_or_else(|| CliError::Usage(format!("k 应为正整数: {}", k)))?;
+    }
+    if let Some(birth) = value("--birth")? {
+        anonymization.birth_granularity = match birth.as_str() {
+            "year" => BirthGranularity::Year,
+            "5" => BirthGranularity::FiveYears,
+            "10" => BirthGranularity::TenYears,
+            other => return Err(CliError::Usage(format!("未知的出生概括方式: {}", other))),
+        };
+    }
+    if let Some(region) = value("--region")? {
+        anonymization.region_level = parse_level(region).ok_or_else(|| CliError::Usage(format!("未知的区划级别: {}", region)))?;
+    }
+    if let Some(key) = value("--key")? {
+        anonymization.key = key.clone();
+    }
+    Ok(anonymization)
+}
+
+fn print_audit(entries: &[AuditEntry], json: bool) -> Result<(), CliError> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(entries)?);
+        return Ok(());
+    }
+    println!("时间\t操作员\t操作\t内容");
+    for entry in entries {
+        println!(
+            "{}\t{}\t{}\t{}",
+            entry.recorded_at.format("%Y-%m-%d %H:%M:%S"),
+            entry.operator,
+            entry.action,
+            entry.detail,
+        );
+    }
+    Ok(())
+}
+
+fn print_households(households: &[Household], json: bool) -> Result<(), CliError> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(households)?);
+        return Ok(());
+    }
+    println!("户籍ID\t户主\t证件号码\t户口类型\t在册人数\t地址");
+    for household in households {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            household.id,
+            household.head_name,
+            household.id_number,
+            household.household_type,
+            household.members.iter().filter(|m| m.is_active()).count(),
+            household.address,
+        );
+    }
+    Ok(())
+}
+
+fn print_household_details(household: &Household) {
+    println!("户籍ID: {}", household.id);
+    println!("户主: {} ({} {})", household.head_name, household.head_document_type, household.id_number);
+    println!("户口类型: {}", household.household_type);
+    println!("联系电话: {}", household.phone);
+    println!("家庭地址: {}", household.address);
+    println!("登记日期: {}", household.registration_date.format("%Y-%m-%d %H:%M:%S"));
+    println!("成员:");
+    for member in &household.members {
+        println!(
+            "  {}\t{}\t{}\t{} {}\t{}\t{}",
+            member.name,
+            member.relationship,
+            member.gender,
+            member.document_type,
+            member.id_number,
+            member.birth_date.format("%Y-%m-%d"),
+            member.status,
+        );
+    }
+}
+
+fn print_statistics<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, options: &Options) -> Result<(), CliError> {
+    let level = match options.args.iter().position(|a| a == "--by") {
+        Some(i) => {
+            let name = options.args.get(i + 1).ok_or_else(|| CliError::Usage("--by 需要指定级别".into()))?;
+            Some(parse_level(name).ok_or_else(|| CliError::Usage(format!("未知的区划级别: {}", name)))?)
+        }
+        None => None,
+    };
+
+    let stats = manager.get_statistics()?;
+    let divisions = level.map(|level| manager.count_by_division(level)).transpose()?.unwrap_or_default();
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&statistics_json(&stats, &divisions))?);
+        return Ok(());
+    }
+
+    println!("总户数: {}", stats.total_households);
+    println!("城镇户口: {}", stats.urban_households);
+    println!("农村户口: {}", stats.rural_households);
+    println!("在册人数: {}", stats.total_members);
+    println!("注销/迁出: {}", stats.inactive_members);
+    println!("境外证件: {}", stats.non_mainland_members);
+    if let Some(level) = level {
+        println!();
+        println!("按{}统计:", level);
+        for count in &divisions {
+            println!("  {}\t{}户\t{}人", division_name(&count.code), count.households, count.members);
+        }
+    }
+    Ok(())
+}
+
+/// 区划级别的英文名，供 `--by` 和接口的 `by` 参数使用
+pub(crate) fn parse_level(name: &str) -> Option<DivisionLevel> {
+    ["province", "city", "county", "township", "village"].iter()
+        .position(|n| *n == name)
+        .map(|index| DivisionLevel::ALL[index])
+}
+
+fn division_name(code: &str) -> String {
+    if code.is_empty() { "未指定".to_string() } else { divisions::full_name(code) }
+}
+
+/// `stats --json` 和接口共用的统计格式
+pub(crate) fn statistics_json(stats: &HouseholdStatistics, divisions: &[DivisionCount]) -> serde_json::Value {
+    serde_json::json!({
+        "total_households": stats.total_households,
+        "urban_households": stats.urban_households,
+        "rural_households": stats.rural_households,
+        "total_members": stats.total_members,
+        "inactive_members": stats.inactive_members,
+        "non_mainland_members": stats.non_mainland_members,
+        "divisions": divisions.iter().map(|d| serde_json::json!({
+            "code": d.code,
+            "name": division_name(&d.code),
+            "households": d.households,
+            "members": d.members,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+// 有错误时退出码为 1，只有警告时为 0
+fn run_checks<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, json: bool) -> Result<ExitCode, CliError> {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    for problem in manager.integrity_check()? {
+        errors.push(format!("数据库文件: {}", problem));
+    }
+    let households = manager.get_households()?;
+    for error in manager.decode_errors() {
+        errors.push(format!("无法读取: {}", error));
+    }
+    for household in &households {
+        let form = manager.household_form(household)?;
+        if let Err(validation) = form.validate() {
+            errors.push(format!("户主 {}: {}", household.head_name, validation));
+        }
+        for issue in consistency::check_household(&form).into_iter().flat_map(|c| c.issues) {
+            if issue.severity == Severity::Warning {
+                warnings.push(format!("户主 {}: {}", household.head_name, issue.message));
+            }
+        }
+    }
+    for group in manager.find_duplicates()? {
+        let entries: Vec<String> = group.entries.iter().map(|e| e.describe()).collect();
+        let message = format!("{}: {}", group.kind, entries.join("；"));
+        if group.kind.is_exact() { errors.push(message) } else { warnings.push(message) }
+    }
+    for household in manager.pending_address_reviews()? {
+        warnings.push(format!("户主 {}: 地址待核对: {}", household.head_name, household.address));
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "errors": errors, "warnings": warnings }))?);
+    } else {
+        for error in &errors {
+            println!("错误\t{}", error);
+        }
+        for warning in &warnings {
+            println!("警告\t{}", warning);
+        }
+        println!("检查完成: {} 户，{} 个错误，{} 个警告", households.len(), errors.len(), warnings.len());
+    }
+    Ok(if errors.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::memory::MemoryRepository;
+    use chrono::NaiveDate;
+
+    // 测试结束时删除的临时文件
+    struct TempFile(String);
+
+    impl TempFile {
+        fn new(extension: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("household-cli-{}.{}", Uuid::new_v4(), extension));
+            TempFile(path.to_string_lossy().into_owned())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn member(name: &str, id_number: &str, relationship: Relationship) -> Member {
+        Member {
+            name: name.to_string(),
+            document_type: DocumentType::ResidentId,
+            id_number: id_number.to_string(),
+            nationality: "CHN".to_string(),
+            relationship,
+            birth_date: NaiveDate::from_ymd_opt(1980, 1, 1).unwrap(),
+            gender: Gender::Male,
+            education: Education::University,
+            occupation: String::new(),
+            status: MemberStatus::Active,
+            marital_status: MaritalStatus::Unmarried,
+            death_date: None,
+        }
+    }
+
+    fn household(head: &str, id_number: &str) -> Household {
+        let mut household = Household {
+            id: Uuid::new_v4(),
+            head_name: String::new(),
+            id_number: String::new(),
+            head_document_type: DocumentType::ResidentId,
+            address: Address::from_division("110105001", "XXX路XXX号"),
+            dwelling_id: None,
+            phone: "13800138000".to_string(),
+            household_type: HouseholdType::Urban,
+            registration_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            members: vec![member(head, id_number, Relationship::HEAD)],
+            revision: 0,
+        };
+        household.sync_head();
+        household
+    }
+
+    fn options(command: &str, args: &[&str]) -> Options {
+        Options {
+            db_path: String::new(),
+            json: false,
+            masked: false,
+            operator: "测试员".to_string(),
+            role: Role::Editor,
+            command: command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    fn manager() -> HouseholdManager<MemoryRepository> {
+        HouseholdManager::with_repository(MemoryRepository::new())
+    }
+
+    fn run<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, options: &Options) -> Result<ExitCode, String> {
+        execute(manager, options).map_err(|e| match e {
+            CliError::Usage(message) => format!("用法: {}", message),
+            CliError::Failed(message) => message,
+        })
+    }
+
+    fn audit_actions<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) -> Vec<AuditAction> {
+        manager.audit_log(100).unwrap().into_iter().map(|e| e.action).collect()
+    }
+
+    #[test]
+    fn parses_global_options() {
+        let args: Vec<String> = ["--db", "a.db", "show", "--json", "张三"].iter().map(|a| a.to_string()).collect();
+        let options = parse_options(&args).ok().unwrap();
+        assert_eq!(options.db_path, "a.db");
+        assert!(options.json);
+        assert_eq!(options.command, "show");
+        assert_eq!(options.args, ["张三"]);
+
+        assert!(matches!(parse_options(&[]), Err(CliError::Usage(_))));
+        assert!(matches!(parse_options(&["--db".to_string()]), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn add_reads_json() {
+        let mut manager = manager();
+        let file = TempFile::new("json");
+        let household = household("张三", "110101198001011234");
+        std::fs::write(&file.0, serde_json::to_string(&household).unwrap()).unwrap();
+
+        run(&mut manager, &options("add", &[&file.0])).unwrap();
+        let households = manager.get_households().unwrap();
+        assert_eq!(households.len(), 1);
+        assert_eq!(households[0].id, household.id);
+
+        std::fs::write(&file.0, "{").unwrap();
+        assert!(run(&mut manager, &options("add", &[&file.0])).unwrap_err().starts_with("JSON 格式错误"));
+        assert_eq!(run(&mut manager, &options("add", &[])).unwrap_err(), "用法: add 需要参数: 文件");
+    }
+
+    #[test]
+    fn import_rejects_whole_file_on_invalid_household() {
+        let mut manager = manager();
+        let file = TempFile::new("json");
+        let mut invalid = household("李四", "110101198202022345");
+        invalid.members[0].name = String::new();
+        let households = [household("张三", "110101198001011234"), invalid];
+        std::fs::write(&file.0, serde_json::to_string(&households).unwrap()).unwrap();
+
+        let error = run(&mut manager, &options("import", &[&file.0])).unwrap_err();
+        assert!(error.starts_with("1户未通过校验，未导入"), "{}", error);
+        assert!(manager.get_households().unwrap().is_empty());
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let mut manager = manager();
+        let household = household("张三", "110101198001011234");
+        manager.add_household(household.clone()).unwrap();
+
+        for extension in ["json", "csv"] {
+            let file = TempFile::new(extension);
+            run(&mut manager, &options("export", &[&file.0])).unwrap();
+
+            let mut imported = self::manager();
+            run(&mut imported, &options("import", &[&file.0])).unwrap();
+            let households = imported.get_households().unwrap();
+            assert_eq!(households.len(), 1, "{}", extension);
+            assert_eq!(households[0].id, household.id);
+            assert_eq!(households[0].id_number, household.id_number);
+            assert_eq!(households[0].members.len(), 1);
+        }
+        assert_eq!(audit_actions(&mut manager)[..2], [AuditAction::Export, AuditAction::Export]);
+    }
+
+    #[test]
+    fn masked_export_hides_id_numbers() {
+        let mut manager = manager();
+        manager.add_household(household("张三", "110101198001011234")).unwrap();
+        let file = TempFile::new("json");
+        let mut masked = options("export", &[&file.0]);
+        masked.masked = true;
+
+        run(&mut manager, &masked).unwrap();
+        let content = std::fs::read_to_string(&file.0).unwrap();
+        assert!(!content.contains("110101198001011234"));
+        assert!(!content.contains("13800138000"));
+        assert_eq!(audit_actions(&mut manager)[0], AuditAction::MaskedExport);
+    }
+
+    #[test]
+    fn printing_is_audited_unless_masked() {
+        let mut manager = manager();
+        manager.add_household(household("张三", "110101198001011234")).unwrap();
+        let before = manager.audit_log(100).unwrap().len();
+
+        let mut masked = options("list", &[]);
+        masked.masked = true;
+        run(&mut manager, &masked).unwrap();
+        assert_eq!(manager.audit_log(100).unwrap().len(), before);
+
+        run(&mut manager, &options("show", &["110101198001011234"])).unwrap();
+        let entry = manager.audit_log(1).unwrap().remove(0);
+        assert_eq!(entry.detail, "命令行查看 张三户");
+        assert_eq!(entry.operator, "测试员");
+    }
+
+    #[test]
+    fn reports_usage_errors() {
+        let mut manager = manager();
+        assert_eq!(run(&mut manager, &options("show", &["110101198001011234"])).unwrap_err(), "未找到户籍: 110101198001011234");
+        assert_eq!(run(&mut manager, &options("audit", &["十"])).unwrap_err(), "用法: 记录数量无效: 十");
+        assert_eq!(run(&mut manager, &options("stats", &["--by", "town"])).unwrap_err(), "用法: 未知的区划级别: town");
+        assert_eq!(run(&mut manager, &options("remove", &[])).unwrap_err(), "用法: 未知命令: remove");
+    }
+
+    #[test]
+    fn check_fails_on_invalid_household() {
+        let mut manager = manager();
+        manager.add_household(household("张三", "110101198001011234")).unwrap();
+        let code = run(&mut manager, &options("check", &[])).unwrap();
+        assert_eq!(format!("{:?}", code), format!("{:?}", ExitCode::SUCCESS));
+
+        // 数据库中已有的记录不经过表单校验
+        let mut other = household("李四", "110101198202022345");
+        other.members.push(member("", "110101201003033456", Relationship::SON));
+        manager.add_household(other).unwrap();
+        let code = run(&mut manager, &options("check", &[])).unwrap();
+        assert_eq!(format!("{:?}", code), format!("{:?}", ExitCode::FAILURE));
+    }
+
+    #[test]
+    fn level_names() {
+        assert_eq!(parse_level("county"), Some(DivisionLevel::County));
+        assert_eq!(parse_level("County"), None);
+    }
+
+    fn viewer(command: &str, args: &[&str]) -> Options {
+        let mut options = options(command, args);
+        options.apply_settings(&serde_json::from_str(r#"{ "operator": "查看员甲" }"#).unwrap());
+        options
+    }
+
+    #[test]
+    fn settings_default_to_viewer() {
+        let options = viewer("list", &[]);
+        assert_eq!(options.role, Role::Viewer);
+        assert_eq!(options.operator, "查看员甲");
+        assert!(options.masked);
+
+        let mut editor = self::options("list", &[]);
+        editor.apply_settings(&serde_json::from_str(r#"{ "role": "editor" }"#).unwrap());
+        assert_eq!(editor.role, Role::Editor);
+        assert!(!editor.masked);
+    }
+
+    #[test]
+    fn viewer_cannot_change_or_copy_data() {
+        let mut manager = manager();
+        let file = TempFile::new("json");
+        std::fs::write(&file.0, serde_json::to_string(&household("张三", "110101198001011234")).unwrap()).unwrap();
+
+        for command in EDITOR_COMMANDS {
+            let error = run(&mut manager, &viewer(command, &[&file.0])).unwrap_err();
+            assert_eq!(error, format!("当前角色为查看员，不能执行 {} 命令", command));
+        }
+        assert!(manager.get_households().unwrap().is_empty());
+        assert!(manager.audit_log(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn viewer_output_is_masked() {
+        let mut manager = manager();
+        manager.add_household(household("张三", "110101198001011234")).unwrap();
+        let file = TempFile::new("csv");
+
+        run(&mut manager, &viewer("export", &[&file.0])).unwrap();
+        let content = std::fs::read_to_string(&file.0).unwrap();
+        assert!(!content.contains("110101198001011234"));
+        let entry = manager.audit_log(1).unwrap().remove(0);
+        assert_eq!((entry.action, entry.operator.as_str()), (AuditAction::MaskedExport, "查看员甲"));
+    }
+
+    #[test]
+    fn viewer_anonymizes_without_fixed_key() {
+        let mut manager = manager();
+        manager.add_household(household("张三", "110101198001011234")).unwrap();
+        let file = TempFile::new("csv");
+
+        let error = run(&mut manager, &viewer("anonymize", &[&file.0, "--key", "密钥"])).unwrap_err();
+        assert_eq!(error, "当前角色为查看员，不能用 --key 指定密钥");
+        assert!(!std::path::Path::new(&file.0).exists());
+
+        run(&mut manager, &viewer("anonymize", &[&file.0, "--k", "1"])).unwrap();
+        assert!(!std::fs::read_to_string(&file.0).unwrap().contains("110101198001011234"));
+        assert_eq!(manager.audit_log(1).unwrap()[0].action, AuditAction::AnonymizedExport);
+        run(&mut manager, &options("anonymize", &[&file.0, "--key", "密钥"])).unwrap();
+    }
+}
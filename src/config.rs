@@ -1,4 +1,11 @@
+use crate::data::privacy::Role;
+use crate::ui::shortcuts::ShortcutAction;
 use eframe::egui::{self, FontData, FontDefinitions, FontFamily};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 用户设置文件，位于程序运行目录
+pub const SETTINGS_FILE: &str = "settings.json";
 
 pub struct AppConfig {
     pub window_title: &'static str,
@@ -20,6 +27,47 @@ impl Default for AppConfig {
     }
 }
 
+/// 可由用户修改并保存的设置，未列出的项使用默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// 快捷键，如 `"new_household": "Ctrl+N"`
+    pub shortcuts: BTreeMap<ShortcutAction, String>,
+    /// 操作员名称，用于区分各人的表单草稿；为空时使用系统登录名
+    pub operator: String,
+    /// 操作员角色，`"viewer"` 只能查看遮蔽后的证件号码和电话，不能修改数据；默认为 `"viewer"`，
+    /// 需要修改数据的操作员须填写 `"editor"`
+    pub role: Role,
+}
+
+impl Settings {
+    /// 读取设置文件，文件不存在时使用默认设置
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("无法读取 {}: {}", path, e)),
+        };
+        serde_json::from_str(&content).map_err(|e| format!("{} 格式错误: {}", path, e))
+    }
+
+    /// 当前操作员，多人共用同一系统账户时应在设置中分别填写
+    pub fn operator(&self) -> String {
+        let configured = self.operator.trim();
+        if !configured.is_empty() {
+            return configured.to_string();
+        }
+        std::env::var("USERNAME")
+            .or_else(|_| std::env::var("USER"))
+            .unwrap_or_else(|_| "default".to_string())
+    }
+    
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, content).map_err(|e| format!("无法保存 {}: {}", path, e))
+    }
+}
+
 pub fn setup_custom_fonts(ctx: &egui::Context) {
     let mut fonts = FontDefinitions::default();
     
@@ -53,7 +101,9 @@ pub fn create_native_options() -> eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([config.default_width, config.default_height])
             .with_title(config.window_title)
-            .with_min_inner_size([config.min_width, config.min_height]),
+            .with_min_inner_size([config.min_width, config.min_height])
+            // 附件可直接拖入户籍表单
+            .with_drag_and_drop(true),
         ..Default::default()
     }
 }
@@ -0,0 +1,304 @@
+use super::codes::StableCode;
+use super::error::DataError;
+use base64::Engine;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 单个附件的大小上限
+pub const MAX_ATTACHMENT_SIZE: usize = 20 * 1024 * 1024;
+
+/// 户籍档案中附件的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AttachmentKind {
+    #[default]
+    Photo,
+    BirthCertificate,
+    MarriageCertificate,
+    TransferCertificate,
+    Other,
+}
+
+impl std::fmt::Display for AttachmentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachmentKind::Photo => write!(f, "证件照"),
+            AttachmentKind::BirthCertificate => write!(f, "出生证明"),
+            AttachmentKind::MarriageCertificate => write!(f, "结婚证"),
+            AttachmentKind::TransferCertificate => write!(f, "迁移证"),
+            AttachmentKind::Other => write!(f, "其他材料"),
+        }
+    }
+}
+
+/// 附件的文件格式，只接受图片和 PDF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Png,
+    Jpeg,
+    Pdf,
+}
+
+impl MediaType {
+    /// 按文件内容的开头识别格式，不看扩展名
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(MediaType::Png)
+        } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(MediaType::Jpeg)
+        } else if data.starts_with(b"%PDF-") {
+            Some(MediaType::Pdf)
+        } else {
+            None
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            MediaType::Png => "image/png",
+            MediaType::Jpeg => "image/jpeg",
+            MediaType::Pdf => "application/pdf",
+        }
+    }
+
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        [MediaType::Png, MediaType::Jpeg, MediaType::Pdf].into_iter().find(|m| m.mime() == mime)
+    }
+
+    pub fn is_image(&self) -> bool {
+        !matches!(self, MediaType::Pdf)
+    }
+}
+
+/// 附件所属的户籍或成员。成员按证件号码关联，与变动记录相同，迁往他户后附件随人
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttachmentOwner {
+    Household(Uuid),
+    Member(String),
+}
+
+/// 证件照、出生证明等扫描件，内容按哈希单独存放，相同文件只存一份
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub id: Uuid,
+    pub owner: AttachmentOwner,
+    pub kind: AttachmentKind,
+    pub file_name: String,
+    pub media_type: MediaType,
+    /// 文件内容的 SHA-256（十六进制）
+    pub hash: String,
+    pub size: usize,
+    pub added_at: NaiveDateTime,
+}
+
+impl Attachment {
+    /// 检查文件格式和大小后创建附件记录，内容另行保存
+    pub fn new(owner: AttachmentOwner, kind: AttachmentKind, file_name: &str, data: &[u8]) -> Result<Self, DataError> {
+        if let AttachmentOwner::Member(id_number) = &owner {
+            if id_number.trim().is_empty() {
+                return Err(DataError::Validation("成员尚未填写证件号码，不能添加附件".into()));
+            }
+        }
+        if data.len() > MAX_ATTACHMENT_SIZE {
+            return Err(DataError::Validation(format!(
+                "{} 超过 {} MB，请压缩后再添加", file_name, MAX_ATTACHMENT_SIZE / 1024 / 1024
+            )));
+        }
+        let media_type = MediaType::sniff(data)
+            .ok_or_else(|| DataError::Validation(format!("{} 不是 PNG、JPEG 图片或 PDF 文件", file_name)))?;
+        Ok(Self {
+            id: Uuid::new_v4(),
+            owner,
+            kind,
+            file_name: file_name.to_string(),
+            media_type,
+            hash: content_hash(data),
+            size: data.len(),
+            added_at: chrono::Local::now().naive_local(),
+        })
+    }
+}
+
+pub fn content_hash(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 导出为 JSON 时随户籍一起写出的附件，内容以 Base64 编码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedAttachment {
+    pub id: Uuid,
+    /// 成员附件为该成员的证件号码，户籍本身的附件为空
+    #[serde(default)]
+    pub member_id_number: Option<String>,
+    pub kind: AttachmentKind,
+    pub file_name: String,
+    pub added_at: NaiveDateTime,
+    pub data: String,
+}
+
+impl ExportedAttachment {
+    pub fn encode(attachment: &Attachment, data: &[u8]) -> Self {
+        Self {
+            id: attachment.id,
+            member_id_number: match &attachment.owner {
+                AttachmentOwner::Member(id_number) => Some(id_number.clone()),
+                AttachmentOwner::Household(_) => None,
+            },
+            kind: attachment.kind,
+            file_name: attachment.file_name.clone(),
+            added_at: attachment.added_at,
+            data: base64::engine::general_purpose::STANDARD.encode(data),
+        }
+    }
+
+    /// 还原为附件记录及其内容，户籍附件归入 `household_id`
+    pub fn decode(&self, household_id: Uuid) -> Result<(Attachment, Vec<u8>), DataError> {
+        let data = base64::engine::general_purpose::STANDARD.decode(&self.data)
+            .map_err(|e| DataError::Validation(format!("附件 {} 内容无法解码: {}", self.file_name, e)))?;
+        let owner = match &self.member_id_number {
+            Some(id_number) => AttachmentOwner::Member(id_number.clone()),
+            None => AttachmentOwner::Household(household_id),
+        };
+        let mut attachment = Attachment::new(owner, self.kind, &self.file_name, &data)?;
+        attachment.id = self.id;
+        attachment.added_at = self.added_at;
+        Ok((attachment, data))
+    }
+}
+
+// 附件在数据库中的原始行；SQLite 和 PostgreSQL 后端共用
+pub(super) struct AttachmentRow {
+    pub(super) id: String,
+    pub(super) household_id: Option<String>,
+    pub(super) member_id_number: Option<String>,
+    pub(super) kind: String,
+    pub(super) file_name: String,
+    pub(super) media_type: String,
+    pub(super) hash: String,
+    pub(super) size: i64,
+    pub(super) added_at: String,
+}
+
+impl AttachmentRow {
+    pub(super) fn encode(attachment: &Attachment) -> Self {
+        let (household_id, member_id_number) = match &attachment.owner {
+            AttachmentOwner::Household(id) => (Some(id.to_string()), None),
+            AttachmentOwner::Member(id_number) => (None, Some(id_number.clone())),
+        };
+        Self {
+            id: attachment.id.to_string(),
+            household_id,
+            member_id_number,
+            kind: attachment.kind.code().to_string(),
+            file_name: attachment.file_name.clone(),
+            media_type: attachment.media_type.mime().to_string(),
+            hash: attachment.hash.clone(),
+            size: attachment.size as i64,
+            added_at: attachment.added_at.format(DATE_FORMAT).to_string(),
+        }
+    }
+
+    /// 无法识别的记录返回 `None`，不影响同户的其他附件
+    pub(super) fn decode(&self) -> Option<Attachment> {
+        let owner = match (&self.household_id, &self.member_id_number) {
+            (Some(id), None) => AttachmentOwner::Household(Uuid::parse_str(id).ok()?),
+            (None, Some(id_number)) => AttachmentOwner::Member(id_number.clone()),
+            _ => return None,
+        };
+        Some(Attachment {
+            id: Uuid::parse_str(&self.id).ok()?,
+            owner,
+            kind: AttachmentKind::from_code(&self.kind).ok()?,
+            file_name: self.file_name.clone(),
+            media_type: MediaType::from_mime(&self.media_type)?,
+            hash: self.hash.clone(),
+            size: usize::try_from(self.size).ok()?,
+            added_at: NaiveDateTime::parse_from_str(&self.added_at, DATE_FORMAT).ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn content_hash_is_sha256_hex() {
+        assert_eq!(content_hash(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(content_hash(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    }
+
+    #[test]
+    fn sniffs_content_not_extension() {
+        assert_eq!(MediaType::sniff(PNG), Some(MediaType::Png));
+        assert_eq!(MediaType::sniff(&[0xFF, 0xD8, 0xFF, 0xE0]), Some(MediaType::Jpeg));
+        assert_eq!(MediaType::sniff(b"%PDF-1.7"), Some(MediaType::Pdf));
+        assert_eq!(MediaType::sniff(b"GIF89a"), None);
+        assert_eq!(MediaType::sniff(b""), None);
+        for media_type in [MediaType::Png, MediaType::Jpeg, MediaType::Pdf] {
+            assert_eq!(MediaType::from_mime(media_type.mime()), Some(media_type));
+        }
+    }
+
+    #[test]
+    fn new_checks_owner_size_and_format() {
+        let household = AttachmentOwner::Household(Uuid::new_v4());
+        let attachment = Attachment::new(household.clone(), AttachmentKind::Photo, "照片.jpg", PNG).unwrap();
+        assert_eq!(attachment.media_type, MediaType::Png);
+        assert_eq!((attachment.hash.as_str(), attachment.size), (content_hash(PNG).as_str(), PNG.len()));
+        // 内容相同的附件哈希相同
+        assert_eq!(Attachment::new(household.clone(), AttachmentKind::Other, "副本.png", PNG).unwrap().hash, attachment.hash);
+
+        let invalid = |owner: AttachmentOwner, data: &[u8]| match Attachment::new(owner, AttachmentKind::Other, "扫描件", data) {
+            Err(DataError::Validation(message)) => message,
+            other => panic!("应未通过校验: {:?}", other),
+        };
+        assert_eq!(invalid(AttachmentOwner::Member(" ".into()), PNG), "成员尚未填写证件号码，不能添加附件");
+        assert_eq!(invalid(household.clone(), b"GIF89a"), "扫描件 不是 PNG、JPEG 图片或 PDF 文件");
+        let mut large = b"%PDF-".to_vec();
+        large.resize(MAX_ATTACHMENT_SIZE + 1, 0);
+        assert_eq!(invalid(household, &large), "扫描件 超过 20 MB，请压缩后再添加");
+    }
+
+    #[test]
+    fn exported_attachments_round_trip() {
+        let member = Attachment::new(AttachmentOwner::Member("110101198001011234".into()), AttachmentKind::Photo, "照片.png", PNG).unwrap();
+        let exported = ExportedAttachment::encode(&member, PNG);
+        assert_eq!(exported.member_id_number.as_deref(), Some("110101198001011234"));
+        let (decoded, data) = exported.decode(Uuid::new_v4()).unwrap();
+        assert_eq!((decoded, data.as_slice()), (member, PNG));
+
+        // 户籍附件归入导入时的户
+        let household_id = Uuid::new_v4();
+        let household = Attachment::new(AttachmentOwner::Household(Uuid::new_v4()), AttachmentKind::Other, "户口本.png", PNG).unwrap();
+        let (decoded, _) = ExportedAttachment::encode(&household, PNG).decode(household_id).unwrap();
+        assert_eq!(decoded.owner, AttachmentOwner::Household(household_id));
+
+        let broken = ExportedAttachment { data: "不是Base64".to_string(), ..ExportedAttachment::encode(&household, PNG) };
+        assert!(matches!(broken.decode(household_id), Err(DataError::Validation(_))));
+    }
+
+    #[test]
+    fn unreadable_rows_are_skipped() {
+        let attachment = Attachment::new(AttachmentOwner::Household(Uuid::new_v4()), AttachmentKind::BirthCertificate, "出生证明.png", PNG).unwrap();
+        let row = || AttachmentRow::encode(&attachment);
+        let mut decoded = row().decode().unwrap();
+        // 保存时间只精确到秒
+        decoded.added_at = attachment.added_at;
+        assert_eq!(decoded, attachment);
+
+        let broken = [
+            AttachmentRow { member_id_number: Some("110101198001011234".into()), ..row() },
+            AttachmentRow { household_id: None, ..row() },
+            AttachmentRow { kind: "99".into(), ..row() },
+            AttachmentRow { media_type: "image/gif".into(), ..row() },
+            AttachmentRow { size: -1, ..row() },
+        ];
+        assert!(broken.iter().all(|row| row.decode().is_none()));
+    }
+}
@@ -0,0 +1,92 @@
+use super::codes::StableCode;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use uuid::Uuid;
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 需要留痕的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// 在界面上显示完整的证件号码或电话
+    Reveal,
+    /// 导出或打印未遮蔽的数据
+    Export,
+    /// 导出或打印遮蔽后的数据
+    MaskedExport,
+    /// 导出匿名化的统计数据
+    AnonymizedExport,
+}
+
+impl std::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditAction::Reveal => write!(f, "查看完整信息"),
+            AuditAction::Export => write!(f, "导出"),
+            AuditAction::MaskedExport => write!(f, "脱敏导出"),
+            AuditAction::AnonymizedExport => write!(f, "匿名化导出"),
+        }
+    }
+}
+
+/// 操作记录中的一条，只追加不修改
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub operator: String,
+    pub action: AuditAction,
+    /// 涉及的户籍，批量导出时为空
+    pub household_id: Option<Uuid>,
+    /// 如“张三户 成员李四的证件号码”“导出 12 户到 households.csv”
+    pub detail: String,
+    pub recorded_at: NaiveDateTime,
+}
+
+impl AuditEntry {
+    pub fn new(operator: &str, action: AuditAction, household_id: Option<Uuid>, detail: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            operator: operator.to_string(),
+            action,
+            household_id,
+            detail,
+            recorded_at: chrono::Local::now().naive_local(),
+        }
+    }
+}
+
+// 操作记录在数据库中的原始行；SQLite 和 PostgreSQL 后端共用
+pub(super) struct AuditRow {
+    pub(super) id: String,
+    pub(super) operator: String,
+    pub(super) action: String,
+    pub(super) household_id: Option<String>,
+    pub(super) detail: String,
+    pub(super) recorded_at: String,
+}
+
+impl AuditRow {
+    pub(super) fn encode(entry: &AuditEntry) -> Self {
+        Self {
+            id: entry.id.to_string(),
+            operator: entry.operator.clone(),
+            action: entry.action.code().to_string(),
+            household_id: entry.household_id.map(|id| id.to_string()),
+            detail: entry.detail.clone(),
+            recorded_at: entry.recorded_at.format(DATE_FORMAT).to_string(),
+        }
+    }
+
+    /// 无法识别的记录返回 `None`
+    pub(super) fn decode(&self) -> Option<AuditEntry> {
+        Some(AuditEntry {
+            id: Uuid::parse_str(&self.id).ok()?,
+            operator: self.operator.clone(),
+            action: AuditAction::from_code(&self.action).ok()?,
+            household_id: self.household_id.as_deref().map(Uuid::parse_str).transpose().ok()?,
+            detail: self.detail.clone(),
+            recorded_at: NaiveDateTime::parse_from_str(&self.recorded_at, DATE_FORMAT).ok()?,
+        })
+    }
+}
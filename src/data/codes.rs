@@ -0,0 +1,242 @@
+use super::attachments::AttachmentKind;
+use super::audit::AuditAction;
+use super::models::*;
+
+/// 数据库中的代码无法识别
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownCode {
+    pub kind: &'static str,
+    pub value: String,
+}
+
+impl std::fmt::Display for UnknownCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "未知的{}代码: {}", self.kind, self.value)
+    }
+}
+
+impl std::error::Error for UnknownCode {}
+
+/// 持久化用的稳定代码，与界面显示的中文名称无关。
+/// 已发布的代码不能修改，新增取值只能追加新代码
+pub trait StableCode: Sized + Copy + 'static {
+    const KIND: &'static str;
+    const ALL: &'static [Self];
+
+    fn code(&self) -> &'static str;
+
+    fn from_code(code: &str) -> Result<Self, UnknownCode> {
+        Self::ALL.iter()
+            .copied()
+            .find(|v| v.code() == code.trim())
+            .ok_or_else(|| UnknownCode { kind: Self::KIND, value: code.to_string() })
+    }
+}
+
+macro_rules! impl_from_str {
+    ($($t:ty),*) => {
+        $(
+            impl std::str::FromStr for $t {
+                type Err = UnknownCode;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    <$t as StableCode>::from_code(s)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_str!(HouseholdType, Gender, Education, MemberStatus, MaritalStatus, EventType, DocumentType, AttachmentKind, AuditAction);
+
+impl StableCode for HouseholdType {
+    const KIND: &'static str = "户口类型";
+    const ALL: &'static [Self] = &[HouseholdType::Urban, HouseholdType::Rural];
+
+    fn code(&self) -> &'static str {
+        match self {
+            HouseholdType::Urban => "urban",
+            HouseholdType::Rural => "rural",
+        }
+    }
+}
+
+// 性别代码采用 GB/T 2261.1
+impl StableCode for Gender {
+    const KIND: &'static str = "性别";
+    const ALL: &'static [Self] = &[Gender::Male, Gender::Female];
+
+    fn code(&self) -> &'static str {
+        match self {
+            Gender::Male => "1",
+            Gender::Female => "2",
+        }
+    }
+}
+
+// 学历代码采用 GB/T 4658
+impl StableCode for Education {
+    const KIND: &'static str = "学历";
+    const ALL: &'static [Self] = &[
+        Education::Primary,
+        Education::MiddleSchool,
+        Education::HighSchool,
+        Education::College,
+        Education::University,
+        Education::Graduate,
+        Education::Other,
+    ];
+
+    fn code(&self) -> &'static str {
+        match self {
+            Education::Primary => "80",
+            Education::MiddleSchool => "70",
+            Education::HighSchool => "60",
+            Education::College => "30",
+            Education::University => "20",
+            Education::Graduate => "10",
+            Education::Other => "90",
+        }
+    }
+}
+
+impl StableCode for MemberStatus {
+    const KIND: &'static str = "成员状态";
+    const ALL: &'static [Self] = &[MemberStatus::Active, MemberStatus::Cancelled, MemberStatus::MigratedOut];
+
+    fn code(&self) -> &'static str {
+        match self {
+            MemberStatus::Active => "active",
+            MemberStatus::Cancelled => "cancelled",
+            MemberStatus::MigratedOut => "migrated_out",
+        }
+    }
+}
+
+// 婚姻状况代码采用 GB/T 2261.2
+impl StableCode for MaritalStatus {
+    const KIND: &'static str = "婚姻状况";
+    const ALL: &'static [Self] = &[
+        MaritalStatus::Unmarried,
+        MaritalStatus::Married,
+        MaritalStatus::Widowed,
+        MaritalStatus::Divorced,
+    ];
+
+    fn code(&self) -> &'static str {
+        match self {
+            MaritalStatus::Unmarried => "10",
+            MaritalStatus::Married => "20",
+            MaritalStatus::Widowed => "30",
+            MaritalStatus::Divorced => "40",
+        }
+    }
+}
+
+impl StableCode for EventType {
+    const KIND: &'static str = "变动类型";
+    const ALL: &'static [Self] = &[
+        EventType::TransferIn,
+        EventType::TransferOut,
+        EventType::Split,
+        EventType::Merge,
+        EventType::MigrateOut,
+        EventType::HeadChange,
+        EventType::Birth,
+        EventType::Death,
+        EventType::Marriage,
+        EventType::Divorce,
+    ];
+
+    fn code(&self) -> &'static str {
+        match self {
+            EventType::TransferIn => "transfer_in",
+            EventType::TransferOut => "transfer_out",
+            EventType::Split => "split",
+            EventType::Merge => "merge",
+            EventType::MigrateOut => "migrate_out",
+            EventType::HeadChange => "head_change",
+            EventType::Birth => "birth",
+            EventType::Death => "death",
+            EventType::Marriage => "marriage",
+            EventType::Divorce => "divorce",
+        }
+    }
+}
+
+impl StableCode for DocumentType {
+    const KIND: &'static str = "证件类型";
+    const ALL: &'static [Self] = &DocumentType::ALL;
+
+    fn code(&self) -> &'static str {
+        match self {
+            DocumentType::ResidentId => "resident_id",
+            DocumentType::HmtResidencePermit => "hmt_residence_permit",
+            DocumentType::ForeignPermanentId => "foreign_permanent_id",
+            DocumentType::Passport => "passport",
+        }
+    }
+}
+
+impl StableCode for AttachmentKind {
+    const KIND: &'static str = "附件类别";
+    const ALL: &'static [Self] = &[
+        AttachmentKind::Photo,
+        AttachmentKind::BirthCertificate,
+        AttachmentKind::MarriageCertificate,
+        AttachmentKind::TransferCertificate,
+        AttachmentKind::Other,
+    ];
+
+    fn code(&self) -> &'static str {
+        match self {
+            AttachmentKind::Photo => "photo",
+            AttachmentKind::BirthCertificate => "birth_certificate",
+            AttachmentKind::MarriageCertificate => "marriage_certificate",
+            AttachmentKind::TransferCertificate => "transfer_certificate",
+            AttachmentKind::Other => "other",
+        }
+    }
+}
+
+impl StableCode for AuditAction {
+    const KIND: &'static str = "操作记录类别";
+    const ALL: &'static [Self] = &[
+        AuditAction::Reveal,
+        AuditAction::Export,
+        AuditAction::MaskedExport,
+        AuditAction::AnonymizedExport,
+    ];
+
+    fn code(&self) -> &'static str {
+        match self {
+            AuditAction::Reveal => "reveal",
+            AuditAction::Export => "export",
+            AuditAction::MaskedExport => "masked_export",
+            AuditAction::AnonymizedExport => "anonymized_export",
+        }
+    }
+}
+
+impl std::str::FromStr for Relationship {
+    type Err = UnknownCode;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Relationship::from_code(s).ok_or_else(|| UnknownCode { kind: "家庭关系", value: s.to_string() })
+    }
+}
+
+/// 无法解码的数据库记录
+#[derive(Debug, Clone)]
+pub struct DecodeError {
+    pub table: &'static str,
+    pub record_id: String,
+    pub description: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} 表记录 {} ({}): {}", self.table, self.record_id, self.description, self.message)
+    }
+}
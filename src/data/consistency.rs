@@ -0,0 +1,354 @@
+use super::models::{Gender, HouseholdForm, MaritalStatus, MemberForm, MemberStatus, Relationship, RelationshipCategory};
+use chrono::NaiveDate;
+
+/// 法定婚龄
+const MARRIAGE_AGE_MALE: i32 = 22;
+const MARRIAGE_AGE_FEMALE: i32 = 20;
+
+/// 亲生父母与子女的最小年龄差，小于此值视为录入错误
+const MIN_PARENT_GAP: i32 = 14;
+/// 年龄差在此范围外时提示核实
+const USUAL_PARENT_GAP: std::ops::RangeInclusive<i32> = 18..=50;
+const USUAL_SPOUSE_GAP: i32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// 少见但合法，保存前提示核实
+    Warning,
+    /// 数据矛盾，不能保存
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    /// 字段路径，与 `ValidationErrors` 一致
+    pub field: String,
+    pub message: String,
+}
+
+/// 检查清单中的一项
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub issues: Vec<Issue>,
+}
+
+impl Check {
+    fn new(name: &'static str) -> Self {
+        Self { name, issues: Vec::new() }
+    }
+
+    fn error(&mut self, field: String, message: String) {
+        self.issues.push(Issue { severity: Severity::Error, field, message });
+    }
+
+    fn warning(&mut self, field: String, message: String) {
+        self.issues.push(Issue { severity: Severity::Warning, field, message });
+    }
+
+    /// 最严重的问题，没有问题时为 `None`
+    pub fn severity(&self) -> Option<Severity> {
+        self.issues.iter().map(|i| i.severity).max()
+    }
+}
+
+/// 户内成员之间的一致性检查，只检查在册成员
+pub fn check_household(form: &HouseholdForm) -> Vec<Check> {
+    let members: Vec<(usize, &MemberForm)> = form.members.iter()
+        .enumerate()
+        .filter(|(_, m)| m.status == MemberStatus::Active)
+        .collect();
+    let heads: Vec<(usize, &MemberForm)> = members.iter()
+        .copied()
+        .filter(|(_, m)| m.relationship.is_head())
+        .collect();
+    let head = match heads.as_slice() {
+        [head] => Some(*head),
+        _ => None,
+    };
+
+    vec![
+        check_single_head(form, &heads),
+        check_head_info(form, head),
+        check_unique_id_numbers(&members),
+        check_id_number_details(&members),
+        check_spouses(form, &members, head),
+        check_generations(&members, head),
+    ]
+}
+
+fn check_single_head(form: &HouseholdForm, heads: &[(usize, &MemberForm)]) -> Check {
+    let mut check = Check::new("户主唯一");
+    if heads.is_empty() && !form.members.is_empty() {
+        check.error("members".to_string(), "成员中必须指定一名户主".to_string());
+    }
+    if heads.len() > 1 {
+        check.error("members".to_string(), format!("只能有一名户主，当前有{}名", heads.len()));
+    }
+    check
+}
+
+fn check_head_info(form: &HouseholdForm, head: Option<(usize, &MemberForm)>) -> Check {
+    let mut check = Check::new("户主信息与成员一致");
+    if let Some((index, head)) = head {
+        if form.head_name != head.name || form.id_number != head.id_number {
+            check.error("members".to_string(), format!("户主姓名或证件号码与成员 {} 的信息不一致", head.name));
+        }
+        if let Some(age) = age(head) {
+            if age < 16 {
+                check.warning(field(index, "birth_date"), format!("户主 {} 未满16周岁", head.name));
+            }
+        }
+    }
+    check
+}
+
+fn check_unique_id_numbers(members: &[(usize, &MemberForm)]) -> Check {
+    let mut check = Check::new("户内证件号码不重复");
+    for (n, (i, member)) in members.iter().enumerate() {
+        let id_number = normalize(&member.id_number);
+        if id_number.is_empty() {
+            continue;
+        }
+        if let Some((_, first)) = members[..n].iter().find(|(_, m)| normalize(&m.id_number) == id_number) {
+            check.error(field(*i, "id_number"), format!("{} 与 {} 的证件号码重复", member.name, first.name));
+        }
+    }
+    check
+}
+
+// 身份证号第7-14位为出生日期，第17位奇数为男性；护照等不含这些信息的证件跳过
+fn check_id_number_details(members: &[(usize, &MemberForm)]) -> Check {
+    let mut check = Check::new("证件号码与出生日期、性别相符");
+    for (i, member) in members {
+        let id_number = member.id_number.trim();
+        if !member.document_type.encodes_birth_and_gender(id_number)
+            || id_number.len() != 18
+            || !id_number.is_ascii()
+            || !id_number[..17].chars().all(|c| c.is_ascii_digit())
+        {
+            continue;
+        }
+        if let (Some(birth_date), Ok(id_date)) = (birth_date(member), NaiveDate::parse_from_str(&id_number[6..14], "%Y%m%d")) {
+            if birth_date != id_date {
+                check.warning(field(*i, "birth_date"), format!("{} 的出生日期与证件号码中的 {} 不符", member.name, id_date));
+            }
+        }
+        let id_gender = if id_number.as_bytes()[16] % 2 == 1 { Gender::Male } else { Gender::Female };
+        if member.gender != id_gender {
+            check.warning(field(*i, "gender"), format!("{} 的性别与证件号码不符", member.name));
+        }
+    }
+    check
+}
+
+fn check_spouses(form: &HouseholdForm, members: &[(usize, &MemberForm)], head: Option<(usize, &MemberForm)>) -> Check {
+    let mut check = Check::new("配偶关系合理");
+    let spouses: Vec<&(usize, &MemberForm)> = members.iter().filter(|(_, m)| m.relationship.is_spouse()).collect();
+    if spouses.len() > 1 {
+        check.error("members".to_string(), format!("户主只能有一名配偶，当前有{}名", spouses.len()));
+    }
+
+    let Some((head_index, head)) = head else {
+        return check;
+    };
+    for (i, spouse) in spouses {
+        // 夫、妻的关系代码本身限定了性别
+        let expected = match spouse.relationship {
+            Relationship::HUSBAND => Some(Gender::Male),
+            Relationship::WIFE => Some(Gender::Female),
+            _ => None,
+        };
+        if expected.is_some_and(|g| g != spouse.gender) {
+            check.error(field(*i, "relationship"), format!("{} 的关系为{}，与性别不符", spouse.name, spouse.relationship));
+        } else if spouse.gender == head.gender {
+            check.warning(field(*i, "gender"), format!("{} 与户主性别相同", spouse.name));
+        }
+
+        // 按结婚登记时的年龄检查，没有登记记录的不检查
+        for (index, person) in [(head_index, head), (*i, *spouse)] {
+            let legal_age = match person.gender {
+                Gender::Male => MARRIAGE_AGE_MALE,
+                Gender::Female => MARRIAGE_AGE_FEMALE,
+            };
+            if marriage_age(form, person).is_some_and(|age| age < legal_age) {
+                check.error(field(index, "birth_date"), format!("{} 结婚登记时未达法定婚龄（{}周岁）", person.name, legal_age));
+            }
+        }
+
+        if let Some(gap) = years_between(head, spouse) {
+            if gap.abs() > USUAL_SPOUSE_GAP {
+                check.warning(field(*i, "birth_date"), format!("{} 与户主年龄相差{}岁", spouse.name, gap.abs()));
+            }
+        }
+        if spouse.marital_status != MaritalStatus::Married {
+            check.warning(field(*i, "marital_status"), format!("{} 为户主配偶，婚姻状况为{}", spouse.name, spouse.marital_status));
+        }
+    }
+    check
+}
+
+// 子女、父母、孙辈、祖辈与户主的年龄差
+fn check_generations(members: &[(usize, &MemberForm)], head: Option<(usize, &MemberForm)>) -> Check {
+    let mut check = Check::new("长幼年龄差合理");
+    let Some((_, head)) = head else {
+        return check;
+    };
+
+    for (i, member) in members {
+        let Some((generations, blood)) = generation(member.relationship) else {
+            continue;
+        };
+        // 换算为长辈比晚辈年长的岁数
+        let Some(gap) = years_between(member, head) else {
+            continue;
+        };
+        let gap = gap * generations.signum();
+        let reversed = if generations > 0 { "作为长辈比户主年轻" } else { "作为晚辈比户主年长" };
+
+        if blood && gap < MIN_PARENT_GAP * generations.abs() {
+            check.error(field(*i, "birth_date"), format!("{}（{}）与户主年龄仅相差{}岁", member.name, member.relationship, gap));
+        } else if gap < 0 {
+            check.warning(field(*i, "birth_date"), format!("{}（{}）{}", member.name, member.relationship, reversed));
+        } else if generations.abs() == 1 && !USUAL_PARENT_GAP.contains(&gap) {
+            check.warning(field(*i, "birth_date"), format!("{}（{}）与户主年龄相差{}岁，请核实", member.name, member.relationship, gap));
+        }
+    }
+    check
+}
+
+// 相对户主的辈分（正数为长辈）以及是否为直系血亲；姻亲和继、养关系只提示不报错
+fn generation(relationship: Relationship) -> Option<(i32, bool)> {
+    let code: u8 = relationship.code().parse().ok()?;
+    match relationship.category() {
+        RelationshipCategory::Son | RelationshipCategory::Daughter => match code {
+            28 | 38 => None,
+            27 | 37 => Some((-1, false)),
+            _ => Some((-1, true)),
+        },
+        RelationshipCategory::Grandchild => match code {
+            45 | 46 => None,
+            47 | 48 => Some((-3, true)),
+            _ => Some((-2, true)),
+        },
+        RelationshipCategory::Parent => match code {
+            50..=52 => Some((1, true)),
+            _ => Some((1, false)),
+        },
+        RelationshipCategory::Grandparent => match code {
+            65 | 68 => None,
+            66 | 67 => Some((3, true)),
+            _ => Some((2, true)),
+        },
+        _ => None,
+    }
+}
+
+fn field(index: usize, name: &str) -> String {
+    format!("members[{}].{}", index, name)
+}
+
+fn normalize(id_number: &str) -> String {
+    id_number.trim().to_uppercase()
+}
+
+fn birth_date(member: &MemberForm) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(member.birth_year, member.birth_month, member.birth_day)
+}
+
+fn age(member: &MemberForm) -> Option<i32> {
+    let birth = birth_date(member)?;
+    let today = chrono::Local::now().date_naive();
+    today.years_since(birth).map(|years| years as i32)
+}
+
+fn marriage_age(form: &HouseholdForm, member: &MemberForm) -> Option<i32> {
+    let married = form.marriage_dates.get(&normalize(&member.id_number))?;
+    married.years_since(birth_date(member)?).map(|years| years as i32)
+}
+
+// `older` 比 `younger` 年长的整岁数，可为负
+fn years_between(older: &MemberForm, younger: &MemberForm) -> Option<i32> {
+    let (a, b) = (birth_date(older)?, birth_date(younger)?);
+    Some(match b.years_since(a) {
+        Some(years) => years as i32,
+        None => -(a.years_since(b)? as i32),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+
+    fn member(name: &str, id_number: &str, relationship: Relationship, gender: Gender, birth_year: i32) -> MemberForm {
+        MemberForm {
+            name: name.to_string(),
+            id_number: id_number.to_string(),
+            relationship,
+            gender,
+            birth_year,
+            birth_month: 1,
+            birth_day: 1,
+            marital_status: MaritalStatus::Married,
+            ..MemberForm::default()
+        }
+    }
+
+    // 1950年出生的户主与1952年出生的妻子
+    fn couple() -> HouseholdForm {
+        let mut form = HouseholdForm {
+            members: vec![
+                member("张三", "110101195001011234", Relationship::HEAD, Gender::Male, 1950),
+                member("李四", "110101195201012345", Relationship::WIFE, Gender::Female, 1952),
+            ],
+            ..HouseholdForm::default()
+        };
+        form.sync_head();
+        form
+    }
+
+    fn spouse_errors(form: &HouseholdForm) -> Vec<String> {
+        check_household(form).into_iter()
+            .filter(|c| c.name == "配偶关系合理")
+            .flat_map(|c| c.issues)
+            .filter(|i| i.severity == Severity::Error)
+            .map(|i| i.field)
+            .collect()
+    }
+
+    #[test]
+    fn legal_age_uses_marriage_date() {
+        let mut form = couple();
+        let date = |year| NaiveDate::from_ymd_opt(year, 6, 1).unwrap();
+        form.marriage_dates.insert("110101195001011234".to_string(), date(1975));
+        form.marriage_dates.insert("110101195201012345".to_string(), date(1975));
+        assert!(spouse_errors(&form).is_empty());
+
+        // 1970年结婚时户主20岁、妻子18岁，均未达法定婚龄
+        form.marriage_dates.insert("110101195001011234".to_string(), date(1970));
+        form.marriage_dates.insert("110101195201012345".to_string(), date(1970));
+        assert_eq!(spouse_errors(&form), ["members[0].birth_date", "members[1].birth_date"]);
+    }
+
+    #[test]
+    fn legal_age_skipped_without_marriage_record() {
+        let mut form = couple();
+        form.members[1].birth_year = chrono::Local::now().year() - 16;
+        assert!(spouse_errors(&form).is_empty());
+
+        form.marriage_dates.insert("110101195201012345".to_string(), chrono::Local::now().date_naive());
+        assert_eq!(spouse_errors(&form), ["members[1].birth_date"]);
+    }
+
+    #[test]
+    fn spouse_relationship_must_match_gender() {
+        let mut form = couple();
+        form.members[1].gender = Gender::Male;
+        assert_eq!(spouse_errors(&form), ["members[1].relationship"]);
+
+        form.members.push(member("王五", "110101195301013456", Relationship::SPOUSE, Gender::Female, 1953));
+        assert!(spouse_errors(&form).contains(&"members".to_string()));
+    }
+}
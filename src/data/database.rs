@@ -1,16 +1,101 @@
-use rusqlite::{Connection, Result, params};
+use rusqlite::{Connection, OptionalExtension, Result, Transaction, TransactionBehavior, params};
 use crate::data::models::*;
-use crate::data::manager::HouseholdStatistics;
+use crate::data::manager::{DivisionCount, HouseholdStatistics};
+use crate::data::codes::{DecodeError, StableCode};
+use crate::data::divisions::{self, DivisionLevel};
+use crate::data::attachments::{Attachment, AttachmentRow};
+use crate::data::audit::{AuditEntry, AuditRow};
+use crate::data::drafts::{Draft, DraftRow};
+use crate::data::error::DataError;
+use crate::data::repository::{HouseholdRepository, LoadedHouseholds};
 use chrono::{NaiveDateTime, NaiveDate};
+use std::time::Duration;
 use uuid::Uuid;
 
 pub struct Database {
     conn: Connection,
 }
 
+// 数据库中的原始行，解码为模型时校验每个字段；PostgreSQL 后端使用相同的列格式
+pub(super) struct HouseholdRow {
+    pub(super) id: String,
+    pub(super) head_name: String,
+    pub(super) id_number: String,
+    pub(super) address: Address,
+    pub(super) phone: String,
+    pub(super) household_type: String,
+    pub(super) registration_date: String,
+    pub(super) document_type: String,
+    pub(super) dwelling_id: Option<String>,
+    pub(super) revision: i64,
+}
+
+impl HouseholdRow {
+    pub(super) fn decode(&self) -> std::result::Result<Household, String> {
+        Ok(Household {
+            id: Uuid::parse_str(&self.id).map_err(|_| format!("无效的户籍ID: {}", self.id))?,
+            head_name: self.head_name.clone(),
+            id_number: self.id_number.clone(),
+            head_document_type: self.document_type.parse().map_err(|e| format!("{}", e))?,
+            address: self.address.clone(),
+            dwelling_id: self.dwelling_id.as_deref()
+                .map(|id| Uuid::parse_str(id).map_err(|_| format!("无效的门牌ID: {}", id)))
+                .transpose()?,
+            phone: self.phone.clone(),
+            household_type: self.household_type.parse().map_err(|e| format!("{}", e))?,
+            registration_date: NaiveDateTime::parse_from_str(&self.registration_date, "%Y-%m-%d %H:%M:%S")
+                .map_err(|_| format!("无效的登记日期: {}", self.registration_date))?,
+            members: Vec::new(),
+            revision: self.revision,
+        })
+    }
+}
+
+pub(super) struct MemberRow {
+    pub(super) id: i64,
+    pub(super) name: String,
+    pub(super) id_number: String,
+    pub(super) relationship: String,
+    pub(super) birth_date: String,
+    pub(super) gender: String,
+    pub(super) education: String,
+    pub(super) occupation: String,
+    pub(super) status: String,
+    pub(super) marital_status: String,
+    pub(super) death_date: Option<String>,
+    pub(super) document_type: String,
+    pub(super) nationality: String,
+}
+
+impl MemberRow {
+    pub(super) fn decode(&self) -> std::result::Result<Member, String> {
+        let parse_date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| format!("无效的日期: {}", s));
+        
+        Ok(Member {
+            name: self.name.clone(),
+            document_type: self.document_type.parse().map_err(|e| format!("{}", e))?,
+            id_number: self.id_number.clone(),
+            nationality: self.nationality.clone(),
+            relationship: self.relationship.parse().map_err(|e| format!("{}", e))?,
+            birth_date: parse_date(&self.birth_date)?,
+            gender: self.gender.parse().map_err(|e| format!("{}", e))?,
+            education: self.education.parse().map_err(|e| format!("{}", e))?,
+            occupation: self.occupation.clone(),
+            status: self.status.parse().map_err(|e| format!("{}", e))?,
+            marital_status: self.marital_status.parse().map_err(|e| format!("{}", e))?,
+            death_date: self.death_date.as_deref().map(parse_date).transpose()?,
+        })
+    }
+}
+
 impl Database {
     pub fn new(db_path: &str) -> Result<Self> {
         let conn = Connection::open(db_path)?;
+        // 多个程序同时打开同一文件时，写入方等待对方完成而不是立即报错；
+        // WAL 模式下读取不会被写入阻塞
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get::<_, String>(0))?;
         let db = Database { conn };
         db.init_tables()?;
         Ok(db)
@@ -48,6 +133,206 @@ impl Database {
             [],
         )?;
         
+        // 旧版本数据库的成员表没有状态相关字段，补充列
+        self.add_column_if_missing("members", "status", "TEXT NOT NULL DEFAULT 'active'")?;
+        self.add_column_if_missing("members", "marital_status", "TEXT NOT NULL DEFAULT '10'")?;
+        self.add_column_if_missing("members", "death_date", "TEXT")?;
+        // 证件类型和国籍，旧数据均为居民身份证
+        self.add_column_if_missing("members", "document_type", "TEXT NOT NULL DEFAULT 'resident_id'")?;
+        self.add_column_if_missing("members", "nationality", "TEXT NOT NULL DEFAULT 'CHN'")?;
+        self.add_column_if_missing("households", "document_type", "TEXT NOT NULL DEFAULT 'resident_id'")?;
+        // 结构化地址：各级区划代码和详细地址，address 列保留完整地址文本用于搜索
+        for level in DivisionLevel::ALL {
+            self.add_column_if_missing("households", division_column(level), "TEXT NOT NULL DEFAULT ''")?;
+        }
+        self.add_column_if_missing("households", "address_detail", "TEXT NOT NULL DEFAULT ''")?;
+        // 迁移时未能确定区划、需要人工核对的地址
+        self.add_column_if_missing("households", "address_review", "INTEGER NOT NULL DEFAULT 0")?;
+        
+        // 创建门牌表，同一社区内楼栋、单元、房号不能重复
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS dwellings (
+                id TEXT PRIMARY KEY,
+                division TEXT NOT NULL,
+                building TEXT NOT NULL,
+                unit TEXT NOT NULL DEFAULT '',
+                room TEXT NOT NULL,
+                capacity INTEGER NOT NULL DEFAULT 0,
+                UNIQUE (division, building, unit, room)
+            )",
+            [],
+        )?;
+        self.add_column_if_missing("households", "dwelling_id", "TEXT REFERENCES dwellings (id)")?;
+        self.add_column_if_missing("households", "revision", "INTEGER NOT NULL DEFAULT 0")?;
+        
+        // 创建户籍变动事件表
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS household_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                event_type TEXT NOT NULL,
+                event_date TEXT NOT NULL,
+                household_id TEXT NOT NULL,
+                related_household_id TEXT,
+                member_name TEXT NOT NULL,
+                member_id_number TEXT NOT NULL,
+                destination TEXT,
+                reason TEXT,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        
+        // 创建表单草稿表，表单以 JSON 保存
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS drafts (
+                id TEXT PRIMARY KEY,
+                operator TEXT NOT NULL,
+                household_id TEXT,
+                form TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        
+        // 创建附件表，文件内容按 SHA-256 单独存放，备份时随数据库文件一起复制
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS attachment_files (
+                hash TEXT PRIMARY KEY,
+                data BLOB NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS attachments (
+                id TEXT PRIMARY KEY,
+                household_id TEXT,
+                member_id_number TEXT,
+                kind TEXT NOT NULL,
+                file_name TEXT NOT NULL,
+                media_type TEXT NOT NULL,
+                hash TEXT NOT NULL REFERENCES attachment_files (hash),
+                size INTEGER NOT NULL,
+                added_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_attachments_household ON attachments (household_id);
+            CREATE INDEX IF NOT EXISTS idx_attachments_member ON attachments (member_id_number);"
+        )?;
+        
+        // 创建操作记录表，只追加不修改
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id TEXT PRIMARY KEY,
+                operator TEXT NOT NULL,
+                action TEXT NOT NULL,
+                household_id TEXT,
+                detail TEXT NOT NULL,
+                recorded_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        
+        self.migrate()
+    }
+    
+    /// 按 `PRAGMA user_version` 记录的版本依次执行数据迁移
+    fn migrate(&self) -> Result<()> {
+        let version: i64 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        
+        // 版本 1：枚举字段由中文显示名称改为稳定代码存储
+        if version < 1 {
+            let tx = self.conn.unchecked_transaction()?;
+            
+            // 子女按性别区分儿子、女儿，需在性别转换为代码之前执行
+            self.conn.execute_batch(
+                "UPDATE members SET relationship = '02' WHERE relationship = '户主';
+                 UPDATE members SET relationship = '10' WHERE relationship = '配偶';
+                 UPDATE members SET relationship = CASE gender WHEN '女' THEN '30' ELSE '20' END WHERE relationship = '子女';
+                 UPDATE members SET relationship = '50' WHERE relationship = '父母';
+                 UPDATE members SET relationship = '97' WHERE relationship = '其他';"
+            )?;
+            self.migrate_column::<HouseholdType>("households", "household_type")?;
+            self.migrate_column::<Gender>("members", "gender")?;
+            self.migrate_column::<Education>("members", "education")?;
+            self.migrate_column::<MemberStatus>("members", "status")?;
+            self.migrate_column::<MaritalStatus>("members", "marital_status")?;
+            self.migrate_column::<EventType>("household_events", "event_type")?;
+            
+            self.conn.execute_batch("PRAGMA user_version = 1")?;
+            tx.commit()?;
+        }
+        
+        // 版本 2：自由文本地址拆分为各级行政区划，无法唯一确定的留待人工核对
+        if version < 2 {
+            let tx = self.conn.unchecked_transaction()?;
+            
+            let mut stmt = self.conn.prepare("SELECT id, address FROM households")?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<Result<Vec<_>>>()?;
+            for (id, text) in rows {
+                let mut candidates = divisions::parse_address(&text);
+                let (address, review) = match candidates.len() {
+                    1 => (candidates.remove(0), false),
+                    _ => (Address { detail: text.clone(), ..Default::default() }, true),
+                };
+                self.write_address(&id, &address)?;
+                self.conn.execute(
+                    "UPDATE households SET address_review = ?1 WHERE id = ?2",
+                    params![review, id],
+                )?;
+            }
+            
+            self.conn.execute_batch("PRAGMA user_version = 2")?;
+            tx.commit()?;
+        }
+        
+        Ok(())
+    }
+    
+    // 将列中的中文显示名称替换为对应代码，无法识别的值保持原样，读取时报告
+    fn migrate_column<T: StableCode + std::fmt::Display>(&self, table: &str, column: &str) -> Result<()> {
+        for value in T::ALL {
+            self.conn.execute(
+                &format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table, column, column),
+                params![value.code(), value.to_string()],
+            )?;
+        }
+        Ok(())
+    }
+    
+    fn write_dwelling_id(&self, household: &Household) -> Result<()> {
+        self.conn.execute(
+            "UPDATE households SET dwelling_id = ?1 WHERE id = ?2",
+            params![household.dwelling_id.map(|id| id.to_string()), household.id.to_string()],
+        )?;
+        Ok(())
+    }
+    
+    fn write_address(&self, household_id: &str, address: &Address) -> Result<()> {
+        self.conn.execute(
+            "UPDATE households SET address = ?1, province = ?2, city = ?3, county = ?4, township = ?5, 
+             village = ?6, address_detail = ?7 WHERE id = ?8",
+            params![
+                address.to_string(),
+                address.province,
+                address.city,
+                address.county,
+                address.township,
+                address.village,
+                address.detail,
+                household_id,
+            ],
+        )?;
+        Ok(())
+    }
+    
+    fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let columns = stmt.query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<_>>>()?;
+        
+        if !columns.iter().any(|c| c == column) {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
         Ok(())
     }
     
@@ -58,21 +343,42 @@ impl Database {
         Ok(count == 0)
     }
     
+    // 立即取得写锁，避免先读后写期间其他程序写入导致查重失效
+    fn begin_write(&self) -> Result<Transaction<'_>> {
+        Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)
+    }
+    
+    /// 其他连接每提交一次写入，该值就会变化
+    pub fn data_version(&self) -> Result<i64> {
+        self.conn.query_row("PRAGMA data_version", [], |row| row.get(0))
+    }
+    
     // 户籍相关操作
-    pub fn insert_household(&self, household: &Household) -> Result<()> {
+    pub fn insert_household(&self, household: &Household) -> std::result::Result<(), DataError> {
+        let tx = self.begin_write()?;
+        self.insert_household_rows(household)?;
+        Ok(tx.commit()?)
+    }
+    
+    fn insert_household_rows(&self, household: &Household) -> std::result::Result<(), DataError> {
+        self.check_unique_household(household)?;
+        
         self.conn.execute(
-            "INSERT INTO households (id, head_name, id_number, address, phone, household_type, registration_date) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO households (id, head_name, id_number, address, phone, household_type, registration_date, document_type) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 household.id.to_string(),
                 household.head_name,
                 household.id_number,
-                household.address,
+                household.address.to_string(),
                 household.phone,
-                household.household_type.to_string(),
+                household.household_type.code(),
                 household.registration_date.format("%Y-%m-%d %H:%M:%S").to_string(),
+                household.head_document_type.code(),
             ],
         )?;
+        self.write_address(&household.id.to_string(), &household.address)?;
+        self.write_dwelling_id(household)?;
         
         // 插入成员
         for member in &household.members {
@@ -82,21 +388,45 @@ impl Database {
         Ok(())
     }
     
-    pub fn update_household(&self, household: &Household) -> Result<()> {
-        // 更新户籍信息
-        self.conn.execute(
+    /// 更新户籍，数据库中的版本与 `household.revision` 不同或户籍已被删除时返回 `DataError::Conflict`
+    pub fn update_household(&self, household: &Household) -> std::result::Result<(), DataError> {
+        let tx = self.begin_write()?;
+        self.update_household_rows(household)?;
+        Ok(tx.commit()?)
+    }
+    
+    fn update_household_rows(&self, household: &Household) -> std::result::Result<(), DataError> {
+        self.check_unique_household(household)?;
+        
+        // 更新户籍信息，同时确认读取后没有其他用户保存过
+        let changed = self.conn.execute(
             "UPDATE households SET head_name = ?1, id_number = ?2, address = ?3, phone = ?4, 
-             household_type = ?5, registration_date = ?6 WHERE id = ?7",
+             household_type = ?5, registration_date = ?6, document_type = ?8, revision = revision + 1 
+             WHERE id = ?7 AND revision = ?9",
             params![
                 household.head_name,
                 household.id_number,
-                household.address,
+                household.address.to_string(),
                 household.phone,
-                household.household_type.to_string(),
+                household.household_type.code(),
                 household.registration_date.format("%Y-%m-%d %H:%M:%S").to_string(),
                 household.id.to_string(),
+                household.head_document_type.code(),
+                household.revision,
             ],
         )?;
+        if changed == 0 {
+            return Err(DataError::Conflict(household.id));
+        }
+        self.write_address(&household.id.to_string(), &household.address)?;
+        self.write_dwelling_id(household)?;
+        // 选择了区划即视为已核对
+        if !household.address.province.is_empty() {
+            self.conn.execute(
+                "UPDATE households SET address_review = 0 WHERE id = ?1",
+                params![household.id.to_string()],
+            )?;
+        }
         
         // 删除旧成员
         self.conn.execute(
@@ -112,202 +442,549 @@ impl Database {
         Ok(())
     }
     
-    pub fn delete_household(&self, household_id: &Uuid) -> Result<()> {
+    /// 删除户籍，数据库中的版本与 `revision` 不同或户籍已被删除时返回 `DataError::Conflict`
+    pub fn delete_household(&self, household_id: &Uuid, revision: i64) -> std::result::Result<(), DataError> {
+        let tx = self.begin_write()?;
+        self.delete_household_rows(household_id, revision)?;
+        Ok(tx.commit()?)
+    }
+    
+    fn delete_household_rows(&self, household_id: &Uuid, revision: i64) -> std::result::Result<(), DataError> {
         // 先删除成员
         self.conn.execute(
             "DELETE FROM members WHERE household_id = ?1",
             params![household_id.to_string()],
         )?;
         
-        // 再删除户籍
-        self.conn.execute(
-            "DELETE FROM households WHERE id = ?1",
-            params![household_id.to_string()],
+        // 再删除户籍，同时确认读取后没有其他用户保存过；冲突时随事务回滚恢复成员
+        let deleted = self.conn.execute(
+            "DELETE FROM households WHERE id = ?1 AND revision = ?2",
+            params![household_id.to_string(), revision],
         )?;
+        if deleted == 0 {
+            return Err(DataError::Conflict(*household_id));
+        }
+        
+        Ok(())
+    }
+    
+    /// 在同一事务中批量更新和删除户籍并记录变动事件，任一步失败则全部回滚
+    pub fn apply_changes(&self, updates: &[Household], deletions: &[(Uuid, i64)], events: &[HouseholdEvent]) -> std::result::Result<(), DataError> {
+        let tx = self.begin_write()?;
+        
+        for (household_id, revision) in deletions {
+            self.delete_household_rows(household_id, *revision)?;
+        }
+        
+        // 先清空所有待更新户的成员，避免成员在户之间移动时误报重复
+        for household in updates {
+            self.conn.execute(
+                "DELETE FROM members WHERE household_id = ?1",
+                params![household.id.to_string()],
+            )?;
+        }
+        
+        for household in updates {
+            let exists: bool = self.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM households WHERE id = ?1)",
+                params![household.id.to_string()],
+                |row| row.get(0),
+            )?;
+            if exists {
+                self.update_household_rows(household)?;
+            } else if household.revision > 0 {
+                // 读取过的户籍已不存在，说明被其他用户删除
+                return Err(DataError::Conflict(household.id));
+            } else {
+                self.insert_household_rows(household)?;
+            }
+        }
         
+        for event in events {
+            self.insert_event(event)?;
+        }
+        
+        Ok(tx.commit()?)
+    }
+    
+    // 变动事件相关操作
+    fn insert_event(&self, event: &HouseholdEvent) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO household_events (event_type, event_date, household_id, related_household_id, 
+             member_name, member_id_number, destination, reason, created_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                event.event_type.code(),
+                event.event_date.to_string(),
+                event.household_id.to_string(),
+                event.related_household_id.map(|id| id.to_string()),
+                event.member_name,
+                event.member_id_number,
+                event.destination,
+                event.reason,
+                chrono::Local::now().naive_local().format("%Y-%m-%d %H:%M:%S").to_string(),
+            ],
+        )?;
         Ok(())
     }
     
-    pub fn get_all_households(&self) -> Result<Vec<Household>> {
+    pub fn get_events_by_household_id(&self, household_id: &Uuid) -> Result<Vec<HouseholdEvent>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, head_name, id_number, address, phone, household_type, registration_date 
-             FROM households ORDER BY registration_date DESC"
+            "SELECT event_type, event_date, household_id, related_household_id, member_name, 
+             member_id_number, destination, reason 
+             FROM household_events WHERE household_id = ?1 ORDER BY event_date DESC, id DESC"
         )?;
         
-        let household_iter = stmt.query_map([], |row| {
-            let id_str: String = row.get(0)?;
-            let household_type_str: String = row.get(5)?;
-            let registration_date_str: String = row.get(6)?;
+        let event_iter = stmt.query_map(params![household_id.to_string()], |row| {
+            let event_type_str: String = row.get(0)?;
+            let event_date_str: String = row.get(1)?;
+            let household_id_str: String = row.get(2)?;
+            let related_str: Option<String> = row.get(3)?;
             
-            let id = Uuid::parse_str(&id_str).map_err(|_| rusqlite::Error::InvalidParameterName("Invalid UUID".to_string()))?;
-            let household_type = match household_type_str.as_str() {
-                "城镇户口" => HouseholdType::Urban,
-                "农村户口" => HouseholdType::Rural,
-                _ => HouseholdType::Urban,
-            };
-            let registration_date = if registration_date_str.contains(' ') {
-                NaiveDateTime::parse_from_str(&registration_date_str, "%Y-%m-%d %H:%M:%S")
-                    .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid date format".to_string()))?
-            } else {
-                // 如果只有日期，则使用默认时间
-                let date = NaiveDate::parse_from_str(&registration_date_str, "%Y-%m-%d")
-                    .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid date format".to_string()))?;
-                date.and_hms_opt(0, 0, 0).unwrap()
+            let event_type = event_type_str.parse::<EventType>()
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?;
+            let event_date = NaiveDate::parse_from_str(&event_date_str, "%Y-%m-%d")
+                .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid event date".to_string()))?;
+            let household_id = Uuid::parse_str(&household_id_str)
+                .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid UUID".to_string()))?;
+            let related_household_id = match related_str {
+                Some(s) => Some(Uuid::parse_str(&s)
+                    .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid UUID".to_string()))?),
+                None => None,
             };
             
-            Ok(Household {
-                id,
+            Ok(HouseholdEvent {
+                event_type,
+                event_date,
+                household_id,
+                related_household_id,
+                member_name: row.get(4)?,
+                member_id_number: row.get(5)?,
+                destination: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                reason: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+            })
+        })?;
+        
+        let mut events = Vec::new();
+        for event_result in event_iter {
+            events.push(event_result?);
+        }
+        
+        Ok(events)
+    }
+    
+    /// 读取全部户籍。无法解码的记录不会被静默替换为默认值，而是连同所在户一起
+    /// 跳过并记录在 `errors` 中，避免编辑该户时覆盖掉无法识别的数据
+    pub fn load_households(&self) -> Result<LoadedHouseholds> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, head_name, id_number, province, city, county, township, village, address_detail, 
+             phone, household_type, registration_date, document_type, dwelling_id, revision 
+             FROM households ORDER BY registration_date DESC"
+        )?;
+        
+        let rows = stmt.query_map([], |row| {
+            Ok(HouseholdRow {
+                id: row.get(0)?,
                 head_name: row.get(1)?,
                 id_number: row.get(2)?,
-                address: row.get(3)?,
-                phone: row.get(4)?,
-                household_type,
-                registration_date,
-                members: Vec::new(), // 稍后填充
+                address: Address {
+                    province: row.get(3)?,
+                    city: row.get(4)?,
+                    county: row.get(5)?,
+                    township: row.get(6)?,
+                    village: row.get(7)?,
+                    detail: row.get(8)?,
+                },
+                phone: row.get::<_, Option<String>>(9)?.unwrap_or_default(),
+                household_type: row.get(10)?,
+                registration_date: row.get(11)?,
+                document_type: row.get(12)?,
+                dwelling_id: row.get(13)?,
+                revision: row.get(14)?,
             })
-        })?;
+        })?.collect::<Result<Vec<_>>>()?;
         
-        let mut households = Vec::new();
-        for household_result in household_iter {
-            let mut household = household_result?;
-            // 获取成员信息
-            household.members = self.get_members_by_household_id(&household.id)?;
-            households.push(household);
+        let mut loaded = LoadedHouseholds::default();
+        for row in rows {
+            let mut household = match row.decode() {
+                Ok(household) => household,
+                Err(message) => {
+                    loaded.errors.push(DecodeError {
+                        table: "households",
+                        record_id: row.id.clone(),
+                        description: format!("户主 {}", row.head_name),
+                        message,
+                    });
+                    continue;
+                }
+            };
+            
+            let mut member_errors = Vec::new();
+            for member_row in self.get_member_rows(&row.id)? {
+                match member_row.decode() {
+                    Ok(member) => household.members.push(member),
+                    Err(message) => member_errors.push(DecodeError {
+                        table: "members",
+                        record_id: member_row.id.to_string(),
+                        description: format!("{}，户主 {}", member_row.name, row.head_name),
+                        message,
+                    }),
+                }
+            }
+            
+            if member_errors.is_empty() {
+                loaded.households.push(household);
+            } else {
+                loaded.errors.extend(member_errors);
+            }
         }
         
-        Ok(households)
+        Ok(loaded)
+    }
+    
+    /// 读取全部门牌，ID无法识别的记录跳过并记录在错误中
+    pub fn load_dwellings(&self) -> Result<(Vec<Dwelling>, Vec<DecodeError>)> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, division, building, unit, room, capacity FROM dwellings"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                Dwelling {
+                    id: Uuid::nil(),
+                    division: row.get(1)?,
+                    building: row.get(2)?,
+                    unit: row.get(3)?,
+                    room: row.get(4)?,
+                    capacity: row.get(5)?,
+                },
+            ))
+        })?.collect::<Result<Vec<_>>>()?;
+        
+        let mut dwellings = Vec::new();
+        let mut errors = Vec::new();
+        for (id, mut dwelling) in rows {
+            match Uuid::parse_str(&id) {
+                Ok(id) => {
+                    dwelling.id = id;
+                    dwellings.push(dwelling);
+                }
+                Err(_) => errors.push(DecodeError {
+                    table: "dwellings",
+                    record_id: id.clone(),
+                    description: dwelling.short_name(),
+                    message: format!("无效的门牌ID: {}", id),
+                }),
+            }
+        }
+        Ok((dwellings, errors))
+    }
+    
+    pub fn save_dwelling(&self, dwelling: &Dwelling) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO dwellings (id, division, building, unit, room, capacity) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (id) DO UPDATE SET division = ?2, building = ?3, unit = ?4, room = ?5, capacity = ?6",
+            params![
+                dwelling.id.to_string(),
+                dwelling.division,
+                dwelling.building,
+                dwelling.unit,
+                dwelling.room,
+                dwelling.capacity,
+            ],
+        )?;
+        Ok(())
+    }
+    
+    /// 删除门牌，仍有户籍登记在此门牌时拒绝删除
+    pub fn delete_dwelling(&self, dwelling_id: &Uuid) -> std::result::Result<(), DataError> {
+        let residents: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM households WHERE dwelling_id = ?1",
+            params![dwelling_id.to_string()],
+            |row| row.get(0),
+        )?;
+        if residents > 0 {
+            return Err(DataError::Constraint(format!("仍有{}户登记在此门牌，不能删除", residents)));
+        }
+        self.conn.execute("DELETE FROM dwellings WHERE id = ?1", params![dwelling_id.to_string()])?;
+        Ok(())
+    }
+    
+    pub fn save_draft(&self, draft: &Draft) -> std::result::Result<(), DataError> {
+        let row = DraftRow::encode(draft)?;
+        self.conn.execute(
+            "INSERT INTO drafts (id, operator, household_id, form, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (id) DO UPDATE SET operator = ?2, household_id = ?3, form = ?4, updated_at = ?5",
+            params![row.id, row.operator, row.household_id, row.form, row.updated_at],
+        )?;
+        Ok(())
+    }
+    
+    pub fn load_drafts(&self, operator: &str) -> Result<Vec<Draft>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, operator, household_id, form, updated_at FROM drafts WHERE operator = ?1 ORDER BY updated_at DESC"
+        )?;
+        let rows = stmt.query_map(params![operator], |row| {
+            Ok(DraftRow {
+                id: row.get(0)?,
+                operator: row.get(1)?,
+                household_id: row.get(2)?,
+                form: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })?.collect::<Result<Vec<_>>>()?;
+        Ok(rows.iter().filter_map(DraftRow::decode).collect())
+    }
+    
+    pub fn delete_draft(&self, draft_id: &Uuid) -> Result<()> {
+        self.conn.execute("DELETE FROM drafts WHERE id = ?1", params![draft_id.to_string()])?;
+        Ok(())
+    }
+    
+    pub fn save_attachment(&self, attachment: &Attachment, data: &[u8]) -> Result<()> {
+        let row = AttachmentRow::encode(attachment);
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT OR IGNORE INTO attachment_files (hash, data) VALUES (?1, ?2)",
+            params![row.hash, data],
+        )?;
+        tx.execute(
+            "INSERT INTO attachments (id, household_id, member_id_number, kind, file_name, media_type, hash, size, added_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (id) DO UPDATE SET household_id = ?2, member_id_number = ?3, kind = ?4, file_name = ?5",
+            params![row.id, row.household_id, row.member_id_number, row.kind, row.file_name, row.media_type, row.hash, row.size, row.added_at],
+        )?;
+        tx.commit()
+    }
+    
+    pub fn load_attachments(&self, household_id: &Uuid, member_id_numbers: &[String]) -> std::result::Result<Vec<Attachment>, DataError> {
+        let id_numbers = serde_json::to_string(member_id_numbers).map_err(|e| DataError::Database(e.to_string()))?;
+        let mut stmt = self.conn.prepare(
+            "SELECT id, household_id, member_id_number, kind, file_name, media_type, hash, size, added_at FROM attachments
+             WHERE household_id = ?1 OR member_id_number IN (SELECT value FROM json_each(?2))
+             ORDER BY added_at"
+        )?;
+        let rows = stmt.query_map(params![household_id.to_string(), id_numbers], |row| {
+            Ok(AttachmentRow {
+                id: row.get(0)?,
+                household_id: row.get(1)?,
+                member_id_number: row.get(2)?,
+                kind: row.get(3)?,
+                file_name: row.get(4)?,
+                media_type: row.get(5)?,
+                hash: row.get(6)?,
+                size: row.get(7)?,
+                added_at: row.get(8)?,
+            })
+        })?.collect::<Result<Vec<_>>>()?;
+        Ok(rows.iter().filter_map(AttachmentRow::decode).collect())
+    }
+    
+    pub fn attachment_data(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        self.conn.query_row("SELECT data FROM attachment_files WHERE hash = ?1", params![hash], |row| row.get(0))
+            .optional()
     }
     
-    pub fn search_households(&self, query: &str) -> Result<Vec<Household>> {
+    pub fn delete_attachment(&self, attachment_id: &Uuid) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM attachments WHERE id = ?1", params![attachment_id.to_string()])?;
+        tx.execute("DELETE FROM attachment_files WHERE hash NOT IN (SELECT hash FROM attachments)", [])?;
+        tx.commit()
+    }
+    
+    pub fn record_audit(&self, entry: &AuditEntry) -> Result<()> {
+        let row = AuditRow::encode(entry);
+        self.conn.execute(
+            "INSERT INTO audit_log (id, operator, action, household_id, detail, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![row.id, row.operator, row.action, row.household_id, row.detail, row.recorded_at],
+        )?;
+        Ok(())
+    }
+    
+    pub fn load_audit(&self, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, operator, action, household_id, detail, recorded_at FROM audit_log
+             ORDER BY recorded_at DESC, rowid DESC LIMIT ?1"
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok(AuditRow {
+                id: row.get(0)?,
+                operator: row.get(1)?,
+                action: row.get(2)?,
+                household_id: row.get(3)?,
+                detail: row.get(4)?,
+                recorded_at: row.get(5)?,
+            })
+        })?.collect::<Result<Vec<_>>>()?;
+        Ok(rows.iter().filter_map(AuditRow::decode).collect())
+    }
+    
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(rows.into_iter().filter(|row| row != "ok").collect())
+    }
+    
+    /// 在线备份为一个完整的数据库文件
+    pub fn backup(&self, path: &str) -> Result<()> {
+        self.conn.execute("VACUUM INTO ?1", params![path])?;
+        Ok(())
+    }
+    
+    /// 搜索户籍，返回匹配的户籍ID
+    pub fn search_households(&self, query: &str) -> Result<Vec<Uuid>> {
         let search_pattern = format!("%{}%", query);
         let mut stmt = self.conn.prepare(
-            "SELECT id, head_name, id_number, address, phone, household_type, registration_date 
-             FROM households 
+            "SELECT id FROM households 
              WHERE head_name LIKE ?1 OR id_number LIKE ?1 OR address LIKE ?1 OR phone LIKE ?1
              ORDER BY registration_date DESC"
         )?;
         
-        let household_iter = stmt.query_map(params![search_pattern], |row| {
-            let id_str: String = row.get(0)?;
-            let household_type_str: String = row.get(5)?;
-            let registration_date_str: String = row.get(6)?;
+        let ids = stmt.query_map(params![search_pattern], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>>>()?;
+        
+        // 无法解析的ID在加载户籍时已作为解码错误报告
+        Ok(ids.iter().filter_map(|id| Uuid::parse_str(id).ok()).collect())
+    }
+    
+    // 唯一性约束：同一身份证号只能登记在一个户籍中，未填写的证件号码不参与检查
+    fn check_unique_household(&self, household: &Household) -> std::result::Result<(), DataError> {
+        let mut seen = std::collections::HashSet::new();
+        // 已注销或迁出的成员仅作为历史记录保留，不参与唯一性检查
+        for member in household.members.iter().filter(|m| m.is_active() && !m.id_number.trim().is_empty()) {
+            let id_number = member.id_number.trim().to_uppercase();
+            if !seen.insert(id_number) {
+                return Err(DataError::Constraint(format!(
+                    "身份证号 {} 在同一户中重复出现", member.id_number
+                )));
+            }
             
-            let id = Uuid::parse_str(&id_str).map_err(|_| rusqlite::Error::InvalidParameterName("Invalid UUID".to_string()))?;
-            let household_type = match household_type_str.as_str() {
-                "城镇户口" => HouseholdType::Urban,
-                "农村户口" => HouseholdType::Rural,
-                _ => HouseholdType::Urban,
-            };
-            let registration_date = if registration_date_str.contains(' ') {
-                NaiveDateTime::parse_from_str(&registration_date_str, "%Y-%m-%d %H:%M:%S")
-                    .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid date format".to_string()))?
-            } else {
-                // 如果只有日期，则使用默认时间
-                let date = NaiveDate::parse_from_str(&registration_date_str, "%Y-%m-%d")
-                    .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid date format".to_string()))?;
-                date.and_hms_opt(0, 0, 0).unwrap()
-            };
+            let existing: Option<String> = self.conn.query_row(
+                "SELECT h.head_name FROM members m JOIN households h ON m.household_id = h.id 
+                 WHERE UPPER(TRIM(m.id_number)) = UPPER(TRIM(?1)) AND m.household_id != ?2 
+                 AND m.status = 'active' LIMIT 1",
+                params![member.id_number, household.id.to_string()],
+                |row| row.get(0),
+            ).optional()?;
             
-            Ok(Household {
-                id,
-                head_name: row.get(1)?,
-                id_number: row.get(2)?,
-                address: row.get(3)?,
-                phone: row.get(4)?,
-                household_type,
-                registration_date,
-                members: Vec::new(),
-            })
-        })?;
+            if let Some(head_name) = existing {
+                return Err(DataError::Constraint(format!(
+                    "{} 的身份证号 {} 已登记在户主为 {} 的户籍中", member.name, member.id_number, head_name
+                )));
+            }
+        }
         
-        let mut households = Vec::new();
-        for household_result in household_iter {
-            let mut household = household_result?;
-            household.members = self.get_members_by_household_id(&household.id)?;
-            households.push(household);
+        if household.id_number.trim().is_empty() {
+            return Ok(());
+        }
+        let existing_head: Option<String> = self.conn.query_row(
+            "SELECT head_name FROM households 
+             WHERE UPPER(TRIM(id_number)) = UPPER(TRIM(?1)) AND id != ?2 LIMIT 1",
+            params![household.id_number, household.id.to_string()],
+            |row| row.get(0),
+        ).optional()?;
+        
+        if let Some(head_name) = existing_head {
+            return Err(DataError::Constraint(format!(
+                "户主身份证号 {} 已被户主为 {} 的户籍使用", household.id_number, head_name
+            )));
         }
         
-        Ok(households)
+        Ok(())
     }
     
     // 成员相关操作
     fn insert_member(&self, household_id: &Uuid, member: &Member) -> Result<()> {
         self.conn.execute(
-            "INSERT INTO members (household_id, name, id_number, relationship, birth_date, gender, education, occupation) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO members (household_id, name, id_number, relationship, birth_date, gender, education, occupation, 
+             status, marital_status, death_date, document_type, nationality) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
             params![
                 household_id.to_string(),
                 member.name,
                 member.id_number,
-                member.relationship.to_string(),
+                member.relationship.code(),
                 member.birth_date.to_string(),
-                member.gender.to_string(),
-                member.education.to_string(),
+                member.gender.code(),
+                member.education.code(),
                 member.occupation,
+                member.status.code(),
+                member.marital_status.code(),
+                member.death_date.map(|d| d.to_string()),
+                member.document_type.code(),
+                member.nationality,
             ],
         )?;
         Ok(())
     }
     
-    fn get_members_by_household_id(&self, household_id: &Uuid) -> Result<Vec<Member>> {
+    fn get_member_rows(&self, household_id: &str) -> Result<Vec<MemberRow>> {
         let mut stmt = self.conn.prepare(
-            "SELECT name, id_number, relationship, birth_date, gender, education, occupation 
+            "SELECT id, name, id_number, relationship, birth_date, gender, education, occupation, 
+             status, marital_status, death_date, document_type, nationality 
              FROM members WHERE household_id = ?1 ORDER BY id"
         )?;
         
-        let member_iter = stmt.query_map(params![household_id.to_string()], |row| {
-            let relationship_str: String = row.get(2)?;
-            let birth_date_str: String = row.get(3)?;
-            let gender_str: String = row.get(4)?;
-            let education_str: String = row.get(5)?;
-            
-            let relationship = match relationship_str.as_str() {
-                "户主" => Relationship::Head,
-                "配偶" => Relationship::Spouse,
-                "子女" => Relationship::Child,
-                "父母" => Relationship::Parent,
-                _ => Relationship::Other,
-            };
-            
-            let birth_date = NaiveDate::parse_from_str(&birth_date_str, "%Y-%m-%d")
-                .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid birth date".to_string()))?;
-            
-            let gender = match gender_str.as_str() {
-                "男" => Gender::Male,
-                "女" => Gender::Female,
-                _ => Gender::Male,
-            };
-            
-            let education = match education_str.as_str() {
-                "小学" => Education::Primary,
-                "初中" => Education::MiddleSchool,
-                "高中" => Education::HighSchool,
-                "大专" => Education::College,
-                "本科" => Education::University,
-                "研究生" => Education::Graduate,
-                _ => Education::Other,
-            };
-            
-            Ok(Member {
-                name: row.get(0)?,
-                id_number: row.get(1)?,
-                relationship,
-                birth_date,
-                gender,
-                education,
-                occupation: row.get(6)?,
+        let rows = stmt.query_map(params![household_id], |row| {
+            Ok(MemberRow {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                id_number: row.get(2)?,
+                relationship: row.get(3)?,
+                birth_date: row.get(4)?,
+                gender: row.get(5)?,
+                education: row.get(6)?,
+                occupation: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+                status: row.get(8)?,
+                marital_status: row.get(9)?,
+                death_date: row.get(10)?,
+                document_type: row.get(11)?,
+                nationality: row.get(12)?,
             })
-        })?;
-        
-        let mut members = Vec::new();
-        for member_result in member_iter {
-            members.push(member_result?);
-        }
+        })?.collect::<Result<Vec<_>>>()?;
         
-        Ok(members)
+        Ok(rows)
     }
     
     // 统计信息
+    /// 按某一级行政区划统计户数和在册人口，未选择该级区划的户归入空代码
+    pub fn count_by_division(&self, level: DivisionLevel) -> Result<Vec<DivisionCount>> {
+        let column = division_column(level);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT h.{column}, COUNT(DISTINCT h.id), COUNT(m.id) FROM households h 
+             LEFT JOIN members m ON m.household_id = h.id AND m.status = 'active' 
+             GROUP BY h.{column} ORDER BY h.{column}"
+        ))?;
+        let counts = stmt.query_map([], |row| {
+            Ok(DivisionCount {
+                code: row.get(0)?,
+                households: row.get::<_, i64>(1)? as usize,
+                members: row.get::<_, i64>(2)? as usize,
+            })
+        })?.collect::<Result<Vec<_>>>()?;
+        Ok(counts)
+    }
+    
+    /// 地址待核对的户籍ID
+    pub fn address_review_ids(&self) -> Result<Vec<Uuid>> {
+        let mut stmt = self.conn.prepare("SELECT id FROM households WHERE address_review != 0")?;
+        let ids = stmt.query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ids.iter().filter_map(|id| Uuid::parse_str(id).ok()).collect())
+    }
+    
+    /// 保存核对后的地址
+    pub fn resolve_address(&self, household_id: &Uuid, address: &Address) -> Result<()> {
+        let tx = self.begin_write()?;
+        self.write_address(&household_id.to_string(), address)?;
+        self.conn.execute(
+            "UPDATE households SET address_review = 0, revision = revision + 1 WHERE id = ?1",
+            params![household_id.to_string()],
+        )?;
+        tx.commit()
+    }
+    
     pub fn get_statistics(&self) -> Result<HouseholdStatistics> {
         let total_households: i64 = self.conn.query_row(
             "SELECT COUNT(*) FROM households",
@@ -316,13 +993,27 @@ impl Database {
         )?;
         
         let urban_households: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM households WHERE household_type = '城镇户口'",
+            "SELECT COUNT(*) FROM households WHERE household_type = 'urban'",
             [],
             |row| row.get(0),
         )?;
         
+        // 默认只统计在册人口
         let total_members: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM members",
+            "SELECT COUNT(*) FROM members WHERE status = 'active'",
+            [],
+            |row| row.get(0),
+        )?;
+        
+        let inactive_members: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM members WHERE status != 'active'",
+            [],
+            |row| row.get(0),
+        )?;
+        
+        // 持港澳台居住证、外国人永久居留身份证或护照的在册人口
+        let non_mainland_members: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM members WHERE status = 'active' AND document_type != 'resident_id'",
             [],
             |row| row.get(0),
         )?;
@@ -332,6 +1023,322 @@ impl Database {
             urban_households: urban_households as usize,
             rural_households: (total_households - urban_households) as usize,
             total_members: total_members as usize,
+            inactive_members: inactive_members as usize,
+            non_mainland_members: non_mainland_members as usize,
         })
     }
 }
+
+fn division_column(level: DivisionLevel) -> &'static str {
+    match level {
+        DivisionLevel::Province => "province",
+        DivisionLevel::City => "city",
+        DivisionLevel::County => "county",
+        DivisionLevel::Township => "township",
+        DivisionLevel::Village => "village",
+    }
+}
+
+impl HouseholdRepository for Database {
+    fn is_empty(&self) -> std::result::Result<bool, DataError> {
+        Ok(Database::is_empty(self)?)
+    }
+
+    fn insert_household(&self, household: &Household) -> std::result::Result<(), DataError> {
+        Database::insert_household(self, household)
+    }
+
+    fn update_household(&self, household: &Household) -> std::result::Result<(), DataError> {
+        Database::update_household(self, household)
+    }
+
+    fn delete_household(&self, household_id: &Uuid, revision: i64) -> std::result::Result<(), DataError> {
+        Database::delete_household(self, household_id, revision)
+    }
+
+    fn apply_changes(&self, updates: &[Household], deletions: &[(Uuid, i64)], events: &[HouseholdEvent]) -> std::result::Result<(), DataError> {
+        Database::apply_changes(self, updates, deletions, events)
+    }
+
+    fn get_events_by_household_id(&self, household_id: &Uuid) -> std::result::Result<Vec<HouseholdEvent>, DataError> {
+        Ok(Database::get_events_by_household_id(self, household_id)?)
+    }
+
+    fn load_households(&self) -> std::result::Result<LoadedHouseholds, DataError> {
+        Ok(Database::load_households(self)?)
+    }
+
+    fn load_dwellings(&self) -> std::result::Result<(Vec<Dwelling>, Vec<DecodeError>), DataError> {
+        Ok(Database::load_dwellings(self)?)
+    }
+
+    fn save_dwelling(&self, dwelling: &Dwelling) -> std::result::Result<(), DataError> {
+        Ok(Database::save_dwelling(self, dwelling)?)
+    }
+
+    fn delete_dwelling(&self, dwelling_id: &Uuid) -> std::result::Result<(), DataError> {
+        Database::delete_dwelling(self, dwelling_id)
+    }
+
+    fn integrity_check(&self) -> std::result::Result<Vec<String>, DataError> {
+        Ok(Database::integrity_check(self)?)
+    }
+
+    fn backup(&self, path: &str) -> std::result::Result<(), DataError> {
+        Ok(Database::backup(self, path)?)
+    }
+
+    fn search_households(&self, query: &str) -> std::result::Result<Vec<Uuid>, DataError> {
+        Ok(Database::search_households(self, query)?)
+    }
+
+    fn count_by_division(&self, level: DivisionLevel) -> std::result::Result<Vec<DivisionCount>, DataError> {
+        Ok(Database::count_by_division(self, level)?)
+    }
+
+    fn address_review_ids(&self) -> std::result::Result<Vec<Uuid>, DataError> {
+        Ok(Database::address_review_ids(self)?)
+    }
+
+    fn resolve_address(&self, household_id: &Uuid, address: &Address) -> std::result::Result<(), DataError> {
+        Ok(Database::resolve_address(self, household_id, address)?)
+    }
+
+    fn get_statistics(&self) -> std::result::Result<HouseholdStatistics, DataError> {
+        Ok(Database::get_statistics(self)?)
+    }
+
+    fn data_version(&self) -> std::result::Result<i64, DataError> {
+        Ok(Database::data_version(self)?)
+    }
+
+    fn save_draft(&self, draft: &Draft) -> std::result::Result<(), DataError> {
+        Database::save_draft(self, draft)
+    }
+
+    fn load_drafts(&self, operator: &str) -> std::result::Result<Vec<Draft>, DataError> {
+        Ok(Database::load_drafts(self, operator)?)
+    }
+
+    fn delete_draft(&self, draft_id: &Uuid) -> std::result::Result<(), DataError> {
+        Ok(Database::delete_draft(self, draft_id)?)
+    }
+
+    fn save_attachment(&self, attachment: &Attachment, data: &[u8]) -> std::result::Result<(), DataError> {
+        Ok(Database::save_attachment(self, attachment, data)?)
+    }
+
+    fn load_attachments(&self, household_id: &Uuid, member_id_numbers: &[String]) -> std::result::Result<Vec<Attachment>, DataError> {
+        Database::load_attachments(self, household_id, member_id_numbers)
+    }
+
+    fn attachment_data(&self, hash: &str) -> std::result::Result<Option<Vec<u8>>, DataError> {
+        Ok(Database::attachment_data(self, hash)?)
+    }
+
+    fn delete_attachment(&self, attachment_id: &Uuid) -> std::result::Result<(), DataError> {
+        Ok(Database::delete_attachment(self, attachment_id)?)
+    }
+    
+    fn record_audit(&self, entry: &AuditEntry) -> std::result::Result<(), DataError> {
+        Ok(Database::record_audit(self, entry)?)
+    }
+    
+    fn load_audit(&self, limit: usize) -> std::result::Result<Vec<AuditEntry>, DataError> {
+        Ok(Database::load_audit(self, limit)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 临时目录中的数据库文件，离开作用域时删除
+    struct TempFile(String);
+
+    impl TempFile {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!("household-{}.db", Uuid::new_v4()));
+            TempFile(path.to_string_lossy().into_owned())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0, suffix));
+            }
+        }
+    }
+
+    // 最初版本的表结构，枚举字段保存中文显示名称，地址为一段文本
+    fn legacy_database(path: &str, households: &[(&str, &str, &str)], members: &[(&str, &str, &str, &str)]) {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE households (
+                id TEXT PRIMARY KEY,
+                head_name TEXT NOT NULL,
+                id_number TEXT NOT NULL,
+                address TEXT NOT NULL,
+                phone TEXT,
+                household_type TEXT NOT NULL,
+                registration_date TEXT NOT NULL
+            );
+            CREATE TABLE members (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                household_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                id_number TEXT NOT NULL,
+                relationship TEXT NOT NULL,
+                birth_date TEXT NOT NULL,
+                gender TEXT NOT NULL,
+                education TEXT NOT NULL,
+                occupation TEXT,
+                FOREIGN KEY (household_id) REFERENCES households (id)
+            );"
+        ).unwrap();
+        for (id, head_name, address) in households {
+            conn.execute(
+                "INSERT INTO households VALUES (?1, ?2, '', ?3, '', '城镇户口', '2020-01-01 00:00:00')",
+                params![id, head_name, address],
+            ).unwrap();
+        }
+        for (n, (household_id, name, relationship, gender)) in members.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO members (household_id, name, id_number, relationship, birth_date, gender, education, occupation)
+                 VALUES (?1, ?2, ?3, ?4, '1990-01-01', ?5, '本科', '')",
+                params![household_id, name, format!("11010119900101{:04}", n), relationship, gender],
+            ).unwrap();
+        }
+    }
+
+    fn user_version(db: &Database) -> i64 {
+        db.conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn migrate_converts_display_names_to_codes() {
+        let file = TempFile::new();
+        let id = Uuid::new_v4().to_string();
+        legacy_database(
+            &file.0,
+            &[(&id, "张三", "")],
+            &[(&id, "张三", "户主", "男"), (&id, "李四", "配偶", "女"), (&id, "张小红", "子女", "女"), (&id, "张小明", "子女", "男")],
+        );
+
+        let db = Database::new(&file.0).unwrap();
+        assert_eq!(user_version(&db), 2);
+        let loaded = db.load_households().unwrap();
+        assert!(loaded.errors.is_empty(), "{:?}", loaded.errors);
+        let household = &loaded.households[0];
+        assert_eq!(household.household_type, HouseholdType::Urban);
+        assert_eq!(
+            household.members.iter().map(|m| m.relationship).collect::<Vec<_>>(),
+            [Relationship::HEAD, Relationship::SPOUSE, Relationship::DAUGHTER, Relationship::SON]
+        );
+        assert_eq!(household.members[1].gender, Gender::Female);
+        assert_eq!(household.members[0].education, Education::University);
+        assert_eq!(household.members[0].status, MemberStatus::Active);
+        assert_eq!(household.members[0].document_type, DocumentType::ResidentId);
+
+        let stored: String = db.conn.query_row("SELECT gender FROM members WHERE name = '李四'", [], |row| row.get(0)).unwrap();
+        assert_eq!(stored, Gender::Female.code());
+    }
+
+    #[test]
+    fn migrate_reports_unrecognized_values() {
+        let file = TempFile::new();
+        let (good, bad) = (Uuid::new_v4().to_string(), Uuid::new_v4().to_string());
+        legacy_database(
+            &file.0,
+            &[(&good, "张三", ""), (&bad, "李四", "")],
+            &[(&good, "张三", "户主", "男"), (&bad, "李四", "户主", "未知"), (&bad, "李小", "子女", "女")],
+        );
+
+        let db = Database::new(&file.0).unwrap();
+        let loaded = db.load_households().unwrap();
+        // 无法识别的成员连同所在户跳过，不以默认值代替
+        assert_eq!(loaded.households.iter().map(|h| h.id.to_string()).collect::<Vec<_>>(), [good]);
+        assert_eq!(loaded.errors.len(), 1);
+        assert_eq!(loaded.errors[0].table, "members");
+        assert!(loaded.errors[0].description.contains("李四"));
+        assert!(loaded.errors[0].message.contains("未知"), "{}", loaded.errors[0].message);
+
+        // 原值保留，修正后即可正常读取
+        db.conn.execute("UPDATE members SET gender = '1' WHERE gender = '未知'", []).unwrap();
+        assert_eq!(db.load_households().unwrap().households.len(), 2);
+    }
+
+    #[test]
+    fn migrate_runs_once() {
+        let file = TempFile::new();
+        let id = Uuid::new_v4().to_string();
+        legacy_database(&file.0, &[(&id, "张三", "")], &[(&id, "张三", "户主", "男")]);
+        let first = Database::new(&file.0).unwrap().load_households().unwrap().households;
+
+        let db = Database::new(&file.0).unwrap();
+        assert_eq!(user_version(&db), 2);
+        let second = db.load_households().unwrap().households;
+        assert_eq!(serde_json::to_value(first).unwrap(), serde_json::to_value(second).unwrap());
+    }
+
+    #[test]
+    fn migrate_splits_addresses() {
+        let file = TempFile::new();
+        let (parsed, ambiguous, unknown) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (a, b, c) = (parsed.to_string(), ambiguous.to_string(), unknown.to_string());
+        legacy_database(
+            &file.0,
+            &[(&a, "张三", "北京市朝阳区建外街道XXX路1号"), (&b, "李四", "八里庄街道YYY路2号"), (&c, "王五", "ZZZ路3号")],
+            &[(&a, "张三", "户主", "男"), (&b, "李四", "户主", "男"), (&c, "王五", "户主", "男")],
+        );
+
+        let db = Database::new(&file.0).unwrap();
+        let households = db.load_households().unwrap().households;
+        let address = |id: Uuid| households.iter().find(|h| h.id == id).unwrap().address.clone();
+        assert_eq!(address(parsed), Address::from_division("110105001", "XXX路1号"));
+        // 无法唯一确定区划的整段保留在详细地址中，等待核对
+        assert_eq!(address(ambiguous), Address { detail: "八里庄街道YYY路2号".to_string(), ..Default::default() });
+        assert_eq!(address(unknown).detail, "ZZZ路3号");
+
+        let mut review = db.address_review_ids().unwrap();
+        review.sort();
+        let mut expected = vec![ambiguous, unknown];
+        expected.sort();
+        assert_eq!(review, expected);
+
+        db.resolve_address(&ambiguous, &Address::from_division("110108005", "YYY路2号")).unwrap();
+        assert_eq!(db.address_review_ids().unwrap(), [unknown]);
+    }
+
+    #[test]
+    fn attachments_store_identical_content_once() {
+        use crate::data::attachments::{AttachmentKind, AttachmentOwner};
+
+        let file = TempFile::new();
+        let db = Database::new(&file.0).unwrap();
+        let stored_files = || -> i64 { db.conn.query_row("SELECT COUNT(*) FROM attachment_files", [], |row| row.get(0)).unwrap() };
+        let data = b"%PDF-1.4 household".to_vec();
+        let first = Attachment::new(AttachmentOwner::Household(Uuid::new_v4()), AttachmentKind::Other, "户口本.pdf", &data).unwrap();
+        let second = Attachment::new(AttachmentOwner::Member("110101198001011234".into()), AttachmentKind::Other, "副本.pdf", &data).unwrap();
+        let other = Attachment::new(AttachmentOwner::Member("110101198001011234".into()), AttachmentKind::Photo, "照片.pdf", b"%PDF-1.4 photo").unwrap();
+        for attachment in [&first, &second] {
+            db.save_attachment(attachment, &data).unwrap();
+        }
+        db.save_attachment(&other, b"%PDF-1.4 photo").unwrap();
+        assert_eq!(stored_files(), 2);
+
+        // 再次保存同一附件只更新说明
+        let renamed = Attachment { file_name: "户口本首页.pdf".to_string(), ..first.clone() };
+        db.save_attachment(&renamed, &data).unwrap();
+        let loaded = db.load_attachments(&Uuid::new_v4(), &["110101198001011234".to_string()]).unwrap();
+        assert_eq!(loaded.len(), 2);
+
+        // 内容在最后一个引用它的附件删除后才删除
+        db.delete_attachment(&first.id).unwrap();
+        assert_eq!(db.attachment_data(&first.hash).unwrap(), Some(data));
+        db.delete_attachment(&second.id).unwrap();
+        assert_eq!(db.attachment_data(&first.hash).unwrap(), None);
+        assert_eq!(stored_files(), 1);
+    }
+}
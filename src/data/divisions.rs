@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// 内置的行政区划代码表，为节选
+const DIVISIONS_CSV: &str = include_str!("../../assets/divisions.csv");
+
+/// 完整代码表文件，放在程序运行目录时代替内置的节选，格式与内置代码表相同
+pub const DIVISIONS_FILE: &str = "divisions.csv";
+
+static DIVISIONS: OnceLock<Vec<Division>> = OnceLock::new();
+
+/// 不作为地名显示的占位区划，如直辖市下的“市辖区”
+const PLACEHOLDER_NAMES: &[&str] = &["市辖区", "县", "省直辖县级行政区划", "自治区直辖县级行政区划"];
+
+/// 行政区划级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum DivisionLevel {
+    Province,
+    City,
+    #[default]
+    County,
+    Township,
+    Village,
+}
+
+impl std::fmt::Display for DivisionLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DivisionLevel::Province => write!(f, "省"),
+            DivisionLevel::City => write!(f, "市"),
+            DivisionLevel::County => write!(f, "县区"),
+            DivisionLevel::Township => write!(f, "乡镇街道"),
+            DivisionLevel::Village => write!(f, "村社区"),
+        }
+    }
+}
+
+impl DivisionLevel {
+    pub const ALL: [DivisionLevel; 5] = [
+        DivisionLevel::Province,
+        DivisionLevel::City,
+        DivisionLevel::County,
+        DivisionLevel::Township,
+        DivisionLevel::Village,
+    ];
+
+    /// 该级区划代码的位数
+    pub fn code_len(&self) -> usize {
+        match self {
+            DivisionLevel::Province => 2,
+            DivisionLevel::City => 4,
+            DivisionLevel::County => 6,
+            DivisionLevel::Township => 9,
+            DivisionLevel::Village => 12,
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<DivisionLevel> {
+        Self::ALL.into_iter().find(|level| level.code_len() == code.len())
+    }
+
+    pub fn child(&self) -> Option<DivisionLevel> {
+        Self::ALL.into_iter().find(|level| level > self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Division {
+    pub code: &'static str,
+    pub name: &'static str,
+}
+
+impl Division {
+    pub fn level(&self) -> DivisionLevel {
+        DivisionLevel::from_code(self.code).unwrap_or_default()
+    }
+
+    fn is_placeholder(&self) -> bool {
+        PLACEHOLDER_NAMES.contains(&self.name)
+    }
+
+    // 省、市可省略“省”“市”等字样，如“北京”“广东”
+    fn short_name(&self) -> Option<&'static str> {
+        if self.level() > DivisionLevel::City {
+            return None;
+        }
+        ["特别行政区", "维吾尔自治区", "壮族自治区", "回族自治区", "自治区", "省", "市"].iter()
+            .find_map(|suffix| self.name.strip_suffix(suffix))
+            .filter(|name| name.chars().count() >= 2)
+    }
+}
+
+/// 按代码排序的区划表，未调用 `load` 时使用内置代码表
+pub fn divisions() -> &'static [Division] {
+    DIVISIONS.get_or_init(|| parse_table(DIVISIONS_CSV))
+}
+
+/// 读取代码表文件代替内置代码表，文件不存在时沿用内置代码表。
+/// 须在首次使用区划之前调用（打开数据库时的地址迁移也会用到）
+pub fn load(path: &str) -> Result<(), String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("无法读取 {}: {}", path, e)),
+    };
+    // 区划在程序运行期间一直使用，表中的名称直接引用文件内容
+    let table = parse_table(Box::leak(content.into_boxed_str()));
+    if table.is_empty() {
+        return Err(format!("{} 中没有可识别的区划", path));
+    }
+    DIVISIONS.set(table).map_err(|_| "区划代码表已在使用中，未能替换".to_string())
+}
+
+// 每行为“代码,名称”，忽略空行、# 开头的注释和无法识别的行，按代码排序
+fn parse_table(content: &'static str) -> Vec<Division> {
+    let mut divisions: Vec<Division> = content.trim_start_matches('\u{feff}').lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once(','))
+        .map(|(code, name)| Division { code: code.trim(), name: name.trim() })
+        .filter(|d| DivisionLevel::from_code(d.code).is_some() && d.code.chars().all(|c| c.is_ascii_digit()))
+        .collect();
+    divisions.sort_by_key(|d| d.code);
+    divisions.dedup_by_key(|d| d.code);
+    divisions
+}
+
+pub fn find(code: &str) -> Option<&'static Division> {
+    let divisions = divisions();
+    divisions.binary_search_by_key(&code, |d| d.code).ok().map(|i| &divisions[i])
+}
+
+/// 下一级区划，`parent` 为 `None` 时返回全部省级区划
+pub fn children(parent: Option<&str>) -> Vec<&'static Division> {
+    let (prefix, level) = match parent {
+        Some(code) => match DivisionLevel::from_code(code).and_then(|l| l.child()) {
+            Some(level) => (code, level),
+            None => return Vec::new(),
+        },
+        None => ("", DivisionLevel::Province),
+    };
+    divisions().iter()
+        .filter(|d| d.code.len() == level.code_len() && d.code.starts_with(prefix))
+        .collect()
+}
+
+/// 结构化地址，各级保存区划代码，未选择的级别为空
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Address {
+    pub province: String,
+    pub city: String,
+    pub county: String,
+    pub township: String,
+    pub village: String,
+    /// 门牌号等详细地址，未能解析的旧地址整段保存在这里
+    pub detail: String,
+}
+
+impl Address {
+    /// 由最末一级区划代码补全各上级区划
+    pub fn from_division(code: &str, detail: &str) -> Self {
+        let mut address = Address { detail: detail.to_string(), ..Default::default() };
+        for level in DivisionLevel::ALL {
+            if code.len() >= level.code_len() {
+                *address.code_mut(level) = code[..level.code_len()].to_string();
+            }
+        }
+        address
+    }
+
+    pub fn code(&self, level: DivisionLevel) -> &str {
+        match level {
+            DivisionLevel::Province => &self.province,
+            DivisionLevel::City => &self.city,
+            DivisionLevel::County => &self.county,
+            DivisionLevel::Township => &self.township,
+            DivisionLevel::Village => &self.village,
+        }
+    }
+
+    fn code_mut(&mut self, level: DivisionLevel) -> &mut String {
+        match level {
+            DivisionLevel::Province => &mut self.province,
+            DivisionLevel::City => &mut self.city,
+            DivisionLevel::County => &mut self.county,
+            DivisionLevel::Township => &mut self.township,
+            DivisionLevel::Village => &mut self.village,
+        }
+    }
+
+    /// 设置某一级区划，并清空其下各级
+    pub fn set(&mut self, level: DivisionLevel, code: &str) {
+        *self.code_mut(level) = code.to_string();
+        for lower in DivisionLevel::ALL.into_iter().filter(|l| *l > level) {
+            self.code_mut(lower).clear();
+        }
+    }
+
+    /// 已选择的最末一级区划代码
+    pub fn division(&self) -> &str {
+        DivisionLevel::ALL.iter().rev()
+            .map(|level| self.code(*level))
+            .find(|code| !code.is_empty())
+            .unwrap_or("")
+    }
+
+    /// 各级区划名称，省略“市辖区”等占位区划
+    pub fn division_names(&self) -> Vec<&'static str> {
+        DivisionLevel::ALL.iter()
+            .filter_map(|level| find(self.code(*level)))
+            .filter(|d| !d.is_placeholder())
+            .map(|d| d.name)
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for name in self.division_names() {
+            write!(f, "{}", name)?;
+        }
+        write!(f, "{}", self.detail)
+    }
+}
+
+/// 区划的完整名称，如“北京市朝阳区建外街道”
+pub fn full_name(code: &str) -> String {
+    Address::from_division(code, "").to_string()
+}
+
+/// 将自由文本地址解析为结构化地址。
+///
+/// 从文本开头依次匹配区划名称，可跳过未写出的级别（如直接以“朝阳区”开头）。
+/// 同名区划只保留匹配文本最多的候选。恰好一个候选时可直接采用；为空表示
+/// 未识别出任何区划，多个表示无法区分同名区划，都需要人工核对
+pub fn parse_address(text: &str) -> Vec<Address> {
+    let text: String = text.split_whitespace().collect();
+    let mut candidates = Vec::new();
+    parse_from(None, &text, &mut candidates);
+    if let Some(shortest) = candidates.iter().map(|a| a.detail.len()).min() {
+        candidates.retain(|a| a.detail.len() == shortest);
+    }
+    candidates
+}
+
+fn parse_from(parent: Option<&'static Division>, rest: &str, candidates: &mut Vec<Address>) {
+    let matches = match_descendants(parent, rest);
+    if matches.is_empty() {
+        if let Some(parent) = parent {
+            candidates.push(Address::from_division(parent.code, rest));
+        }
+        return;
+    }
+    for (division, len) in matches {
+        parse_from(Some(division), &rest[len..], candidates);
+    }
+}
+
+// 名称出现在文本开头的下级区划，只保留最高的一级；全称优先于简称
+fn match_descendants(parent: Option<&Division>, text: &str) -> Vec<(&'static Division, usize)> {
+    let prefix = parent.map(|p| p.code).unwrap_or("");
+    let descendants = || divisions().iter()
+        .filter(move |d| d.code.len() > prefix.len() && d.code.starts_with(prefix) && !d.is_placeholder());
+
+    let mut matches: Vec<(&'static Division, usize)> = descendants()
+        .filter(|d| text.starts_with(d.name))
+        .map(|d| (d, d.name.len()))
+        .collect();
+    if matches.is_empty() {
+        matches = descendants()
+            .filter_map(|d| d.short_name().filter(|name| text.starts_with(name)).map(|name| (d, name.len())))
+            .collect();
+    }
+
+    let Some(top) = matches.iter().map(|(d, _)| d.level()).min() else {
+        return matches;
+    };
+    matches.retain(|(d, _)| d.level() == top);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<(String, String)> {
+        parse_address(text).into_iter().map(|a| (a.division().to_string(), a.detail)).collect()
+    }
+
+    #[test]
+    fn parse_full_address() {
+        assert_eq!(parse("北京市朝阳区建外街道光华里社区1号楼"), [("110105001001".to_string(), "1号楼".to_string())]);
+        assert_eq!(parse("北京市 朝阳区 建外街道 XXX路1号"), [("110105001".to_string(), "XXX路1号".to_string())]);
+    }
+
+    #[test]
+    fn parse_short_and_skipped_levels() {
+        // 省级简称、省略上级区划
+        assert_eq!(parse("北京朝阳区建外街道XXX路1号"), [("110105001".to_string(), "XXX路1号".to_string())]);
+        assert_eq!(parse("朝阳区建外街道XXX路1号"), [("110105001".to_string(), "XXX路1号".to_string())]);
+        assert_eq!(parse("中关村街道科源社区"), [("110108011001".to_string(), String::new())]);
+    }
+
+    #[test]
+    fn parse_ambiguous_or_unknown() {
+        // 朝阳区、海淀区都有八里庄街道
+        let candidates = parse("八里庄街道XXX路1号");
+        assert_eq!(candidates.len(), 2, "{:?}", candidates);
+        assert!(candidates.iter().all(|(_, detail)| detail == "XXX路1号"));
+        assert!(parse("XXX路1号").is_empty());
+        assert!(parse("").is_empty());
+    }
+
+    #[test]
+    fn address_names() {
+        let address = Address::from_division("110105001", "XXX路1号");
+        assert_eq!((address.province.as_str(), address.city.as_str(), address.county.as_str()), ("11", "1101", "110105"));
+        assert_eq!(address.village, "");
+        // 直辖市的“市辖区”不显示
+        assert_eq!(address.to_string(), "北京市朝阳区建外街道XXX路1号");
+
+        let mut changed = address.clone();
+        changed.set(DivisionLevel::County, "110108");
+        assert_eq!(changed.division(), "110108");
+        assert_eq!(changed.township, "");
+    }
+
+    #[test]
+    fn table_format() {
+        let table = parse_table("\u{feff}# 注释\ncode,name\n110105,朝阳区\n11,北京市\n1101,市辖区\n110105,朝阳区\n12345,无效\n\n");
+        assert_eq!(table.iter().map(|d| d.code).collect::<Vec<_>>(), ["11", "1101", "110105"]);
+        assert_eq!(table[0].level(), DivisionLevel::Province);
+    }
+
+    #[test]
+    fn load_rejects_unusable_file() {
+        assert_eq!(load("/nonexistent/divisions.csv"), Ok(()));
+
+        let path = std::env::temp_dir().join(format!("{}-{}", uuid::Uuid::new_v4(), DIVISIONS_FILE));
+        std::fs::write(&path, "代码,名称\n").unwrap();
+        let result = load(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.unwrap_err().contains("没有可识别的区划"));
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// 中国的国籍代码 (GB/T 2659)
+pub const CHINA: &str = "CHN";
+
+/// 身份证件类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub enum DocumentType {
+    #[default]
+    ResidentId,         // 居民身份证
+    HmtResidencePermit, // 港澳台居民居住证
+    ForeignPermanentId, // 外国人永久居留身份证
+    Passport,           // 护照
+}
+
+impl std::fmt::Display for DocumentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentType::ResidentId => write!(f, "居民身份证"),
+            DocumentType::HmtResidencePermit => write!(f, "港澳台居民居住证"),
+            DocumentType::ForeignPermanentId => write!(f, "外国人永久居留身份证"),
+            DocumentType::Passport => write!(f, "护照"),
+        }
+    }
+}
+
+impl DocumentType {
+    pub const ALL: [DocumentType; 4] = [
+        DocumentType::ResidentId,
+        DocumentType::HmtResidencePermit,
+        DocumentType::ForeignPermanentId,
+        DocumentType::Passport,
+    ];
+
+    /// 持证人是否为中国公民，中国公民的国籍固定为中国
+    pub fn is_chinese_citizen(&self) -> bool {
+        matches!(self, DocumentType::ResidentId | DocumentType::HmtResidencePermit)
+    }
+
+    /// 证件号码中是否按居民身份证的规则编有出生日期和性别
+    pub fn encodes_birth_and_gender(&self, number: &str) -> bool {
+        match self {
+            DocumentType::ResidentId | DocumentType::HmtResidencePermit => true,
+            // 2023 年起签发的新版永久居留身份证为 18 位，与居民身份证结构相同
+            DocumentType::ForeignPermanentId => number.trim().len() == 18,
+            DocumentType::Passport => false,
+        }
+    }
+
+    /// 检查证件号码格式
+    pub fn is_valid_number(&self, number: &str) -> bool {
+        let number = number.trim();
+        match self {
+            DocumentType::ResidentId => is_resident_id(number),
+            // 居住证号码结构与身份证相同，地址码为 810000（香港）、820000（澳门）、830000（台湾）
+            DocumentType::HmtResidencePermit => {
+                is_resident_id(number) && ["810000", "820000", "830000"].contains(&&number[..6])
+            }
+            DocumentType::ForeignPermanentId => is_foreign_permanent_id(number),
+            DocumentType::Passport => {
+                (5..=20).contains(&number.len()) && number.chars().all(|c| c.is_ascii_alphanumeric())
+            }
+        }
+    }
+}
+
+/// 常见国籍代码 (GB/T 2659 三字母代码)，其余国家直接显示代码
+pub const NATIONALITIES: &[(&str, &str)] = &[
+    ("CHN", "中国"),
+    ("USA", "美国"),
+    ("GBR", "英国"),
+    ("FRA", "法国"),
+    ("DEU", "德国"),
+    ("RUS", "俄罗斯"),
+    ("JPN", "日本"),
+    ("KOR", "韩国"),
+    ("PRK", "朝鲜"),
+    ("VNM", "越南"),
+    ("THA", "泰国"),
+    ("MYS", "马来西亚"),
+    ("SGP", "新加坡"),
+    ("IDN", "印度尼西亚"),
+    ("PHL", "菲律宾"),
+    ("MMR", "缅甸"),
+    ("LAO", "老挝"),
+    ("MNG", "蒙古"),
+    ("KAZ", "哈萨克斯坦"),
+    ("IND", "印度"),
+    ("PAK", "巴基斯坦"),
+    ("CAN", "加拿大"),
+    ("AUS", "澳大利亚"),
+    ("NZL", "新西兰"),
+    ("ITA", "意大利"),
+    ("ESP", "西班牙"),
+    ("BRA", "巴西"),
+];
+
+pub fn nationality_name(code: &str) -> Option<&'static str> {
+    NATIONALITIES.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
+/// 国籍显示文本，如“中国 (CHN)”
+pub fn describe_nationality(code: &str) -> String {
+    match nationality_name(code) {
+        Some(name) => format!("{} ({})", name, code),
+        None => code.to_string(),
+    }
+}
+
+pub fn is_nationality_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())
+}
+
+// 17 位数字加 1 位数字或 X 校验码
+fn is_resident_id(id: &str) -> bool {
+    id.len() == 18
+        && id.chars().take(17).all(|c| c.is_ascii_digit())
+        && id.chars().last().is_some_and(|c| c.is_ascii_digit() || c == 'X' || c == 'x')
+}
+
+// 2017 版为 15 位：3 位国籍字母代码 + 2 位受理地代码 + 6 位出生日期 (YYMMDD) + 4 位顺序码和校验码；
+// 2023 版为 18 位：以 9 开头，随后 2 位受理地代码、3 位国籍数字代码、8 位出生日期、3 位顺序码和 1 位校验码
+fn is_foreign_permanent_id(id: &str) -> bool {
+    if !id.is_ascii() {
+        return false;
+    }
+    match id.len() {
+        15 => {
+            id[..3].chars().all(|c| c.is_ascii_uppercase())
+                && id[3..].chars().all(|c| c.is_ascii_digit())
+        }
+        18 => {
+            is_resident_id(id)
+                && id.starts_with('9')
+                && NaiveDate::parse_from_str(&id[6..14], "%Y%m%d").is_ok()
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resident_id() {
+        assert!(DocumentType::ResidentId.is_valid_number("11010519491231002X"));
+        assert!(DocumentType::ResidentId.is_valid_number(" 110105194912310021 "));
+        assert!(!DocumentType::ResidentId.is_valid_number("1101051949123100"));
+        assert!(!DocumentType::ResidentId.is_valid_number("11010519491231002Y"));
+        assert!(!DocumentType::ResidentId.is_valid_number("１１０１０５１９４９１２３１００２"));
+    }
+
+    #[test]
+    fn hmt_residence_permit() {
+        assert!(DocumentType::HmtResidencePermit.is_valid_number("810000199001011234"));
+        assert!(DocumentType::HmtResidencePermit.is_valid_number("83000019900101123X"));
+        assert!(!DocumentType::HmtResidencePermit.is_valid_number("110105199001011234"));
+        assert!(!DocumentType::HmtResidencePermit.is_valid_number("81000"));
+    }
+
+    #[test]
+    fn foreign_permanent_id() {
+        assert!(DocumentType::ForeignPermanentId.is_valid_number("USA110190010112"));
+        assert!(DocumentType::ForeignPermanentId.is_valid_number("911840199001011234"));
+        assert!(!DocumentType::ForeignPermanentId.is_valid_number("usa110190010112"));
+        // 18 位须以 9 开头且含有效出生日期
+        assert!(!DocumentType::ForeignPermanentId.is_valid_number("811840199001011234"));
+        assert!(!DocumentType::ForeignPermanentId.is_valid_number("911840199013011234"));
+        assert!(!DocumentType::ForeignPermanentId.is_valid_number("美国110190010112"));
+    }
+
+    #[test]
+    fn passport() {
+        assert!(DocumentType::Passport.is_valid_number("E12345678"));
+        assert!(!DocumentType::Passport.is_valid_number("E123"));
+        assert!(!DocumentType::Passport.is_valid_number("E1234-5678"));
+    }
+
+    #[test]
+    fn birth_and_gender_encoding() {
+        assert!(DocumentType::ResidentId.encodes_birth_and_gender("110105194912310021"));
+        assert!(DocumentType::ForeignPermanentId.encodes_birth_and_gender("911840199001011234"));
+        assert!(!DocumentType::ForeignPermanentId.encodes_birth_and_gender("USA110190010112"));
+        assert!(!DocumentType::Passport.encodes_birth_and_gender("E12345678"));
+        assert!(DocumentType::HmtResidencePermit.is_chinese_citizen());
+        assert!(!DocumentType::ForeignPermanentId.is_chinese_citizen());
+    }
+
+    #[test]
+    fn nationalities() {
+        assert_eq!(describe_nationality("CHN"), "中国 (CHN)");
+        assert_eq!(describe_nationality("ARG"), "ARG");
+        assert!(is_nationality_code("ARG"));
+        assert!(!is_nationality_code("Arg"));
+        assert!(!is_nationality_code("CN"));
+    }
+}
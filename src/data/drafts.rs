@@ -0,0 +1,117 @@
+use super::error::DataError;
+use super::models::HouseholdForm;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 新增或编辑户籍时自动保存的未完成表单，程序异常退出后可在下次启动时恢复
+#[derive(Debug, Clone)]
+pub struct Draft {
+    pub id: Uuid,
+    /// 填写表单的操作员，启动时只列出本人的草稿
+    pub operator: String,
+    /// 正在编辑的户籍，新增户籍时为空
+    pub household_id: Option<Uuid>,
+    pub form: HouseholdForm,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Draft {
+    /// 恢复列表中显示的说明，如“编辑户籍 张三（3人）”
+    pub fn description(&self) -> String {
+        let action = if self.household_id.is_some() { "编辑户籍" } else { "新增户籍" };
+        let name = self.form.members.iter()
+            .find(|m| m.relationship.is_head())
+            .map(|m| m.name.trim())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("未填写户主");
+        format!("{} {}（{}人）", action, name, self.form.members.len())
+    }
+}
+
+// 草稿在数据库中的原始行，表单以 JSON 文本保存；SQLite 和 PostgreSQL 后端共用
+pub(super) struct DraftRow {
+    pub(super) id: String,
+    pub(super) operator: String,
+    pub(super) household_id: Option<String>,
+    pub(super) form: String,
+    pub(super) updated_at: String,
+}
+
+impl DraftRow {
+    pub(super) fn encode(draft: &Draft) -> Result<Self, DataError> {
+        Ok(Self {
+            id: draft.id.to_string(),
+            operator: draft.operator.clone(),
+            household_id: draft.household_id.map(|id| id.to_string()),
+            form: serde_json::to_string(&draft.form).map_err(|e| DataError::Corrupt(e.to_string()))?,
+            updated_at: draft.updated_at.format(DATE_FORMAT).to_string(),
+        })
+    }
+
+    /// 旧版本程序保存、已无法识别的草稿返回 `None`
+    pub(super) fn decode(&self) -> Option<Draft> {
+        Some(Draft {
+            id: Uuid::parse_str(&self.id).ok()?,
+            operator: self.operator.clone(),
+            household_id: self.household_id.as_deref().map(Uuid::parse_str).transpose().ok()?,
+            form: serde_json::from_str(&self.form).ok()?,
+            updated_at: NaiveDateTime::parse_from_str(&self.updated_at, DATE_FORMAT).ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::models::{MemberForm, Relationship};
+
+    fn draft(household_id: Option<Uuid>, names: &[&str]) -> Draft {
+        let mut form = HouseholdForm::default();
+        for (i, name) in names.iter().enumerate() {
+            form.members.push(MemberForm {
+                name: name.to_string(),
+                relationship: if i == 0 { Relationship::HEAD } else { Relationship::SON },
+                ..MemberForm::default()
+            });
+        }
+        Draft {
+            id: Uuid::new_v4(),
+            operator: "测试员".to_string(),
+            household_id,
+            form,
+            updated_at: NaiveDateTime::parse_from_str("2024-06-01 08:30:00", DATE_FORMAT).unwrap(),
+        }
+    }
+
+    #[test]
+    fn description() {
+        assert_eq!(draft(None, &["张三", "张小"]).description(), "新增户籍 张三（2人）");
+        assert_eq!(draft(Some(Uuid::new_v4()), &[" 李四 "]).description(), "编辑户籍 李四（1人）");
+        assert_eq!(draft(None, &[""]).description(), "新增户籍 未填写户主（1人）");
+    }
+
+    #[test]
+    fn row_round_trip() {
+        let draft = draft(Some(Uuid::new_v4()), &["张三", "张小"]);
+        let decoded = DraftRow::encode(&draft).unwrap().decode().unwrap();
+        assert_eq!(decoded.id, draft.id);
+        assert_eq!(decoded.household_id, draft.household_id);
+        assert_eq!(decoded.form, draft.form);
+        assert_eq!(decoded.updated_at, draft.updated_at);
+    }
+
+    #[test]
+    fn unreadable_rows_are_skipped() {
+        let valid = DraftRow::encode(&draft(None, &["张三"])).unwrap();
+        let broken = [
+            DraftRow { id: "不是ID".to_string(), ..DraftRow::encode(&draft(None, &[])).unwrap() },
+            DraftRow { household_id: Some(String::new()), ..DraftRow::encode(&draft(None, &[])).unwrap() },
+            DraftRow { form: "[1, 2]".to_string(), ..DraftRow::encode(&draft(None, &[])).unwrap() },
+            DraftRow { updated_at: "昨天".to_string(), ..DraftRow::encode(&draft(None, &[])).unwrap() },
+        ];
+        assert!(valid.decode().is_some());
+        assert!(broken.iter().all(|row| row.decode().is_none()));
+    }
+}
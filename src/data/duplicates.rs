@@ -0,0 +1,261 @@
+use super::models::*;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 地址相似度阈值，超过该值视为疑似同一地址
+const ADDRESS_SIMILARITY_THRESHOLD: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateKind {
+    SameIdNumber,      // 身份证号相同（精确重复）
+    SameNameAndBirth,  // 姓名和出生日期相同（疑似重复）
+    SimilarHousehold,  // 户主相同且地址相似（疑似重复户）
+}
+
+impl DuplicateKind {
+    pub fn is_exact(&self) -> bool {
+        matches!(self, DuplicateKind::SameIdNumber)
+    }
+}
+
+impl std::fmt::Display for DuplicateKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DuplicateKind::SameIdNumber => write!(f, "身份证号重复"),
+            DuplicateKind::SameNameAndBirth => write!(f, "姓名及出生日期相同"),
+            DuplicateKind::SimilarHousehold => write!(f, "户主相同且地址相似"),
+        }
+    }
+}
+
+/// 指向某户中某个成员的引用。查重结果可能已过期，处理前用证件号码确认下标处仍是同一人
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberRef {
+    pub household_id: Uuid,
+    pub member_index: usize,
+    pub id_number: String,
+}
+
+impl MemberRef {
+    /// 下标处的成员是否仍是扫描时的那个人
+    pub fn matches(&self, household: &Household) -> bool {
+        household.members.get(self.member_index)
+            .is_some_and(|m| normalize_id_number(&m.id_number) == normalize_id_number(&self.id_number))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DuplicateEntry {
+    Member { member: MemberRef, head_name: String, name: String, id_number: String, address: String },
+    Household { household_id: Uuid, head_name: String, id_number: String, address: String },
+}
+
+impl DuplicateEntry {
+    pub fn household_id(&self) -> Uuid {
+        match self {
+            DuplicateEntry::Member { member, .. } => member.household_id,
+            DuplicateEntry::Household { household_id, .. } => *household_id,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            DuplicateEntry::Member { head_name, name, id_number, address, .. } => {
+                format!("{} ({}) - 户主: {}, 地址: {}", name, id_number, head_name, address)
+            }
+            DuplicateEntry::Household { head_name, id_number, address, .. } => {
+                format!("户主: {} ({}), 地址: {}", head_name, id_number, address)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    pub entries: Vec<DuplicateEntry>,
+}
+
+/// 保存前查重时发现的与已有数据的冲突
+#[derive(Debug, Clone)]
+pub struct DuplicateMatch {
+    pub kind: DuplicateKind,
+    pub message: String,
+}
+
+/// 扫描全部户籍，找出精确重复和疑似重复的记录
+pub fn find_duplicates(households: &[Household]) -> Vec<DuplicateGroup> {
+    let mut groups = Vec::new();
+
+    let mut by_id: HashMap<String, Vec<DuplicateEntry>> = HashMap::new();
+    let mut by_name_birth: HashMap<(String, chrono::NaiveDate), Vec<DuplicateEntry>> = HashMap::new();
+
+    for household in households {
+        for (i, member) in household.members.iter().enumerate().filter(|(_, m)| m.is_active()) {
+            let entry = DuplicateEntry::Member {
+                member: MemberRef {
+                    household_id: household.id,
+                    member_index: i,
+                    id_number: member.id_number.clone(),
+                },
+                head_name: household.head_name.clone(),
+                name: member.name.clone(),
+                id_number: member.id_number.clone(),
+                address: household.address.to_string(),
+            };
+
+            by_id.entry(normalize_id_number(&member.id_number))
+                .or_default()
+                .push(entry.clone());
+            by_name_birth.entry((member.name.trim().to_string(), member.birth_date))
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    for (_, entries) in by_id {
+        if entries.len() > 1 {
+            groups.push(DuplicateGroup { kind: DuplicateKind::SameIdNumber, entries });
+        }
+    }
+
+    for (_, entries) in by_name_birth {
+        if entries.len() < 2 {
+            continue;
+        }
+        // 身份证号都相同的已在精确重复中列出
+        let first_id = normalize_id_number(entry_id_number(&entries[0]));
+        if entries.iter().all(|e| normalize_id_number(entry_id_number(e)) == first_id) {
+            continue;
+        }
+        groups.push(DuplicateGroup { kind: DuplicateKind::SameNameAndBirth, entries });
+    }
+
+    // 户主姓名相同且地址相似的户籍
+    let mut visited = vec![false; households.len()];
+    for i in 0..households.len() {
+        if visited[i] {
+            continue;
+        }
+        let mut entries = vec![household_entry(&households[i])];
+        for j in (i + 1)..households.len() {
+            if !visited[j] && is_similar_household(&households[i], &households[j]) {
+                visited[j] = true;
+                entries.push(household_entry(&households[j]));
+            }
+        }
+        if entries.len() > 1 {
+            groups.push(DuplicateGroup { kind: DuplicateKind::SimilarHousehold, entries });
+        }
+    }
+
+    groups.sort_by_key(|g| match g.kind {
+        DuplicateKind::SameIdNumber => 0,
+        DuplicateKind::SameNameAndBirth => 1,
+        DuplicateKind::SimilarHousehold => 2,
+    });
+    groups
+}
+
+/// 检查待保存的户籍是否与已有户籍重复，`exclude` 为正在编辑的户籍自身
+pub fn find_matches(household: &Household, existing: &[Household], exclude: Option<Uuid>) -> Vec<DuplicateMatch> {
+    let mut matches = Vec::new();
+
+    for other in existing.iter().filter(|h| Some(h.id) != exclude) {
+        for member in household.members.iter().filter(|m| m.is_active()) {
+            for other_member in other.members.iter().filter(|m| m.is_active()) {
+                if normalize_id_number(&member.id_number) == normalize_id_number(&other_member.id_number) {
+                    matches.push(DuplicateMatch {
+                        kind: DuplicateKind::SameIdNumber,
+                        message: format!(
+                            "{} 的身份证号 {} 已登记在户主为 {} 的户籍中",
+                            member.name, member.id_number, other.head_name
+                        ),
+                    });
+                } else if member.name.trim() == other_member.name.trim()
+                    && member.birth_date == other_member.birth_date
+                {
+                    matches.push(DuplicateMatch {
+                        kind: DuplicateKind::SameNameAndBirth,
+                        message: format!(
+                            "{} ({}) 与户主为 {} 的户籍中的成员姓名、出生日期相同",
+                            member.name, member.birth_date.format("%Y-%m-%d"), other.head_name
+                        ),
+                    });
+                }
+            }
+        }
+
+        if is_similar_household(household, other) {
+            matches.push(DuplicateMatch {
+                kind: DuplicateKind::SimilarHousehold,
+                message: format!("已存在户主为 {}、地址为 {} 的户籍", other.head_name, other.address),
+            });
+        }
+    }
+
+    matches
+}
+
+/// 计算两个地址的相似度（0.0 ~ 1.0），基于字符二元组的 Dice 系数
+pub fn address_similarity(a: &str, b: &str) -> f64 {
+    let a = normalize_address(a);
+    let b = normalize_address(b);
+
+    if a == b {
+        return 1.0;
+    }
+
+    let bigrams_a = bigrams(&a);
+    let bigrams_b = bigrams(&b);
+    if bigrams_a.is_empty() || bigrams_b.is_empty() {
+        return 0.0;
+    }
+
+    let mut remaining = bigrams_b.clone();
+    let mut common = 0;
+    for bigram in &bigrams_a {
+        if let Some(pos) = remaining.iter().position(|b| b == bigram) {
+            remaining.swap_remove(pos);
+            common += 1;
+        }
+    }
+
+    (2 * common) as f64 / (bigrams_a.len() + bigrams_b.len()) as f64
+}
+
+fn is_similar_household(a: &Household, b: &Household) -> bool {
+    a.head_name.trim() == b.head_name.trim()
+        && address_similarity(&a.address.to_string(), &b.address.to_string()) >= ADDRESS_SIMILARITY_THRESHOLD
+}
+
+fn household_entry(household: &Household) -> DuplicateEntry {
+    DuplicateEntry::Household {
+        household_id: household.id,
+        head_name: household.head_name.clone(),
+        id_number: household.id_number.clone(),
+        address: household.address.to_string(),
+    }
+}
+
+fn entry_id_number(entry: &DuplicateEntry) -> &str {
+    match entry {
+        DuplicateEntry::Member { id_number, .. } => id_number,
+        DuplicateEntry::Household { id_number, .. } => id_number,
+    }
+}
+
+fn normalize_id_number(id_number: &str) -> String {
+    id_number.trim().to_uppercase()
+}
+
+fn normalize_address(address: &str) -> Vec<char> {
+    address
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_ascii_punctuation() && !"，。、；：（）".contains(*c))
+        .collect()
+}
+
+fn bigrams(chars: &[char]) -> Vec<(char, char)> {
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
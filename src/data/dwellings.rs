@@ -0,0 +1,219 @@
+use super::divisions;
+use super::models::Household;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+/// 门牌：所在社区（村）下的楼栋、单元和房号，多户可登记在同一门牌
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dwelling {
+    pub id: Uuid,
+    /// 所在行政区划代码，通常为村或社区
+    pub division: String,
+    pub building: String,
+    /// 平房等没有单元的为空
+    pub unit: String,
+    pub room: String,
+    /// 核定居住人数，0 表示未核定
+    pub capacity: u32,
+}
+
+impl Dwelling {
+    pub fn building_label(&self) -> String {
+        label(&self.building, "号楼")
+    }
+
+    pub fn unit_label(&self) -> String {
+        label(&self.unit, "单元")
+    }
+
+    pub fn room_label(&self) -> String {
+        label(&self.room, "室")
+    }
+
+    /// 楼栋、单元、房号，如“3号楼2单元101室”
+    pub fn short_name(&self) -> String {
+        format!("{}{}{}", self.building_label(), self.unit_label(), self.room_label())
+    }
+
+    /// 楼栋、单元、房号是否与另一门牌相同（忽略首尾空格）
+    pub fn same_place(&self, other: &Dwelling) -> bool {
+        self.division == other.division
+            && self.building.trim() == other.building.trim()
+            && self.unit.trim() == other.unit.trim()
+            && self.room.trim() == other.room.trim()
+    }
+
+    /// 按社区、楼栋、单元、房号排序，数字部分按数值比较
+    pub fn compare(&self, other: &Dwelling) -> Ordering {
+        self.division.cmp(&other.division)
+            .then_with(|| compare_numbers(&self.building, &other.building))
+            .then_with(|| compare_numbers(&self.unit, &other.unit))
+            .then_with(|| compare_numbers(&self.room, &other.room))
+    }
+}
+
+impl std::fmt::Display for Dwelling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", divisions::full_name(&self.division), self.short_name())
+    }
+}
+
+/// 门牌的居住情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OccupancyStatus {
+    Empty,
+    Occupied,
+    /// 在册人数超过核定人数
+    Overcrowded,
+}
+
+impl std::fmt::Display for OccupancyStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OccupancyStatus::Empty => write!(f, "空置"),
+            OccupancyStatus::Occupied => write!(f, "正常"),
+            OccupancyStatus::Overcrowded => write!(f, "超员"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Occupancy {
+    pub dwelling: Dwelling,
+    /// 登记在此门牌的户籍ID和户主姓名
+    pub households: Vec<(Uuid, String)>,
+    /// 在册人数
+    pub members: usize,
+}
+
+impl Occupancy {
+    pub fn status(&self) -> OccupancyStatus {
+        if self.households.is_empty() {
+            OccupancyStatus::Empty
+        } else if self.dwelling.capacity > 0 && self.members > self.dwelling.capacity as usize {
+            OccupancyStatus::Overcrowded
+        } else {
+            OccupancyStatus::Occupied
+        }
+    }
+}
+
+/// 统计每个门牌的居住情况，按门牌排序
+pub fn occupancy(dwellings: &[Dwelling], households: &[Household]) -> Vec<Occupancy> {
+    let mut result: Vec<Occupancy> = dwellings.iter()
+        .map(|dwelling| {
+            let residents: Vec<&Household> = households.iter()
+                .filter(|h| h.dwelling_id == Some(dwelling.id))
+                .collect();
+            Occupancy {
+                dwelling: dwelling.clone(),
+                households: residents.iter().map(|h| (h.id, h.head_name.clone())).collect(),
+                members: residents.iter().map(|h| h.members.iter().filter(|m| m.is_active()).count()).sum(),
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.dwelling.compare(&b.dwelling));
+    result
+}
+
+// 纯数字的编号加上后缀，如“3”显示为“3号楼”，其他写法原样显示
+fn label(value: &str, suffix: &str) -> String {
+    let value = value.trim();
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        format!("{}{}", value, suffix)
+    } else {
+        value.to_string()
+    }
+}
+
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<u64>(), b.trim().parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.trim().cmp(b.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dwelling(building: &str, unit: &str, room: &str, capacity: u32) -> Dwelling {
+        Dwelling {
+            id: Uuid::new_v4(),
+            division: "110105001001".to_string(),
+            building: building.to_string(),
+            unit: unit.to_string(),
+            room: room.to_string(),
+            capacity,
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(dwelling("3", "2", "101", 0).short_name(), "3号楼2单元101室");
+        assert_eq!(dwelling("东配楼", "", "甲1", 0).short_name(), "东配楼甲1");
+        assert_eq!(dwelling("3", "", "101", 0).to_string(), "北京市朝阳区建外街道光华里社区3号楼101室");
+    }
+
+    #[test]
+    fn same_place_ignores_spaces() {
+        assert!(dwelling("3", "2", "101", 0).same_place(&dwelling(" 3", "2 ", "101", 4)));
+        assert!(!dwelling("3", "2", "101", 0).same_place(&dwelling("3", "1", "101", 0)));
+        let mut other = dwelling("3", "2", "101", 0);
+        other.division = "110105001002".to_string();
+        assert!(!dwelling("3", "2", "101", 0).same_place(&other));
+    }
+
+    #[test]
+    fn numbers_sort_by_value() {
+        let mut dwellings = [dwelling("10", "", "1", 0), dwelling("2", "", "1", 0), dwelling("2", "", "甲", 0), dwelling("2", "", "12", 0)];
+        dwellings.sort_by(|a, b| a.compare(b));
+        let names: Vec<String> = dwellings.iter().map(|d| d.short_name()).collect();
+        assert_eq!(names, ["2号楼1室", "2号楼12室", "2号楼甲", "10号楼1室"]);
+    }
+
+    #[test]
+    fn occupancy_status() {
+        use crate::data::models::*;
+        let full = dwelling("1", "", "101", 2);
+        let empty = dwelling("1", "", "102", 2);
+        let member = |status| Member {
+            name: "张三".to_string(),
+            document_type: DocumentType::ResidentId,
+            id_number: String::new(),
+            nationality: "CHN".to_string(),
+            relationship: Relationship::HEAD,
+            birth_date: chrono::NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
+            gender: Gender::Male,
+            education: Education::University,
+            occupation: String::new(),
+            status,
+            marital_status: MaritalStatus::Unmarried,
+            death_date: None,
+        };
+        let household = Household {
+            id: Uuid::new_v4(),
+            head_name: "张三".to_string(),
+            id_number: String::new(),
+            head_document_type: DocumentType::ResidentId,
+            address: Address::default(),
+            dwelling_id: Some(full.id),
+            phone: String::new(),
+            household_type: HouseholdType::Urban,
+            registration_date: chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            members: vec![member(MemberStatus::Active), member(MemberStatus::Active), member(MemberStatus::Cancelled)],
+            revision: 0,
+        };
+
+        let report = occupancy(&[empty.clone(), full.clone()], std::slice::from_ref(&household));
+        assert_eq!(report[0].dwelling.id, full.id);
+        assert_eq!(report[0].members, 2);
+        assert_eq!(report[0].status(), OccupancyStatus::Occupied);
+        assert_eq!(report[1].status(), OccupancyStatus::Empty);
+
+        let mut crowded = household;
+        crowded.members.push(member(MemberStatus::Active));
+        assert_eq!(occupancy(&[full], &[crowded])[0].status(), OccupancyStatus::Overcrowded);
+    }
+}
@@ -0,0 +1,141 @@
+use rusqlite::ErrorCode;
+use uuid::Uuid;
+
+/// 数据层错误，显示文本直接面向用户
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataError {
+    /// 要操作的户籍或成员不存在
+    NotFound(String),
+    /// 输入不符合业务规则
+    Validation(String),
+    /// 违反唯一性等数据库约束
+    Constraint(String),
+    /// 数据库中的数据无法识别
+    Corrupt(String),
+    /// 数据库文件或导出文件读写失败
+    Io(String),
+    /// 数据库被其他程序占用
+    Locked,
+    /// 户籍在读取后已被其他用户修改或删除，保存的版本已过期
+    Conflict(Uuid),
+    /// 其他数据库错误
+    Database(String),
+}
+
+impl DataError {
+    /// 是否为用户可自行修正的错误（输入有误、记录重复等）
+    pub fn is_user_error(&self) -> bool {
+        matches!(self, DataError::NotFound(_) | DataError::Validation(_) | DataError::Constraint(_) | DataError::Conflict(_))
+    }
+}
+
+impl std::fmt::Display for DataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataError::NotFound(message) => write!(f, "{}", message),
+            DataError::Validation(message) => write!(f, "{}", message),
+            DataError::Constraint(message) => write!(f, "{}", message),
+            DataError::Corrupt(message) => write!(f, "数据无法识别: {}", message),
+            DataError::Io(message) => write!(f, "文件读写失败: {}", message),
+            DataError::Locked => write!(f, "数据库正被其他程序使用，请稍后重试"),
+            DataError::Conflict(_) => write!(f, "该户籍已被其他用户修改，请核对后再保存"),
+            DataError::Database(message) => write!(f, "数据库错误: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl From<rusqlite::Error> for DataError {
+    fn from(error: rusqlite::Error) -> Self {
+        match &error {
+            rusqlite::Error::SqliteFailure(failure, message) => {
+                let message = message.clone().unwrap_or_else(|| failure.to_string());
+                match failure.code {
+                    ErrorCode::ConstraintViolation => DataError::Constraint(message),
+                    ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => DataError::Locked,
+                    ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase => DataError::Corrupt(message),
+                    ErrorCode::CannotOpen
+                    | ErrorCode::ReadOnly
+                    | ErrorCode::DiskFull
+                    | ErrorCode::SystemIoFailure
+                    | ErrorCode::PermissionDenied => DataError::Io(message),
+                    _ => DataError::Database(message),
+                }
+            }
+            rusqlite::Error::FromSqlConversionFailure(_, _, inner) => DataError::Corrupt(inner.to_string()),
+            rusqlite::Error::InvalidColumnType(..) => DataError::Corrupt(error.to_string()),
+            _ => DataError::Database(error.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for DataError {
+    fn from(error: std::io::Error) -> Self {
+        DataError::Io(error.to_string())
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<postgres::Error> for DataError {
+    fn from(error: postgres::Error) -> Self {
+        use postgres::error::SqlState;
+
+        let Some(db_error) = error.as_db_error() else {
+            // 没有错误代码的是连接层面的错误
+            return DataError::Io(error.to_string());
+        };
+        let message = db_error.message().to_string();
+        match db_error.code() {
+            code if *code == SqlState::UNIQUE_VIOLATION || *code == SqlState::FOREIGN_KEY_VIOLATION => DataError::Constraint(message),
+            code if *code == SqlState::T_R_SERIALIZATION_FAILURE
+                || *code == SqlState::T_R_DEADLOCK_DETECTED
+                || *code == SqlState::LOCK_NOT_AVAILABLE => DataError::Locked,
+            code if *code == SqlState::DATA_CORRUPTED || *code == SqlState::INDEX_CORRUPTED => DataError::Corrupt(message),
+            _ => DataError::Database(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn sqlite_error(sql: &str) -> DataError {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT NOT NULL); INSERT INTO t VALUES (1, 'a');").unwrap();
+        conn.execute_batch(sql).unwrap_err().into()
+    }
+
+    #[test]
+    fn sqlite_errors_map_to_variants() {
+        assert!(matches!(sqlite_error("INSERT INTO t VALUES (1, 'b')"), DataError::Constraint(_)));
+        assert!(matches!(sqlite_error("INSERT INTO t VALUES (2, NULL)"), DataError::Constraint(_)));
+        assert!(matches!(sqlite_error("SELECT * FROM missing"), DataError::Database(_)));
+
+        let conn = Connection::open_in_memory().unwrap();
+        let wrong_type = conn.query_row("SELECT 'abc'", [], |row| row.get::<_, i64>(0)).unwrap_err();
+        assert!(matches!(DataError::from(wrong_type), DataError::Corrupt(_)));
+    }
+
+    #[test]
+    fn non_database_file_is_corrupt() {
+        let path = std::env::temp_dir().join(format!("household-{}.db", Uuid::new_v4()));
+        std::fs::write(&path, b"not a database file, just some text padding it out past the header").unwrap();
+        let conn = Connection::open(&path).unwrap();
+        let error = DataError::from(conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0)).unwrap_err());
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(error, DataError::Corrupt(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn user_errors() {
+        assert!(DataError::Validation("姓名不能为空".into()).is_user_error());
+        assert!(DataError::Conflict(Uuid::nil()).is_user_error());
+        assert!(!DataError::Locked.is_user_error());
+        assert!(!DataError::Io("磁盘已满".into()).is_user_error());
+        assert_eq!(DataError::Constraint("身份证号重复".into()).to_string(), "身份证号重复");
+        assert_eq!(DataError::Corrupt("未知的性别代码: 9".into()).to_string(), "数据无法识别: 未知的性别代码: 9");
+    }
+}
@@ -0,0 +1,141 @@
+use super::models::Household;
+use uuid::Uuid;
+
+/// 最多保留的撤销步数
+pub const MAX_UNDO_STEPS: usize = 100;
+
+/// 一次操作前后某户的内容，户籍不存在时为 `None`
+#[derive(Debug, Clone)]
+pub struct HouseholdChange {
+    pub household_id: Uuid,
+    pub before: Option<Household>,
+    pub after: Option<Household>,
+}
+
+impl HouseholdChange {
+    /// 操作是否确实改变了该户（保存后版本号总会变化）
+    pub fn is_changed(&self) -> bool {
+        self.before.as_ref().map(|h| h.revision) != self.after.as_ref().map(|h| h.revision)
+            || self.before.is_some() != self.after.is_some()
+    }
+}
+
+/// 一次可撤销的操作，如新增户籍、分户，涉及的所有户一起撤销
+#[derive(Debug, Clone)]
+pub struct Command {
+    /// 显示在编辑菜单中，如“删除户籍 张三”
+    pub description: String,
+    pub changes: Vec<HouseholdChange>,
+}
+
+/// 撤销与重做栈，在本次运行期间一直保留
+#[derive(Debug, Default)]
+pub struct UndoHistory {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+}
+
+impl UndoHistory {
+    /// 记录新的操作；此后不能再重做已撤销的操作
+    pub fn push(&mut self, command: Command) {
+        if command.changes.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.push_undo(command);
+    }
+
+    fn push_undo(&mut self, command: Command) {
+        self.undo.push(command);
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn next_undo(&self) -> Option<&str> {
+        self.undo.last().map(|c| c.description.as_str())
+    }
+
+    pub fn next_redo(&self) -> Option<&str> {
+        self.redo.last().map(|c| c.description.as_str())
+    }
+
+    pub fn take_undo(&mut self) -> Option<Command> {
+        self.undo.pop()
+    }
+
+    pub fn take_redo(&mut self) -> Option<Command> {
+        self.redo.pop()
+    }
+
+    /// 撤销完成后移入重做栈
+    pub fn undone(&mut self, command: Command) {
+        self.redo.push(command);
+    }
+
+    /// 重做完成后移回撤销栈
+    pub fn redone(&mut self, command: Command) {
+        self.push_undo(command);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(description: &str) -> Command {
+        Command {
+            description: description.to_string(),
+            changes: vec![HouseholdChange { household_id: Uuid::new_v4(), before: None, after: None }],
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_order() {
+        let mut history = UndoHistory::default();
+        history.push(command("新增户籍 张三"));
+        history.push(command("修改户籍 张三"));
+        assert_eq!(history.next_undo(), Some("修改户籍 张三"));
+        assert_eq!(history.next_redo(), None);
+
+        let undone = history.take_undo().unwrap();
+        history.undone(undone);
+        assert_eq!(history.next_undo(), Some("新增户籍 张三"));
+        assert_eq!(history.next_redo(), Some("修改户籍 张三"));
+
+        let redone = history.take_redo().unwrap();
+        history.redone(redone);
+        assert_eq!(history.next_undo(), Some("修改户籍 张三"));
+        assert!(history.take_redo().is_none());
+    }
+
+    #[test]
+    fn new_command_clears_redo() {
+        let mut history = UndoHistory::default();
+        history.push(command("新增户籍 张三"));
+        let undone = history.take_undo().unwrap();
+        history.undone(undone);
+
+        // 没有改变任何户的操作不记录，也不影响重做
+        history.push(Command { description: "修改户籍 张三".to_string(), changes: Vec::new() });
+        assert_eq!(history.next_redo(), Some("新增户籍 张三"));
+
+        history.push(command("删除户籍 李四"));
+        assert_eq!(history.next_redo(), None);
+        assert_eq!(history.next_undo(), Some("删除户籍 李四"));
+    }
+
+    #[test]
+    fn keeps_limited_steps() {
+        let mut history = UndoHistory::default();
+        for i in 0..=MAX_UNDO_STEPS {
+            history.push(command(&i.to_string()));
+        }
+        let mut count = 0;
+        while let Some(command) = history.take_undo() {
+            assert_ne!(command.description, "0");
+            count += 1;
+        }
+        assert_eq!(count, MAX_UNDO_STEPS);
+    }
+}
@@ -1,35 +1,72 @@
 use super::models::*;
+use super::divisions::DivisionLevel;
+use super::dwellings::{self, Occupancy};
+use super::drafts::Draft;
+use super::attachments::{Attachment, ExportedAttachment};
+use super::audit::AuditEntry;
 use super::database::Database;
+use super::repository::HouseholdRepository;
+use super::codes::DecodeError;
+use super::error::DataError;
+use super::validation::Validate;
+use super::duplicates::{self, DuplicateGroup, DuplicateMatch, MemberRef};
+use super::history::HouseholdChange;
 use chrono::NaiveDate;
 use uuid::Uuid;
 use std::collections::HashMap;
 
-pub struct HouseholdManager {
-    database: Database,
+/// 户籍业务操作，经由存储后端 `R` 读写数据，默认为 SQLite 文件
+pub struct HouseholdManager<R: HouseholdRepository = Database> {
+    repository: R,
     households_cache: HashMap<Uuid, Household>,
+    dwellings_cache: Vec<Dwelling>,
+    decode_errors: Vec<DecodeError>,
     cache_dirty: bool,
+    /// 缓存加载时存储的修改计数
+    data_version: Option<i64>,
+    /// 自 `start_recording` 以来写入过的户籍及其写入前的内容，未在记录时为空
+    journal: Option<Vec<(Uuid, Option<Household>)>>,
 }
 
-impl HouseholdManager {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let database = Database::new("household_management.db")?;
-        Ok(Self {
-            database,
+/// 默认的数据库文件，位于程序运行目录
+pub const DEFAULT_DB_PATH: &str = "household_management.db";
+
+impl HouseholdManager<Database> {
+    pub fn new() -> Result<Self, DataError> {
+        Self::open(DEFAULT_DB_PATH)
+    }
+    
+    /// 打开指定的数据库文件，文件不存在时新建
+    pub fn open(db_path: &str) -> Result<Self, DataError> {
+        Ok(Self::with_repository(Database::new(db_path)?))
+    }
+}
+
+impl<R: HouseholdRepository> HouseholdManager<R> {
+    pub fn with_repository(repository: R) -> Self {
+        Self {
+            repository,
             households_cache: HashMap::new(),
+            dwellings_cache: Vec::new(),
+            decode_errors: Vec::new(),
             cache_dirty: true,
-        })
+            data_version: None,
+            journal: None,
+        }
     }
     
-    pub fn is_empty(&self) -> Result<bool, Box<dyn std::error::Error>> {
-        Ok(self.database.is_empty()?)
+    pub fn is_empty(&self) -> Result<bool, DataError> {
+        self.repository.is_empty()
     }
     
-    pub fn add_sample_data(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn add_sample_data(&mut self) -> Result<(), DataError> {
         let sample1 = Household {
             id: Uuid::new_v4(),
             head_name: "张三".to_string(),
             id_number: "110101199001011234".to_string(),
-            address: "北京市朝阳区XXX街道XXX号".to_string(),
+            head_document_type: DocumentType::ResidentId,
+            address: Address::from_division("110105001", "XXX路XXX号"),
+            dwelling_id: None,
             phone: "13800138000".to_string(),
             household_type: HouseholdType::Urban,
             registration_date: chrono::NaiveDateTime::new(
@@ -39,30 +76,43 @@ impl HouseholdManager {
             members: vec![
                 Member {
                     name: "张三".to_string(),
+                    document_type: DocumentType::ResidentId,
                     id_number: "110101199001011234".to_string(),
-                    relationship: Relationship::Head,
+                    nationality: "CHN".to_string(),
+                    relationship: Relationship::HEAD,
                     birth_date: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
                     gender: Gender::Male,
                     education: Education::University,
                     occupation: "工程师".to_string(),
+                    status: MemberStatus::Active,
+                    marital_status: MaritalStatus::Married,
+                    death_date: None,
                 },
                 Member {
                     name: "李四".to_string(),
+                    document_type: DocumentType::ResidentId,
                     id_number: "110101199205051235".to_string(),
-                    relationship: Relationship::Spouse,
+                    nationality: "CHN".to_string(),
+                    relationship: Relationship::WIFE,
                     birth_date: NaiveDate::from_ymd_opt(1992, 5, 5).unwrap(),
                     gender: Gender::Female,
                     education: Education::University,
                     occupation: "教师".to_string(),
+                    status: MemberStatus::Active,
+                    marital_status: MaritalStatus::Married,
+                    death_date: None,
                 },
             ],
+            revision: 0,
         };
         
         let sample2 = Household {
             id: Uuid::new_v4(),
             head_name: "王五".to_string(),
             id_number: "110101198506061236".to_string(),
-            address: "北京市海淀区YYY街道YYY号".to_string(),
+            head_document_type: DocumentType::ResidentId,
+            address: Address::from_division("110108011", "YYY路YYY号"),
+            dwelling_id: None,
             phone: "13900139000".to_string(),
             household_type: HouseholdType::Rural,
             registration_date: chrono::NaiveDateTime::new(
@@ -72,84 +122,793 @@ impl HouseholdManager {
             members: vec![
                 Member {
                     name: "王五".to_string(),
+                    document_type: DocumentType::ResidentId,
                     id_number: "110101198506061236".to_string(),
-                    relationship: Relationship::Head,
+                    nationality: "CHN".to_string(),
+                    relationship: Relationship::HEAD,
                     birth_date: NaiveDate::from_ymd_opt(1985, 6, 6).unwrap(),
                     gender: Gender::Male,
                     education: Education::HighSchool,
                     occupation: "农民".to_string(),
+                    status: MemberStatus::Active,
+                    marital_status: MaritalStatus::Unmarried,
+                    death_date: None,
                 },
             ],
+            revision: 0,
         };
         
-        self.database.insert_household(&sample1)?;
-        self.database.insert_household(&sample2)?;
+        self.repository.insert_household(&sample1)?;
+        self.repository.insert_household(&sample2)?;
         self.cache_dirty = true;
         Ok(())
     }
     
-    fn refresh_cache(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    fn refresh_cache(&mut self) -> Result<(), DataError> {
         if self.cache_dirty {
-            match self.database.get_all_households() {
-                Ok(households) => {
-                    self.households_cache.clear();
-                    for household in households {
-                        self.households_cache.insert(household.id, household);
-                    }
-                    self.cache_dirty = false;
-                }
-                Err(_) => {
-                    // 如果数据库为空或出错，清空缓存
-                    self.households_cache.clear();
-                    self.cache_dirty = false;
-                    // 不返回错误，允许继续运行
-                }
+            // 先记录计数再加载，加载期间的外部修改留待下次检查
+            self.data_version = Some(self.repository.data_version()?);
+            let loaded = self.repository.load_households()?;
+            self.households_cache.clear();
+            for household in loaded.households {
+                self.households_cache.insert(household.id, household);
+            }
+            self.decode_errors = loaded.errors;
+            
+            let (mut dwellings, errors) = self.repository.load_dwellings()?;
+            dwellings.sort_by(|a, b| a.compare(b));
+            self.dwellings_cache = dwellings;
+            self.decode_errors.extend(errors);
+            self.cache_dirty = false;
+        }
+        Ok(())
+    }
+    
+    /// 检查其他程序（另一台电脑上的本程序、命令行工具或接口服务）是否修改了数据，
+    /// 有修改时使缓存失效并返回 true，调用方应重新读取显示的数据
+    pub fn check_external_changes(&mut self) -> Result<bool, DataError> {
+        if self.cache_dirty {
+            return Ok(false);
+        }
+        let version = self.repository.data_version()?;
+        if self.data_version == Some(version) {
+            return Ok(false);
+        }
+        // 其他操作员自动保存草稿也会改变计数，户籍和门牌都没有变化时不重新加载
+        let loaded = self.repository.load_households()?;
+        let (mut dwellings, _) = self.repository.load_dwellings()?;
+        dwellings.sort_by(|a, b| a.compare(b));
+        let unchanged = loaded.households.len() == self.households_cache.len()
+            && loaded.households.iter().all(|h| {
+                self.households_cache.get(&h.id).is_some_and(|cached| cached.revision == h.revision)
+            })
+            && dwellings == self.dwellings_cache;
+        if unchanged {
+            self.data_version = Some(version);
+            return Ok(false);
+        }
+        self.cache_dirty = true;
+        Ok(true)
+    }
+    
+    // 记录中时保存写入前的内容，同一户只保留第一次写入前的
+    fn remember(&mut self, household_ids: impl IntoIterator<Item = Uuid>) -> Result<(), DataError> {
+        if self.journal.is_none() {
+            return Ok(());
+        }
+        self.refresh_cache()?;
+        let journal = self.journal.get_or_insert_with(Vec::new);
+        for household_id in household_ids {
+            if !journal.iter().any(|(id, _)| *id == household_id) {
+                journal.push((household_id, self.households_cache.get(&household_id).cloned()));
             }
         }
         Ok(())
     }
     
-    pub fn get_households(&mut self) -> Result<Vec<Household>, Box<dyn std::error::Error>> {
+    /// 开始记录之后的写入，供撤销使用
+    pub fn start_recording(&mut self) {
+        self.journal = Some(Vec::new());
+    }
+    
+    /// 结束记录，返回自 `start_recording` 以来各户写入前后的内容，未实际改变的户不列出
+    pub fn recorded_changes(&mut self) -> Result<Vec<HouseholdChange>, DataError> {
+        let journal = self.journal.take().unwrap_or_default();
+        self.refresh_cache()?;
+        Ok(journal.into_iter()
+            .map(|(household_id, before)| HouseholdChange {
+                household_id,
+                before,
+                after: self.households_cache.get(&household_id).cloned(),
+            })
+            .filter(HouseholdChange::is_changed)
+            .collect())
+    }
+    
+    /// 撤销（`undo` 为 true）或重做一组修改，在同一事务中完成。
+    /// 各户须仍是该操作留下的版本，其间被其他用户修改过时返回 `DataError::Conflict`；
+    /// 成功后返回更新了版本号的记录，供之后反向操作使用
+    pub fn replay_changes(&mut self, changes: &[HouseholdChange], undo: bool) -> Result<Vec<HouseholdChange>, DataError> {
+        self.check_external_changes()?;
+        self.refresh_cache()?;
+        let mut updates = Vec::new();
+        let mut deletions = Vec::new();
+        for change in changes {
+            let (from, to) = if undo { (&change.after, &change.before) } else { (&change.before, &change.after) };
+            let current = self.households_cache.get(&change.household_id).map(|h| h.revision);
+            if current != from.as_ref().map(|h| h.revision) {
+                return Err(DataError::Conflict(change.household_id));
+            }
+            match to {
+                Some(household) => updates.push(Household {
+                    revision: current.unwrap_or(0),
+                    ..household.clone()
+                }),
+                None => deletions.push((change.household_id, current.unwrap_or(0))),
+            }
+        }
+        self.commit_changes(updates, &deletions, &[])?;
+        
+        self.refresh_cache()?;
+        Ok(changes.iter()
+            .map(|change| {
+                let current = self.households_cache.get(&change.household_id).cloned();
+                if undo {
+                    HouseholdChange { before: current, ..change.clone() }
+                } else {
+                    HouseholdChange { after: current, ..change.clone() }
+                }
+            })
+            .collect())
+    }
+    
+    /// 最近一次加载时无法读取的记录
+    pub fn decode_errors(&self) -> &[DecodeError] {
+        &self.decode_errors
+    }
+    
+    pub fn get_households(&mut self) -> Result<Vec<Household>, DataError> {
         self.refresh_cache()?;
         Ok(self.households_cache.values().cloned().collect())
     }
     
-    pub fn get_household(&mut self, index: usize) -> Result<Option<Household>, Box<dyn std::error::Error>> {
+    pub fn get_household(&mut self, index: usize) -> Result<Option<Household>, DataError> {
         self.refresh_cache()?;
         let households: Vec<_> = self.households_cache.values().cloned().collect();
         Ok(households.get(index).cloned())
     }
     
-    pub fn add_household(&mut self, household: Household) -> Result<(), Box<dyn std::error::Error>> {
-        self.database.insert_household(&household)?;
+    pub fn add_household(&mut self, mut household: Household) -> Result<(), DataError> {
+        household.sync_head();
+        self.remember([household.id])?;
+        self.repository.insert_household(&household)?;
         self.cache_dirty = true;
         Ok(())
     }
     
-    pub fn update_household(&mut self, household: Household) -> Result<(), Box<dyn std::error::Error>> {
-        self.database.update_household(&household)?;
+    /// 保存修改，`household.revision` 须为读取时的版本；其间被其他用户修改或删除时返回 `DataError::Conflict`
+    pub fn update_household(&mut self, mut household: Household) -> Result<(), DataError> {
+        household.sync_head();
+        self.remember([household.id])?;
+        // 冲突时缓存中的也是旧版本，同样需要重新加载
+        self.cache_dirty = true;
+        self.repository.update_household(&household)
+    }
+    
+    /// 删除户籍，`revision` 须为读取时的版本；其间被其他用户修改或删除时返回 `DataError::Conflict`
+    pub fn remove_household(&mut self, household_id: &Uuid, revision: i64) -> Result<(), DataError> {
+        self.remember([*household_id])?;
         self.cache_dirty = true;
+        self.repository.delete_household(household_id, revision)
+    }
+    
+    pub fn get_household_by_id(&mut self, household_id: &Uuid) -> Result<Option<Household>, DataError> {
+        self.refresh_cache()?;
+        Ok(self.households_cache.get(household_id).cloned())
+    }
+    
+    /// 查重：扫描数据库中的精确重复和疑似重复记录
+    pub fn find_duplicates(&mut self) -> Result<Vec<DuplicateGroup>, DataError> {
+        let households = self.get_households()?;
+        Ok(duplicates::find_duplicates(&households))
+    }
+    
+    /// 保存前检查待保存户籍与已有户籍是否重复
+    pub fn find_matches(&mut self, household: &Household, exclude: Option<Uuid>) -> Result<Vec<DuplicateMatch>, DataError> {
+        let households = self.get_households()?;
+        Ok(duplicates::find_matches(household, &households, exclude))
+    }
+    
+    /// 编辑户籍用的表单，附带成员的结婚登记日期
+    pub fn household_form(&mut self, household: &Household) -> Result<HouseholdForm, DataError> {
+        let mut form = HouseholdForm::from_household(household);
+        // 事件按日期从新到旧，保留每人最近一次登记
+        for event in self.get_events(&household.id)? {
+            if event.event_type == EventType::Marriage {
+                form.marriage_dates.entry(event.member_id_number.trim().to_uppercase()).or_insert(event.event_date);
+            }
+        }
+        Ok(form)
+    }
+    
+    /// 新增或修改前的校验和查重，与界面保存时一致：校验未通过或精确重复时返回错误，疑似重复作为提示返回
+    pub fn check_household(&mut self, household: &Household, exclude: Option<Uuid>) -> Result<Vec<String>, DataError> {
+        self.household_form(household)?
+            .validate()
+            .map_err(|errors| DataError::Validation(format!("校验未通过: {}", errors)))?;
+        let mut warnings = Vec::new();
+        for duplicate in self.find_matches(household, exclude)? {
+            if duplicate.kind.is_exact() {
+                return Err(DataError::Constraint(duplicate.message));
+            }
+            warnings.push(duplicate.message);
+        }
+        Ok(warnings)
+    }
+    
+    /// 合并重复户：将其他户中尚未登记的成员并入保留户，然后删除其他户
+    pub fn merge_duplicate_households(&mut self, keep_id: &Uuid, others: &[Uuid]) -> Result<(), DataError> {
+        let mut keep = self.get_household_by_id(keep_id)?.ok_or_else(|| DataError::NotFound("要保留的户籍不存在".into()))?;
+        let mut deletions = Vec::new();
+        
+        for other_id in others.iter().filter(|id| *id != keep_id) {
+            let other = self.get_household_by_id(other_id)?.ok_or_else(|| DataError::NotFound("要合并的户籍不存在".into()))?;
+            deletions.push((other.id, other.revision));
+            for member in other.members {
+                let already_present = keep.members.iter().any(|m| {
+                    m.id_number.trim().eq_ignore_ascii_case(member.id_number.trim())
+                });
+                if !already_present {
+                    let mut member = member;
+                    if member.relationship.is_head() {
+                        member.relationship = Relationship::OTHER_RELATIVE;
+                    }
+                    keep.members.push(member);
+                }
+            }
+        }
+        
+        self.commit_changes(vec![keep], &deletions, &[])?;
+        Ok(())
+    }
+    
+    /// 处理重复成员：保留指定记录，从其他户中移除重复的成员记录。
+    /// 查重后成员顺序已变化时返回 `DataError::Conflict`，不能移除仍有其他成员的户的户主
+    pub fn resolve_duplicate_member(&mut self, keep: &MemberRef, others: &[MemberRef]) -> Result<(), DataError> {
+        let mut updates: Vec<Household> = Vec::new();
+        
+        for other in others.iter().filter(|r| *r != keep) {
+            if !updates.iter().any(|h| h.id == other.household_id) {
+                let household = self.get_household_by_id(&other.household_id)?.ok_or_else(|| DataError::NotFound("户籍不存在".into()))?;
+                updates.push(household);
+            }
+        }
+        
+        // 同一户中按下标从大到小删除，避免下标错位
+        let mut removals: Vec<&MemberRef> = others.iter().filter(|r| *r != keep).collect();
+        removals.sort_by_key(|r| std::cmp::Reverse(r.member_index));
+        let mut heads_removed = Vec::new();
+        for removal in removals {
+            if let Some(household) = updates.iter_mut().find(|h| h.id == removal.household_id) {
+                if !removal.matches(household) {
+                    return Err(DataError::Conflict(household.id));
+                }
+                let member = household.members.remove(removal.member_index);
+                if member.is_active() && member.relationship.is_head() {
+                    heads_removed.push(household.id);
+                }
+            }
+        }
+
+        // 移除户主后户内还有其他成员时，需先办理户主变更
+        for household in updates.iter().filter(|h| heads_removed.contains(&h.id)) {
+            if household.members.iter().any(|m| m.is_active()) {
+                return Err(DataError::Validation(format!(
+                    "重复成员是户主为 {} 的户籍的户主，请先变更户主再移除", household.head_name
+                )));
+            }
+        }
+        
+        // 成员全部被移除的户籍直接删除
+        let deletions: Vec<(Uuid, i64)> = updates.iter().filter(|h| h.members.is_empty()).map(|h| (h.id, h.revision)).collect();
+        updates.retain(|h| !h.members.is_empty());
+        
+        self.commit_changes(updates, &deletions, &[])?;
         Ok(())
     }
     
-    pub fn remove_household(&mut self, household_id: &Uuid) -> Result<(), Box<dyn std::error::Error>> {
-        self.database.delete_household(household_id)?;
+    // 批量写入前同步户主信息，写入后使缓存失效
+    fn commit_changes(&mut self, mut updates: Vec<Household>, deletions: &[(Uuid, i64)], events: &[HouseholdEvent]) -> Result<(), DataError> {
+        for household in &mut updates {
+            household.sync_head();
+        }
+        self.remember(updates.iter().map(|h| h.id).chain(deletions.iter().map(|(id, _)| *id)))?;
+        self.cache_dirty = true;
+        self.repository.apply_changes(&updates, deletions, events)
+    }
+    
+    /// 批量导入户籍，ID已存在的户整体替换（不论其间是否被修改），全部成功或全部回滚
+    pub fn import_households(&mut self, mut households: Vec<Household>) -> Result<(), DataError> {
         self.cache_dirty = true;
+        self.refresh_cache()?;
+        for household in &mut households {
+            household.revision = self.households_cache.get(&household.id).map_or(0, |h| h.revision);
+        }
+        self.commit_changes(households, &[], &[])
+    }
+    
+    pub fn get_events(&mut self, household_id: &Uuid) -> Result<Vec<HouseholdEvent>, DataError> {
+        self.repository.get_events_by_household_id(household_id)
+    }
+    
+    /// 按迁移表单执行对应的迁移操作
+    pub fn execute_transfer(&mut self, form: &TransferForm) -> Result<(), DataError> {
+        let source = form.source.ok_or_else(|| DataError::Validation("请选择迁出户".into()))?;
+        let date = form.event_date().ok_or_else(|| DataError::Validation("无效的办理日期".into()))?;
+        let selected = form.selected_indices();
+        let relationships: Vec<Relationship> = selected.iter().map(|&i| form.relationships[i]).collect();
+        
+        match form.kind {
+            TransferKind::MoveMember => {
+                let target = form.target.ok_or_else(|| DataError::Validation("请选择迁入户".into()))?;
+                self.transfer_members(&source, &selected, &target, &relationships, date, &form.reason)
+            }
+            TransferKind::Split => {
+                let new_head = form.new_head.ok_or_else(|| DataError::Validation("请指定新户的户主".into()))?;
+                self.split_household(&source, &selected, new_head, &relationships, &form.address, &form.phone, date, &form.reason)
+                    .map(|_| ())
+            }
+            TransferKind::Merge => {
+                let target = form.target.ok_or_else(|| DataError::Validation("请选择合并到的户".into()))?;
+                let relationships: Vec<Relationship> = form.relationships.clone();
+                self.merge_households(&source, &target, &relationships, date, &form.reason)
+            }
+            TransferKind::MigrateOut => {
+                self.migrate_out(&source, &selected, &form.destination, date, &form.reason)
+            }
+        }
+    }
+    
+    /// 户间迁移：将成员从一户迁到另一户，`relationships` 为成员在新户中与户主的关系
+    pub fn transfer_members(
+        &mut self,
+        from: &Uuid,
+        member_indices: &[usize],
+        to: &Uuid,
+        relationships: &[Relationship],
+        date: NaiveDate,
+        reason: &str,
+    ) -> Result<(), DataError> {
+        if from == to {
+            return Err(DataError::Validation("迁出户和迁入户不能相同".into()));
+        }
+        if member_indices.is_empty() {
+            return Err(DataError::Validation("请选择要迁移的成员".into()));
+        }
+        if relationships.len() != member_indices.len() {
+            return Err(DataError::Validation("成员关系数量与迁移成员数量不一致".into()));
+        }
+        if relationships.iter().any(|r| r.is_head()) {
+            return Err(DataError::Validation("迁入成员不能作为新户的户主，请先办理变更户主".into()));
+        }
+        
+        let mut source = self.get_household_by_id(from)?.ok_or_else(|| DataError::NotFound("迁出户不存在".into()))?;
+        let mut target = self.get_household_by_id(to)?.ok_or_else(|| DataError::NotFound("迁入户不存在".into()))?;
+        
+        let moved = take_members(&mut source, member_indices)?;
+        if let Some(member) = moved.iter().find(|m| !m.is_active()) {
+            return Err(DataError::Validation(format!("{} 不是在册人口", member.name)));
+        }
+        if source.members.is_empty() {
+            return Err(DataError::Validation("不能迁出全部成员，请使用合户".into()));
+        }
+        if !source.members.iter().any(|m| m.relationship.is_head()) {
+            return Err(DataError::Validation("户主不能迁出，请先办理变更户主".into()));
+        }
+        
+        let mut events = Vec::new();
+        for (mut member, relationship) in moved.into_iter().zip(relationships.iter()) {
+            events.push(transfer_event(EventType::TransferOut, date, &source.id, Some(target.id), &member, "", reason));
+            events.push(transfer_event(EventType::TransferIn, date, &target.id, Some(source.id), &member, "", reason));
+            member.relationship = *relationship;
+            target.members.push(member);
+        }
+        
+        self.commit_changes(vec![source, target], &[], &events)?;
+        Ok(())
+    }
+    
+    /// 分户：将选中的成员从原户分出，组成新户，返回新户的ID
+    #[allow(clippy::too_many_arguments)]
+    pub fn split_household(
+        &mut self,
+        source_id: &Uuid,
+        member_indices: &[usize],
+        new_head: usize,
+        relationships: &[Relationship],
+        address: &Address,
+        phone: &str,
+        date: NaiveDate,
+        reason: &str,
+    ) -> Result<Uuid, DataError> {
+        if member_indices.is_empty() {
+            return Err(DataError::Validation("请选择要分出的成员".into()));
+        }
+        if !member_indices.contains(&new_head) {
+            return Err(DataError::Validation("新户户主必须是分出的成员之一".into()));
+        }
+        if relationships.len() != member_indices.len() {
+            return Err(DataError::Validation("成员关系数量与分出成员数量不一致".into()));
+        }
+        if address.province.is_empty() {
+            return Err(DataError::Validation("请选择新户地址的行政区划".into()));
+        }
+        
+        let mut source = self.get_household_by_id(source_id)?.ok_or_else(|| DataError::NotFound("原户不存在".into()))?;
+        let head_position = member_indices.iter().position(|&i| i == new_head).unwrap();
+        let moved = take_members(&mut source, member_indices)?;
+        if source.members.is_empty() {
+            return Err(DataError::Validation("分户后原户至少需要保留一名成员".into()));
+        }
+        if !source.members.iter().any(|m| m.relationship.is_head()) {
+            return Err(DataError::Validation("原户户主不能分出，请先办理变更户主".into()));
+        }
+        
+        let mut new_household = Household {
+            id: Uuid::new_v4(),
+            head_name: moved[head_position].name.clone(),
+            id_number: moved[head_position].id_number.clone(),
+            head_document_type: moved[head_position].document_type,
+            address: address.clone(),
+            dwelling_id: None,
+            phone: phone.to_string(),
+            household_type: source.household_type,
+            registration_date: chrono::Local::now().naive_local(),
+            members: Vec::new(),
+            revision: 0,
+        };
+        
+        let mut events = Vec::new();
+        for (i, (mut member, relationship)) in moved.into_iter().zip(relationships.iter()).enumerate() {
+            events.push(transfer_event(EventType::Split, date, &source.id, Some(new_household.id), &member, "", reason));
+            events.push(transfer_event(EventType::Split, date, &new_household.id, Some(source.id), &member, "", reason));
+            member.relationship = if i == head_position {
+                Relationship::HEAD
+            } else if relationship.is_head() {
+                Relationship::OTHER_RELATIVE
+            } else {
+                *relationship
+            };
+            new_household.members.push(member);
+        }
+        
+        let new_id = new_household.id;
+        self.commit_changes(vec![source, new_household], &[], &events)?;
+        Ok(new_id)
+    }
+    
+    /// 合户：将 `source` 户的全部成员并入 `target` 户并注销 `source` 户，
+    /// `relationships` 为原户各成员在新户中与户主的关系
+    pub fn merge_households(
+        &mut self,
+        source_id: &Uuid,
+        target_id: &Uuid,
+        relationships: &[Relationship],
+        date: NaiveDate,
+        reason: &str,
+    ) -> Result<(), DataError> {
+        if source_id == target_id {
+            return Err(DataError::Validation("不能与自身合户".into()));
+        }
+        if relationships.iter().any(|r| r.is_head()) {
+            return Err(DataError::Validation("并入成员不能作为户主，合户后户主为目标户的户主".into()));
+        }
+        
+        let source = self.get_household_by_id(source_id)?.ok_or_else(|| DataError::NotFound("原户不存在".into()))?;
+        let mut target = self.get_household_by_id(target_id)?.ok_or_else(|| DataError::NotFound("目标户不存在".into()))?;
+        if relationships.len() != source.members.len() {
+            return Err(DataError::Validation("成员关系数量与原户成员数量不一致".into()));
+        }
+        
+        let mut events = Vec::new();
+        for (mut member, relationship) in source.members.into_iter().zip(relationships.iter()) {
+            events.push(transfer_event(EventType::Merge, date, &target.id, Some(source.id), &member, "", reason));
+            member.relationship = *relationship;
+            target.members.push(member);
+        }
+        
+        self.commit_changes(vec![target], &[(source.id, source.revision)], &events)?;
+        Ok(())
+    }
+    
+    /// 迁出辖区：登记成员迁往外地，成员记录保留并标记为迁出
+    pub fn migrate_out(
+        &mut self,
+        household_id: &Uuid,
+        member_indices: &[usize],
+        destination: &str,
+        date: NaiveDate,
+        reason: &str,
+    ) -> Result<(), DataError> {
+        if member_indices.is_empty() {
+            return Err(DataError::Validation("请选择要迁出的成员".into()));
+        }
+        if destination.trim().is_empty() {
+            return Err(DataError::Validation("请填写迁往地".into()));
+        }
+        
+        let mut household = self.get_household_by_id(household_id)?.ok_or_else(|| DataError::NotFound("户籍不存在".into()))?;
+        let mut events = Vec::new();
+        for &i in member_indices {
+            let member = household.members.get_mut(i).ok_or_else(|| DataError::NotFound("成员不存在".into()))?;
+            if !member.is_active() {
+                return Err(DataError::Validation(format!("{} 不是在册人口", member.name)));
+            }
+            member.status = MemberStatus::MigratedOut;
+            events.push(transfer_event(EventType::MigrateOut, date, &household.id, None, member, destination, reason));
+        }
+        
+        // 仍有在册成员时户主不能迁出；全部迁出则整户迁出
+        let has_active = household.members.iter().any(|m| m.is_active());
+        let head_active = household.members.iter().any(|m| m.is_active() && m.relationship.is_head());
+        if has_active && !head_active {
+            return Err(DataError::Validation("户主迁出前请先办理变更户主".into()));
+        }
+        
+        self.commit_changes(vec![household], &[], &events)?;
+        Ok(())
+    }
+    
+    /// 变更户主：`relationships` 为各成员与新户主的关系（通常来自 `HeadChangeForm::preview`）
+    pub fn change_head(
+        &mut self,
+        household_id: &Uuid,
+        new_head: usize,
+        relationships: &[Relationship],
+        date: NaiveDate,
+        reason: &str,
+    ) -> Result<(), DataError> {
+        let mut household = self.get_household_by_id(household_id)?.ok_or_else(|| DataError::NotFound("户籍不存在".into()))?;
+        if relationships.len() != household.members.len() {
+            return Err(DataError::Validation("成员关系数量与户内成员数量不一致".into()));
+        }
+        let candidate = household.members.get(new_head).ok_or_else(|| DataError::NotFound("成员不存在".into()))?;
+        if !candidate.is_active() {
+            return Err(DataError::Validation(format!("{} 不是在册人口，不能作为户主", candidate.name)));
+        }
+        if candidate.relationship.is_head() {
+            return Err(DataError::Validation(format!("{} 已是户主", candidate.name)));
+        }
+        if !relationships[new_head].is_head() {
+            return Err(DataError::Validation("新户主的关系必须为户主".into()));
+        }
+        if relationships.iter().filter(|r| r.is_head()).count() != 1 {
+            return Err(DataError::Validation("只能有一名户主".into()));
+        }
+        
+        for (member, relationship) in household.members.iter_mut().zip(relationships.iter()) {
+            member.relationship = *relationship;
+        }
+        
+        let event = transfer_event(EventType::HeadChange, date, &household.id, None, &household.members[new_head], "", reason);
+        self.commit_changes(vec![household], &[], &[event])?;
+        Ok(())
+    }
+    
+    /// 按登记表单办理出生、死亡、结婚或离婚登记
+    pub fn execute_vital_event(&mut self, form: &VitalEventForm) -> Result<(), DataError> {
+        let date = form.event_date().ok_or_else(|| DataError::Validation("无效的登记日期".into()))?;
+        
+        match form.kind {
+            EventType::Birth => {
+                form.new_member.validate().map_err(|e| DataError::Validation(e.to_string()))?;
+                let member = form.new_member.to_member().map_err(DataError::Validation)?;
+                self.register_birth(&form.household_id, member, &form.reason)
+            }
+            EventType::Death => {
+                let index = form.member_index.ok_or_else(|| DataError::Validation("请选择死亡人员".into()))?;
+                self.register_death(&form.household_id, index, date, &form.reason)
+            }
+            EventType::Marriage => {
+                let index = form.member_index.ok_or_else(|| DataError::Validation("请选择登记结婚的成员".into()))?;
+                let spouse = if form.partner_joins {
+                    form.new_member.validate().map_err(|e| DataError::Validation(e.to_string()))?;
+                    Some(form.new_member.to_member().map_err(DataError::Validation)?)
+                } else {
+                    None
+                };
+                self.register_marriage(&form.household_id, index, form.partner_index, spouse, date, &form.reason)
+            }
+            EventType::Divorce => {
+                let index = form.member_index.ok_or_else(|| DataError::Validation("请选择登记离婚的成员".into()))?;
+                let destination = if form.partner_leaves {
+                    Some(form.destination.as_str())
+                } else {
+                    None
+                };
+                self.register_divorce(&form.household_id, index, form.partner_index, destination, date, &form.reason)
+            }
+            _ => Err(DataError::Validation("不支持的登记类型".into())),
+        }
+    }
+    
+    /// 出生登记：新生儿以在册状态落户，登记日期即出生日期
+    pub fn register_birth(&mut self, household_id: &Uuid, mut member: Member, reason: &str) -> Result<(), DataError> {
+        if member.name.trim().is_empty() {
+            return Err(DataError::Validation("姓名不能为空".into()));
+        }
+        if member.relationship.is_head() {
+            return Err(DataError::Validation("新生儿不能登记为户主".into()));
+        }
+        if member.birth_date > chrono::Local::now().date_naive() {
+            return Err(DataError::Validation("出生日期不能晚于今天".into()));
+        }
+        
+        let mut household = self.get_household_by_id(household_id)?.ok_or_else(|| DataError::NotFound("户籍不存在".into()))?;
+        member.status = MemberStatus::Active;
+        member.marital_status = MaritalStatus::Unmarried;
+        member.death_date = None;
+        
+        let event = transfer_event(EventType::Birth, member.birth_date, &household.id, None, &member, "", reason);
+        household.members.push(member);
+        
+        self.commit_changes(vec![household], &[], &[event])?;
         Ok(())
     }
     
-    pub fn search(&mut self, query: &str) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    /// 死亡注销：成员状态改为注销并记录死亡日期，其配偶婚姻状况改为丧偶
+    pub fn register_death(&mut self, household_id: &Uuid, member_index: usize, death_date: NaiveDate, reason: &str) -> Result<(), DataError> {
+        let mut household = self.get_household_by_id(household_id)?.ok_or_else(|| DataError::NotFound("户籍不存在".into()))?;
+        let member = household.members.get(member_index).ok_or_else(|| DataError::NotFound("成员不存在".into()))?;
+        if !member.is_active() {
+            return Err(DataError::Validation(format!("{} 不是在册人口", member.name)));
+        }
+        if death_date < member.birth_date || death_date > chrono::Local::now().date_naive() {
+            return Err(DataError::Validation("死亡日期不合理".into()));
+        }
+        
+        // 户主去世则配偶丧偶，配偶去世则户主丧偶
+        let deceased_is_head = member.relationship.is_head();
+        let deceased_is_spouse = member.relationship.is_spouse();
+        if deceased_is_head || deceased_is_spouse {
+            for spouse in household.members.iter_mut().filter(|m| {
+                m.is_active()
+                    && m.marital_status == MaritalStatus::Married
+                    && ((deceased_is_head && m.relationship.is_spouse()) || (deceased_is_spouse && m.relationship.is_head()))
+            }) {
+                spouse.marital_status = MaritalStatus::Widowed;
+            }
+        }
+        
+        let member = &mut household.members[member_index];
+        member.status = MemberStatus::Cancelled;
+        member.death_date = Some(death_date);
+        let event = transfer_event(EventType::Death, death_date, &household.id, None, member, "", reason);
+        
+        self.commit_changes(vec![household], &[], &[event])?;
+        Ok(())
+    }
+    
+    /// 结婚登记：配偶可以是户内已有成员，也可以是随结婚迁入的新成员
+    pub fn register_marriage(
+        &mut self,
+        household_id: &Uuid,
+        member_index: usize,
+        partner_index: Option<usize>,
+        new_spouse: Option<Member>,
+        date: NaiveDate,
+        reason: &str,
+    ) -> Result<(), DataError> {
+        let mut household = self.get_household_by_id(household_id)?.ok_or_else(|| DataError::NotFound("户籍不存在".into()))?;
+        let member = household.members.get(member_index).ok_or_else(|| DataError::NotFound("成员不存在".into()))?;
+        if !member.is_active() {
+            return Err(DataError::Validation(format!("{} 不是在册人口", member.name)));
+        }
+        if member.marital_status == MaritalStatus::Married {
+            return Err(DataError::Validation(format!("{} 已是已婚状态", member.name)));
+        }
+        
+        let mut events = Vec::new();
+        match (partner_index, new_spouse) {
+            (Some(partner_index), None) => {
+                if partner_index == member_index {
+                    return Err(DataError::Validation("不能与本人登记结婚".into()));
+                }
+                let partner = household.members.get_mut(partner_index).ok_or_else(|| DataError::NotFound("配偶不存在".into()))?;
+                if !partner.is_active() || partner.marital_status == MaritalStatus::Married {
+                    return Err(DataError::Validation(format!("{} 不能登记结婚", partner.name)));
+                }
+                partner.marital_status = MaritalStatus::Married;
+                events.push(transfer_event(EventType::Marriage, date, &household.id, None, partner, "", reason));
+            }
+            (None, Some(mut spouse)) => {
+                if spouse.name.trim().is_empty() {
+                    return Err(DataError::Validation("配偶姓名不能为空".into()));
+                }
+                if spouse.relationship.is_head() {
+                    return Err(DataError::Validation("迁入的配偶不能登记为户主".into()));
+                }
+                spouse.status = MemberStatus::Active;
+                spouse.marital_status = MaritalStatus::Married;
+                spouse.death_date = None;
+                events.push(transfer_event(EventType::Marriage, date, &household.id, None, &spouse, "", reason));
+                events.push(transfer_event(EventType::TransferIn, date, &household.id, None, &spouse, "", "结婚迁入"));
+                household.members.push(spouse);
+            }
+            _ => return Err(DataError::Validation("请指定配偶".into())),
+        }
+        
+        let member = &mut household.members[member_index];
+        member.marital_status = MaritalStatus::Married;
+        events.insert(0, transfer_event(EventType::Marriage, date, &household.id, None, member, "", reason));
+        
+        self.commit_changes(vec![household], &[], &events)?;
+        Ok(())
+    }
+    
+    /// 离婚登记：双方婚姻状况改为离婚，可同时登记配偶迁出
+    pub fn register_divorce(
+        &mut self,
+        household_id: &Uuid,
+        member_index: usize,
+        partner_index: Option<usize>,
+        partner_destination: Option<&str>,
+        date: NaiveDate,
+        reason: &str,
+    ) -> Result<(), DataError> {
+        let mut household = self.get_household_by_id(household_id)?.ok_or_else(|| DataError::NotFound("户籍不存在".into()))?;
+        let member = household.members.get_mut(member_index).ok_or_else(|| DataError::NotFound("成员不存在".into()))?;
+        if !member.is_active() {
+            return Err(DataError::Validation(format!("{} 不是在册人口", member.name)));
+        }
+        if member.marital_status != MaritalStatus::Married {
+            return Err(DataError::Validation(format!("{} 不是已婚状态", member.name)));
+        }
+        member.marital_status = MaritalStatus::Divorced;
+        let mut events = vec![transfer_event(EventType::Divorce, date, &household.id, None, member, "", reason)];
+        
+        if let Some(partner_index) = partner_index {
+            if partner_index == member_index {
+                return Err(DataError::Validation("不能与本人登记离婚".into()));
+            }
+            let partner = household.members.get_mut(partner_index).ok_or_else(|| DataError::NotFound("配偶不存在".into()))?;
+            if !partner.is_active() {
+                return Err(DataError::Validation(format!("{} 不是在册人口", partner.name)));
+            }
+            if partner.marital_status != MaritalStatus::Married {
+                return Err(DataError::Validation(format!("{} 不是已婚状态", partner.name)));
+            }
+            partner.marital_status = MaritalStatus::Divorced;
+            events.push(transfer_event(EventType::Divorce, date, &household.id, None, partner, "", reason));
+            
+            if let Some(destination) = partner_destination {
+                if destination.trim().is_empty() {
+                    return Err(DataError::Validation("请填写配偶迁往地".into()));
+                }
+                if partner.relationship.is_head() {
+                    return Err(DataError::Validation("户主迁出前请先办理变更户主".into()));
+                }
+                partner.status = MemberStatus::MigratedOut;
+                events.push(transfer_event(EventType::MigrateOut, date, &household.id, None, partner, destination, "离婚迁出"));
+            }
+        }
+        
+        self.commit_changes(vec![household], &[], &events)?;
+        Ok(())
+    }
+    
+    pub fn search(&mut self, query: &str) -> Result<Vec<usize>, DataError> {
         if query.is_empty() {
             self.refresh_cache()?;
             let count = self.households_cache.len();
             Ok((0..count).collect())
         } else {
-            let households = self.database.search_households(query)?;
+            let matched_ids = self.repository.search_households(query)?;
             let all_households = self.get_households()?;
             
             let mut indices = Vec::new();
             for (i, household) in all_households.iter().enumerate() {
-                if households.iter().any(|h| h.id == household.id) {
+                if matched_ids.contains(&household.id) {
                     indices.push(i);
                 }
             }
@@ -157,13 +916,209 @@ impl HouseholdManager {
         }
     }
     
-    pub fn count(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+    pub fn count(&mut self) -> Result<usize, DataError> {
         self.refresh_cache()?;
         Ok(self.households_cache.len())
     }
     
-    pub fn get_statistics(&mut self) -> Result<HouseholdStatistics, Box<dyn std::error::Error>> {
-        Ok(self.database.get_statistics()?)
+    pub fn get_statistics(&mut self) -> Result<HouseholdStatistics, DataError> {
+        self.repository.get_statistics()
+    }
+    
+    /// 全部门牌，按社区、楼栋、单元、房号排序
+    pub fn dwellings(&mut self) -> Result<Vec<Dwelling>, DataError> {
+        self.refresh_cache()?;
+        Ok(self.dwellings_cache.clone())
+    }
+    
+    /// 新增或修改门牌
+    pub fn save_dwelling(&mut self, dwelling: Dwelling) -> Result<(), DataError> {
+        if dwelling.division.is_empty() {
+            return Err(DataError::Validation("请选择门牌所在社区".into()));
+        }
+        if dwelling.building.is_empty() || dwelling.room.is_empty() {
+            return Err(DataError::Validation("楼栋和房号不能为空".into()));
+        }
+        self.refresh_cache()?;
+        if let Some(existing) = self.dwellings_cache.iter().find(|d| d.id != dwelling.id && d.same_place(&dwelling)) {
+            return Err(DataError::Validation(format!("门牌 {} 已存在", existing)));
+        }
+        self.repository.save_dwelling(&dwelling)?;
+        self.cache_dirty = true;
+        Ok(())
+    }
+    
+    /// 保存表单草稿，不影响户籍缓存
+    pub fn save_draft(&mut self, draft: &Draft) -> Result<(), DataError> {
+        self.repository.save_draft(draft)
+    }
+    
+    pub fn drafts(&mut self, operator: &str) -> Result<Vec<Draft>, DataError> {
+        self.repository.load_drafts(operator)
+    }
+    
+    pub fn discard_draft(&mut self, draft_id: &Uuid) -> Result<(), DataError> {
+        self.repository.delete_draft(draft_id)
+    }
+    
+    /// 保存附件，文件格式和大小已在 `Attachment::new` 中检查；不影响户籍缓存
+    pub fn add_attachment(&mut self, attachment: &Attachment, data: &[u8]) -> Result<(), DataError> {
+        self.repository.save_attachment(attachment, data)
+    }
+    
+    /// 户籍本身及证件号码为 `member_id_numbers` 的成员的附件
+    pub fn attachments<'a>(
+        &mut self,
+        household_id: &Uuid,
+        member_id_numbers: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<Attachment>, DataError> {
+        let id_numbers: Vec<String> = member_id_numbers.into_iter()
+            .filter(|id_number| !id_number.trim().is_empty())
+            .map(String::from)
+            .collect();
+        self.repository.load_attachments(household_id, &id_numbers)
+    }
+    
+    pub fn attachment_data(&mut self, attachment: &Attachment) -> Result<Vec<u8>, DataError> {
+        self.repository.attachment_data(&attachment.hash)?
+            .ok_or_else(|| DataError::NotFound(format!("附件 {} 的内容已不存在", attachment.file_name)))
+    }
+    
+    pub fn remove_attachment(&mut self, attachment_id: &Uuid) -> Result<(), DataError> {
+        self.repository.delete_attachment(attachment_id)
+    }
+    
+    /// 全部户籍的 JSON，每户的 `attachments` 中附带其本身及成员的附件内容；
+    /// 成员先后登记在多户时其附件只随第一户导出
+    pub fn export_json(&mut self) -> Result<serde_json::Value, DataError> {
+        let households = self.get_households()?;
+        let mut exported_ids = std::collections::HashSet::new();
+        let mut items = Vec::with_capacity(households.len());
+        for household in &households {
+            let mut item = serde_json::to_value(household).map_err(|e| DataError::Io(e.to_string()))?;
+            let mut attachments = Vec::new();
+            for attachment in self.attachments(&household.id, household.members.iter().map(|m| m.id_number.as_str()))? {
+                if exported_ids.insert(attachment.id) {
+                    let data = self.attachment_data(&attachment)?;
+                    attachments.push(ExportedAttachment::encode(&attachment, &data));
+                }
+            }
+            if !attachments.is_empty() {
+                item["attachments"] = serde_json::to_value(attachments).map_err(|e| DataError::Io(e.to_string()))?;
+            }
+            items.push(item);
+        }
+        Ok(serde_json::Value::Array(items))
+    }
+    
+    /// 导入随户籍导出的附件，附件ID已存在的覆盖其说明
+    pub fn import_attachments(&mut self, attachments: &[(Attachment, Vec<u8>)]) -> Result<(), DataError> {
+        for (attachment, data) in attachments {
+            self.repository.save_attachment(attachment, data)?;
+        }
+        Ok(())
+    }
+    
+    /// 记录查看完整信息、导出等操作，不影响户籍缓存
+    pub fn record_audit(&mut self, entry: &AuditEntry) -> Result<(), DataError> {
+        self.repository.record_audit(entry)
+    }
+    
+    pub fn audit_log(&mut self, limit: usize) -> Result<Vec<AuditEntry>, DataError> {
+        self.repository.load_audit(limit)
+    }
+    
+    pub fn remove_dwelling(&mut self, dwelling_id: &Uuid) -> Result<(), DataError> {
+        self.repository.delete_dwelling(dwelling_id)?;
+        self.cache_dirty = true;
+        Ok(())
+    }
+    
+    /// 各门牌的居住户数和人数
+    pub fn occupancy(&mut self) -> Result<Vec<Occupancy>, DataError> {
+        self.refresh_cache()?;
+        let households: Vec<Household> = self.households_cache.values().cloned().collect();
+        Ok(dwellings::occupancy(&self.dwellings_cache, &households))
+    }
+    
+    /// 存储完整性检查（SQLite 为文件校验），返回发现的问题，没有问题时为空
+    pub fn integrity_check(&self) -> Result<Vec<String>, DataError> {
+        self.repository.integrity_check()
+    }
+    
+    /// 将数据库备份到指定文件，目标文件已存在时报错
+    pub fn backup(&self, path: &str) -> Result<(), DataError> {
+        if std::path::Path::new(path).exists() {
+            return Err(DataError::Validation(format!("备份文件 {} 已存在", path)));
+        }
+        self.repository.backup(path)?;
+        Ok(())
+    }
+    
+    /// 按任一级行政区划分组统计
+    pub fn count_by_division(&self, level: DivisionLevel) -> Result<Vec<DivisionCount>, DataError> {
+        self.repository.count_by_division(level)
+    }
+    
+    /// 迁移时无法确定行政区划、需要人工核对地址的户籍
+    pub fn pending_address_reviews(&mut self) -> Result<Vec<Household>, DataError> {
+        self.refresh_cache()?;
+        let ids = self.repository.address_review_ids()?;
+        Ok(ids.iter().filter_map(|id| self.households_cache.get(id).cloned()).collect())
+    }
+    
+    pub fn address_review_count(&self) -> Result<usize, DataError> {
+        Ok(self.repository.address_review_ids()?.len())
+    }
+    
+    pub fn resolve_address(&mut self, household_id: &Uuid, address: &Address) -> Result<(), DataError> {
+        if address.province.is_empty() {
+            return Err(DataError::Validation("请选择所在行政区划".into()));
+        }
+        self.remember([*household_id])?;
+        self.repository.resolve_address(household_id, address)?;
+        self.cache_dirty = true;
+        Ok(())
+    }
+}
+
+// 按下标从户中取出成员，返回顺序与 `indices` 一致
+fn take_members(household: &mut Household, indices: &[usize]) -> Result<Vec<Member>, DataError> {
+    if indices.iter().any(|&i| i >= household.members.len()) {
+        return Err(DataError::NotFound("成员不存在".into()));
+    }
+    if indices.iter().enumerate().any(|(n, i)| indices[..n].contains(i)) {
+        return Err(DataError::Validation("成员重复选择".into()));
+    }
+    
+    let moved = indices.iter().map(|&i| household.members[i].clone()).collect();
+    let mut index = 0;
+    household.members.retain(|_| {
+        let keep = !indices.contains(&index);
+        index += 1;
+        keep
+    });
+    Ok(moved)
+}
+
+fn transfer_event(
+    event_type: EventType,
+    event_date: NaiveDate,
+    household_id: &Uuid,
+    related_household_id: Option<Uuid>,
+    member: &Member,
+    destination: &str,
+    reason: &str,
+) -> HouseholdEvent {
+    HouseholdEvent {
+        event_type,
+        event_date,
+        household_id: *household_id,
+        related_household_id,
+        member_name: member.name.clone(),
+        member_id_number: member.id_number.clone(),
+        destination: destination.to_string(),
+        reason: reason.to_string(),
     }
 }
 
@@ -173,4 +1128,557 @@ pub struct HouseholdStatistics {
     pub urban_households: usize,
     pub rural_households: usize,
     pub total_members: usize,
+    pub inactive_members: usize,
+    pub non_mainland_members: usize,
+}
+
+/// 某一行政区划的户数和在册人口
+#[derive(Debug, Clone)]
+pub struct DivisionCount {
+    /// 区划代码，未选择该级区划的户为空
+    pub code: String,
+    pub households: usize,
+    pub members: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::memory::MemoryRepository;
+
+    fn member(name: &str, id_number: &str, relationship: Relationship) -> Member {
+        Member {
+            name: name.to_string(),
+            document_type: DocumentType::ResidentId,
+            id_number: id_number.to_string(),
+            nationality: "CHN".to_string(),
+            relationship,
+            birth_date: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
+            gender: Gender::Male,
+            education: Education::University,
+            occupation: String::new(),
+            status: MemberStatus::Active,
+            marital_status: MaritalStatus::Unmarried,
+            death_date: None,
+        }
+    }
+
+    fn household(members: Vec<Member>) -> Household {
+        Household {
+            id: Uuid::new_v4(),
+            head_name: String::new(),
+            id_number: String::new(),
+            head_document_type: DocumentType::ResidentId,
+            address: Address::from_division("110105001", "XXX路XXX号"),
+            dwelling_id: None,
+            phone: String::new(),
+            household_type: HouseholdType::Urban,
+            registration_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            members,
+            revision: 0,
+        }
+    }
+
+    fn manager() -> HouseholdManager<MemoryRepository> {
+        HouseholdManager::with_repository(MemoryRepository::new())
+    }
+
+    // 两户中各有一个姓名、出生日期相同而证件号码不同的“赵六”，返回两户和查重结果中的两条引用
+    fn duplicate_members(manager: &mut HouseholdManager<MemoryRepository>) -> (Household, Household, Vec<MemberRef>) {
+        let first = household(vec![
+            member("张三", "110101199001011234", Relationship::HEAD),
+            member("赵六", "110101199001012345", Relationship::SON),
+        ]);
+        let second = household(vec![
+            member("王五", "110101198506061236", Relationship::HEAD),
+            member("钱七", "110101198506062347", Relationship::DAUGHTER),
+            member("赵六", "110101199001013456", Relationship::SON),
+        ]);
+        manager.add_household(first.clone()).unwrap();
+        manager.add_household(second.clone()).unwrap();
+        let groups = manager.find_duplicates().unwrap();
+        let refs = groups.into_iter()
+            .flat_map(|g| g.entries)
+            .filter_map(|e| match e {
+                duplicates::DuplicateEntry::Member { member, .. } => Some(member),
+                duplicates::DuplicateEntry::Household { .. } => None,
+            })
+            .collect();
+        (first, second, refs)
+    }
+
+    #[test]
+    fn resolve_duplicate_member_removes_other_record() {
+        let mut manager = manager();
+        let (first, second, refs) = duplicate_members(&mut manager);
+        let keep = refs.iter().find(|r| r.household_id == first.id).unwrap().clone();
+        manager.resolve_duplicate_member(&keep, &refs).unwrap();
+
+        let second = manager.get_household_by_id(&second.id).unwrap().unwrap();
+        assert_eq!(second.members.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["王五", "钱七"]);
+        assert_eq!(manager.get_household_by_id(&first.id).unwrap().unwrap().members.len(), 2);
+    }
+
+    #[test]
+    fn resolve_duplicate_member_rejects_stale_scan() {
+        let mut manager = manager();
+        let (first, second, refs) = duplicate_members(&mut manager);
+        let keep = refs.iter().find(|r| r.household_id == first.id).unwrap().clone();
+
+        // 查重后其他用户删除了“钱七”并新增“孙八”，原下标处已是另一人
+        let mut changed = manager.get_household_by_id(&second.id).unwrap().unwrap();
+        changed.members.remove(1);
+        changed.members.push(member("孙八", "110101200001014567", Relationship::SON));
+        manager.update_household(changed).unwrap();
+
+        let result = manager.resolve_duplicate_member(&keep, &refs);
+        assert!(matches!(result, Err(DataError::Conflict(id)) if id == second.id));
+        let second = manager.get_household_by_id(&second.id).unwrap().unwrap();
+        assert_eq!(second.members.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["王五", "赵六", "孙八"]);
+    }
+
+    #[test]
+    fn resolve_duplicate_member_keeps_head_of_remaining_household() {
+        let mut manager = manager();
+        let first = household(vec![member("赵六", "110101199001012345", Relationship::HEAD)]);
+        let second = household(vec![
+            member("赵六", "110101199001013456", Relationship::HEAD),
+            member("钱七", "110101198506062347", Relationship::DAUGHTER),
+        ]);
+        manager.add_household(first.clone()).unwrap();
+        manager.add_household(second.clone()).unwrap();
+        let keep = MemberRef { household_id: first.id, member_index: 0, id_number: "110101199001012345".to_string() };
+        let remove = MemberRef { household_id: second.id, member_index: 0, id_number: "110101199001013456".to_string() };
+
+        let result = manager.resolve_duplicate_member(&keep, &[keep.clone(), remove]);
+        assert!(matches!(result, Err(DataError::Validation(_))));
+        assert_eq!(manager.get_household_by_id(&second.id).unwrap().unwrap().members.len(), 2);
+    }
+
+    #[test]
+    fn resolve_duplicate_member_deletes_emptied_household() {
+        let mut manager = manager();
+        let first = household(vec![member("赵六", "110101199001012345", Relationship::HEAD)]);
+        let second = household(vec![member("赵六", "110101199001013456", Relationship::HEAD)]);
+        manager.add_household(first.clone()).unwrap();
+        manager.add_household(second.clone()).unwrap();
+        let keep = MemberRef { household_id: first.id, member_index: 0, id_number: "110101199001012345".to_string() };
+        let remove = MemberRef { household_id: second.id, member_index: 0, id_number: "110101199001013456".to_string() };
+
+        manager.resolve_duplicate_member(&keep, &[remove]).unwrap();
+        assert!(manager.get_household_by_id(&second.id).unwrap().is_none());
+        assert_eq!(manager.count().unwrap(), 1);
+    }
+
+    // 户主张三、妻李四、子张小组成的一户，以及只有户主王五的一户
+    fn two_households(manager: &mut HouseholdManager<MemoryRepository>) -> (Household, Household) {
+        let family = household(vec![
+            member("张三", "110101198001011234", Relationship::HEAD),
+            member("李四", "110101198202022345", Relationship::WIFE),
+            member("张小", "110101201003033456", Relationship::SON),
+        ]);
+        let other = household(vec![member("王五", "110101197504046789", Relationship::HEAD)]);
+        manager.add_household(family.clone()).unwrap();
+        manager.add_household(other.clone()).unwrap();
+        (family, other)
+    }
+
+    fn names(manager: &mut HouseholdManager<MemoryRepository>, household_id: &Uuid) -> Vec<String> {
+        let household = manager.get_household_by_id(household_id).unwrap().unwrap();
+        household.members.into_iter().map(|m| m.name).collect()
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+    }
+
+    #[test]
+    fn transfer_members_moves_members_and_records_events() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        manager.transfer_members(&family.id, &[2], &other.id, &[Relationship::OTHER_RELATIVE], date(), "投亲").unwrap();
+
+        assert_eq!(names(&mut manager, &family.id), ["张三", "李四"]);
+        let target = manager.get_household_by_id(&other.id).unwrap().unwrap();
+        assert_eq!(target.members[1].name, "张小");
+        assert_eq!(target.members[1].relationship, Relationship::OTHER_RELATIVE);
+        let out = manager.get_events(&family.id).unwrap();
+        assert!(out.iter().any(|e| e.event_type == EventType::TransferOut && e.member_name == "张小" && e.reason == "投亲"));
+        let into = manager.get_events(&other.id).unwrap();
+        assert!(into.iter().any(|e| e.event_type == EventType::TransferIn && e.related_household_id == Some(family.id)));
+    }
+
+    #[test]
+    fn transfer_members_requires_relationship_per_member() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        let result = manager.transfer_members(&family.id, &[1, 2], &other.id, &[Relationship::OTHER_RELATIVE], date(), "");
+        assert!(matches!(result, Err(DataError::Validation(_))), "{:?}", result);
+        assert_eq!(names(&mut manager, &family.id), ["张三", "李四", "张小"]);
+        assert_eq!(names(&mut manager, &other.id), ["王五"]);
+        assert!(manager.get_events(&family.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn transfer_members_rejects_head_and_inactive_members() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        let head = manager.transfer_members(&family.id, &[0], &other.id, &[Relationship::OTHER_RELATIVE], date(), "");
+        assert!(matches!(head, Err(DataError::Validation(_))), "{:?}", head);
+
+        let mut changed = manager.get_household_by_id(&family.id).unwrap().unwrap();
+        changed.members[2].status = MemberStatus::Cancelled;
+        manager.update_household(changed).unwrap();
+        let inactive = manager.transfer_members(&family.id, &[2], &other.id, &[Relationship::OTHER_RELATIVE], date(), "");
+        assert!(matches!(inactive, Err(DataError::Validation(_))), "{:?}", inactive);
+        assert_eq!(names(&mut manager, &other.id), ["王五"]);
+    }
+
+    #[test]
+    fn split_household_creates_household_with_new_head() {
+        let mut manager = manager();
+        let (family, _) = two_households(&mut manager);
+        let address = Address::from_division("110105001", "YYY路YYY号");
+        let new_id = manager
+            .split_household(&family.id, &[1, 2], 1, &[Relationship::WIFE, Relationship::SON], &address, "13800138000", date(), "分户")
+            .unwrap();
+
+        assert_eq!(names(&mut manager, &family.id), ["张三"]);
+        let split = manager.get_household_by_id(&new_id).unwrap().unwrap();
+        assert_eq!(split.head_name, "李四");
+        assert_eq!(split.members.iter().map(|m| m.relationship).collect::<Vec<_>>(), [Relationship::HEAD, Relationship::SON]);
+        assert_eq!(split.address, address);
+        let events = manager.get_events(&new_id).unwrap();
+        assert_eq!(events.iter().filter(|e| e.event_type == EventType::Split).count(), 2);
+    }
+
+    #[test]
+    fn split_household_requires_relationship_per_member() {
+        let mut manager = manager();
+        let (family, _) = two_households(&mut manager);
+        let address = Address::from_division("110105001", "YYY路YYY号");
+        let result = manager.split_household(&family.id, &[1, 2], 1, &[Relationship::HEAD], &address, "", date(), "");
+        assert!(matches!(result, Err(DataError::Validation(_))), "{:?}", result);
+        assert_eq!(manager.count().unwrap(), 2);
+        assert_eq!(names(&mut manager, &family.id), ["张三", "李四", "张小"]);
+    }
+
+    #[test]
+    fn split_household_keeps_head_in_source() {
+        let mut manager = manager();
+        let (family, _) = two_households(&mut manager);
+        let address = Address::from_division("110105001", "YYY路YYY号");
+        let relationships = [Relationship::HEAD, Relationship::SON];
+        let result = manager.split_household(&family.id, &[0, 2], 0, &relationships, &address, "", date(), "");
+        assert!(matches!(result, Err(DataError::Validation(_))), "{:?}", result);
+        assert_eq!(manager.count().unwrap(), 2);
+    }
+
+    #[test]
+    fn merge_households_moves_all_members_and_deletes_source() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        let relationships = [Relationship::OTHER_RELATIVE; 3];
+        manager.merge_households(&family.id, &other.id, &relationships, date(), "合户").unwrap();
+
+        assert!(manager.get_household_by_id(&family.id).unwrap().is_none());
+        assert_eq!(names(&mut manager, &other.id), ["王五", "张三", "李四", "张小"]);
+        let events = manager.get_events(&other.id).unwrap();
+        assert_eq!(events.iter().filter(|e| e.event_type == EventType::Merge).count(), 3);
+    }
+
+    #[test]
+    fn merge_households_rejects_mismatched_relationships_and_head() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        let short = manager.merge_households(&family.id, &other.id, &[Relationship::OTHER_RELATIVE; 2], date(), "");
+        assert!(matches!(short, Err(DataError::Validation(_))), "{:?}", short);
+        let relationships = [Relationship::HEAD, Relationship::OTHER_RELATIVE, Relationship::OTHER_RELATIVE];
+        let head = manager.merge_households(&family.id, &other.id, &relationships, date(), "");
+        assert!(matches!(head, Err(DataError::Validation(_))), "{:?}", head);
+        assert_eq!(manager.count().unwrap(), 2);
+    }
+
+    #[test]
+    fn migrate_out_marks_members_and_records_destination() {
+        let mut manager = manager();
+        let (family, _) = two_households(&mut manager);
+        manager.migrate_out(&family.id, &[2], "上海市浦东新区", date(), "就学").unwrap();
+
+        let household = manager.get_household_by_id(&family.id).unwrap().unwrap();
+        assert_eq!(household.members[2].status, MemberStatus::MigratedOut);
+        let events = manager.get_events(&family.id).unwrap();
+        assert!(events.iter().any(|e| e.event_type == EventType::MigrateOut && e.destination == "上海市浦东新区"));
+
+        // 已迁出的成员不能再次迁出
+        let again = manager.migrate_out(&family.id, &[2], "上海市浦东新区", date(), "");
+        assert!(matches!(again, Err(DataError::Validation(_))), "{:?}", again);
+    }
+
+    #[test]
+    fn migrate_out_keeps_head_while_members_remain() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        let head = manager.migrate_out(&family.id, &[0], "上海市", date(), "");
+        assert!(matches!(head, Err(DataError::Validation(_))), "{:?}", head);
+        assert!(manager.get_events(&family.id).unwrap().is_empty());
+
+        // 只有户主一人时整户迁出
+        manager.migrate_out(&other.id, &[0], "上海市", date(), "").unwrap();
+        let household = manager.get_household_by_id(&other.id).unwrap().unwrap();
+        assert!(!household.members[0].is_active());
+    }
+
+    // 户主与妻子已婚的一户，返回户籍ID
+    fn married_couple(manager: &mut HouseholdManager<MemoryRepository>) -> Uuid {
+        let (family, _) = two_households(manager);
+        let mut changed = manager.get_household_by_id(&family.id).unwrap().unwrap();
+        changed.members[0].marital_status = MaritalStatus::Married;
+        changed.members[1].marital_status = MaritalStatus::Married;
+        changed.members[1].gender = Gender::Female;
+        manager.update_household(changed).unwrap();
+        family.id
+    }
+
+    fn events_of(manager: &mut HouseholdManager<MemoryRepository>, household_id: &Uuid, event_type: EventType) -> usize {
+        manager.get_events(household_id).unwrap().iter().filter(|e| e.event_type == event_type).count()
+    }
+
+    #[test]
+    fn register_birth_adds_active_member() {
+        let mut manager = manager();
+        let (family, _) = two_households(&mut manager);
+        let mut baby = member("张二小", "110101202301014567", Relationship::DAUGHTER);
+        baby.birth_date = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        baby.status = MemberStatus::Cancelled;
+        manager.register_birth(&family.id, baby.clone(), "").unwrap();
+
+        let household = manager.get_household_by_id(&family.id).unwrap().unwrap();
+        assert!(household.members[3].is_active());
+        let events = manager.get_events(&family.id).unwrap();
+        assert!(events.iter().any(|e| e.event_type == EventType::Birth && e.event_date == baby.birth_date));
+
+        let mut head = baby.clone();
+        head.relationship = Relationship::HEAD;
+        assert!(matches!(manager.register_birth(&family.id, head, ""), Err(DataError::Validation(_))));
+        let mut unborn = baby;
+        unborn.birth_date = chrono::Local::now().date_naive() + chrono::Duration::days(1);
+        assert!(matches!(manager.register_birth(&family.id, unborn, ""), Err(DataError::Validation(_))));
+    }
+
+    #[test]
+    fn register_death_widows_spouse() {
+        let mut manager = manager();
+        let household_id = married_couple(&mut manager);
+        manager.register_death(&household_id, 0, date(), "").unwrap();
+
+        let household = manager.get_household_by_id(&household_id).unwrap().unwrap();
+        assert_eq!(household.members[0].status, MemberStatus::Cancelled);
+        assert_eq!(household.members[0].death_date, Some(date()));
+        assert_eq!(household.members[1].marital_status, MaritalStatus::Widowed);
+        assert_eq!(events_of(&mut manager, &household_id, EventType::Death), 1);
+
+        let again = manager.register_death(&household_id, 0, date(), "");
+        assert!(matches!(again, Err(DataError::Validation(_))), "{:?}", again);
+    }
+
+    #[test]
+    fn register_marriage_with_member_or_new_spouse() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        manager.register_marriage(&family.id, 0, Some(1), None, date(), "").unwrap();
+        let household = manager.get_household_by_id(&family.id).unwrap().unwrap();
+        assert!(household.members[..2].iter().all(|m| m.marital_status == MaritalStatus::Married));
+        assert_eq!(events_of(&mut manager, &family.id, EventType::Marriage), 2);
+
+        let again = manager.register_marriage(&family.id, 0, Some(2), None, date(), "");
+        assert!(matches!(again, Err(DataError::Validation(_))), "{:?}", again);
+
+        let spouse = member("赵敏", "110101197808087890", Relationship::WIFE);
+        manager.register_marriage(&other.id, 0, None, Some(spouse), date(), "").unwrap();
+        let household = manager.get_household_by_id(&other.id).unwrap().unwrap();
+        assert_eq!(household.members[1].name, "赵敏");
+        assert_eq!(household.members[1].marital_status, MaritalStatus::Married);
+        assert_eq!(events_of(&mut manager, &other.id, EventType::TransferIn), 1);
+    }
+
+    #[test]
+    fn register_divorce_with_partner_leaving() {
+        let mut manager = manager();
+        let household_id = married_couple(&mut manager);
+        manager.register_divorce(&household_id, 0, Some(1), Some("上海市"), date(), "").unwrap();
+
+        let household = manager.get_household_by_id(&household_id).unwrap().unwrap();
+        assert_eq!(household.members[0].marital_status, MaritalStatus::Divorced);
+        assert_eq!(household.members[1].marital_status, MaritalStatus::Divorced);
+        assert_eq!(household.members[1].status, MemberStatus::MigratedOut);
+        assert_eq!(events_of(&mut manager, &household_id, EventType::Divorce), 2);
+        assert_eq!(events_of(&mut manager, &household_id, EventType::MigrateOut), 1);
+    }
+
+    #[test]
+    fn register_divorce_requires_active_members() {
+        let mut manager = manager();
+        let household_id = married_couple(&mut manager);
+        let mut changed = manager.get_household_by_id(&household_id).unwrap().unwrap();
+        changed.members[1].status = MemberStatus::MigratedOut;
+        manager.update_household(changed).unwrap();
+
+        let partner = manager.register_divorce(&household_id, 0, Some(1), None, date(), "");
+        assert!(matches!(partner, Err(DataError::Validation(_))), "{:?}", partner);
+        let member = manager.register_divorce(&household_id, 1, None, None, date(), "");
+        assert!(matches!(member, Err(DataError::Validation(_))), "{:?}", member);
+
+        let household = manager.get_household_by_id(&household_id).unwrap().unwrap();
+        assert!(household.members[..2].iter().all(|m| m.marital_status == MaritalStatus::Married));
+        assert_eq!(events_of(&mut manager, &household_id, EventType::Divorce), 0);
+    }
+
+    #[test]
+    fn change_head_applies_previewed_relationships() {
+        let mut manager = manager();
+        let mut wife = member("李四", "110101198202022345", Relationship::WIFE);
+        wife.gender = Gender::Female;
+        let mut daughter_in_law = member("王芳", "110101201104044567", Relationship::DAUGHTER_IN_LAW);
+        daughter_in_law.gender = Gender::Female;
+        let family = household(vec![
+            member("张三", "110101198001011234", Relationship::HEAD),
+            wife,
+            member("张小", "110101201003033456", Relationship::SON),
+            daughter_in_law,
+        ]);
+        manager.add_household(family.clone()).unwrap();
+
+        let mut form = HeadChangeForm::new(&family);
+        form.new_head = Some(2);
+        form.preview(&family);
+        manager.change_head(&family.id, 2, &form.relationships, date(), "户主年迈").unwrap();
+
+        let household = manager.get_household_by_id(&family.id).unwrap().unwrap();
+        assert_eq!(household.head_name, "张小");
+        assert_eq!(
+            household.members.iter().map(|m| m.relationship).collect::<Vec<_>>(),
+            [Relationship::FATHER, Relationship::MOTHER, Relationship::HEAD, Relationship::WIFE]
+        );
+        let events = manager.get_events(&family.id).unwrap();
+        assert!(events.iter().any(|e| e.event_type == EventType::HeadChange && e.member_name == "张小"));
+    }
+
+    #[test]
+    fn change_head_rejects_invalid_relationships() {
+        let mut manager = manager();
+        let (family, _) = two_households(&mut manager);
+        let two_heads = [Relationship::HEAD, Relationship::WIFE, Relationship::HEAD];
+        let result = manager.change_head(&family.id, 2, &two_heads, date(), "");
+        assert!(matches!(result, Err(DataError::Validation(_))), "{:?}", result);
+        let short = manager.change_head(&family.id, 2, &[Relationship::FATHER, Relationship::HEAD], date(), "");
+        assert!(matches!(short, Err(DataError::Validation(_))), "{:?}", short);
+        let current = [Relationship::HEAD, Relationship::WIFE, Relationship::SON];
+        let same = manager.change_head(&family.id, 0, &current, date(), "");
+        assert!(matches!(same, Err(DataError::Validation(_))), "{:?}", same);
+
+        let household = manager.get_household_by_id(&family.id).unwrap().unwrap();
+        assert_eq!(household.head_name, "张三");
+        assert!(manager.get_events(&family.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn household_form_includes_marriage_dates() {
+        let mut manager = manager();
+        let (family, _) = two_households(&mut manager);
+        manager.register_marriage(&family.id, 0, Some(1), None, date(), "").unwrap();
+
+        let household = manager.get_household_by_id(&family.id).unwrap().unwrap();
+        let form = manager.household_form(&household).unwrap();
+        assert_eq!(form.marriage_dates.get("110101198001011234"), Some(&date()));
+        assert_eq!(form.marriage_dates.get("110101198202022345"), Some(&date()));
+        assert_eq!(form.marriage_dates.len(), 2);
+    }
+
+    #[test]
+    fn recorded_changes_lists_changed_households_only() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        manager.start_recording();
+        manager.transfer_members(&family.id, &[2], &other.id, &[Relationship::OTHER_RELATIVE], date(), "投亲").unwrap();
+        // 查询和保存失败不算修改
+        manager.get_household_by_id(&family.id).unwrap();
+        let result = manager.update_household(household(vec![member("赵六", "110101199001017890", Relationship::HEAD)]));
+        assert!(matches!(result, Err(DataError::Conflict(_))));
+        let changes = manager.recorded_changes().unwrap();
+
+        let ids: Vec<Uuid> = changes.iter().map(|c| c.household_id).collect();
+        assert_eq!(ids, [family.id, other.id]);
+        assert_eq!(changes[0].before.as_ref().unwrap().members.len(), 3);
+        assert_eq!(changes[0].after.as_ref().unwrap().members.len(), 2);
+        // 记录已结束，之后的修改不再列出
+        manager.remove_household(&other.id, changes[1].after.as_ref().unwrap().revision).unwrap();
+        assert!(manager.recorded_changes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn replay_restores_all_households_of_an_operation() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        manager.start_recording();
+        manager.transfer_members(&family.id, &[2], &other.id, &[Relationship::OTHER_RELATIVE], date(), "投亲").unwrap();
+        let changes = manager.recorded_changes().unwrap();
+
+        let undone = manager.replay_changes(&changes, true).unwrap();
+        assert_eq!(names(&mut manager, &family.id), ["张三", "李四", "张小"]);
+        assert_eq!(names(&mut manager, &other.id), ["王五"]);
+
+        let redone = manager.replay_changes(&undone, false).unwrap();
+        assert_eq!(names(&mut manager, &family.id), ["张三", "李四"]);
+        assert_eq!(names(&mut manager, &other.id), ["王五", "张小"]);
+        // 重做后仍可再次撤销
+        manager.replay_changes(&redone, true).unwrap();
+        assert_eq!(names(&mut manager, &other.id), ["王五"]);
+    }
+
+    #[test]
+    fn replay_adds_and_deletes_households() {
+        let mut manager = manager();
+        let (family, _) = two_households(&mut manager);
+        let new = household(vec![member("赵六", "110101199001017890", Relationship::HEAD)]);
+        manager.start_recording();
+        manager.add_household(new.clone()).unwrap();
+        let revision = manager.get_household_by_id(&family.id).unwrap().unwrap().revision;
+        manager.remove_household(&family.id, revision).unwrap();
+        let changes = manager.recorded_changes().unwrap();
+        assert_eq!(changes.len(), 2);
+
+        let undone = manager.replay_changes(&changes, true).unwrap();
+        assert!(manager.get_household_by_id(&new.id).unwrap().is_none());
+        assert_eq!(names(&mut manager, &family.id), ["张三", "李四", "张小"]);
+
+        manager.replay_changes(&undone, false).unwrap();
+        assert_eq!(names(&mut manager, &new.id), ["赵六"]);
+        assert!(manager.get_household_by_id(&family.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn replay_conflicts_leave_data_unchanged() {
+        let mut manager = manager();
+        let (family, other) = two_households(&mut manager);
+        manager.start_recording();
+        manager.transfer_members(&family.id, &[2], &other.id, &[Relationship::OTHER_RELATIVE], date(), "投亲").unwrap();
+        let changes = manager.recorded_changes().unwrap();
+
+        // 其中一户此后又被修改，整个操作都不撤销
+        let mut changed = manager.get_household_by_id(&other.id).unwrap().unwrap();
+        changed.phone = "13900139000".to_string();
+        manager.update_household(changed).unwrap();
+        let result = manager.replay_changes(&changes, true);
+        assert!(matches!(result, Err(DataError::Conflict(id)) if id == other.id), "{:?}", result);
+        assert_eq!(names(&mut manager, &family.id), ["张三", "李四"]);
+
+        // 新增的户被删除后不能撤销新增
+        let new = household(vec![member("赵六", "110101199001017890", Relationship::HEAD)]);
+        manager.start_recording();
+        manager.add_household(new.clone()).unwrap();
+        let changes = manager.recorded_changes().unwrap();
+        manager.remove_household(&new.id, changes[0].after.as_ref().unwrap().revision).unwrap();
+        assert!(matches!(manager.replay_changes(&changes, true), Err(DataError::Conflict(_))));
+    }
 }
@@ -0,0 +1,353 @@
+use super::attachments::{Attachment, AttachmentOwner};
+use super::audit::AuditEntry;
+use super::codes::DecodeError;
+use super::divisions::DivisionLevel;
+use super::drafts::Draft;
+use super::error::DataError;
+use super::manager::{DivisionCount, HouseholdStatistics};
+use super::models::*;
+use super::repository::{HouseholdRepository, LoadedHouseholds};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// 内存中的存储后端，不读写文件，供测试和临时数据使用；约束与 SQLite 后端一致
+#[derive(Debug, Default)]
+pub struct MemoryRepository {
+    state: RefCell<MemoryState>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MemoryState {
+    /// 户籍及其地址是否待核对，按新增顺序
+    households: Vec<(Household, bool)>,
+    dwellings: Vec<Dwelling>,
+    events: Vec<HouseholdEvent>,
+    drafts: Vec<Draft>,
+    attachments: Vec<Attachment>,
+    /// 附件内容，按哈希存放
+    attachment_files: HashMap<String, Vec<u8>>,
+    /// 操作记录，按追加顺序
+    audit_log: Vec<AuditEntry>,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MemoryState {
+    fn position(&self, household_id: &Uuid) -> Option<usize> {
+        self.households.iter().position(|(h, _)| h.id == *household_id)
+    }
+
+    // 与 SQLite 后端的唯一性约束相同：同一证件号码只能登记在一户的在册成员中，未填写的不参与检查
+    fn check_unique(&self, household: &Household) -> Result<(), DataError> {
+        let normalize = |id_number: &str| id_number.trim().to_uppercase();
+        let mut seen = HashSet::new();
+        for member in household.members.iter().filter(|m| m.is_active() && !m.id_number.trim().is_empty()) {
+            let id_number = normalize(&member.id_number);
+            if !seen.insert(id_number.clone()) {
+                return Err(DataError::Constraint(format!("身份证号 {} 在同一户中重复出现", member.id_number)));
+            }
+            let existing = self.households.iter()
+                .filter(|(other, _)| other.id != household.id)
+                .find(|(other, _)| other.members.iter().any(|m| m.is_active() && normalize(&m.id_number) == id_number));
+            if let Some((other, _)) = existing {
+                return Err(DataError::Constraint(format!(
+                    "{} 的身份证号 {} 已登记在户主为 {} 的户籍中", member.name, member.id_number, other.head_name
+                )));
+            }
+        }
+
+        let head_id_number = normalize(&household.id_number);
+        if head_id_number.is_empty() {
+            return Ok(());
+        }
+        let existing_head = self.households.iter()
+            .find(|(other, _)| other.id != household.id && normalize(&other.id_number) == head_id_number);
+        if let Some((other, _)) = existing_head {
+            return Err(DataError::Constraint(format!(
+                "户主身份证号 {} 已被户主为 {} 的户籍使用", household.id_number, other.head_name
+            )));
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, household: &Household) -> Result<(), DataError> {
+        self.check_unique(household)?;
+        if self.position(&household.id).is_some() {
+            return Err(DataError::Constraint(format!("户籍ID {} 已存在", household.id)));
+        }
+        let mut household = household.clone();
+        household.revision = 0;
+        self.households.push((household, false));
+        Ok(())
+    }
+
+    fn update(&mut self, household: &Household) -> Result<(), DataError> {
+        self.check_unique(household)?;
+        let index = self.position(&household.id)
+            .filter(|&index| self.households[index].0.revision == household.revision)
+            .ok_or(DataError::Conflict(household.id))?;
+        let (stored, review) = &mut self.households[index];
+        *stored = household.clone();
+        stored.revision += 1;
+        // 选择了区划即视为已核对
+        if !household.address.province.is_empty() {
+            *review = false;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, household_id: &Uuid, revision: i64) -> Result<(), DataError> {
+        match self.position(household_id) {
+            Some(index) if self.households[index].0.revision == revision => {
+                self.households.remove(index);
+                Ok(())
+            }
+            _ => Err(DataError::Conflict(*household_id)),
+        }
+    }
+
+    fn apply_changes(&mut self, updates: &[Household], deletions: &[(Uuid, i64)], events: &[HouseholdEvent]) -> Result<(), DataError> {
+        for (household_id, revision) in deletions {
+            self.delete(household_id, *revision)?;
+        }
+        // 先清空所有待更新户的成员，避免成员在户之间移动时误报重复
+        for household in updates {
+            if let Some(index) = self.position(&household.id) {
+                self.households[index].0.members.clear();
+            }
+        }
+        for household in updates {
+            if self.position(&household.id).is_some() {
+                self.update(household)?;
+            } else if household.revision > 0 {
+                return Err(DataError::Conflict(household.id));
+            } else {
+                self.insert(household)?;
+            }
+        }
+        self.events.extend(events.iter().cloned());
+        Ok(())
+    }
+}
+
+// 与 SQL 的 LIKE 一样，ASCII 字母不区分大小写
+fn contains_ignore_case(text: &str, query: &str) -> bool {
+    text.to_ascii_lowercase().contains(&query.to_ascii_lowercase())
+}
+
+impl HouseholdRepository for MemoryRepository {
+    fn is_empty(&self) -> Result<bool, DataError> {
+        Ok(self.state.borrow().households.is_empty())
+    }
+
+    fn insert_household(&self, household: &Household) -> Result<(), DataError> {
+        self.state.borrow_mut().insert(household)
+    }
+
+    fn update_household(&self, household: &Household) -> Result<(), DataError> {
+        self.state.borrow_mut().update(household)
+    }
+
+    fn delete_household(&self, household_id: &Uuid, revision: i64) -> Result<(), DataError> {
+        self.state.borrow_mut().delete(household_id, revision)
+    }
+
+    // 在副本上执行，全部成功后才替换，相当于事务回滚
+    fn apply_changes(&self, updates: &[Household], deletions: &[(Uuid, i64)], events: &[HouseholdEvent]) -> Result<(), DataError> {
+        let mut state = self.state.borrow().clone();
+        state.apply_changes(updates, deletions, events)?;
+        *self.state.borrow_mut() = state;
+        Ok(())
+    }
+
+    fn get_events_by_household_id(&self, household_id: &Uuid) -> Result<Vec<HouseholdEvent>, DataError> {
+        let state = self.state.borrow();
+        // 同一天的事件后记录的在前
+        let mut events: Vec<HouseholdEvent> = state.events.iter().rev()
+            .filter(|e| e.household_id == *household_id)
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| std::cmp::Reverse(e.event_date));
+        Ok(events)
+    }
+
+    fn load_households(&self) -> Result<LoadedHouseholds, DataError> {
+        let mut households: Vec<Household> = self.state.borrow().households.iter().map(|(h, _)| h.clone()).collect();
+        households.sort_by_key(|h| std::cmp::Reverse(h.registration_date));
+        Ok(LoadedHouseholds { households, errors: Vec::new() })
+    }
+
+    fn load_dwellings(&self) -> Result<(Vec<Dwelling>, Vec<DecodeError>), DataError> {
+        Ok((self.state.borrow().dwellings.clone(), Vec::new()))
+    }
+
+    fn save_dwelling(&self, dwelling: &Dwelling) -> Result<(), DataError> {
+        let mut state = self.state.borrow_mut();
+        // 与 SQLite 后端的 UNIQUE (division, building, unit, room) 相同，不去除空格
+        let duplicate = state.dwellings.iter().any(|d| {
+            d.id != dwelling.id
+                && d.division == dwelling.division
+                && d.building == dwelling.building
+                && d.unit == dwelling.unit
+                && d.room == dwelling.room
+        });
+        if duplicate {
+            return Err(DataError::Constraint(format!("门牌 {} 已存在", dwelling)));
+        }
+        match state.dwellings.iter_mut().find(|d| d.id == dwelling.id) {
+            Some(existing) => *existing = dwelling.clone(),
+            None => state.dwellings.push(dwelling.clone()),
+        }
+        Ok(())
+    }
+
+    fn delete_dwelling(&self, dwelling_id: &Uuid) -> Result<(), DataError> {
+        let mut state = self.state.borrow_mut();
+        let residents = state.households.iter().filter(|(h, _)| h.dwelling_id == Some(*dwelling_id)).count();
+        if residents > 0 {
+            return Err(DataError::Constraint(format!("仍有{}户登记在此门牌，不能删除", residents)));
+        }
+        state.dwellings.retain(|d| d.id != *dwelling_id);
+        Ok(())
+    }
+
+    fn integrity_check(&self) -> Result<Vec<String>, DataError> {
+        Ok(Vec::new())
+    }
+
+    fn backup(&self, _path: &str) -> Result<(), DataError> {
+        Err(DataError::Validation("内存存储不支持备份".into()))
+    }
+
+    fn search_households(&self, query: &str) -> Result<Vec<Uuid>, DataError> {
+        let loaded = self.load_households()?;
+        Ok(loaded.households.iter()
+            .filter(|h| {
+                contains_ignore_case(&h.head_name, query)
+                    || contains_ignore_case(&h.id_number, query)
+                    || contains_ignore_case(&h.address.to_string(), query)
+                    || contains_ignore_case(&h.phone, query)
+            })
+            .map(|h| h.id)
+            .collect())
+    }
+
+    fn count_by_division(&self, level: DivisionLevel) -> Result<Vec<DivisionCount>, DataError> {
+        let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for (household, _) in &self.state.borrow().households {
+            let count = counts.entry(household.address.code(level).to_string()).or_default();
+            count.0 += 1;
+            count.1 += household.members.iter().filter(|m| m.is_active()).count();
+        }
+        Ok(counts.into_iter()
+            .map(|(code, (households, members))| DivisionCount { code, households, members })
+            .collect())
+    }
+
+    fn address_review_ids(&self) -> Result<Vec<Uuid>, DataError> {
+        Ok(self.state.borrow().households.iter().filter(|(_, review)| *review).map(|(h, _)| h.id).collect())
+    }
+
+    fn resolve_address(&self, household_id: &Uuid, address: &Address) -> Result<(), DataError> {
+        let mut state = self.state.borrow_mut();
+        if let Some(index) = state.position(household_id) {
+            let (household, review) = &mut state.households[index];
+            household.address = address.clone();
+            household.revision += 1;
+            *review = false;
+        }
+        Ok(())
+    }
+
+    fn get_statistics(&self) -> Result<HouseholdStatistics, DataError> {
+        let state = self.state.borrow();
+        let members = || state.households.iter().flat_map(|(h, _)| h.members.iter());
+        let total_households = state.households.len();
+        let urban_households = state.households.iter().filter(|(h, _)| h.household_type == HouseholdType::Urban).count();
+        Ok(HouseholdStatistics {
+            total_households,
+            urban_households,
+            rural_households: total_households - urban_households,
+            total_members: members().filter(|m| m.is_active()).count(),
+            inactive_members: members().filter(|m| !m.is_active()).count(),
+            non_mainland_members: members().filter(|m| m.is_active() && m.document_type != DocumentType::ResidentId).count(),
+        })
+    }
+
+    fn data_version(&self) -> Result<i64, DataError> {
+        Ok(0)
+    }
+
+    fn save_draft(&self, draft: &Draft) -> Result<(), DataError> {
+        let mut state = self.state.borrow_mut();
+        state.drafts.retain(|d| d.id != draft.id);
+        state.drafts.push(draft.clone());
+        Ok(())
+    }
+
+    fn load_drafts(&self, operator: &str) -> Result<Vec<Draft>, DataError> {
+        let mut drafts: Vec<Draft> = self.state.borrow().drafts.iter()
+            .filter(|d| d.operator == operator)
+            .cloned()
+            .collect();
+        drafts.sort_by_key(|d| std::cmp::Reverse(d.updated_at));
+        Ok(drafts)
+    }
+
+    fn delete_draft(&self, draft_id: &Uuid) -> Result<(), DataError> {
+        self.state.borrow_mut().drafts.retain(|d| d.id != *draft_id);
+        Ok(())
+    }
+
+    fn save_attachment(&self, attachment: &Attachment, data: &[u8]) -> Result<(), DataError> {
+        let mut state = self.state.borrow_mut();
+        state.attachment_files.entry(attachment.hash.clone()).or_insert_with(|| data.to_vec());
+        match state.attachments.iter_mut().find(|a| a.id == attachment.id) {
+            Some(existing) => {
+                existing.owner = attachment.owner.clone();
+                existing.kind = attachment.kind;
+                existing.file_name = attachment.file_name.clone();
+            }
+            None => state.attachments.push(attachment.clone()),
+        }
+        Ok(())
+    }
+
+    fn load_attachments(&self, household_id: &Uuid, member_id_numbers: &[String]) -> Result<Vec<Attachment>, DataError> {
+        let mut attachments: Vec<Attachment> = self.state.borrow().attachments.iter()
+            .filter(|a| match &a.owner {
+                AttachmentOwner::Household(id) => id == household_id,
+                AttachmentOwner::Member(id_number) => member_id_numbers.contains(id_number),
+            })
+            .cloned()
+            .collect();
+        attachments.sort_by_key(|a| a.added_at);
+        Ok(attachments)
+    }
+
+    fn attachment_data(&self, hash: &str) -> Result<Option<Vec<u8>>, DataError> {
+        Ok(self.state.borrow().attachment_files.get(hash).cloned())
+    }
+
+    fn delete_attachment(&self, attachment_id: &Uuid) -> Result<(), DataError> {
+        let mut state = self.state.borrow_mut();
+        state.attachments.retain(|a| a.id != *attachment_id);
+        let used: HashSet<String> = state.attachments.iter().map(|a| a.hash.clone()).collect();
+        state.attachment_files.retain(|hash, _| used.contains(hash));
+        Ok(())
+    }
+
+    fn record_audit(&self, entry: &AuditEntry) -> Result<(), DataError> {
+        self.state.borrow_mut().audit_log.push(entry.clone());
+        Ok(())
+    }
+
+    fn load_audit(&self, limit: usize) -> Result<Vec<AuditEntry>, DataError> {
+        Ok(self.state.borrow().audit_log.iter().rev().take(limit).cloned().collect())
+    }
+}
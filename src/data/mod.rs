@@ -1,4 +1,22 @@
 pub mod models;
+pub mod relationship;
+pub mod documents;
+pub mod divisions;
+pub mod dwellings;
+pub mod drafts;
+pub mod attachments;
+pub mod audit;
+pub mod privacy;
+pub mod codes;
 pub mod manager;
 pub mod validation;
+pub mod rules;
+pub mod consistency;
 pub mod database;
+pub mod repository;
+pub mod memory;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod error;
+pub mod duplicates;
+pub mod history;
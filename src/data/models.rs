@@ -2,74 +2,118 @@ use chrono::{Datelike, NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+pub use super::divisions::Address;
+pub use super::documents::DocumentType;
+pub use super::dwellings::Dwelling;
+pub use super::relationship::{Relationship, RelationshipCategory};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Household {
     pub id: Uuid,
     pub head_name: String,
     pub id_number: String,
-    pub address: String,
+    #[serde(default)]
+    pub head_document_type: DocumentType,
+    pub address: Address,
+    /// 登记的门牌，未指定时为 `None`
+    #[serde(default)]
+    pub dwelling_id: Option<Uuid>,
     pub phone: String,
     pub household_type: HouseholdType,
     pub registration_date: NaiveDateTime,
     pub members: Vec<Member>,
+    /// 每次保存加一，保存时与数据库中的比较，以发现其他用户在此期间的修改
+    #[serde(default)]
+    pub revision: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Member {
     pub name: String,
+    #[serde(default)]
+    pub document_type: DocumentType,
     pub id_number: String,
+    #[serde(default = "default_nationality")]
+    pub nationality: String,
     pub relationship: Relationship,
     pub birth_date: NaiveDate,
     pub gender: Gender,
     pub education: Education,
     pub occupation: String,
+    #[serde(default)]
+    pub status: MemberStatus,
+    #[serde(default)]
+    pub marital_status: MaritalStatus,
+    #[serde(default)]
+    pub death_date: Option<NaiveDate>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
-pub enum HouseholdType {
-    Urban,   // 城镇户口
-    Rural,   // 农村户口
-}
+impl Household {
+    /// 户主为成员中关系为“户主”的在册成员
+    pub fn head(&self) -> Option<&Member> {
+        self.members.iter().find(|m| m.is_active() && m.relationship.is_head())
+    }
+    
+    /// 根据成员列表同步户主姓名和证件
+    pub fn sync_head(&mut self) {
+        if let Some(head) = self.head() {
+            let (name, id_number, document_type) = (head.name.clone(), head.id_number.clone(), head.document_type);
+            self.head_name = name;
+            self.id_number = id_number;
+            self.head_document_type = document_type;
+        }
+    }
 
-impl std::fmt::Display for HouseholdType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            HouseholdType::Urban => write!(f, "城镇户口"),
-            HouseholdType::Rural => write!(f, "农村户口"),
+    /// 从外部提交的 JSON 构造户籍，可省略户籍ID、登记日期和户主信息，分别自动生成或由成员带出
+    pub fn from_json(mut value: serde_json::Value) -> Result<Household, serde_json::Error> {
+        if let Some(object) = value.as_object_mut() {
+            let now = chrono::Local::now().naive_local().format("%Y-%m-%dT%H:%M:%S").to_string();
+            let defaults = [
+                ("id", serde_json::Value::String(Uuid::new_v4().to_string())),
+                ("registration_date", serde_json::Value::String(now)),
+                ("head_name", serde_json::Value::String(String::new())),
+                ("id_number", serde_json::Value::String(String::new())),
+                ("address", serde_json::json!({})),
+                ("phone", serde_json::Value::String(String::new())),
+            ];
+            for (key, default) in defaults {
+                object.entry(key).or_insert(default);
+            }
         }
+        let mut household: Household = serde_json::from_value(value)?;
+        household.sync_head();
+        Ok(household)
     }
 }
 
-impl Default for HouseholdType {
-    fn default() -> Self {
-        HouseholdType::Urban
+fn default_nationality() -> String {
+    super::documents::CHINA.to_string()
+}
+
+impl Member {
+    pub fn is_active(&self) -> bool {
+        self.status == MemberStatus::Active
     }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
-pub enum Relationship {
-    Head,     // 户主
-    Spouse,   // 配偶
-    Child,    // 子女
-    Parent,   // 父母
-    Other,    // 其他
+pub enum HouseholdType {
+    Urban,   // 城镇户口
+    Rural,   // 农村户口
 }
 
-impl std::fmt::Display for Relationship {
+impl std::fmt::Display for HouseholdType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Relationship::Head => write!(f, "户主"),
-            Relationship::Spouse => write!(f, "配偶"),
-            Relationship::Child => write!(f, "子女"),
-            Relationship::Parent => write!(f, "父母"),
-            Relationship::Other => write!(f, "其他"),
+            HouseholdType::Urban => write!(f, "城镇户口"),
+            HouseholdType::Rural => write!(f, "农村户口"),
         }
     }
 }
 
-impl Default for Relationship {
+impl Default for HouseholdType {
     fn default() -> Self {
-        Relationship::Head
+        HouseholdType::Urban
     }
 }
 
@@ -125,21 +169,132 @@ impl Default for Education {
     }
 }
 
-// 表单数据结构
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum MemberStatus {
+    #[default]
+    Active,       // 在册
+    Cancelled,    // 注销
+    MigratedOut,  // 迁出
+}
+
+impl std::fmt::Display for MemberStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemberStatus::Active => write!(f, "在册"),
+            MemberStatus::Cancelled => write!(f, "注销"),
+            MemberStatus::MigratedOut => write!(f, "迁出"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub enum MaritalStatus {
+    #[default]
+    Unmarried,  // 未婚
+    Married,    // 已婚
+    Divorced,   // 离婚
+    Widowed,    // 丧偶
+}
+
+impl std::fmt::Display for MaritalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaritalStatus::Unmarried => write!(f, "未婚"),
+            MaritalStatus::Married => write!(f, "已婚"),
+            MaritalStatus::Divorced => write!(f, "离婚"),
+            MaritalStatus::Widowed => write!(f, "丧偶"),
+        }
+    }
+}
+
+// 户籍变动事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HouseholdEvent {
+    pub event_type: EventType,
+    pub event_date: NaiveDate,
+    pub household_id: Uuid,
+    pub related_household_id: Option<Uuid>,
+    pub member_name: String,
+    pub member_id_number: String,
+    pub destination: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum EventType {
+    TransferIn,   // 迁入
+    TransferOut,  // 迁出（户间）
+    Split,        // 分户
+    Merge,        // 合户
+    MigrateOut,   // 迁出辖区
+    HeadChange,   // 变更户主
+    Birth,        // 出生登记
+    Death,        // 死亡注销
+    Marriage,     // 结婚
+    Divorce,      // 离婚
+}
+
+impl std::fmt::Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EventType::TransferIn => write!(f, "迁入"),
+            EventType::TransferOut => write!(f, "迁出"),
+            EventType::Split => write!(f, "分户"),
+            EventType::Merge => write!(f, "合户"),
+            EventType::MigrateOut => write!(f, "迁出辖区"),
+            EventType::HeadChange => write!(f, "变更户主"),
+            EventType::Birth => write!(f, "出生登记"),
+            EventType::Death => write!(f, "死亡注销"),
+            EventType::Marriage => write!(f, "结婚"),
+            EventType::Divorce => write!(f, "离婚"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferKind {
+    MoveMember,  // 户间迁移
+    Split,       // 分户
+    Merge,       // 合户
+    MigrateOut,  // 迁出辖区
+}
+
+impl std::fmt::Display for TransferKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransferKind::MoveMember => write!(f, "户间迁移"),
+            TransferKind::Split => write!(f, "分户"),
+            TransferKind::Merge => write!(f, "合户"),
+            TransferKind::MigrateOut => write!(f, "迁出辖区"),
+        }
+    }
+}
+
+// 表单数据结构，可保存为草稿
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct HouseholdForm {
     pub head_name: String,
     pub id_number: String,
-    pub address: String,
+    pub address: Address,
+    pub dwelling_id: Option<Uuid>,
     pub phone: String,
     pub household_type: HouseholdType,
     pub members: Vec<MemberForm>,
+    /// 开始编辑时户籍的版本
+    pub revision: i64,
+    /// 成员最近一次结婚登记的日期，按证件号码，用于检查登记时是否达到法定婚龄；不保存到户籍
+    pub marriage_dates: std::collections::HashMap<String, NaiveDate>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MemberForm {
     pub name: String,
+    pub document_type: DocumentType,
     pub id_number: String,
+    /// 仅外国证件需要填写，中国公民固定为中国
+    pub nationality: String,
     pub relationship: Relationship,
     pub birth_year: i32,
     pub birth_month: u32,
@@ -147,6 +302,9 @@ pub struct MemberForm {
     pub gender: Gender,
     pub education: Education,
     pub occupation: String,
+    pub status: MemberStatus,
+    pub marital_status: MaritalStatus,
+    pub death_date: Option<NaiveDate>,
 }
 
 impl HouseholdForm {
@@ -155,9 +313,12 @@ impl HouseholdForm {
             head_name: household.head_name.clone(),
             id_number: household.id_number.clone(),
             address: household.address.clone(),
+            dwelling_id: household.dwelling_id,
             phone: household.phone.clone(),
             household_type: household.household_type,
             members: household.members.iter().map(MemberForm::from_member).collect(),
+            revision: household.revision,
+            marriage_dates: Default::default(),
         }
     }
     
@@ -165,16 +326,34 @@ impl HouseholdForm {
         let members: Result<Vec<_>, _> = self.members.iter().map(|m| m.to_member()).collect();
         let members = members.ok()?;
         
-        Some(Household {
+        let mut household = Household {
             id: id.unwrap_or_else(Uuid::new_v4),
             head_name: self.head_name.clone(),
             id_number: self.id_number.clone(),
+            head_document_type: DocumentType::default(),
             address: self.address.clone(),
+            dwelling_id: self.dwelling_id,
             phone: self.phone.clone(),
             household_type: self.household_type,
             registration_date: chrono::Utc::now().naive_utc(),
             members,
-        })
+            revision: self.revision,
+        };
+        household.sync_head();
+        Some(household)
+    }
+    
+    /// 户主信息由成员中的户主带出，成员修改后调用
+    pub fn sync_head(&mut self) {
+        if let Some(head) = self.members.iter()
+            .find(|m| m.status == MemberStatus::Active && m.relationship.is_head())
+        {
+            self.head_name = head.name.clone();
+            self.id_number = head.id_number.clone();
+        } else {
+            self.head_name.clear();
+            self.id_number.clear();
+        }
     }
     
     pub fn clear(&mut self) {
@@ -183,11 +362,35 @@ impl HouseholdForm {
     }
 }
 
+impl Default for MemberForm {
+    // 出生日期取表单输入框允许的最小值，否则首次显示时被输入框改写，空表单也会被当作已修改
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            document_type: DocumentType::default(),
+            id_number: String::new(),
+            nationality: String::new(),
+            relationship: Relationship::default(),
+            birth_year: 1900,
+            birth_month: 1,
+            birth_day: 1,
+            gender: Gender::default(),
+            education: Education::default(),
+            occupation: String::new(),
+            status: MemberStatus::default(),
+            marital_status: MaritalStatus::default(),
+            death_date: None,
+        }
+    }
+}
+
 impl MemberForm {
     pub fn from_member(member: &Member) -> Self {
         Self {
             name: member.name.clone(),
+            document_type: member.document_type,
             id_number: member.id_number.clone(),
+            nationality: member.nationality.clone(),
             relationship: member.relationship,
             birth_year: member.birth_date.year(),
             birth_month: member.birth_date.month(),
@@ -195,6 +398,18 @@ impl MemberForm {
             gender: member.gender,
             education: member.education,
             occupation: member.occupation.clone(),
+            status: member.status,
+            marital_status: member.marital_status,
+            death_date: member.death_date,
+        }
+    }
+    
+    /// 国籍代码，中国公民的证件固定为中国
+    pub fn nationality(&self) -> String {
+        if self.document_type.is_chinese_citizen() {
+            super::documents::CHINA.to_string()
+        } else {
+            self.nationality.trim().to_uppercase()
         }
     }
     
@@ -207,12 +422,197 @@ impl MemberForm {
         
         Ok(Member {
             name: self.name.clone(),
-            id_number: self.id_number.clone(),
+            document_type: self.document_type,
+            id_number: self.id_number.trim().to_string(),
+            nationality: self.nationality(),
             relationship: self.relationship,
             birth_date,
             gender: self.gender,
             education: self.education,
             occupation: self.occupation.clone(),
+            status: self.status,
+            marital_status: self.marital_status,
+            death_date: self.death_date,
         })
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct TransferForm {
+    pub kind: TransferKind,
+    pub source: Option<Uuid>,
+    pub target: Option<Uuid>,
+    pub selected_members: Vec<bool>,
+    pub relationships: Vec<Relationship>,
+    pub new_head: Option<usize>,
+    pub address: Address,
+    pub phone: String,
+    pub destination: String,
+    pub reason: String,
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl TransferForm {
+    pub fn new(kind: TransferKind, source: &Household) -> Self {
+        let today = chrono::Local::now().date_naive();
+        Self {
+            kind,
+            source: Some(source.id),
+            target: None,
+            selected_members: vec![false; source.members.len()],
+            relationships: source.members.iter().map(|m| m.relationship).collect(),
+            new_head: None,
+            address: source.address.clone(),
+            phone: String::new(),
+            destination: String::new(),
+            reason: String::new(),
+            year: today.year(),
+            month: today.month(),
+            day: today.day(),
+        }
+    }
+    
+    pub fn event_date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year, self.month, self.day)
+    }
+    
+    pub fn selected_indices(&self) -> Vec<usize> {
+        self.selected_members.iter()
+            .enumerate()
+            .filter(|(_, selected)| **selected)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+// 出生、死亡、结婚、离婚登记表单
+#[derive(Debug, Clone)]
+pub struct VitalEventForm {
+    pub kind: EventType,
+    pub household_id: Uuid,
+    pub member_index: Option<usize>,
+    pub partner_index: Option<usize>,
+    pub new_member: MemberForm,
+    pub partner_joins: bool,
+    pub partner_leaves: bool,
+    pub destination: String,
+    pub reason: String,
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl VitalEventForm {
+    pub fn new(kind: EventType, household: &Household) -> Self {
+        let today = chrono::Local::now().date_naive();
+        let (relationship, marital_status) = match kind {
+            EventType::Birth => (Relationship::SON, MaritalStatus::Unmarried),
+            _ => (Relationship::SPOUSE, MaritalStatus::Married),
+        };
+        let new_member = MemberForm {
+            relationship,
+            marital_status,
+            birth_year: today.year(),
+            birth_month: today.month(),
+            birth_day: today.day(),
+            ..Default::default()
+        };
+        
+        Self {
+            kind,
+            household_id: household.id,
+            member_index: None,
+            partner_index: None,
+            new_member,
+            partner_joins: true,
+            partner_leaves: false,
+            destination: String::new(),
+            reason: String::new(),
+            year: today.year(),
+            month: today.month(),
+            day: today.day(),
+        }
+    }
+    
+    pub fn event_date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year, self.month, self.day)
+    }
+}
+
+// 门牌登记表单
+#[derive(Debug, Clone, Default)]
+pub struct DwellingForm {
+    /// 编辑已有门牌时为其ID
+    pub id: Option<Uuid>,
+    pub address: Address,
+    pub building: String,
+    pub unit: String,
+    pub room: String,
+    pub capacity: u32,
+}
+
+impl DwellingForm {
+    pub fn from_dwelling(dwelling: &Dwelling) -> Self {
+        Self {
+            id: Some(dwelling.id),
+            address: Address::from_division(&dwelling.division, ""),
+            building: dwelling.building.clone(),
+            unit: dwelling.unit.clone(),
+            room: dwelling.room.clone(),
+            capacity: dwelling.capacity,
+        }
+    }
+    
+    pub fn to_dwelling(&self) -> Dwelling {
+        Dwelling {
+            id: self.id.unwrap_or_else(Uuid::new_v4),
+            division: self.address.division().to_string(),
+            building: self.building.trim().to_string(),
+            unit: self.unit.trim().to_string(),
+            room: self.room.trim().to_string(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+// 变更户主表单，`relationships` 为预览后可调整的各成员新关系
+#[derive(Debug, Clone)]
+pub struct HeadChangeForm {
+    pub household_id: Uuid,
+    pub new_head: Option<usize>,
+    pub relationships: Vec<Relationship>,
+    pub reason: String,
+}
+
+impl HeadChangeForm {
+    pub fn new(household: &Household) -> Self {
+        Self {
+            household_id: household.id,
+            new_head: None,
+            relationships: household.members.iter().map(|m| m.relationship).collect(),
+            reason: String::new(),
+        }
+    }
+    
+    /// 选定新户主后按原关系推算各成员的新关系
+    pub fn preview(&mut self, household: &Household) {
+        let Some((new_head_index, new_head)) = self.new_head.and_then(|i| household.members.get(i).map(|m| (i, m))) else {
+            return;
+        };
+        let old_head = household.head();
+        let spouse_index = super::relationship::child_spouse_index(&household.members, new_head_index);
+        
+        self.relationships = household.members.iter()
+            .enumerate()
+            .map(|(i, m)| {
+                if i == new_head_index {
+                    Relationship::HEAD
+                } else {
+                    super::relationship::remap_for_new_head(m, new_head, old_head, spouse_index == Some(i))
+                }
+            })
+            .collect();
+    }
+}
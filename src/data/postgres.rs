@@ -0,0 +1,756 @@
+//! PostgreSQL 存储后端，启用 `postgres` 功能时编译，供多个办公点共用一个数据库
+//!
+//! 表结构与 SQLite 后端相同，枚举和日期同样以代码和文本保存，解码沿用 `database` 模块的行结构。
+//! 写入在事务中先锁定户籍表，使多个客户端同时保存时的唯一性检查依次进行。
+
+use super::attachments::{Attachment, AttachmentRow};
+use super::audit::{AuditEntry, AuditRow};
+use super::codes::{DecodeError, StableCode};
+use super::database::{HouseholdRow, MemberRow};
+use super::divisions::DivisionLevel;
+use super::drafts::{Draft, DraftRow};
+use super::error::DataError;
+use super::manager::{DivisionCount, HouseholdStatistics};
+use super::models::*;
+use super::repository::{HouseholdRepository, LoadedHouseholds};
+use ::postgres::{Client, GenericClient, NoTls};
+use chrono::NaiveDate;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub struct PostgresRepository {
+    client: RefCell<Client>,
+}
+
+impl PostgresRepository {
+    /// 连接如 `postgres://用户:密码@主机/数据库` 的地址，表不存在时新建
+    pub fn connect(url: &str) -> Result<Self, DataError> {
+        let client = Client::connect(url, NoTls)?;
+        let repository = PostgresRepository { client: RefCell::new(client) };
+        repository.init_tables()?;
+        Ok(repository)
+    }
+
+    fn init_tables(&self) -> Result<(), DataError> {
+        self.client.borrow_mut().batch_execute(
+            "CREATE TABLE IF NOT EXISTS dwellings (
+                id TEXT PRIMARY KEY,
+                division TEXT NOT NULL,
+                building TEXT NOT NULL,
+                unit TEXT NOT NULL DEFAULT '',
+                room TEXT NOT NULL,
+                capacity BIGINT NOT NULL DEFAULT 0,
+                UNIQUE (division, building, unit, room)
+            );
+            CREATE TABLE IF NOT EXISTS households (
+                id TEXT PRIMARY KEY,
+                head_name TEXT NOT NULL,
+                id_number TEXT NOT NULL,
+                address TEXT NOT NULL,
+                province TEXT NOT NULL DEFAULT '',
+                city TEXT NOT NULL DEFAULT '',
+                county TEXT NOT NULL DEFAULT '',
+                township TEXT NOT NULL DEFAULT '',
+                village TEXT NOT NULL DEFAULT '',
+                address_detail TEXT NOT NULL DEFAULT '',
+                address_review BOOLEAN NOT NULL DEFAULT FALSE,
+                phone TEXT NOT NULL DEFAULT '',
+                household_type TEXT NOT NULL,
+                registration_date TEXT NOT NULL,
+                document_type TEXT NOT NULL DEFAULT 'resident_id',
+                dwelling_id TEXT REFERENCES dwellings (id),
+                revision BIGINT NOT NULL DEFAULT 0
+            );
+            ALTER TABLE households ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 0;
+            CREATE TABLE IF NOT EXISTS members (
+                id BIGSERIAL PRIMARY KEY,
+                household_id TEXT NOT NULL REFERENCES households (id),
+                name TEXT NOT NULL,
+                id_number TEXT NOT NULL,
+                relationship TEXT NOT NULL,
+                birth_date TEXT NOT NULL,
+                gender TEXT NOT NULL,
+                education TEXT NOT NULL,
+                occupation TEXT NOT NULL DEFAULT '',
+                status TEXT NOT NULL DEFAULT 'active',
+                marital_status TEXT NOT NULL DEFAULT '10',
+                death_date TEXT,
+                document_type TEXT NOT NULL DEFAULT 'resident_id',
+                nationality TEXT NOT NULL DEFAULT 'CHN'
+            );
+            CREATE INDEX IF NOT EXISTS members_household_id ON members (household_id);
+            CREATE TABLE IF NOT EXISTS household_events (
+                id BIGSERIAL PRIMARY KEY,
+                event_type TEXT NOT NULL,
+                event_date TEXT NOT NULL,
+                household_id TEXT NOT NULL,
+                related_household_id TEXT,
+                member_name TEXT NOT NULL,
+                member_id_number TEXT NOT NULL,
+                destination TEXT NOT NULL DEFAULT '',
+                reason TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS drafts (
+                id TEXT PRIMARY KEY,
+                operator TEXT NOT NULL,
+                household_id TEXT,
+                form TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS attachment_files (
+                hash TEXT PRIMARY KEY,
+                data BYTEA NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS attachments (
+                id TEXT PRIMARY KEY,
+                household_id TEXT,
+                member_id_number TEXT,
+                kind TEXT NOT NULL,
+                file_name TEXT NOT NULL,
+                media_type TEXT NOT NULL,
+                hash TEXT NOT NULL REFERENCES attachment_files (hash),
+                size BIGINT NOT NULL,
+                added_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_attachments_household ON attachments (household_id);
+            CREATE INDEX IF NOT EXISTS idx_attachments_member ON attachments (member_id_number);
+            CREATE TABLE IF NOT EXISTS audit_log (
+                seq BIGSERIAL,
+                id TEXT PRIMARY KEY,
+                operator TEXT NOT NULL,
+                action TEXT NOT NULL,
+                household_id TEXT,
+                detail TEXT NOT NULL,
+                recorded_at TEXT NOT NULL
+            );
+            -- 只有一行，每次写入加一，供各客户端判断数据是否被其他客户端修改
+            CREATE TABLE IF NOT EXISTS data_version (
+                version BIGINT NOT NULL
+            );
+            INSERT INTO data_version (version) SELECT 0 WHERE NOT EXISTS (SELECT 1 FROM data_version);",
+        )?;
+        Ok(())
+    }
+
+    // 在事务中执行写入，先锁定户籍表使其他客户端的写入排队，读取不受影响
+    fn write<T>(&self, f: impl FnOnce(&mut ::postgres::Transaction<'_>) -> Result<T, DataError>) -> Result<T, DataError> {
+        let mut client = self.client.borrow_mut();
+        let mut tx = client.transaction()?;
+        tx.batch_execute("LOCK TABLE households IN SHARE ROW EXCLUSIVE MODE")?;
+        let result = f(&mut tx)?;
+        tx.batch_execute("UPDATE data_version SET version = version + 1")?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    fn count(&self, sql: &str) -> Result<usize, DataError> {
+        let count: i64 = self.client.borrow_mut().query_one(sql, &[])?.get(0);
+        Ok(count as usize)
+    }
+}
+
+// 唯一性约束：同一身份证号只能登记在一个户籍中
+fn check_unique(client: &mut impl GenericClient, household: &Household) -> Result<(), DataError> {
+    let mut seen = std::collections::HashSet::new();
+    // 已注销或迁出的成员仅作为历史记录保留，不参与唯一性检查；未填写的证件号码同样不检查
+    for member in household.members.iter().filter(|m| m.is_active() && !m.id_number.trim().is_empty()) {
+        if !seen.insert(member.id_number.trim().to_uppercase()) {
+            return Err(DataError::Constraint(format!("身份证号 {} 在同一户中重复出现", member.id_number)));
+        }
+        let existing = client.query_opt(
+            "SELECT h.head_name FROM members m JOIN households h ON m.household_id = h.id
+             WHERE UPPER(TRIM(m.id_number)) = UPPER(TRIM($1)) AND m.household_id != $2
+             AND m.status = 'active' LIMIT 1",
+            &[&member.id_number, &household.id.to_string()],
+        )?;
+        if let Some(row) = existing {
+            return Err(DataError::Constraint(format!(
+                "{} 的身份证号 {} 已登记在户主为 {} 的户籍中", member.name, member.id_number, row.get::<_, String>(0)
+            )));
+        }
+    }
+
+    if household.id_number.trim().is_empty() {
+        return Ok(());
+    }
+    let existing_head = client.query_opt(
+        "SELECT head_name FROM households WHERE UPPER(TRIM(id_number)) = UPPER(TRIM($1)) AND id != $2 LIMIT 1",
+        &[&household.id_number, &household.id.to_string()],
+    )?;
+    if let Some(row) = existing_head {
+        return Err(DataError::Constraint(format!(
+            "户主身份证号 {} 已被户主为 {} 的户籍使用", household.id_number, row.get::<_, String>(0)
+        )));
+    }
+    Ok(())
+}
+
+fn insert_household(client: &mut impl GenericClient, household: &Household) -> Result<(), DataError> {
+    check_unique(client, household)?;
+    client.execute(
+        "INSERT INTO households (id, head_name, id_number, address, phone, household_type, registration_date, document_type)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        &[
+            &household.id.to_string(),
+            &household.head_name,
+            &household.id_number,
+            &household.address.to_string(),
+            &household.phone,
+            &household.household_type.code(),
+            &household.registration_date.format("%Y-%m-%d %H:%M:%S").to_string(),
+            &household.head_document_type.code(),
+        ],
+    )?;
+    write_address(client, &household.id, &household.address)?;
+    client.execute(
+        "UPDATE households SET dwelling_id = $1 WHERE id = $2",
+        &[&household.dwelling_id.map(|id| id.to_string()), &household.id.to_string()],
+    )?;
+    for member in &household.members {
+        insert_member(client, &household.id, member)?;
+    }
+    Ok(())
+}
+
+fn update_household(client: &mut impl GenericClient, household: &Household) -> Result<(), DataError> {
+    check_unique(client, household)?;
+    // 同时确认读取后没有其他用户保存过
+    let changed = client.execute(
+        "UPDATE households SET head_name = $1, id_number = $2, phone = $3, household_type = $4,
+         registration_date = $5, document_type = $6, dwelling_id = $7, revision = revision + 1
+         WHERE id = $8 AND revision = $9",
+        &[
+            &household.head_name,
+            &household.id_number,
+            &household.phone,
+            &household.household_type.code(),
+            &household.registration_date.format("%Y-%m-%d %H:%M:%S").to_string(),
+            &household.head_document_type.code(),
+            &household.dwelling_id.map(|id| id.to_string()),
+            &household.id.to_string(),
+            &household.revision,
+        ],
+    )?;
+    if changed == 0 {
+        return Err(DataError::Conflict(household.id));
+    }
+    write_address(client, &household.id, &household.address)?;
+    // 选择了区划即视为已核对
+    if !household.address.province.is_empty() {
+        client.execute("UPDATE households SET address_review = FALSE WHERE id = $1", &[&household.id.to_string()])?;
+    }
+    client.execute("DELETE FROM members WHERE household_id = $1", &[&household.id.to_string()])?;
+    for member in &household.members {
+        insert_member(client, &household.id, member)?;
+    }
+    Ok(())
+}
+
+// 版本不符时返回冲突，已删除的成员随事务回滚
+fn delete_household(client: &mut impl GenericClient, household_id: &Uuid, revision: i64) -> Result<(), DataError> {
+    client.execute("DELETE FROM members WHERE household_id = $1", &[&household_id.to_string()])?;
+    let deleted = client.execute(
+        "DELETE FROM households WHERE id = $1 AND revision = $2",
+        &[&household_id.to_string(), &revision],
+    )?;
+    if deleted == 0 {
+        return Err(DataError::Conflict(*household_id));
+    }
+    Ok(())
+}
+
+fn write_address(client: &mut impl GenericClient, household_id: &Uuid, address: &Address) -> Result<(), DataError> {
+    client.execute(
+        "UPDATE households SET address = $1, province = $2, city = $3, county = $4, township = $5,
+         village = $6, address_detail = $7 WHERE id = $8",
+        &[
+            &address.to_string(),
+            &address.province,
+            &address.city,
+            &address.county,
+            &address.township,
+            &address.village,
+            &address.detail,
+            &household_id.to_string(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_member(client: &mut impl GenericClient, household_id: &Uuid, member: &Member) -> Result<(), DataError> {
+    client.execute(
+        "INSERT INTO members (household_id, name, id_number, relationship, birth_date, gender, education, occupation,
+         status, marital_status, death_date, document_type, nationality)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+        &[
+            &household_id.to_string(),
+            &member.name,
+            &member.id_number,
+            &member.relationship.code(),
+            &member.birth_date.to_string(),
+            &member.gender.code(),
+            &member.education.code(),
+            &member.occupation,
+            &member.status.code(),
+            &member.marital_status.code(),
+            &member.death_date.map(|d| d.to_string()),
+            &member.document_type.code(),
+            &member.nationality,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_event(client: &mut impl GenericClient, event: &HouseholdEvent) -> Result<(), DataError> {
+    client.execute(
+        "INSERT INTO household_events (event_type, event_date, household_id, related_household_id,
+         member_name, member_id_number, destination, reason, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        &[
+            &event.event_type.code(),
+            &event.event_date.to_string(),
+            &event.household_id.to_string(),
+            &event.related_household_id.map(|id| id.to_string()),
+            &event.member_name,
+            &event.member_id_number,
+            &event.destination,
+            &event.reason,
+            &chrono::Local::now().naive_local().format("%Y-%m-%d %H:%M:%S").to_string(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn division_column(level: DivisionLevel) -> &'static str {
+    match level {
+        DivisionLevel::Province => "province",
+        DivisionLevel::City => "city",
+        DivisionLevel::County => "county",
+        DivisionLevel::Township => "township",
+        DivisionLevel::Village => "village",
+    }
+}
+
+fn parse_ids(ids: Vec<String>) -> Vec<Uuid> {
+    // 无法解析的ID在加载户籍时已作为解码错误报告
+    ids.iter().filter_map(|id| Uuid::parse_str(id).ok()).collect()
+}
+
+impl HouseholdRepository for PostgresRepository {
+    fn is_empty(&self) -> Result<bool, DataError> {
+        Ok(self.count("SELECT COUNT(*) FROM households")? == 0)
+    }
+
+    fn insert_household(&self, household: &Household) -> Result<(), DataError> {
+        self.write(|tx| insert_household(tx, household))
+    }
+
+    fn update_household(&self, household: &Household) -> Result<(), DataError> {
+        self.write(|tx| update_household(tx, household))
+    }
+
+    fn delete_household(&self, household_id: &Uuid, revision: i64) -> Result<(), DataError> {
+        self.write(|tx| delete_household(tx, household_id, revision))
+    }
+
+    fn apply_changes(&self, updates: &[Household], deletions: &[(Uuid, i64)], events: &[HouseholdEvent]) -> Result<(), DataError> {
+        self.write(|tx| {
+            for (household_id, revision) in deletions {
+                delete_household(tx, household_id, *revision)?;
+            }
+            // 先清空所有待更新户的成员，避免成员在户之间移动时误报重复
+            for household in updates {
+                tx.execute("DELETE FROM members WHERE household_id = $1", &[&household.id.to_string()])?;
+            }
+            for household in updates {
+                let exists: bool = tx.query_one(
+                    "SELECT EXISTS(SELECT 1 FROM households WHERE id = $1)",
+                    &[&household.id.to_string()],
+                )?.get(0);
+                if exists {
+                    update_household(tx, household)?;
+                } else if household.revision > 0 {
+                    // 读取过的户籍已不存在，说明被其他用户删除
+                    return Err(DataError::Conflict(household.id));
+                } else {
+                    insert_household(tx, household)?;
+                }
+            }
+            for event in events {
+                insert_event(tx, event)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn get_events_by_household_id(&self, household_id: &Uuid) -> Result<Vec<HouseholdEvent>, DataError> {
+        let rows = self.client.borrow_mut().query(
+            "SELECT event_type, event_date, household_id, related_household_id, member_name,
+             member_id_number, destination, reason
+             FROM household_events WHERE household_id = $1 ORDER BY event_date DESC, id DESC",
+            &[&household_id.to_string()],
+        )?;
+        let uuid = |s: &str| Uuid::parse_str(s).map_err(|_| DataError::Corrupt(format!("无效的户籍ID: {}", s)));
+        rows.iter()
+            .map(|row| {
+                let event_date: String = row.get(1);
+                Ok(HouseholdEvent {
+                    event_type: row.get::<_, String>(0).parse().map_err(|e| DataError::Corrupt(format!("{}", e)))?,
+                    event_date: NaiveDate::parse_from_str(&event_date, "%Y-%m-%d")
+                        .map_err(|_| DataError::Corrupt(format!("无效的日期: {}", event_date)))?,
+                    household_id: uuid(row.get(2))?,
+                    related_household_id: row.get::<_, Option<&str>>(3).map(uuid).transpose()?,
+                    member_name: row.get(4),
+                    member_id_number: row.get(5),
+                    destination: row.get(6),
+                    reason: row.get(7),
+                })
+            })
+            .collect()
+    }
+
+    fn load_households(&self) -> Result<LoadedHouseholds, DataError> {
+        let mut client = self.client.borrow_mut();
+        let household_rows = client.query(
+            "SELECT id, head_name, id_number, province, city, county, township, village, address_detail,
+             phone, household_type, registration_date, document_type, dwelling_id, revision
+             FROM households ORDER BY registration_date DESC",
+            &[],
+        )?;
+        // 一次读出全部成员再按户分组
+        let mut member_rows: HashMap<String, Vec<MemberRow>> = HashMap::new();
+        for row in client.query(
+            "SELECT id, household_id, name, id_number, relationship, birth_date, gender, education, occupation,
+             status, marital_status, death_date, document_type, nationality
+             FROM members ORDER BY id",
+            &[],
+        )? {
+            member_rows.entry(row.get(1)).or_default().push(MemberRow {
+                id: row.get(0),
+                name: row.get(2),
+                id_number: row.get(3),
+                relationship: row.get(4),
+                birth_date: row.get(5),
+                gender: row.get(6),
+                education: row.get(7),
+                occupation: row.get(8),
+                status: row.get(9),
+                marital_status: row.get(10),
+                death_date: row.get(11),
+                document_type: row.get(12),
+                nationality: row.get(13),
+            });
+        }
+
+        let mut loaded = LoadedHouseholds::default();
+        for row in household_rows {
+            let row = HouseholdRow {
+                id: row.get(0),
+                head_name: row.get(1),
+                id_number: row.get(2),
+                address: Address {
+                    province: row.get(3),
+                    city: row.get(4),
+                    county: row.get(5),
+                    township: row.get(6),
+                    village: row.get(7),
+                    detail: row.get(8),
+                },
+                phone: row.get(9),
+                household_type: row.get(10),
+                registration_date: row.get(11),
+                document_type: row.get(12),
+                dwelling_id: row.get(13),
+                revision: row.get(14),
+            };
+            let mut household = match row.decode() {
+                Ok(household) => household,
+                Err(message) => {
+                    loaded.errors.push(DecodeError {
+                        table: "households",
+                        record_id: row.id.clone(),
+                        description: format!("户主 {}", row.head_name),
+                        message,
+                    });
+                    continue;
+                }
+            };
+
+            let mut member_errors = Vec::new();
+            for member_row in member_rows.remove(&row.id).unwrap_or_default() {
+                match member_row.decode() {
+                    Ok(member) => household.members.push(member),
+                    Err(message) => member_errors.push(DecodeError {
+                        table: "members",
+                        record_id: member_row.id.to_string(),
+                        description: format!("{}，户主 {}", member_row.name, row.head_name),
+                        message,
+                    }),
+                }
+            }
+
+            if member_errors.is_empty() {
+                loaded.households.push(household);
+            } else {
+                loaded.errors.extend(member_errors);
+            }
+        }
+        Ok(loaded)
+    }
+
+    fn load_dwellings(&self) -> Result<(Vec<Dwelling>, Vec<DecodeError>), DataError> {
+        let rows = self.client.borrow_mut().query(
+            "SELECT id, division, building, unit, room, capacity FROM dwellings",
+            &[],
+        )?;
+        let mut dwellings = Vec::new();
+        let mut errors = Vec::new();
+        for row in rows {
+            let id: String = row.get(0);
+            let dwelling = Dwelling {
+                id: Uuid::nil(),
+                division: row.get(1),
+                building: row.get(2),
+                unit: row.get(3),
+                room: row.get(4),
+                capacity: u32::try_from(row.get::<_, i64>(5)).unwrap_or(0),
+            };
+            match Uuid::parse_str(&id) {
+                Ok(uuid) => dwellings.push(Dwelling { id: uuid, ..dwelling }),
+                Err(_) => errors.push(DecodeError {
+                    table: "dwellings",
+                    record_id: id.clone(),
+                    description: dwelling.short_name(),
+                    message: format!("无效的门牌ID: {}", id),
+                }),
+            }
+        }
+        Ok((dwellings, errors))
+    }
+
+    fn save_dwelling(&self, dwelling: &Dwelling) -> Result<(), DataError> {
+        self.write(|tx| {
+            tx.execute(
+                "INSERT INTO dwellings (id, division, building, unit, room, capacity) VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (id) DO UPDATE SET division = $2, building = $3, unit = $4, room = $5, capacity = $6",
+                &[
+                    &dwelling.id.to_string(),
+                    &dwelling.division,
+                    &dwelling.building,
+                    &dwelling.unit,
+                    &dwelling.room,
+                    &i64::from(dwelling.capacity),
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete_dwelling(&self, dwelling_id: &Uuid) -> Result<(), DataError> {
+        self.write(|tx| {
+            let residents: i64 = tx.query_one(
+                "SELECT COUNT(*) FROM households WHERE dwelling_id = $1",
+                &[&dwelling_id.to_string()],
+            )?.get(0);
+            if residents > 0 {
+                return Err(DataError::Constraint(format!("仍有{}户登记在此门牌，不能删除", residents)));
+            }
+            tx.execute("DELETE FROM dwellings WHERE id = $1", &[&dwelling_id.to_string()])?;
+            Ok(())
+        })
+    }
+
+    // 数据文件的完整性由 PostgreSQL 服务器负责，这里只确认连接可用
+    fn integrity_check(&self) -> Result<Vec<String>, DataError> {
+        self.client.borrow_mut().batch_execute("SELECT 1")?;
+        Ok(Vec::new())
+    }
+
+    fn backup(&self, _path: &str) -> Result<(), DataError> {
+        Err(DataError::Validation("PostgreSQL 数据库请在服务器上使用 pg_dump 备份".into()))
+    }
+
+    fn search_households(&self, query: &str) -> Result<Vec<Uuid>, DataError> {
+        let rows = self.client.borrow_mut().query(
+            "SELECT id FROM households
+             WHERE head_name ILIKE $1 OR id_number ILIKE $1 OR address ILIKE $1 OR phone ILIKE $1
+             ORDER BY registration_date DESC",
+            &[&format!("%{}%", query)],
+        )?;
+        Ok(parse_ids(rows.iter().map(|row| row.get(0)).collect()))
+    }
+
+    fn count_by_division(&self, level: DivisionLevel) -> Result<Vec<DivisionCount>, DataError> {
+        let column = division_column(level);
+        let rows = self.client.borrow_mut().query(
+            &format!(
+                "SELECT h.{column}, COUNT(DISTINCT h.id), COUNT(m.id) FROM households h
+                 LEFT JOIN members m ON m.household_id = h.id AND m.status = 'active'
+                 GROUP BY h.{column} ORDER BY h.{column}"
+            ),
+            &[],
+        )?;
+        Ok(rows.iter()
+            .map(|row| DivisionCount {
+                code: row.get(0),
+                households: row.get::<_, i64>(1) as usize,
+                members: row.get::<_, i64>(2) as usize,
+            })
+            .collect())
+    }
+
+    fn address_review_ids(&self) -> Result<Vec<Uuid>, DataError> {
+        let rows = self.client.borrow_mut().query("SELECT id FROM households WHERE address_review", &[])?;
+        Ok(parse_ids(rows.iter().map(|row| row.get(0)).collect()))
+    }
+
+    fn resolve_address(&self, household_id: &Uuid, address: &Address) -> Result<(), DataError> {
+        self.write(|tx| {
+            write_address(tx, household_id, address)?;
+            tx.execute(
+                "UPDATE households SET address_review = FALSE, revision = revision + 1 WHERE id = $1",
+                &[&household_id.to_string()],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn get_statistics(&self) -> Result<HouseholdStatistics, DataError> {
+        let total_households = self.count("SELECT COUNT(*) FROM households")?;
+        let urban_households = self.count("SELECT COUNT(*) FROM households WHERE household_type = 'urban'")?;
+        Ok(HouseholdStatistics {
+            total_households,
+            urban_households,
+            rural_households: total_households - urban_households,
+            total_members: self.count("SELECT COUNT(*) FROM members WHERE status = 'active'")?,
+            inactive_members: self.count("SELECT COUNT(*) FROM members WHERE status != 'active'")?,
+            non_mainland_members: self.count(
+                "SELECT COUNT(*) FROM members WHERE status = 'active' AND document_type != 'resident_id'",
+            )?,
+        })
+    }
+
+    fn data_version(&self) -> Result<i64, DataError> {
+        Ok(self.client.borrow_mut().query_one("SELECT version FROM data_version", &[])?.get(0))
+    }
+
+    // 草稿不经过 `write`，不锁定户籍表，也不使其他客户端重新加载
+    fn save_draft(&self, draft: &Draft) -> Result<(), DataError> {
+        let row = DraftRow::encode(draft)?;
+        self.client.borrow_mut().execute(
+            "INSERT INTO drafts (id, operator, household_id, form, updated_at) VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (id) DO UPDATE SET operator = $2, household_id = $3, form = $4, updated_at = $5",
+            &[&row.id, &row.operator, &row.household_id, &row.form, &row.updated_at],
+        )?;
+        Ok(())
+    }
+
+    fn load_drafts(&self, operator: &str) -> Result<Vec<Draft>, DataError> {
+        let rows = self.client.borrow_mut().query(
+            "SELECT id, operator, household_id, form, updated_at FROM drafts WHERE operator = $1 ORDER BY updated_at DESC",
+            &[&operator],
+        )?;
+        Ok(rows.iter()
+            .map(|row| DraftRow {
+                id: row.get(0),
+                operator: row.get(1),
+                household_id: row.get(2),
+                form: row.get(3),
+                updated_at: row.get(4),
+            })
+            .filter_map(|row| row.decode())
+            .collect())
+    }
+
+    fn delete_draft(&self, draft_id: &Uuid) -> Result<(), DataError> {
+        self.client.borrow_mut().execute("DELETE FROM drafts WHERE id = $1", &[&draft_id.to_string()])?;
+        Ok(())
+    }
+
+    // 附件与草稿相同，不经过 `write`
+    fn save_attachment(&self, attachment: &Attachment, data: &[u8]) -> Result<(), DataError> {
+        let row = AttachmentRow::encode(attachment);
+        let mut client = self.client.borrow_mut();
+        let mut tx = client.transaction()?;
+        tx.execute(
+            "INSERT INTO attachment_files (hash, data) VALUES ($1, $2) ON CONFLICT (hash) DO NOTHING",
+            &[&row.hash, &data],
+        )?;
+        tx.execute(
+            "INSERT INTO attachments (id, household_id, member_id_number, kind, file_name, media_type, hash, size, added_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             ON CONFLICT (id) DO UPDATE SET household_id = $2, member_id_number = $3, kind = $4, file_name = $5",
+            &[&row.id, &row.household_id, &row.member_id_number, &row.kind, &row.file_name, &row.media_type, &row.hash, &row.size, &row.added_at],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_attachments(&self, household_id: &Uuid, member_id_numbers: &[String]) -> Result<Vec<Attachment>, DataError> {
+        let rows = self.client.borrow_mut().query(
+            "SELECT id, household_id, member_id_number, kind, file_name, media_type, hash, size, added_at FROM attachments
+             WHERE household_id = $1 OR member_id_number = ANY($2)
+             ORDER BY added_at",
+            &[&household_id.to_string(), &member_id_numbers],
+        )?;
+        Ok(rows.iter()
+            .map(|row| AttachmentRow {
+                id: row.get(0),
+                household_id: row.get(1),
+                member_id_number: row.get(2),
+                kind: row.get(3),
+                file_name: row.get(4),
+                media_type: row.get(5),
+                hash: row.get(6),
+                size: row.get(7),
+                added_at: row.get(8),
+            })
+            .filter_map(|row| row.decode())
+            .collect())
+    }
+
+    fn attachment_data(&self, hash: &str) -> Result<Option<Vec<u8>>, DataError> {
+        let row = self.client.borrow_mut().query_opt("SELECT data FROM attachment_files WHERE hash = $1", &[&hash])?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    fn delete_attachment(&self, attachment_id: &Uuid) -> Result<(), DataError> {
+        let mut client = self.client.borrow_mut();
+        let mut tx = client.transaction()?;
+        tx.execute("DELETE FROM attachments WHERE id = $1", &[&attachment_id.to_string()])?;
+        tx.execute("DELETE FROM attachment_files WHERE hash NOT IN (SELECT hash FROM attachments)", &[])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    // 操作记录同样不经过 `write`
+    fn record_audit(&self, entry: &AuditEntry) -> Result<(), DataError> {
+        let row = AuditRow::encode(entry);
+        self.client.borrow_mut().execute(
+            "INSERT INTO audit_log (id, operator, action, household_id, detail, recorded_at) VALUES ($1, $2, $3, $4, $5, $6)",
+            &[&row.id, &row.operator, &row.action, &row.household_id, &row.detail, &row.recorded_at],
+        )?;
+        Ok(())
+    }
+
+    fn load_audit(&self, limit: usize) -> Result<Vec<AuditEntry>, DataError> {
+        let rows = self.client.borrow_mut().query(
+            "SELECT id, operator, action, household_id, detail, recorded_at FROM audit_log
+             ORDER BY recorded_at DESC, seq DESC LIMIT $1",
+            &[&(limit as i64)],
+        )?;
+        Ok(rows.iter()
+            .map(|row| AuditRow {
+                id: row.get(0),
+                operator: row.get(1),
+                action: row.get(2),
+                household_id: row.get(3),
+                detail: row.get(4),
+                recorded_at: row.get(5),
+            })
+            .filter_map(|row| row.decode())
+            .collect())
+    }
+}
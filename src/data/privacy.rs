@@ -0,0 +1,81 @@
+use super::models::Household;
+use crate::utils::format::{format_id_number_masked, format_phone_masked};
+use serde::{Deserialize, Serialize};
+
+/// 操作员角色，决定能否修改数据和查看完整的证件号码、电话。
+/// 设置文件位于程序目录，任何人都可修改，未设置时按权限较小的查看员处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// 只能查看，敏感字段始终遮蔽
+    #[default]
+    Viewer,
+    /// 可修改数据，敏感字段默认遮蔽，点击后显示完整内容并记入操作记录
+    Editor,
+}
+
+impl Role {
+    pub fn can_edit(&self) -> bool {
+        matches!(self, Role::Editor)
+    }
+
+    pub fn can_reveal(&self) -> bool {
+        matches!(self, Role::Editor)
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Viewer => write!(f, "查看员"),
+            Role::Editor => write!(f, "编辑员"),
+        }
+    }
+}
+
+/// 需要遮蔽的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensitiveField {
+    IdNumber,
+    Phone,
+}
+
+impl std::fmt::Display for SensitiveField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SensitiveField::IdNumber => write!(f, "证件号码"),
+            SensitiveField::Phone => write!(f, "联系电话"),
+        }
+    }
+}
+
+/// 遮蔽后的字段内容。居民身份证号保留前6位和后4位，手机号保留前3位和后4位；
+/// 护照等其他证件号码和固定电话保留首尾各2个字符，过短的整体遮蔽
+pub fn mask(field: SensitiveField, value: &str) -> String {
+    let value = value.trim();
+    if value.is_empty() {
+        return String::new();
+    }
+    let length = value.chars().count();
+    match field {
+        SensitiveField::IdNumber if length == 18 => format_id_number_masked(value),
+        SensitiveField::Phone if length == 11 => format_phone_masked(value),
+        _ if length <= 4 => "****".to_string(),
+        _ => {
+            let start: String = value.chars().take(2).collect();
+            let end: String = value.chars().skip(length - 2).collect();
+            format!("{}****{}", start, end)
+        }
+    }
+}
+
+/// 遮蔽户主和各成员证件号码及联系电话后的副本，用于脱敏导出和打印
+pub fn mask_household(household: &Household) -> Household {
+    let mut masked = household.clone();
+    masked.id_number = mask(SensitiveField::IdNumber, &household.id_number);
+    masked.phone = mask(SensitiveField::Phone, &household.phone);
+    for member in &mut masked.members {
+        member.id_number = mask(SensitiveField::IdNumber, &member.id_number);
+    }
+    masked
+}
@@ -0,0 +1,492 @@
+use super::models::{Gender, Member};
+use serde::{Deserialize, Serialize};
+
+/// 与户主关系，按 GB/T 4761《家庭关系代码》以两位代码存储
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Relationship(u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationshipCategory {
+    Head,           // 户主
+    Spouse,         // 配偶
+    Son,            // 子
+    Daughter,       // 女
+    Grandchild,     // 孙子女
+    Parent,         // 父母
+    Grandparent,    // 祖父母
+    Sibling,        // 兄弟姐妹
+    OtherRelative,  // 其他亲属
+    NonRelative,    // 非亲属
+}
+
+impl std::fmt::Display for RelationshipCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RelationshipCategory::Head => write!(f, "户主"),
+            RelationshipCategory::Spouse => write!(f, "配偶"),
+            RelationshipCategory::Son => write!(f, "子"),
+            RelationshipCategory::Daughter => write!(f, "女"),
+            RelationshipCategory::Grandchild => write!(f, "孙子女"),
+            RelationshipCategory::Parent => write!(f, "父母"),
+            RelationshipCategory::Grandparent => write!(f, "祖父母"),
+            RelationshipCategory::Sibling => write!(f, "兄弟姐妹"),
+            RelationshipCategory::OtherRelative => write!(f, "其他亲属"),
+            RelationshipCategory::NonRelative => write!(f, "非亲属"),
+        }
+    }
+}
+
+pub struct RelationshipCode {
+    pub code: &'static str,
+    pub name: &'static str,
+    pub category: RelationshipCategory,
+}
+
+const fn entry(code: &'static str, name: &'static str, category: RelationshipCategory) -> RelationshipCode {
+    RelationshipCode { code, name, category }
+}
+
+use RelationshipCategory as C;
+
+/// GB/T 4761 家庭关系代码表（“01 本人”不用于与户主关系，未列入）
+pub const RELATIONSHIP_CODES: &[RelationshipCode] = &[
+    entry("02", "户主", C::Head),
+    entry("10", "配偶", C::Spouse),
+    entry("11", "夫", C::Spouse),
+    entry("12", "妻", C::Spouse),
+    entry("20", "子", C::Son),
+    entry("21", "独生子", C::Son),
+    entry("22", "长子", C::Son),
+    entry("23", "次子", C::Son),
+    entry("24", "三子", C::Son),
+    entry("25", "四子", C::Son),
+    entry("26", "五子", C::Son),
+    entry("27", "养子或继子", C::Son),
+    entry("28", "女婿", C::Son),
+    entry("29", "其他儿子", C::Son),
+    entry("30", "女", C::Daughter),
+    entry("31", "独生女", C::Daughter),
+    entry("32", "长女", C::Daughter),
+    entry("33", "次女", C::Daughter),
+    entry("34", "三女", C::Daughter),
+    entry("35", "四女", C::Daughter),
+    entry("36", "五女", C::Daughter),
+    entry("37", "养女或继女", C::Daughter),
+    entry("38", "儿媳", C::Daughter),
+    entry("39", "其他女儿", C::Daughter),
+    entry("40", "孙子、孙女或外孙子、外孙女", C::Grandchild),
+    entry("41", "孙子", C::Grandchild),
+    entry("42", "孙女", C::Grandchild),
+    entry("43", "外孙子", C::Grandchild),
+    entry("44", "外孙女", C::Grandchild),
+    entry("45", "孙媳妇或外孙媳妇", C::Grandchild),
+    entry("46", "孙女婿或外孙女婿", C::Grandchild),
+    entry("47", "曾孙子或外曾孙子", C::Grandchild),
+    entry("48", "曾孙女或外曾孙女", C::Grandchild),
+    entry("49", "其他孙子、孙女或外孙子、外孙女", C::Grandchild),
+    entry("50", "父母", C::Parent),
+    entry("51", "父亲", C::Parent),
+    entry("52", "母亲", C::Parent),
+    entry("53", "公公", C::Parent),
+    entry("54", "婆婆", C::Parent),
+    entry("55", "岳父", C::Parent),
+    entry("56", "岳母", C::Parent),
+    entry("57", "继父或养父", C::Parent),
+    entry("58", "继母或养母", C::Parent),
+    entry("59", "其他父母关系", C::Parent),
+    entry("60", "祖父母或外祖父母", C::Grandparent),
+    entry("61", "祖父", C::Grandparent),
+    entry("62", "祖母", C::Grandparent),
+    entry("63", "外祖父", C::Grandparent),
+    entry("64", "外祖母", C::Grandparent),
+    entry("65", "配偶的祖父母或外祖父母", C::Grandparent),
+    entry("66", "曾祖父", C::Grandparent),
+    entry("67", "曾祖母", C::Grandparent),
+    entry("68", "配偶的曾祖父母或外曾祖父母", C::Grandparent),
+    entry("69", "其他祖父母或外祖父母关系", C::Grandparent),
+    entry("70", "兄弟姐妹", C::Sibling),
+    entry("71", "兄", C::Sibling),
+    entry("72", "嫂", C::Sibling),
+    entry("73", "弟", C::Sibling),
+    entry("74", "弟媳", C::Sibling),
+    entry("75", "姐姐", C::Sibling),
+    entry("76", "姐夫", C::Sibling),
+    entry("77", "妹妹", C::Sibling),
+    entry("78", "妹夫", C::Sibling),
+    entry("79", "其他兄弟姐妹", C::Sibling),
+    entry("80", "其他", C::OtherRelative),
+    entry("81", "伯父", C::OtherRelative),
+    entry("82", "伯母", C::OtherRelative),
+    entry("83", "叔父", C::OtherRelative),
+    entry("84", "婶母", C::OtherRelative),
+    entry("85", "舅父", C::OtherRelative),
+    entry("86", "舅母", C::OtherRelative),
+    entry("87", "姨父", C::OtherRelative),
+    entry("88", "姨母", C::OtherRelative),
+    entry("89", "姑父", C::OtherRelative),
+    entry("90", "姑母", C::OtherRelative),
+    entry("91", "堂兄弟、堂姐妹", C::OtherRelative),
+    entry("92", "表兄弟、表姐妹", C::OtherRelative),
+    entry("93", "侄子", C::OtherRelative),
+    entry("94", "侄女", C::OtherRelative),
+    entry("95", "外甥", C::OtherRelative),
+    entry("96", "外甥女", C::OtherRelative),
+    entry("97", "其他亲属", C::OtherRelative),
+    entry("99", "非亲属", C::NonRelative),
+];
+
+/// 下拉框分组显示的顺序
+pub const RELATIONSHIP_CATEGORIES: &[RelationshipCategory] = &[
+    C::Head,
+    C::Spouse,
+    C::Son,
+    C::Daughter,
+    C::Grandchild,
+    C::Parent,
+    C::Grandparent,
+    C::Sibling,
+    C::OtherRelative,
+    C::NonRelative,
+];
+
+impl Relationship {
+    pub const HEAD: Relationship = Relationship(2);
+    pub const SPOUSE: Relationship = Relationship(10);
+    pub const HUSBAND: Relationship = Relationship(11);
+    pub const WIFE: Relationship = Relationship(12);
+    pub const SON: Relationship = Relationship(20);
+    pub const SON_IN_LAW: Relationship = Relationship(28);
+    pub const DAUGHTER: Relationship = Relationship(30);
+    pub const DAUGHTER_IN_LAW: Relationship = Relationship(38);
+    pub const GRANDCHILD: Relationship = Relationship(40);
+    pub const GRANDSON: Relationship = Relationship(41);
+    pub const GRANDDAUGHTER: Relationship = Relationship(42);
+    pub const GRANDDAUGHTER_IN_LAW: Relationship = Relationship(45);
+    pub const GRANDSON_IN_LAW: Relationship = Relationship(46);
+    pub const PARENT: Relationship = Relationship(50);
+    pub const FATHER: Relationship = Relationship(51);
+    pub const MOTHER: Relationship = Relationship(52);
+    pub const FATHER_IN_LAW_OF_WIFE: Relationship = Relationship(53);
+    pub const MOTHER_IN_LAW_OF_WIFE: Relationship = Relationship(54);
+    pub const FATHER_IN_LAW_OF_HUSBAND: Relationship = Relationship(55);
+    pub const MOTHER_IN_LAW_OF_HUSBAND: Relationship = Relationship(56);
+    pub const GRANDPARENT: Relationship = Relationship(60);
+    pub const SIBLING: Relationship = Relationship(70);
+    pub const ELDER_BROTHER: Relationship = Relationship(71);
+    pub const YOUNGER_BROTHER: Relationship = Relationship(73);
+    pub const ELDER_SISTER: Relationship = Relationship(75);
+    pub const YOUNGER_SISTER: Relationship = Relationship(77);
+    pub const NEPHEW: Relationship = Relationship(93);
+    pub const NIECE: Relationship = Relationship(94);
+    pub const SISTERS_SON: Relationship = Relationship(95);
+    pub const SISTERS_DAUGHTER: Relationship = Relationship(96);
+    pub const OTHER_RELATIVE: Relationship = Relationship(97);
+    pub const NON_RELATIVE: Relationship = Relationship(99);
+
+    /// 按两位代码查找，代码不在表中时返回 `None`
+    pub fn from_code(code: &str) -> Option<Relationship> {
+        RELATIONSHIP_CODES.iter()
+            .find(|e| e.code == code.trim())
+            .map(|e| Relationship(code_value(e.code)))
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.entry().map(|e| e.code).unwrap_or("97")
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.entry().map(|e| e.name).unwrap_or("未知")
+    }
+
+    pub fn category(&self) -> RelationshipCategory {
+        self.entry().map(|e| e.category).unwrap_or(C::OtherRelative)
+    }
+
+    pub fn is_head(&self) -> bool {
+        self.category() == C::Head
+    }
+
+    pub fn is_spouse(&self) -> bool {
+        self.category() == C::Spouse
+    }
+
+    /// 女婿、儿媳，代码表中归入子、女两类
+    pub fn is_child_in_law(&self) -> bool {
+        *self == Relationship::SON_IN_LAW || *self == Relationship::DAUGHTER_IN_LAW
+    }
+
+    pub fn all() -> impl Iterator<Item = Relationship> {
+        RELATIONSHIP_CODES.iter().map(|e| Relationship(code_value(e.code)))
+    }
+
+    pub fn in_category(category: RelationshipCategory) -> impl Iterator<Item = Relationship> {
+        RELATIONSHIP_CODES.iter()
+            .filter(move |e| e.category == category)
+            .map(|e| Relationship(code_value(e.code)))
+    }
+
+    fn entry(&self) -> Option<&'static RelationshipCode> {
+        RELATIONSHIP_CODES.iter().find(|e| code_value(e.code) == self.0)
+    }
+}
+
+// 两位代码表中的代码均为数字
+fn code_value(code: &str) -> u8 {
+    code.parse().unwrap_or(97)
+}
+
+impl std::fmt::Display for Relationship {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Default for Relationship {
+    fn default() -> Self {
+        Relationship::HEAD
+    }
+}
+
+impl TryFrom<String> for Relationship {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        // 兼容旧版本导出的枚举名
+        let legacy = match value.as_str() {
+            "Head" => Some(Relationship::HEAD),
+            "Spouse" => Some(Relationship::SPOUSE),
+            "Child" => Some(Relationship::SON),
+            "Parent" => Some(Relationship::PARENT),
+            "Other" => Some(Relationship::OTHER_RELATIVE),
+            _ => None,
+        };
+        legacy
+            .or_else(|| Relationship::from_code(&value))
+            .ok_or_else(|| format!("未知的家庭关系代码: {}", value))
+    }
+}
+
+impl From<Relationship> for String {
+    fn from(value: Relationship) -> Self {
+        value.code().to_string()
+    }
+}
+
+/// 新户主为原户主的子女或女婿、儿媳时，在户内找出其配偶：性别不同、一方为女婿或儿媳的唯一一人。
+/// 有多人符合时无法确定，返回 `None`
+pub fn child_spouse_index(members: &[Member], new_head_index: usize) -> Option<usize> {
+    use RelationshipCategory::*;
+
+    let new_head = members.get(new_head_index)?;
+    if !matches!(new_head.relationship.category(), Son | Daughter) {
+        return None;
+    }
+    let mut candidates = members.iter().enumerate().filter(|(i, m)| {
+        *i != new_head_index
+            && m.is_active()
+            && matches!(m.relationship.category(), Son | Daughter)
+            && m.gender != new_head.gender
+            && m.relationship.is_child_in_law() != new_head.relationship.is_child_in_law()
+    });
+    match (candidates.next(), candidates.next()) {
+        (Some((i, _)), None) => Some(i),
+        _ => None,
+    }
+}
+
+/// 变更户主后推算成员与新户主的关系，`is_new_head_spouse` 表示该成员是新户主的配偶（见 [`child_spouse_index`]）。
+/// 无法确定的归为“其他亲属”，由经办人在预览中调整
+pub fn remap_for_new_head(member: &Member, new_head: &Member, old_head: Option<&Member>, is_new_head_spouse: bool) -> Relationship {
+    use RelationshipCategory::*;
+
+    let old_head_gender = old_head.map(|h| h.gender);
+    let by_gender = |gender: Option<Gender>, male: Relationship, female: Relationship, unknown: Relationship| match gender {
+        Some(Gender::Male) => male,
+        Some(Gender::Female) => female,
+        None => unknown,
+    };
+    let member_gender = Some(member.gender);
+    let new_head_category = new_head.relationship.category();
+    let member_category = member.relationship.category();
+
+    if member_category == NonRelative {
+        return Relationship::NON_RELATIVE;
+    }
+    if is_new_head_spouse {
+        return by_gender(member_gender, Relationship::HUSBAND, Relationship::WIFE, Relationship::SPOUSE);
+    }
+    let new_head_in_law = new_head.relationship.is_child_in_law();
+
+    match (new_head_category, member_category) {
+        // 原户主相对于新户主
+        (Spouse, Head) => by_gender(old_head_gender, Relationship::HUSBAND, Relationship::WIFE, Relationship::SPOUSE),
+        // 新户主为女婿、儿媳时，原户主及其配偶为岳父母或公婆
+        (Son | Daughter, Head | Spouse) if new_head_in_law => {
+            let gender = if member_category == Head { old_head_gender } else { member_gender };
+            match new_head.gender {
+                Gender::Male => by_gender(gender, Relationship::FATHER_IN_LAW_OF_HUSBAND, Relationship::MOTHER_IN_LAW_OF_HUSBAND, Relationship::PARENT),
+                Gender::Female => by_gender(gender, Relationship::FATHER_IN_LAW_OF_WIFE, Relationship::MOTHER_IN_LAW_OF_WIFE, Relationship::PARENT),
+            }
+        }
+        (Son | Daughter, Head) => by_gender(old_head_gender, Relationship::FATHER, Relationship::MOTHER, Relationship::PARENT),
+        (Parent, Head) => by_gender(old_head_gender, Relationship::SON, Relationship::DAUGHTER, Relationship::SON),
+        (Grandchild, Head) => Relationship::GRANDPARENT,
+        (Grandparent, Head) => Relationship::GRANDCHILD,
+        (Sibling, Head) => sibling_of(member, new_head),
+
+        // 新户主为原户主的配偶
+        (Spouse, Son | Daughter) if member.relationship.is_child_in_law() => member.relationship,
+        (Spouse, Son) => Relationship::SON,
+        (Spouse, Daughter) => Relationship::DAUGHTER,
+        (Spouse, Grandchild) => member.relationship,
+        (Spouse, Parent) => match new_head.gender {
+            Gender::Female => by_gender(member_gender, Relationship::FATHER_IN_LAW_OF_WIFE, Relationship::MOTHER_IN_LAW_OF_WIFE, Relationship::PARENT),
+            Gender::Male => by_gender(member_gender, Relationship::FATHER_IN_LAW_OF_HUSBAND, Relationship::MOTHER_IN_LAW_OF_HUSBAND, Relationship::PARENT),
+        },
+
+        // 新户主为原户主的子女：原户主的配偶为其父母，其他子女为兄弟姐妹
+        (Son | Daughter, Spouse) => by_gender(member_gender, Relationship::FATHER, Relationship::MOTHER, Relationship::PARENT),
+        // 不是新户主配偶的女婿、儿媳，或新户主为女婿、儿媳时的其他子女，无法确定长幼
+        (Son | Daughter, Son | Daughter) if new_head_in_law || member.relationship.is_child_in_law() => Relationship::OTHER_RELATIVE,
+        (Son | Daughter, Son | Daughter) => sibling_of(member, new_head),
+        (Son | Daughter, Parent) => Relationship::GRANDPARENT,
+
+        // 新户主为原户主的父母
+        (Parent, Parent) => Relationship::SPOUSE,
+        (Parent, Spouse) => by_gender(member_gender, Relationship::SON_IN_LAW, Relationship::DAUGHTER_IN_LAW, Relationship::OTHER_RELATIVE),
+        (Parent, Son | Daughter) if member.relationship.is_child_in_law() => by_gender(member_gender, Relationship::GRANDSON_IN_LAW, Relationship::GRANDDAUGHTER_IN_LAW, Relationship::GRANDCHILD),
+        (Parent, Son | Daughter) => by_gender(member_gender, Relationship::GRANDSON, Relationship::GRANDDAUGHTER, Relationship::GRANDCHILD),
+        (Parent, Sibling) => by_gender(member_gender, Relationship::SON, Relationship::DAUGHTER, Relationship::SON),
+
+        // 新户主为原户主的兄弟姐妹
+        (Sibling, Parent) => member.relationship,
+        (Sibling, Sibling) => sibling_of(member, new_head),
+        (Sibling, Son | Daughter) if member.relationship.is_child_in_law() => Relationship::OTHER_RELATIVE,
+        (Sibling, Son | Daughter) => match new_head.gender {
+            Gender::Male => by_gender(member_gender, Relationship::NEPHEW, Relationship::NIECE, Relationship::OTHER_RELATIVE),
+            Gender::Female => by_gender(member_gender, Relationship::SISTERS_SON, Relationship::SISTERS_DAUGHTER, Relationship::OTHER_RELATIVE),
+        },
+
+        _ => Relationship::OTHER_RELATIVE,
+    }
+}
+
+// 按出生日期和性别确定兄弟姐妹的具体称谓
+fn sibling_of(member: &Member, new_head: &Member) -> Relationship {
+    let elder = member.birth_date < new_head.birth_date;
+    match (member.gender, elder) {
+        (Gender::Male, true) => Relationship::ELDER_BROTHER,
+        (Gender::Male, false) => Relationship::YOUNGER_BROTHER,
+        (Gender::Female, true) => Relationship::ELDER_SISTER,
+        (Gender::Female, false) => Relationship::YOUNGER_SISTER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::models::{DocumentType, Education, MaritalStatus, MemberStatus};
+    use chrono::NaiveDate;
+
+    fn member(name: &str, relationship: Relationship, gender: Gender, birth_year: i32) -> Member {
+        Member {
+            name: name.to_string(),
+            document_type: DocumentType::ResidentId,
+            id_number: String::new(),
+            nationality: "CHN".to_string(),
+            relationship,
+            birth_date: NaiveDate::from_ymd_opt(birth_year, 1, 1).unwrap(),
+            gender,
+            education: Education::University,
+            occupation: String::new(),
+            status: MemberStatus::Active,
+            marital_status: MaritalStatus::Married,
+            death_date: None,
+        }
+    }
+
+    // 按 `HeadChangeForm::preview` 的方式推算除新户主外各成员的新关系
+    fn remap_all(members: &[Member], new_head_index: usize) -> Vec<Relationship> {
+        let old_head = members.iter().find(|m| m.relationship.is_head());
+        let spouse_index = child_spouse_index(members, new_head_index);
+        members.iter()
+            .enumerate()
+            .map(|(i, m)| {
+                if i == new_head_index {
+                    Relationship::HEAD
+                } else {
+                    remap_for_new_head(m, &members[new_head_index], old_head, spouse_index == Some(i))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn son_becomes_head_with_his_wife() {
+        let members = [
+            member("张三", Relationship::HEAD, Gender::Male, 1960),
+            member("李四", Relationship::WIFE, Gender::Female, 1962),
+            member("张伟", Relationship::SON, Gender::Male, 1985),
+            member("王芳", Relationship::DAUGHTER_IN_LAW, Gender::Female, 1987),
+            member("张丽", Relationship::DAUGHTER, Gender::Female, 1990),
+        ];
+        assert_eq!(child_spouse_index(&members, 2), Some(3));
+        assert_eq!(remap_all(&members, 2), [
+            Relationship::FATHER,
+            Relationship::MOTHER,
+            Relationship::HEAD,
+            Relationship::WIFE,
+            Relationship::YOUNGER_SISTER,
+        ]);
+    }
+
+    #[test]
+    fn son_in_law_becomes_head() {
+        let members = [
+            member("张三", Relationship::HEAD, Gender::Male, 1960),
+            member("李四", Relationship::WIFE, Gender::Female, 1962),
+            member("张丽", Relationship::DAUGHTER, Gender::Female, 1988),
+            member("刘强", Relationship::SON_IN_LAW, Gender::Male, 1986),
+        ];
+        assert_eq!(remap_all(&members, 3), [
+            Relationship::FATHER_IN_LAW_OF_HUSBAND,
+            Relationship::MOTHER_IN_LAW_OF_HUSBAND,
+            Relationship::WIFE,
+            Relationship::HEAD,
+        ]);
+    }
+
+    #[test]
+    fn ambiguous_child_in_law_is_other_relative() {
+        // 两个儿子都已婚，无法确定哪位儿媳是新户主的妻子
+        let members = [
+            member("张三", Relationship::HEAD, Gender::Male, 1960),
+            member("张伟", Relationship::SON, Gender::Male, 1985),
+            member("王芳", Relationship::DAUGHTER_IN_LAW, Gender::Female, 1987),
+            member("张强", Relationship::SON, Gender::Male, 1988),
+            member("赵敏", Relationship::DAUGHTER_IN_LAW, Gender::Female, 1989),
+        ];
+        assert_eq!(child_spouse_index(&members, 1), None);
+        let relationships = remap_all(&members, 1);
+        assert_eq!(relationships[2], Relationship::OTHER_RELATIVE);
+        assert_eq!(relationships[3], Relationship::YOUNGER_BROTHER);
+        assert_eq!(relationships[4], Relationship::OTHER_RELATIVE);
+    }
+
+    #[test]
+    fn spouse_becomes_head_keeps_child_in_law() {
+        let members = [
+            member("张三", Relationship::HEAD, Gender::Male, 1960),
+            member("李四", Relationship::WIFE, Gender::Female, 1962),
+            member("张伟", Relationship::SON, Gender::Male, 1985),
+            member("王芳", Relationship::DAUGHTER_IN_LAW, Gender::Female, 1987),
+        ];
+        assert_eq!(remap_all(&members, 1), [
+            Relationship::HUSBAND,
+            Relationship::HEAD,
+            Relationship::SON,
+            Relationship::DAUGHTER_IN_LAW,
+        ]);
+    }
+}
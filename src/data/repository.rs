@@ -0,0 +1,102 @@
+use super::attachments::Attachment;
+use super::audit::AuditEntry;
+use super::codes::DecodeError;
+use super::divisions::DivisionLevel;
+use super::drafts::Draft;
+use super::error::DataError;
+use super::manager::{DivisionCount, HouseholdStatistics};
+use super::models::*;
+use uuid::Uuid;
+
+/// 加载结果：可正常读取的户籍，以及因数据无法识别而被跳过的记录
+#[derive(Debug, Default)]
+pub struct LoadedHouseholds {
+    pub households: Vec<Household>,
+    pub errors: Vec<DecodeError>,
+}
+
+/// 户籍数据的存储后端，`HouseholdManager` 只通过这些操作读写数据
+///
+/// 默认使用 SQLite 文件（`Database`），另有供测试使用的 `MemoryRepository`，
+/// 以及启用 `postgres` 功能时可供多个办公点共用的 `PostgresRepository`。
+/// 各实现须保持相同的约束：同一证件号码只能登记在一户的在册成员中，仍有户籍登记的门牌不能删除。
+pub trait HouseholdRepository {
+    fn is_empty(&self) -> Result<bool, DataError>;
+
+    /// 新增户籍及其成员，违反唯一性约束时返回 `DataError::Constraint`
+    fn insert_household(&self, household: &Household) -> Result<(), DataError>;
+
+    /// 更新户籍并整体替换其成员，选择了省级区划的地址视为已核对；
+    /// 存储中的版本与 `household.revision` 不同或户籍已被删除时返回 `DataError::Conflict`，成功后版本加一
+    fn update_household(&self, household: &Household) -> Result<(), DataError>;
+
+    /// 删除户籍及其成员，存储中的版本与 `revision` 不同或户籍已被删除时返回 `DataError::Conflict`
+    fn delete_household(&self, household_id: &Uuid, revision: i64) -> Result<(), DataError>;
+
+    /// 在同一事务中批量新增或更新、删除户籍并记录变动事件，任一步失败则全部回滚；
+    /// 已有户籍的版本检查与 `update_household` 相同，版本大于零而存储中不存在的户籍视为已被删除，
+    /// `deletions` 为户籍ID和读取时的版本，检查与 `delete_household` 相同
+    fn apply_changes(&self, updates: &[Household], deletions: &[(Uuid, i64)], events: &[HouseholdEvent]) -> Result<(), DataError>;
+
+    /// 某户的变动事件，按日期从新到旧
+    fn get_events_by_household_id(&self, household_id: &Uuid) -> Result<Vec<HouseholdEvent>, DataError>;
+
+    /// 读取全部户籍，按登记日期从新到旧；无法识别的记录连同所在户跳过并记录在 `errors` 中
+    fn load_households(&self) -> Result<LoadedHouseholds, DataError>;
+
+    fn load_dwellings(&self) -> Result<(Vec<Dwelling>, Vec<DecodeError>), DataError>;
+
+    /// 新增或更新门牌，同一社区内楼栋、单元、房号重复时返回 `DataError::Constraint`
+    fn save_dwelling(&self, dwelling: &Dwelling) -> Result<(), DataError>;
+
+    fn delete_dwelling(&self, dwelling_id: &Uuid) -> Result<(), DataError>;
+
+    /// 存储本身的完整性问题，没有问题时为空
+    fn integrity_check(&self) -> Result<Vec<String>, DataError>;
+
+    /// 备份到指定文件，不支持的后端返回错误说明
+    fn backup(&self, path: &str) -> Result<(), DataError>;
+
+    /// 按户主姓名、证件号码、地址或电话模糊搜索，返回匹配的户籍ID
+    fn search_households(&self, query: &str) -> Result<Vec<Uuid>, DataError>;
+
+    fn count_by_division(&self, level: DivisionLevel) -> Result<Vec<DivisionCount>, DataError>;
+
+    fn address_review_ids(&self) -> Result<Vec<Uuid>, DataError>;
+
+    /// 保存核对后的地址并清除待核对标记
+    fn resolve_address(&self, household_id: &Uuid, address: &Address) -> Result<(), DataError>;
+
+    fn get_statistics(&self) -> Result<HouseholdStatistics, DataError>;
+
+    /// 数据的修改计数，其他程序写入后会变化，用于判断缓存是否需要重新加载；
+    /// 只有本程序访问的后端始终返回同一个值
+    fn data_version(&self) -> Result<i64, DataError>;
+
+    /// 新增或更新表单草稿，草稿不属于户籍数据，不检查版本和唯一性
+    fn save_draft(&self, draft: &Draft) -> Result<(), DataError>;
+
+    /// 某操作员的草稿，按保存时间从新到旧；无法识别的草稿跳过
+    fn load_drafts(&self, operator: &str) -> Result<Vec<Draft>, DataError>;
+
+    fn delete_draft(&self, draft_id: &Uuid) -> Result<(), DataError>;
+
+    /// 保存附件及其内容，内容相同的文件只存一份；附件ID已存在时更新其说明。
+    /// 附件不属于户籍数据，不改变户籍版本，删除户籍时保留
+    fn save_attachment(&self, attachment: &Attachment, data: &[u8]) -> Result<(), DataError>;
+
+    /// 属于某户或证件号码在 `member_id_numbers` 中的成员的附件，按添加时间排序；无法识别的记录跳过
+    fn load_attachments(&self, household_id: &Uuid, member_id_numbers: &[String]) -> Result<Vec<Attachment>, DataError>;
+
+    /// 按哈希读取附件内容，已不存在时为 `None`
+    fn attachment_data(&self, hash: &str) -> Result<Option<Vec<u8>>, DataError>;
+
+    /// 删除附件，其内容不再被其他附件引用时一并删除
+    fn delete_attachment(&self, attachment_id: &Uuid) -> Result<(), DataError>;
+
+    /// 追加一条操作记录
+    fn record_audit(&self, entry: &AuditEntry) -> Result<(), DataError>;
+
+    /// 最近的 `limit` 条操作记录，按时间从新到旧；无法识别的记录跳过
+    fn load_audit(&self, limit: usize) -> Result<Vec<AuditEntry>, DataError>;
+}
@@ -0,0 +1,709 @@
+use super::codes::StableCode;
+use super::documents::{self, DocumentType};
+use super::models::{HouseholdForm, MemberForm};
+use super::validation::ValidationErrors;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// 校验规则配置文件，与数据库文件放在同一目录
+pub const RULES_FILE: &str = "validation_rules.json";
+
+/// 户籍表单可被规则引用的字段
+pub const HOUSEHOLD_FIELDS: &[(&str, &str)] = &[
+    ("address", "家庭地址"),
+    ("address_division", "所在行政区划"),
+    ("address_detail", "详细地址"),
+    ("phone", "联系电话"),
+    ("household_type", "户口类型"),
+    ("members", "家庭成员"),
+];
+
+/// 成员表单可被规则引用的字段
+pub const MEMBER_FIELDS: &[(&str, &str)] = &[
+    ("name", "姓名"),
+    ("document_type", "证件类型"),
+    ("id_number", "证件号码"),
+    ("nationality", "国籍"),
+    ("relationship", "与户主关系"),
+    ("birth_date", "出生日期"),
+    ("gender", "性别"),
+    ("education", "学历"),
+    ("occupation", "职业"),
+    ("marital_status", "婚姻状况"),
+];
+
+/// 规则读取到的字段值。枚举字段以存储代码表示
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Count(usize),
+    Date(Option<NaiveDate>),
+}
+
+/// 可被规则校验的表单
+pub trait FieldSource {
+    fn field(&self, name: &str) -> Option<FieldValue>;
+}
+
+/// 内置的格式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldFormat {
+    /// 11位手机号，以1开头
+    Mobile,
+    /// 固定电话，区号以0开头，如 010-12345678
+    Landline,
+    /// 手机号或固定电话
+    Phone,
+    /// 18位居民身份证号
+    IdNumber,
+    /// 港澳台居民居住证号，地址码为 810000、820000 或 830000
+    HmtResidencePermit,
+    /// 外国人永久居留身份证号，15位旧版或18位新版
+    ForeignPermanentId,
+    /// 护照号，5-20位字母或数字
+    Passport,
+    /// 三字母国籍代码，如 USA
+    Nationality,
+    /// 纯数字
+    Digits,
+}
+
+impl FieldFormat {
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            FieldFormat::Mobile => is_mobile(value),
+            FieldFormat::Landline => is_landline(value),
+            FieldFormat::Phone => is_mobile(value) || is_landline(value),
+            FieldFormat::IdNumber => DocumentType::ResidentId.is_valid_number(value),
+            FieldFormat::HmtResidencePermit => DocumentType::HmtResidencePermit.is_valid_number(value),
+            FieldFormat::ForeignPermanentId => DocumentType::ForeignPermanentId.is_valid_number(value),
+            FieldFormat::Passport => DocumentType::Passport.is_valid_number(value),
+            FieldFormat::Nationality => documents::is_nationality_code(&value.to_uppercase()),
+            FieldFormat::Digits => !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()),
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            FieldFormat::Mobile => "手机号",
+            FieldFormat::Landline => "固定电话",
+            FieldFormat::Phone => "电话号码",
+            FieldFormat::IdNumber => "身份证号",
+            FieldFormat::HmtResidencePermit => "港澳台居民居住证号",
+            FieldFormat::ForeignPermanentId => "永久居留身份证号",
+            FieldFormat::Passport => "护照号",
+            FieldFormat::Nationality => "三字母国籍代码",
+            FieldFormat::Digits => "数字",
+        }
+    }
+}
+
+/// 一条校验规则。格式、长度等规则对空值不生效，必填由 `required` 单独指定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    /// 字段不能为空
+    Required {
+        field: String,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// 字段符合内置格式
+    Format {
+        field: String,
+        format: FieldFormat,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// 文本长度（按字符计）
+    Length {
+        field: String,
+        #[serde(default)]
+        min: Option<usize>,
+        #[serde(default)]
+        max: Option<usize>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// 日期的年份范围，未指定上限时为当前年份
+    YearRange {
+        field: String,
+        #[serde(default)]
+        min: Option<i32>,
+        #[serde(default)]
+        max: Option<i32>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// 列表字段的条目数
+    Count {
+        field: String,
+        #[serde(default)]
+        min: Option<usize>,
+        #[serde(default)]
+        max: Option<usize>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// 任一子规则通过即可，如身份证号或护照号
+    AnyOf {
+        rules: Vec<Rule>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// 字段等于指定值时才检查子规则
+    When {
+        field: String,
+        equals: String,
+        rules: Vec<Rule>,
+    },
+}
+
+impl Rule {
+    /// 检查规则，失败时返回出错字段和错误信息
+    pub fn check(&self, source: &dyn FieldSource, labels: &[(&str, &str)]) -> Option<(String, String)> {
+        let fail = |field: &str, message: &Option<String>, default: String| {
+            Some((field.to_string(), message.clone().unwrap_or(default)))
+        };
+
+        match self {
+            Rule::Required { field, message } => {
+                let empty = match source.field(field)? {
+                    FieldValue::Text(text) => text.trim().is_empty(),
+                    FieldValue::Count(count) => count == 0,
+                    FieldValue::Date(date) => date.is_none(),
+                };
+                if empty {
+                    return fail(field, message, format!("{}不能为空", label(labels, field)));
+                }
+                None
+            }
+            Rule::Format { field, format, message } => {
+                let text = non_empty_text(source, field)?;
+                if !format.matches(&text) {
+                    return fail(field, message, format!("{}格式不正确，应为{}", label(labels, field), format.description()));
+                }
+                None
+            }
+            Rule::Length { field, min, max, message } => {
+                let length = non_empty_text(source, field)?.chars().count();
+                if min.is_some_and(|min| length < min) || max.is_some_and(|max| length > max) {
+                    return fail(field, message, format!("{}长度应为{}", label(labels, field), describe_range(*min, *max, "个字符")));
+                }
+                None
+            }
+            Rule::YearRange { field, min, max, message } => {
+                let FieldValue::Date(Some(date)) = source.field(field)? else {
+                    return None;
+                };
+                let max = max.unwrap_or_else(|| chrono::Local::now().year());
+                if min.is_some_and(|min| date.year() < min) || date.year() > max {
+                    return fail(field, message, format!("{}年份不合理", label(labels, field)));
+                }
+                None
+            }
+            Rule::Count { field, min, max, message } => {
+                let FieldValue::Count(count) = source.field(field)? else {
+                    return None;
+                };
+                if min.is_some_and(|min| count < min) || max.is_some_and(|max| count > max) {
+                    return fail(field, message, format!("{}数量应为{}", label(labels, field), describe_range(*min, *max, "个")));
+                }
+                None
+            }
+            Rule::AnyOf { rules, message } => {
+                let failures: Vec<(String, String)> = rules.iter()
+                    .map(|rule| rule.check(source, labels))
+                    .collect::<Option<Vec<_>>>()?;
+                let (field, first_message) = failures.into_iter().next()?;
+                Some((field, message.clone().unwrap_or(first_message)))
+            }
+            Rule::When { field, equals, rules } => {
+                match source.field(field)? {
+                    FieldValue::Text(text) if text == *equals => {
+                        rules.iter().find_map(|rule| rule.check(source, labels))
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    // 规则可能报告错误的字段，条件规则为其子规则检查的字段
+    fn checked_fields(&self) -> Vec<&str> {
+        match self {
+            Rule::AnyOf { rules, .. } | Rule::When { rules, .. } => rules.iter().flat_map(|r| r.checked_fields()).collect(),
+            _ => self.fields(),
+        }
+    }
+
+    // 规则引用的全部字段，用于加载配置时检查字段名
+    fn fields(&self) -> Vec<&str> {
+        match self {
+            Rule::Required { field, .. }
+            | Rule::Format { field, .. }
+            | Rule::Length { field, .. }
+            | Rule::YearRange { field, .. }
+            | Rule::Count { field, .. } => vec![field.as_str()],
+            Rule::AnyOf { rules, .. } => rules.iter().flat_map(|r| r.fields()).collect(),
+            Rule::When { field, rules, .. } => {
+                let mut fields = vec![field.as_str()];
+                fields.extend(rules.iter().flat_map(|r| r.fields()));
+                fields
+            }
+        }
+    }
+}
+
+/// 户籍和成员表单的校验规则集
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub household: Vec<Rule>,
+    #[serde(default)]
+    pub member: Vec<Rule>,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        let text = |s: &str| s.to_string();
+        Self {
+            household: vec![
+                Rule::Required { field: text("address_division"), message: Some(text("请选择所在行政区划")) },
+                Rule::Required { field: text("address_detail"), message: Some(text("详细地址不能为空")) },
+                Rule::Format { field: text("phone"), format: FieldFormat::Mobile, message: Some(text("手机号格式不正确")) },
+                Rule::Count { field: text("members"), min: Some(1), max: None, message: Some(text("至少需要一个家庭成员")) },
+            ],
+            member: vec![
+                Rule::Required { field: text("name"), message: Some(text("姓名不能为空")) },
+                Rule::Required { field: text("id_number"), message: Some(text("证件号码不能为空")) },
+                Rule::When {
+                    field: text("document_type"),
+                    equals: text(DocumentType::ResidentId.code()),
+                    rules: vec![Rule::Format { field: text("id_number"), format: FieldFormat::IdNumber, message: Some(text("身份证号格式不正确")) }],
+                },
+                Rule::When {
+                    field: text("document_type"),
+                    equals: text(DocumentType::HmtResidencePermit.code()),
+                    rules: vec![Rule::Format { field: text("id_number"), format: FieldFormat::HmtResidencePermit, message: None }],
+                },
+                Rule::When {
+                    field: text("document_type"),
+                    equals: text(DocumentType::ForeignPermanentId.code()),
+                    rules: vec![
+                        Rule::Format { field: text("id_number"), format: FieldFormat::ForeignPermanentId, message: None },
+                        Rule::Required { field: text("nationality"), message: Some(text("外籍人员须填写国籍")) },
+                        Rule::Format { field: text("nationality"), format: FieldFormat::Nationality, message: None },
+                    ],
+                },
+                Rule::When {
+                    field: text("document_type"),
+                    equals: text(DocumentType::Passport.code()),
+                    rules: vec![
+                        Rule::Format { field: text("id_number"), format: FieldFormat::Passport, message: None },
+                        Rule::Required { field: text("nationality"), message: Some(text("外籍人员须填写国籍")) },
+                        Rule::Format { field: text("nationality"), format: FieldFormat::Nationality, message: None },
+                    ],
+                },
+                Rule::YearRange { field: text("birth_date"), min: Some(1900), max: None, message: Some(text("出生年份不合理")) },
+            ],
+        }
+    }
+}
+
+/// 规则配置文件的内容，`replace_defaults` 为真时不保留任何内置规则
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    replace_defaults: bool,
+    #[serde(default)]
+    household: Vec<Rule>,
+    #[serde(default)]
+    member: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// 从配置文件读取规则并与默认规则合并，文件不存在时使用默认规则。
+    /// 配置了某字段的规则时，默认规则中检查该字段的规则被替换，其余默认规则保留
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("无法读取 {}: {}", path, e)),
+        };
+        let file: RulesFile = serde_json::from_str(&content)
+            .map_err(|e| format!("{} 格式错误: {}", path, e))?;
+        let rules = if file.replace_defaults {
+            RuleSet { household: file.household, member: file.member }
+        } else {
+            let defaults = Self::default();
+            RuleSet {
+                household: merge_rules(defaults.household, file.household),
+                member: merge_rules(defaults.member, file.member),
+            }
+        };
+        rules.check_fields()?;
+        Ok(rules)
+    }
+
+    fn check_fields(&self) -> Result<(), String> {
+        for (rules, known, scope) in [(&self.household, HOUSEHOLD_FIELDS, "户籍"), (&self.member, MEMBER_FIELDS, "成员")] {
+            for field in rules.iter().flat_map(|r| r.fields()) {
+                if !known.iter().any(|(name, _)| *name == field) {
+                    return Err(format!("{}规则引用了未知字段: {}", scope, field));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 依次检查规则，同一字段只记录第一条错误
+    pub fn evaluate(rules: &[Rule], source: &dyn FieldSource, labels: &[(&str, &str)], errors: &mut ValidationErrors) {
+        for rule in rules {
+            if let Some((field, message)) = rule.check(source, labels) {
+                if errors.get(&field).is_none() {
+                    errors.add(field, message);
+                }
+            }
+        }
+    }
+}
+
+static ACTIVE_RULES: RwLock<Option<Arc<RuleSet>>> = RwLock::new(None);
+
+/// 当前生效的规则集，未安装时为默认规则
+pub fn active() -> Arc<RuleSet> {
+    ACTIVE_RULES.read()
+        .ok()
+        .and_then(|rules| rules.clone())
+        .unwrap_or_default()
+}
+
+/// 安装启动时读取的规则集
+pub fn install(rules: RuleSet) {
+    if let Ok(mut active) = ACTIVE_RULES.write() {
+        *active = Some(Arc::new(rules));
+    }
+}
+
+impl FieldSource for HouseholdForm {
+    fn field(&self, name: &str) -> Option<FieldValue> {
+        match name {
+            "address" => Some(FieldValue::Text(self.address.to_string())),
+            "address_division" => Some(FieldValue::Text(self.address.division().to_string())),
+            "address_detail" => Some(FieldValue::Text(self.address.detail.clone())),
+            "phone" => Some(FieldValue::Text(self.phone.clone())),
+            "household_type" => Some(FieldValue::Text(self.household_type.code().to_string())),
+            "members" => Some(FieldValue::Count(self.members.len())),
+            _ => None,
+        }
+    }
+}
+
+impl FieldSource for MemberForm {
+    fn field(&self, name: &str) -> Option<FieldValue> {
+        match name {
+            "name" => Some(FieldValue::Text(self.name.clone())),
+            "document_type" => Some(FieldValue::Text(self.document_type.code().to_string())),
+            "id_number" => Some(FieldValue::Text(self.id_number.clone())),
+            "nationality" => Some(FieldValue::Text(self.nationality())),
+            "relationship" => Some(FieldValue::Text(self.relationship.code().to_string())),
+            "birth_date" => Some(FieldValue::Date(NaiveDate::from_ymd_opt(self.birth_year, self.birth_month, self.birth_day))),
+            "gender" => Some(FieldValue::Text(self.gender.code().to_string())),
+            "education" => Some(FieldValue::Text(self.education.code().to_string())),
+            "occupation" => Some(FieldValue::Text(self.occupation.clone())),
+            "marital_status" => Some(FieldValue::Text(self.marital_status.code().to_string())),
+            _ => None,
+        }
+    }
+}
+
+pub fn label<'a>(labels: &[(&str, &'a str)], field: &'a str) -> &'a str {
+    labels.iter().find(|(name, _)| *name == field).map(|(_, label)| *label).unwrap_or(field)
+}
+
+// 默认规则中检查了已配置字段的整条去掉，再追加配置的规则
+fn merge_rules(defaults: Vec<Rule>, configured: Vec<Rule>) -> Vec<Rule> {
+    let configured_fields: Vec<String> = configured.iter()
+        .flat_map(|r| r.checked_fields())
+        .map(str::to_string)
+        .collect();
+    defaults.into_iter()
+        .filter(|rule| !rule.checked_fields().iter().any(|field| configured_fields.iter().any(|f| f == field)))
+        .chain(configured)
+        .collect()
+}
+
+fn non_empty_text(source: &dyn FieldSource, field: &str) -> Option<String> {
+    match source.field(field)? {
+        FieldValue::Text(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        _ => None,
+    }
+}
+
+fn describe_range(min: Option<usize>, max: Option<usize>, unit: &str) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) if min == max => format!("{}{}", min, unit),
+        (Some(min), Some(max)) => format!("{}-{}{}", min, max, unit),
+        (Some(min), None) => format!("至少{}{}", min, unit),
+        (None, Some(max)) => format!("最多{}{}", max, unit),
+        (None, None) => "任意".to_string(),
+    }
+}
+
+fn is_mobile(phone: &str) -> bool {
+    phone.len() == 11 && phone.starts_with('1') && phone.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_landline(phone: &str) -> bool {
+    // 区号3-4位，以0开头；号码7-8位；可带分机号
+    let (main, extension) = match phone.split_once('#') {
+        Some((main, extension)) => (main, Some(extension)),
+        None => (phone, None),
+    };
+    if extension.is_some_and(|e| e.is_empty() || !e.chars().all(|c| c.is_ascii_digit())) {
+        return false;
+    }
+    let (area, number) = match main.split_once('-') {
+        Some(parts) => parts,
+        None if main.len() >= 10 && main.is_ascii() => {
+            let area_len = if main.starts_with("01") || main.starts_with("02") { 3 } else { 4 };
+            main.split_at(area_len.min(main.len()))
+        }
+        None => return false,
+    };
+    area.starts_with('0')
+        && (3..=4).contains(&area.len())
+        && (7..=8).contains(&number.len())
+        && area.chars().chain(number.chars()).all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 按字段名返回固定值的表单
+    struct Fields(Vec<(&'static str, FieldValue)>);
+
+    impl FieldSource for Fields {
+        fn field(&self, name: &str) -> Option<FieldValue> {
+            self.0.iter().find(|(field, _)| *field == name).map(|(_, value)| value.clone())
+        }
+    }
+
+    fn text(value: &str) -> FieldValue {
+        FieldValue::Text(value.to_string())
+    }
+
+    fn date(year: i32) -> FieldValue {
+        FieldValue::Date(NaiveDate::from_ymd_opt(year, 1, 1))
+    }
+
+    fn check(rule: &Rule, fields: Vec<(&'static str, FieldValue)>) -> Option<(String, String)> {
+        rule.check(&Fields(fields), MEMBER_FIELDS)
+    }
+
+    fn format(field: &str, format: FieldFormat) -> Rule {
+        Rule::Format { field: field.to_string(), format, message: None }
+    }
+
+    #[test]
+    fn required() {
+        let rule = Rule::Required { field: "name".to_string(), message: None };
+        assert_eq!(check(&rule, vec![("name", text("  "))]), Some(("name".to_string(), "姓名不能为空".to_string())));
+        assert_eq!(check(&rule, vec![("name", text("张三"))]), None);
+        // 表单中没有的字段不检查
+        assert_eq!(check(&rule, vec![]), None);
+
+        let rule = Rule::Required { field: "birth_date".to_string(), message: Some("请填写出生日期".to_string()) };
+        assert_eq!(check(&rule, vec![("birth_date", FieldValue::Date(None))]).unwrap().1, "请填写出生日期");
+        let rule = Rule::Required { field: "members".to_string(), message: None };
+        assert!(check(&rule, vec![("members", FieldValue::Count(0))]).is_some());
+    }
+
+    #[test]
+    fn format_rule() {
+        let rule = format("id_number", FieldFormat::Digits);
+        assert_eq!(
+            check(&rule, vec![("id_number", text("12a"))]),
+            Some(("id_number".to_string(), "证件号码格式不正确，应为数字".to_string()))
+        );
+        assert_eq!(check(&rule, vec![("id_number", text(" 123 "))]), None);
+        // 空值由必填规则检查
+        assert_eq!(check(&rule, vec![("id_number", text(""))]), None);
+    }
+
+    #[test]
+    fn length() {
+        let rule = Rule::Length { field: "name".to_string(), min: Some(2), max: Some(4), message: None };
+        assert_eq!(check(&rule, vec![("name", text("张"))]).unwrap().1, "姓名长度应为2-4个字符");
+        assert_eq!(check(&rule, vec![("name", text("欧阳娜娜"))]), None);
+        assert!(check(&rule, vec![("name", text("欧阳娜娜娜"))]).is_some());
+        assert_eq!(check(&rule, vec![("name", text(""))]), None);
+    }
+
+    #[test]
+    fn year_range() {
+        let rule = Rule::YearRange { field: "birth_date".to_string(), min: Some(1900), max: None, message: None };
+        assert_eq!(check(&rule, vec![("birth_date", date(1899))]).unwrap().1, "出生日期年份不合理");
+        assert_eq!(check(&rule, vec![("birth_date", date(1990))]), None);
+        assert!(check(&rule, vec![("birth_date", date(chrono::Local::now().year() + 1))]).is_some());
+        assert_eq!(check(&rule, vec![("birth_date", FieldValue::Date(None))]), None);
+
+        let rule = Rule::YearRange { field: "birth_date".to_string(), min: None, max: Some(2000), message: None };
+        assert!(check(&rule, vec![("birth_date", date(2001))]).is_some());
+    }
+
+    #[test]
+    fn count() {
+        let rule = Rule::Count { field: "members".to_string(), min: Some(1), max: Some(2), message: None };
+        let labels = HOUSEHOLD_FIELDS;
+        let check = |count| rule.check(&Fields(vec![("members", FieldValue::Count(count))]), labels);
+        assert_eq!(check(0).unwrap().1, "家庭成员数量应为1-2个");
+        assert_eq!(check(2), None);
+        assert!(check(3).is_some());
+    }
+
+    #[test]
+    fn any_of() {
+        let rule = Rule::AnyOf {
+            rules: vec![format("id_number", FieldFormat::IdNumber), format("id_number", FieldFormat::Passport)],
+            message: Some("证件号码应为身份证号或护照号".to_string()),
+        };
+        assert_eq!(check(&rule, vec![("id_number", text("110101199001011234"))]), None);
+        assert_eq!(check(&rule, vec![("id_number", text("E12345678"))]), None);
+        assert_eq!(check(&rule, vec![("id_number", text("E-1"))]).unwrap().1, "证件号码应为身份证号或护照号");
+
+        // 未指定提示时使用第一条子规则的提示
+        let rule = Rule::AnyOf { rules: vec![format("id_number", FieldFormat::Digits)], message: None };
+        assert_eq!(check(&rule, vec![("id_number", text("x"))]).unwrap().1, "证件号码格式不正确，应为数字");
+    }
+
+    #[test]
+    fn when() {
+        let rule = Rule::When {
+            field: "document_type".to_string(),
+            equals: DocumentType::Passport.code().to_string(),
+            rules: vec![Rule::Required { field: "nationality".to_string(), message: None }],
+        };
+        let passport = text(DocumentType::Passport.code());
+        let resident = text(DocumentType::ResidentId.code());
+        assert_eq!(check(&rule, vec![("document_type", passport.clone()), ("nationality", text(""))]).unwrap().0, "nationality");
+        assert_eq!(check(&rule, vec![("document_type", passport), ("nationality", text("USA"))]), None);
+        assert_eq!(check(&rule, vec![("document_type", resident), ("nationality", text(""))]), None);
+    }
+
+    #[test]
+    fn field_formats() {
+        let cases: &[(FieldFormat, &[&str], &[&str])] = &[
+            (FieldFormat::Mobile, &["13800138000"], &["23800138000", "1380013800", "1380013800a"]),
+            (FieldFormat::Landline, &["010-12345678", "0755-1234567", "01012345678", "010-12345678#123"], &["10-12345678", "010-123456", "010-12345678#", "13800138000"]),
+            (FieldFormat::Phone, &["13800138000", "010-12345678"], &["12345"]),
+            (FieldFormat::IdNumber, &["110101199001011234", "11010119900101123X"], &["11010119900101123", "A10101199001011234"]),
+            (FieldFormat::HmtResidencePermit, &["810000199001011234"], &["110101199001011234"]),
+            (FieldFormat::ForeignPermanentId, &["USA110190010112", "911840199001011234"], &["usa110190010112", "911840199013011234"]),
+            (FieldFormat::Passport, &["E12345678"], &["E123", "E1234-5678"]),
+            (FieldFormat::Nationality, &["USA", "usa"], &["US", "U1A"]),
+            (FieldFormat::Digits, &["0123"], &["", "12.3"]),
+        ];
+        for (format, valid, invalid) in cases {
+            for value in *valid {
+                assert!(format.matches(value), "{:?} 应接受 {}", format, value);
+            }
+            for value in *invalid {
+                assert!(!format.matches(value), "{:?} 应拒绝 {}", format, value);
+            }
+        }
+    }
+
+    #[test]
+    fn field_formats_reject_non_ascii() {
+        let formats = [
+            FieldFormat::Mobile,
+            FieldFormat::Landline,
+            FieldFormat::Phone,
+            FieldFormat::IdNumber,
+            FieldFormat::HmtResidencePermit,
+            FieldFormat::ForeignPermanentId,
+            FieldFormat::Passport,
+            FieldFormat::Nationality,
+            FieldFormat::Digits,
+        ];
+        let values = ["电话号码一", "0电话号码一二三", "０１０１２３４５６７８", "ab中1234567890", "1101011990010112é", "中国"];
+        for format in formats {
+            for value in values {
+                assert!(!format.matches(value), "{:?} 应拒绝 {}", format, value);
+            }
+        }
+    }
+
+    #[test]
+    fn evaluate_keeps_first_error_per_field() {
+        let rules = [
+            Rule::Required { field: "name".to_string(), message: Some("第一条".to_string()) },
+            Rule::Length { field: "name".to_string(), min: Some(2), max: None, message: Some("第二条".to_string()) },
+            Rule::Required { field: "name".to_string(), message: Some("第三条".to_string()) },
+        ];
+        let mut errors = ValidationErrors::default();
+        RuleSet::evaluate(&rules, &Fields(vec![("name", text(""))]), MEMBER_FIELDS, &mut errors);
+        assert_eq!(errors.get("name"), Some("第一条"));
+    }
+
+    #[test]
+    fn check_fields_rejects_unknown_field() {
+        assert!(RuleSet::default().check_fields().is_ok());
+
+        let mut rules = RuleSet::default();
+        rules.member.push(Rule::When {
+            field: "document_type".to_string(),
+            equals: "1".to_string(),
+            rules: vec![Rule::Required { field: "blood_type".to_string(), message: None }],
+        });
+        assert_eq!(rules.check_fields(), Err("成员规则引用了未知字段: blood_type".to_string()));
+
+        let mut rules = RuleSet::default();
+        rules.household.push(Rule::Required { field: "name".to_string(), message: None });
+        assert_eq!(rules.check_fields(), Err("户籍规则引用了未知字段: name".to_string()));
+    }
+
+    #[test]
+    fn load() {
+        let path = std::env::temp_dir().join(format!("{}-{}", uuid::Uuid::new_v4(), RULES_FILE));
+        let path = path.to_str().unwrap();
+        assert_eq!(RuleSet::load(path), Ok(RuleSet::default()));
+
+        // 只配置电话：电话的默认规则被替换，其余默认规则保留
+        std::fs::write(path, r#"{"household": [{"rule": "format", "field": "phone", "format": "phone"}]}"#).unwrap();
+        let rules = RuleSet::load(path).unwrap();
+        let defaults = RuleSet::default();
+        let mut expected: Vec<Rule> = defaults.household.iter()
+            .filter(|r| r.fields() != ["phone"])
+            .cloned()
+            .collect();
+        expected.push(format("phone", FieldFormat::Phone));
+        assert_eq!(rules.household, expected);
+        assert_eq!(rules.member, defaults.member);
+
+        // 配置证件号码时，按证件类型检查证件号码的条件规则一并替换
+        std::fs::write(path, r#"{"member": [{"rule": "format", "field": "id_number", "format": "digits"}]}"#).unwrap();
+        let rules = RuleSet::load(path).unwrap();
+        assert_eq!(rules.household, defaults.household);
+        assert!(rules.member.iter().all(|r| !matches!(r, Rule::When { .. }) || !r.checked_fields().contains(&"id_number")));
+        assert!(rules.member.contains(&Rule::Required { field: "name".to_string(), message: Some("姓名不能为空".to_string()) }));
+        assert_eq!(rules.member.last(), Some(&format("id_number", FieldFormat::Digits)));
+
+        std::fs::write(path, r#"{"replace_defaults": true, "household": [{"rule": "format", "field": "phone", "format": "phone"}]}"#).unwrap();
+        let rules = RuleSet::load(path).unwrap();
+        assert_eq!(rules.household, vec![format("phone", FieldFormat::Phone)]);
+        assert!(rules.member.is_empty());
+
+        std::fs::write(path, r#"{"member": [{"rule": "required", "field": "blood_type"}]}"#).unwrap();
+        assert!(RuleSet::load(path).unwrap_err().contains("blood_type"));
+
+        std::fs::write(path, r#"{"member": [{"rule": "unknown"}]}"#).unwrap();
+        assert!(RuleSet::load(path).unwrap_err().contains("格式错误"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,118 +1,186 @@
+use super::consistency::{self, Severity};
 use super::models::{HouseholdForm, MemberForm};
+use super::rules::{self, RuleSet, HOUSEHOLD_FIELDS, MEMBER_FIELDS};
 use chrono::NaiveDate;
 
+/// 单个字段的校验错误。`field` 为字段路径，如 `address`、`members[0].id_number`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// 一次校验发现的全部错误
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError { field: field.into(), message: message.into() });
+    }
+    
+    /// 合并子表单的错误，字段路径加上前缀
+    pub fn extend_nested(&mut self, prefix: &str, other: ValidationErrors) {
+        for error in other.errors {
+            self.add(format!("{}.{}", prefix, error.field), error.message);
+        }
+    }
+    
+    /// 指定字段的第一条错误
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.errors.iter().find(|e| e.field == field).map(|e| e.message.as_str())
+    }
+    
+    pub fn iter(&self) -> impl Iterator<Item = &FieldError> {
+        self.errors.iter()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+    
+    fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.is_empty() { Ok(()) } else { Err(self) }
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<&str> = self.errors.iter().map(|e| e.message.as_str()).collect();
+        write!(f, "{}", messages.join("；"))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
 pub trait Validate {
-    fn validate(&self) -> Result<(), String>;
+    /// 校验全部字段，返回所有错误而不是遇到第一个就停止
+    fn validate(&self) -> Result<(), ValidationErrors>;
 }
 
 impl Validate for HouseholdForm {
-    fn validate(&self) -> Result<(), String> {
-        if self.head_name.trim().is_empty() {
-            return Err("户主姓名不能为空".to_string());
-        }
-        
-        if self.id_number.trim().is_empty() {
-            return Err("身份证号不能为空".to_string());
-        }
-        
-        if self.id_number.len() != 18 {
-            return Err("身份证号必须是18位".to_string());
-        }
-        
-        if !validate_id_number(&self.id_number) {
-            return Err("身份证号格式不正确".to_string());
-        }
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let rules = rules::active();
+        let mut errors = ValidationErrors::default();
         
-        if self.address.trim().is_empty() {
-            return Err("地址不能为空".to_string());
-        }
+        RuleSet::evaluate(&rules.household, self, HOUSEHOLD_FIELDS, &mut errors);
         
-        if !self.phone.trim().is_empty() && !validate_phone(&self.phone) {
-            return Err("手机号格式不正确".to_string());
-        }
-        
-        if self.members.is_empty() {
-            return Err("至少需要一个家庭成员".to_string());
+        // 成员之间相互矛盾的数据不能保存，少见情况只在检查清单中提示
+        for check in consistency::check_household(self) {
+            for issue in check.issues.into_iter().filter(|i| i.severity == Severity::Error) {
+                errors.add(issue.field, issue.message);
+            }
         }
         
         for (i, member) in self.members.iter().enumerate() {
-            if let Err(e) = member.validate() {
-                return Err(format!("第{}个成员: {}", i + 1, e));
+            if let Err(member_errors) = member.validate() {
+                errors.extend_nested(&format!("members[{}]", i), member_errors);
             }
         }
         
-        Ok(())
+        errors.into_result()
     }
 }
 
 impl Validate for MemberForm {
-    fn validate(&self) -> Result<(), String> {
-        if self.name.trim().is_empty() {
-            return Err("姓名不能为空".to_string());
-        }
-        
-        if self.id_number.trim().is_empty() {
-            return Err("身份证号不能为空".to_string());
-        }
-        
-        if self.id_number.len() != 18 {
-            return Err("身份证号必须是18位".to_string());
-        }
-        
-        if !validate_id_number(&self.id_number) {
-            return Err("身份证号格式不正确".to_string());
-        }
-        
-        if self.birth_year < 1900 || self.birth_year > 2024 {
-            return Err("出生年份不合理".to_string());
-        }
-        
-        if self.birth_month < 1 || self.birth_month > 12 {
-            return Err("出生月份必须在1-12之间".to_string());
-        }
-        
-        if self.birth_day < 1 || self.birth_day > 31 {
-            return Err("出生日期必须在1-31之间".to_string());
-        }
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
         
-        // 验证日期是否有效
+        // 日期本身无效时不再检查配置的日期规则
         if NaiveDate::from_ymd_opt(self.birth_year, self.birth_month, self.birth_day).is_none() {
-            return Err("无效的出生日期".to_string());
+            errors.add("birth_date", "无效的出生日期");
         }
         
-        Ok(())
+        RuleSet::evaluate(&rules::active().member, self, MEMBER_FIELDS, &mut errors);
+        
+        errors.into_result()
     }
 }
 
-fn validate_id_number(id: &str) -> bool {
-    // 简单的身份证号验证
-    if id.len() != 18 {
-        return false;
-    }
-    
-    // 前17位必须是数字
-    for (i, c) in id.chars().enumerate() {
-        if i < 17 && !c.is_ascii_digit() {
-            return false;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::models::*;
+    use uuid::Uuid;
+
+    fn member(name: &str, id_number: &str, relationship: Relationship) -> Member {
+        Member {
+            name: name.to_string(),
+            document_type: DocumentType::ResidentId,
+            id_number: id_number.to_string(),
+            nationality: "CHN".to_string(),
+            relationship,
+            birth_date: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
+            gender: Gender::Male,
+            education: Education::University,
+            occupation: String::new(),
+            status: MemberStatus::Active,
+            marital_status: MaritalStatus::Unmarried,
+            death_date: None,
         }
     }
-    
-    // 最后一位可以是数字或X
-    let last_char = id.chars().last().unwrap();
-    last_char.is_ascii_digit() || last_char == 'X' || last_char == 'x'
-}
 
-fn validate_phone(phone: &str) -> bool {
-    // 简单的手机号验证
-    if phone.len() != 11 {
-        return false;
+    fn form() -> HouseholdForm {
+        let mut household = Household {
+            id: Uuid::new_v4(),
+            head_name: String::new(),
+            id_number: String::new(),
+            head_document_type: DocumentType::ResidentId,
+            address: Address::from_division("110105001", "XXX路XXX号"),
+            dwelling_id: None,
+            phone: "13800138000".to_string(),
+            household_type: HouseholdType::Urban,
+            registration_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            members: vec![
+                member("张三", "110101199001011234", Relationship::HEAD),
+                member("张四", "110101199001012345", Relationship::YOUNGER_BROTHER),
+            ],
+            revision: 0,
+        };
+        household.sync_head();
+        HouseholdForm::from_household(&household)
     }
-    
-    // 必须以1开头
-    if !phone.starts_with('1') {
-        return false;
+
+    #[test]
+    fn valid_form_passes() {
+        assert_eq!(form().validate(), Ok(()));
+    }
+
+    #[test]
+    fn reports_all_errors_with_field_paths() {
+        let mut form = form();
+        form.phone = "12345".to_string();
+        form.members[1].name = String::new();
+        form.members[1].id_number = "1101".to_string();
+        form.members[1].birth_month = 2;
+        form.members[1].birth_day = 30;
+
+        let errors = form.validate().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert!(fields.contains(&"phone"), "{:?}", fields);
+        assert!(fields.contains(&"members[1].name"), "{:?}", fields);
+        assert!(fields.contains(&"members[1].id_number"), "{:?}", fields);
+        assert_eq!(errors.get("members[1].birth_date"), Some("无效的出生日期"));
+        assert_eq!(errors.get("members[0].name"), None);
+        assert_eq!(errors.to_string().matches('；').count(), errors.len() - 1);
+    }
+
+    #[test]
+    fn extend_nested_prefixes_fields() {
+        let mut inner = ValidationErrors::default();
+        inner.add("name", "姓名不能为空");
+        let mut outer = ValidationErrors::default();
+        outer.add("phone", "联系电话格式不正确");
+        outer.extend_nested("members[2]", inner);
+
+        assert_eq!(outer.len(), 2);
+        assert_eq!(outer.get("members[2].name"), Some("姓名不能为空"));
+        assert_eq!(outer.to_string(), "联系电话格式不正确；姓名不能为空");
     }
-    
-    // 全部是数字
-    phone.chars().all(|c| c.is_ascii_digit())
 }
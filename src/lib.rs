@@ -1,8 +1,11 @@
 // 户籍管理系统库模块
 
 pub mod app;
+pub mod cli;
 pub mod config;
 pub mod data;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod ui;
 pub mod utils;
 
@@ -0,0 +1,401 @@
+//! 本地 HTTP/JSON 接口：供社区网格系统、报表脚本等读写户籍数据，通过 `household_cli serve` 启动
+//!
+//! 请求逐个在同一线程中处理，与界面和命令行一样经由 `HouseholdManager` 读写数据库。
+//! 响应中的证件号码和电话默认遮蔽，查询参数 `unmasked=true` 时返回完整内容并记入操作记录。
+//! 接口说明见 `assets/openapi.json`，运行时可从 `/api/openapi.json` 获取。
+
+use crate::cli;
+use crate::data::audit::{AuditAction, AuditEntry};
+use crate::data::error::DataError;
+use crate::data::database::Database;
+use crate::data::manager::HouseholdManager;
+use crate::data::models::*;
+use crate::data::privacy;
+use crate::data::repository::HouseholdRepository;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::net::SocketAddr;
+use tiny_http::{Header, Method, Request, Response};
+use uuid::Uuid;
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+/// 未用 `--token` 指定令牌时从此环境变量读取
+pub const TOKEN_ENV: &str = "HOUSEHOLD_API_TOKEN";
+
+/// 未用 `with_operator` 指定时操作记录中的操作员
+const DEFAULT_OPERATOR: &str = "接口服务";
+
+const OPENAPI: &str = include_str!("../assets/openapi.json");
+const DEFAULT_PER_PAGE: usize = 50;
+const MAX_PER_PAGE: usize = 500;
+/// 请求体上限，防止误传大文件占满内存
+const MAX_BODY: u64 = 4 * 1024 * 1024;
+
+/// 接口错误，转换为对应状态码和 `{"error": "..."}` 响应
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        ApiError { status, message: message.into() }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::new(400, message)
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        ApiError::new(404, message)
+    }
+}
+
+impl From<DataError> for ApiError {
+    fn from(error: DataError) -> Self {
+        let status = match error {
+            DataError::NotFound(_) => 404,
+            DataError::Validation(_) => 422,
+            DataError::Constraint(_) | DataError::Conflict(_) => 409,
+            DataError::Locked => 503,
+            DataError::Corrupt(_) | DataError::Io(_) | DataError::Database(_) => 500,
+        };
+        ApiError::new(status, error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(error: serde_json::Error) -> Self {
+        ApiError::bad_request(format!("JSON 格式错误: {}", error))
+    }
+}
+
+type ApiResult = Result<(u16, Value), ApiError>;
+
+pub struct Server<'a, R: HouseholdRepository = Database> {
+    http: tiny_http::Server,
+    manager: &'a mut HouseholdManager<R>,
+    token: String,
+    operator: String,
+}
+
+impl<'a, R: HouseholdRepository> Server<'a, R> {
+    /// 监听指定地址，端口为 0 时由系统分配
+    pub fn bind(manager: &'a mut HouseholdManager<R>, addr: &str, token: &str) -> Result<Self, String> {
+        if token.is_empty() {
+            return Err(format!("未指定访问令牌，请使用 --token 或设置环境变量 {}", TOKEN_ENV));
+        }
+        let http = tiny_http::Server::http(addr).map_err(|e| format!("无法监听 {}: {}", addr, e))?;
+        Ok(Server { http, manager, token: token.to_string(), operator: DEFAULT_OPERATOR.to_string() })
+    }
+
+    /// 未遮蔽的读取记入操作记录时使用的操作员
+    pub fn with_operator(mut self, operator: &str) -> Self {
+        self.operator = operator.to_string();
+        self
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    /// 处理请求直到进程退出
+    pub fn run(mut self) {
+        while let Ok(request) = self.http.recv() {
+            self.handle(request);
+        }
+    }
+
+    fn handle(&mut self, mut request: Request) {
+        let method = request.method().clone();
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        let result = if method == Method::Get && segments == ["api", "openapi.json"] {
+            serde_json::from_str(OPENAPI).map(|doc| (200, doc)).map_err(ApiError::from)
+        } else if !self.authorized(&request) {
+            Err(ApiError::new(401, "缺少访问令牌或令牌无效"))
+        } else {
+            if_match(&request).and_then(|if_match| {
+                let body = read_body(&mut request)?;
+                // 其他程序可能同时在修改同一数据库
+                self.manager.check_external_changes()?;
+                self.route(&method, &segments, &parse_query(query), if_match, body)
+            })
+        };
+
+        let (status, body) = match result {
+            Ok((204, _)) => (204, String::new()),
+            Ok((status, value)) => (status, value.to_string()),
+            Err(error) => (error.status, json!({ "error": error.message }).to_string()),
+        };
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json; charset=utf-8").expect("固定的响应头"));
+        if let Err(e) = request.respond(response) {
+            eprintln!("警告: 响应 {} {} 失败: {}", method, url, e);
+        }
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        let expected = format!("Bearer {}", self.token);
+        request.headers().iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .is_some_and(|header| constant_time_eq(header.value.as_str().trim().as_bytes(), expected.as_bytes()))
+    }
+
+    /// `if_match` 为 `If-Match` 请求头中客户端读取时的户籍版本
+    fn route(&mut self, method: &Method, segments: &[&str], query: &[(String, String)], if_match: Option<i64>, body: Option<Value>) -> ApiResult {
+        match (method, segments) {
+            (Method::Get, ["api", "households"]) => self.list_households(query),
+            (Method::Post, ["api", "households"]) => self.create_household(require_body(body)?, query),
+            (Method::Get, ["api", "households", id]) => {
+                let household = self.household(id)?;
+                Ok((200, serde_json::to_value(self.disclose(household, query, "查看")?)?))
+            }
+            (Method::Put, ["api", "households", id]) => self.replace_household(id, require_body(body)?, if_match, query),
+            (Method::Delete, ["api", "households", id]) => {
+                let household = self.household(id)?;
+                self.manager.remove_household(&household.id, if_match.unwrap_or(household.revision))?;
+                Ok((204, Value::Null))
+            }
+            (Method::Get, ["api", "households", id, "members"]) => {
+                let household = self.household(id)?;
+                Ok((200, serde_json::to_value(self.disclose(household, query, "查看成员")?.members)?))
+            }
+            (Method::Post, ["api", "households", id, "members"]) => {
+                let member: Member = serde_json::from_value(require_body(body)?)?;
+                let mut household = self.household(id)?;
+                household.members.push(member);
+                self.save_household(household, query).map(|(_, value)| (201, value))
+            }
+            (Method::Get, ["api", "households", id, "members", index]) => {
+                let household = self.household(id)?;
+                let index = member_index(&household, index)?;
+                let household = self.disclose(household, query, "查看成员")?;
+                Ok((200, serde_json::to_value(&household.members[index])?))
+            }
+            (Method::Put, ["api", "households", id, "members", index]) => {
+                let member: Member = serde_json::from_value(require_body(body)?)?;
+                let mut household = self.household(id)?;
+                let index = member_index(&household, index)?;
+                household.members[index] = member;
+                self.save_household(household, query)
+            }
+            (Method::Delete, ["api", "households", id, "members", index]) => {
+                let mut household = self.household(id)?;
+                let index = member_index(&household, index)?;
+                household.members.remove(index);
+                self.save_household(household, query)
+            }
+            (Method::Get, ["api", "statistics"]) => self.statistics(query),
+            (_, ["api", "households"])
+            | (_, ["api", "households", _])
+            | (_, ["api", "households", _, "members"])
+            | (_, ["api", "households", _, "members", _])
+            | (_, ["api", "statistics"]) => Err(ApiError::new(405, format!("不支持的请求方法: {}", method))),
+            _ => Err(ApiError::not_found(format!("未知的接口: /{}", segments.join("/")))),
+        }
+    }
+
+    // 按登记日期排序后分页，保证翻页时顺序稳定
+    fn list_households(&mut self, query: &[(String, String)]) -> ApiResult {
+        let page = query_number(query, "page")?.unwrap_or(1);
+        let per_page = query_number(query, "per_page")?.unwrap_or(DEFAULT_PER_PAGE);
+        if page == 0 || per_page == 0 || per_page > MAX_PER_PAGE {
+            return Err(ApiError::bad_request(format!("page 从 1 开始，per_page 应为 1 到 {}", MAX_PER_PAGE)));
+        }
+
+        let households = self.manager.get_households()?;
+        let mut matched: Vec<Household> = match query_value(query, "q").filter(|q| !q.trim().is_empty()) {
+            Some(q) => self.manager.search(q.trim())?.into_iter().map(|i| households[i].clone()).collect(),
+            None => households,
+        };
+        matched.sort_by(|a, b| a.registration_date.cmp(&b.registration_date).then(a.id.cmp(&b.id)));
+
+        let total = matched.len();
+        let items: Vec<Household> = matched.into_iter().skip((page - 1) * per_page).take(per_page).collect();
+        let items = self.disclose_all(items, query, "列出")?;
+        Ok((200, json!({ "items": items, "total": total, "page": page, "per_page": per_page })))
+    }
+
+    fn create_household(&mut self, body: Value, query: &[(String, String)]) -> ApiResult {
+        let household = Household::from_json(body)?;
+        reject_masked(&household)?;
+        if self.manager.get_household_by_id(&household.id)?.is_some() {
+            return Err(ApiError::new(409, format!("户籍ID已存在: {}", household.id)));
+        }
+        let warnings = self.manager.check_household(&household, None)?;
+        self.manager.add_household(household.clone())?;
+        let household = self.manager.get_household_by_id(&household.id)?.unwrap_or(household);
+        let household = self.disclose(household, query, "新增")?;
+        Ok((201, json!({ "household": household, "warnings": warnings })))
+    }
+
+    // 户籍ID以路径为准；读取时的版本由请求体的 revision 或 If-Match 提供，都没有时不覆盖
+    fn replace_household(&mut self, id: &str, mut body: Value, if_match: Option<i64>, query: &[(String, String)]) -> ApiResult {
+        let existing = self.household(id)?;
+        if let Some(object) = body.as_object_mut() {
+            object.insert("id".to_string(), Value::String(existing.id.to_string()));
+            object.entry("registration_date").or_insert_with(|| json!(existing.registration_date));
+            match (object.get("revision"), if_match) {
+                (Some(revision), Some(expected)) if revision.as_i64() != Some(expected) => {
+                    return Err(ApiError::bad_request("请求体中的 revision 与 If-Match 不一致"));
+                }
+                (Some(_), _) => {}
+                (None, Some(expected)) => {
+                    object.insert("revision".to_string(), json!(expected));
+                }
+                (None, None) => {
+                    return Err(ApiError::new(428, "缺少版本：请在请求体中提供读取时的 revision，或使用 If-Match 请求头"));
+                }
+            }
+        }
+        self.save_household(Household::from_json(body)?, query)
+    }
+
+    fn save_household(&mut self, mut household: Household, query: &[(String, String)]) -> ApiResult {
+        reject_masked(&household)?;
+        household.sync_head();
+        let warnings = self.manager.check_household(&household, Some(household.id))?;
+        self.manager.update_household(household.clone())?;
+        let household = self.manager.get_household_by_id(&household.id)?.unwrap_or(household);
+        let household = self.disclose(household, query, "修改")?;
+        Ok((200, json!({ "household": household, "warnings": warnings })))
+    }
+
+    fn disclose(&mut self, household: Household, query: &[(String, String)], action: &str) -> Result<Household, ApiError> {
+        Ok(self.disclose_all(vec![household], query, action)?.remove(0))
+    }
+
+    // 与命令行的输出相同：默认遮蔽证件号码和电话，`unmasked=true` 时返回完整内容并记入操作记录
+    fn disclose_all(&mut self, households: Vec<Household>, query: &[(String, String)], action: &str) -> Result<Vec<Household>, ApiError> {
+        match query_value(query, "unmasked") {
+            None | Some("false") => return Ok(households.iter().map(privacy::mask_household).collect()),
+            Some("true") => {}
+            Some(other) => return Err(ApiError::bad_request(format!("unmasked 应为 true 或 false: {}", other))),
+        }
+        let (household_id, detail) = match households.as_slice() {
+            [] => return Ok(households),
+            [household] => (Some(household.id), format!("接口{} {}户", action, household.head_name)),
+            _ => (None, format!("接口{} {} 户", action, households.len())),
+        };
+        self.manager.record_audit(&AuditEntry::new(&self.operator, AuditAction::Export, household_id, detail))?;
+        Ok(households)
+    }
+
+    fn statistics(&mut self, query: &[(String, String)]) -> ApiResult {
+        let level = query_value(query, "by")
+            .map(|name| cli::parse_level(name).ok_or_else(|| ApiError::bad_request(format!("未知的区划级别: {}", name))))
+            .transpose()?;
+        let stats = self.manager.get_statistics()?;
+        let divisions = level.map(|level| self.manager.count_by_division(level)).transpose()?.unwrap_or_default();
+        Ok((200, cli::statistics_json(&stats, &divisions)))
+    }
+
+    fn household(&mut self, id: &str) -> Result<Household, ApiError> {
+        let id = Uuid::parse_str(id).map_err(|_| ApiError::not_found(format!("无效的户籍ID: {}", id)))?;
+        self.manager.get_household_by_id(&id)?
+            .ok_or_else(|| ApiError::not_found(format!("户籍不存在: {}", id)))
+    }
+}
+
+// 遮蔽后的内容写回会覆盖真实的证件号码和电话
+fn reject_masked(household: &Household) -> Result<(), ApiError> {
+    let masked = household.phone.contains('*')
+        || household.id_number.contains('*')
+        || household.members.iter().any(|m| m.id_number.contains('*'));
+    if masked {
+        return Err(ApiError::new(422, "证件号码或电话为遮蔽后的内容，请以 unmasked=true 读取完整内容后再修改"));
+    }
+    Ok(())
+}
+
+fn member_index(household: &Household, index: &str) -> Result<usize, ApiError> {
+    index.parse::<usize>().ok()
+        .filter(|&i| i < household.members.len())
+        .ok_or_else(|| ApiError::not_found(format!("成员不存在: {}", index)))
+}
+
+// 接受 `"3"`、`W/"3"` 或 `3`；`*` 等其他形式不能确定版本，视为格式错误
+fn if_match(request: &Request) -> Result<Option<i64>, ApiError> {
+    let Some(header) = request.headers().iter().find(|header| header.field.equiv("If-Match")) else {
+        return Ok(None);
+    };
+    let value = header.value.as_str().trim();
+    value.trim_start_matches("W/").trim_matches('"').parse().map(Some)
+        .map_err(|_| ApiError::bad_request(format!("If-Match 应为户籍的 revision: {}", value)))
+}
+
+fn read_body(request: &mut Request) -> Result<Option<Value>, ApiError> {
+    let mut content = String::new();
+    request.as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_string(&mut content)
+        .map_err(|e| ApiError::bad_request(format!("请求体读取失败: {}", e)))?;
+    if content.len() as u64 > MAX_BODY {
+        return Err(ApiError::new(413, "请求体过大"));
+    }
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&content)?))
+}
+
+fn require_body(body: Option<Value>) -> Result<Value, ApiError> {
+    body.ok_or_else(|| ApiError::bad_request("缺少 JSON 请求体"))
+}
+
+fn query_value<'a>(query: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+}
+
+fn query_number(query: &[(String, String)], name: &str) -> Result<Option<usize>, ApiError> {
+    query_value(query, name)
+        .map(|value| value.parse().map_err(|_| ApiError::bad_request(format!("{} 应为正整数: {}", name, value))))
+        .transpose()
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+// 查询参数中的中文等以 UTF-8 百分号编码，“+”表示空格
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// 逐字节比较全部内容，耗时不随首个不同字节的位置变化
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,226 @@
+use crate::app::HouseholdApp;
+use crate::data::divisions::{self, Address, DivisionLevel};
+use crate::ui::keyboard;
+use eframe::egui::{self, *};
+use uuid::Uuid;
+
+/// 一条待核对的地址
+#[derive(Debug, Clone)]
+pub struct AddressReview {
+    pub household_id: Uuid,
+    pub head_name: String,
+    /// 原始地址文本
+    pub source: String,
+    /// 解析出的候选，同名区划时有多个
+    pub candidates: Vec<Address>,
+    pub address: Address,
+}
+
+/// 逐级选择行政区划的下拉框，上级改变时清空下级。返回是否有改动
+pub(crate) fn division_combo_boxes(ui: &mut Ui, id: &str, address: &mut Address) -> bool {
+    let mut changed = false;
+    let mut parent: Option<String> = None;
+
+    for level in DivisionLevel::ALL {
+        let options = divisions::children(parent.as_deref());
+        if options.is_empty() {
+            break;
+        }
+
+        let current = address.code(level).to_string();
+        let selected_text = divisions::find(&current)
+            .map(|d| d.name.to_string())
+            .unwrap_or_else(|| format!("选择{}", level));
+        let mut selected = current.clone();
+        let response = egui::ComboBox::from_id_salt(format!("{}_{:?}", id, level))
+            .selected_text(selected_text)
+            .height(300.0)
+            .show_ui(ui, |ui| {
+                // 乡镇以下可以不选
+                if level >= DivisionLevel::Township {
+                    ui.selectable_value(&mut selected, String::new(), "（不选）");
+                }
+                for option in &options {
+                    ui.selectable_value(&mut selected, option.code.to_string(), option.name);
+                }
+            })
+            .response;
+        let mut choices: Vec<_> = options.iter().map(|o| (o.code.to_string(), format!("{} {}", o.code, o.name))).collect();
+        if level >= DivisionLevel::Township {
+            choices.insert(0, (String::new(), "不选".to_string()));
+        }
+        keyboard::combo_keys(ui, &response, &mut selected, &choices);
+        if selected != current {
+            address.set(level, &selected);
+            changed = true;
+        }
+
+        if address.code(level).is_empty() {
+            break;
+        }
+        parent = Some(address.code(level).to_string());
+    }
+    changed
+}
+
+impl HouseholdApp {
+    pub fn open_address_review(&mut self) {
+        if !self.ensure_editable() {
+            return;
+        }
+        match self.household_manager.pending_address_reviews() {
+            Ok(households) => {
+                self.ui_state.address_reviews = households.into_iter()
+                    .map(|household| {
+                        // 未能解析的旧地址整段保存在详细地址中
+                        let source = household.address.detail.clone();
+                        let candidates = divisions::parse_address(&source);
+                        let address = candidates.first().cloned().unwrap_or(household.address);
+                        AddressReview { household_id: household.id, head_name: household.head_name, source, candidates, address }
+                    })
+                    .collect();
+                self.ui_state.show_address_review = true;
+            }
+            Err(e) => self.report_error("读取待核对地址失败", &e),
+        }
+    }
+
+    pub fn render_address_review_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut confirmed = None;
+
+        egui::Window::new("地址核对")
+            .id(egui::Id::new("address_review_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| {
+                ui.label("以下地址在升级时未能唯一确定所在行政区划，请选择候选或手动指定后确认:");
+                ui.separator();
+
+                if self.ui_state.address_reviews.is_empty() {
+                    ui.label("没有待核对的地址");
+                    return;
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (i, review) in self.ui_state.address_reviews.iter_mut().enumerate() {
+                        ui.group(|ui| {
+                            ui.vertical(|ui| {
+                                ui.label(RichText::new(format!("户主: {}", review.head_name)).strong());
+                                ui.label(format!("原地址: {}", review.source));
+
+                                if review.candidates.len() > 1 {
+                                    ui.label("存在同名区划:");
+                                    for candidate in &review.candidates {
+                                        let text = divisions::full_name(candidate.division());
+                                        if ui.radio(review.address == *candidate, text).clicked() {
+                                            review.address = candidate.clone();
+                                        }
+                                    }
+                                } else if review.candidates.is_empty() {
+                                    ui.colored_label(Color32::from_rgb(200, 80, 40), "未识别出行政区划，请手动选择");
+                                }
+
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.label("行政区划:");
+                                    division_combo_boxes(ui, &format!("address_review_{}", i), &mut review.address);
+                                });
+                                ui.horizontal(|ui| {
+                                    ui.label("详细地址:");
+                                    ui.add(egui::TextEdit::singleline(&mut review.address.detail)
+                                        .id_source(format!("address_review_detail_{}", i))
+                                        .desired_width(300.0));
+                                    if ui.button("确认").clicked() {
+                                        confirmed = Some(i);
+                                    }
+                                });
+                            });
+                        });
+                        ui.add_space(5.0);
+                    }
+                });
+            });
+
+        if let Some(i) = confirmed {
+            let review = self.ui_state.address_reviews[i].clone();
+            let description = format!("核对地址 {}", review.head_name);
+            match self.record(description, |manager| manager.resolve_address(&review.household_id, &review.address)) {
+                Ok(()) => {
+                    self.ui_state.address_reviews.remove(i);
+                    if let Err(e) = self.update_filtered_households() {
+                        self.report_error("刷新列表失败", &e);
+                    }
+                }
+                Err(e) => self.report_error("保存地址失败", &e),
+            }
+        }
+
+        if !open {
+            self.ui_state.show_address_review = false;
+            self.ui_state.address_reviews.clear();
+        }
+    }
+
+    pub fn render_division_stats_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut level = self.ui_state.division_stats_level;
+
+        egui::Window::new("地区统计")
+            .id(egui::Id::new("division_stats_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("统计级别:");
+                    egui::ComboBox::from_id_salt("division_stats_level")
+                        .selected_text(level.to_string())
+                        .show_ui(ui, |ui| {
+                            for option in DivisionLevel::ALL {
+                                ui.selectable_value(&mut level, option, option.to_string());
+                            }
+                        });
+                });
+                ui.separator();
+
+                let counts = match self.household_manager.count_by_division(level) {
+                    Ok(counts) => counts,
+                    Err(e) => {
+                        ui.colored_label(Color32::from_rgb(200, 80, 40), format!("统计失败: {}", e));
+                        return;
+                    }
+                };
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("division_stats_grid")
+                        .striped(true)
+                        .num_columns(3)
+                        .show(ui, |ui| {
+                            ui.label(RichText::new("行政区划").strong());
+                            ui.label(RichText::new("户数").strong());
+                            ui.label(RichText::new("在册人口").strong());
+                            ui.end_row();
+
+                            for count in &counts {
+                                if count.code.is_empty() {
+                                    ui.colored_label(Color32::GRAY, format!("未指定{}", level));
+                                } else {
+                                    ui.label(divisions::full_name(&count.code));
+                                }
+                                ui.label(count.households.to_string());
+                                ui.label(count.members.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        self.ui_state.division_stats_level = level;
+        if !open {
+            self.ui_state.show_division_stats = false;
+        }
+    }
+}
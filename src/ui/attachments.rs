@@ -0,0 +1,377 @@
+use crate::app::HouseholdApp;
+use crate::data::attachments::{Attachment, AttachmentKind, AttachmentOwner};
+use crate::data::audit::AuditAction;
+use crate::data::codes::StableCode;
+use crate::data::error::DataError;
+use crate::ui::keyboard::{self, DialogKey};
+use crate::utils::format_file_size;
+use eframe::egui::{self, *};
+use std::collections::HashMap;
+
+/// 缩略图的最大边长
+const THUMBNAIL_SIZE: u32 = 64;
+/// 查看器中图片的最大边长，更大的图片缩小后显示
+const VIEWER_MAX_SIZE: u32 = 2048;
+
+/// 附件缩略图缓存、查看器以及表单中添加附件的设置
+#[derive(Default)]
+pub struct AttachmentState {
+    // 按内容哈希缓存，无法解码的图片记为 `None`，不再重复尝试
+    thumbnails: HashMap<String, Option<TextureHandle>>,
+    viewer: Option<AttachmentViewer>,
+    /// 表单中添加附件的归属成员（证件号码），为空时归本户
+    upload_member: Option<String>,
+    upload_kind: AttachmentKind,
+    upload_path: String,
+}
+
+struct AttachmentViewer {
+    attachment: Attachment,
+    image: Option<TextureHandle>,
+    save_path: String,
+    confirm_delete: bool,
+}
+
+enum ViewerAction {
+    Save,
+    OpenExternally,
+    Delete,
+}
+
+impl HouseholdApp {
+    /// 一排附件缩略图，点击打开查看器；PDF 显示为按钮
+    pub fn render_attachment_thumbnails(&mut self, ui: &mut Ui, attachments: &[&Attachment]) {
+        let mut clicked = None;
+        ui.horizontal_wrapped(|ui| {
+            for &attachment in attachments {
+                let hover = format!(
+                    "{} {}\n{}",
+                    attachment.kind, attachment.file_name, format_file_size(attachment.size)
+                );
+                let response = match self.thumbnail(ui.ctx(), attachment) {
+                    Some(texture) => {
+                        let image = Image::from_texture(&texture).max_size(Vec2::splat(THUMBNAIL_SIZE as f32));
+                        ui.add(Button::image(image))
+                    }
+                    None => ui.button(format!("PDF {}", attachment.kind)),
+                };
+                if response.on_hover_text(hover).clicked() {
+                    clicked = Some(attachment.clone());
+                }
+            }
+        });
+        if let Some(attachment) = clicked {
+            self.open_attachment_viewer(ui.ctx(), attachment);
+        }
+    }
+
+    fn thumbnail(&mut self, ctx: &Context, attachment: &Attachment) -> Option<TextureHandle> {
+        if !attachment.media_type.is_image() {
+            return None;
+        }
+        if let Some(cached) = self.ui_state.attachments.thumbnails.get(&attachment.hash) {
+            return cached.clone();
+        }
+        // 读取失败时不缓存，下一帧重试
+        let data = self.household_manager.attachment_data(attachment).ok()?;
+        let texture = decode_image(ctx, &format!("attachment_thumbnail_{}", attachment.hash), &data, THUMBNAIL_SIZE);
+        self.ui_state.attachments.thumbnails.insert(attachment.hash.clone(), texture.clone());
+        texture
+    }
+
+    fn open_attachment_viewer(&mut self, ctx: &Context, attachment: Attachment) {
+        let image = if attachment.media_type.is_image() {
+            match self.household_manager.attachment_data(&attachment) {
+                Ok(data) => decode_image(ctx, &format!("attachment_{}", attachment.hash), &data, VIEWER_MAX_SIZE),
+                Err(e) => {
+                    self.report_error("读取附件失败", &e);
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        let save_path = attachment.file_name.clone();
+        self.ui_state.attachments.viewer = Some(AttachmentViewer { attachment, image, save_path, confirm_delete: false });
+    }
+
+    pub fn render_attachment_viewer(&mut self, ctx: &egui::Context) {
+        let editable = !self.read_only();
+        let Some(viewer) = &mut self.ui_state.attachments.viewer else {
+            return;
+        };
+        let mut open = true;
+        let mut escaped = false;
+        let mut action = None;
+        let attachment = &viewer.attachment;
+
+        egui::Window::new(format!("{} - {}", attachment.kind, attachment.file_name))
+            .id(egui::Id::new("attachment_viewer"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([640.0, 560.0])
+            .show(ctx, |ui| {
+                escaped = keyboard::dialog_key(ui) == Some(DialogKey::Cancel);
+                ui.label(format!(
+                    "添加于 {}，{}",
+                    attachment.added_at.format("%Y-%m-%d %H:%M"),
+                    format_file_size(attachment.size)
+                ));
+                ui.separator();
+                match &viewer.image {
+                    Some(texture) => {
+                        let size = ui.available_size() - vec2(0.0, 70.0);
+                        ui.add(Image::from_texture(texture).max_size(size.max(Vec2::splat(THUMBNAIL_SIZE as f32))));
+                    }
+                    None if attachment.media_type.is_image() => {
+                        ui.label("图片无法显示，文件可能已损坏");
+                    }
+                    None => {
+                        ui.label("PDF 文件请用系统程序打开查看");
+                    }
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("另存为:");
+                    ui.text_edit_singleline(&mut viewer.save_path);
+                    if ui.button("保存").clicked() {
+                        action = Some(ViewerAction::Save);
+                    }
+                    if ui.button("用系统程序打开").clicked() {
+                        action = Some(ViewerAction::OpenExternally);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if viewer.confirm_delete {
+                        ui.label("确定删除此附件？");
+                        if ui.button("删除").clicked() {
+                            action = Some(ViewerAction::Delete);
+                        }
+                        if ui.button("取消").clicked() {
+                            viewer.confirm_delete = false;
+                        }
+                    } else if ui.add_enabled(editable, Button::new("删除附件")).clicked() {
+                        viewer.confirm_delete = true;
+                    }
+                });
+            });
+
+        let attachment = viewer.attachment.clone();
+        let save_path = viewer.save_path.trim().to_string();
+        match action {
+            Some(ViewerAction::Save) => match self.write_attachment(&attachment, &save_path) {
+                Ok(()) => {
+                    let household_id = match attachment.owner {
+                        AttachmentOwner::Household(id) => Some(id),
+                        AttachmentOwner::Member(_) => None,
+                    };
+                    let detail = format!("{} {} 另存为 {}", attachment.kind, attachment.file_name, save_path);
+                    self.record_audit(AuditAction::Export, household_id, detail);
+                    self.notify(format!("已保存到 {}", save_path));
+                }
+                Err(e) => self.report_error("保存附件失败", &e),
+            },
+            Some(ViewerAction::OpenExternally) => {
+                let path = std::env::temp_dir().join(format!("{}_{}", &attachment.hash[..8], file_name(&attachment.file_name)));
+                let path = path.to_string_lossy().into_owned();
+                let result = self.write_attachment(&attachment, &path).and_then(|()| open_with_system(&path));
+                if let Err(e) = result {
+                    self.report_error("打开附件失败", &e);
+                }
+            }
+            Some(ViewerAction::Delete) => match self.household_manager.remove_attachment(&attachment.id) {
+                Ok(()) => {
+                    self.ui_state.attachments.viewer = None;
+                    self.notify(format!("已删除附件 {}", attachment.file_name));
+                }
+                Err(e) => self.report_error("删除附件失败", &e),
+            },
+            None => {}
+        }
+        if !open || escaped {
+            self.ui_state.attachments.viewer = None;
+        }
+    }
+
+    fn write_attachment(&mut self, attachment: &Attachment, path: &str) -> Result<(), DataError> {
+        if path.is_empty() {
+            return Err(DataError::Validation("请填写保存的文件路径".into()));
+        }
+        let data = self.household_manager.attachment_data(attachment)?;
+        std::fs::write(path, data).map_err(|e| DataError::Io(format!("{}: {}", path, e)))
+    }
+
+    /// 户籍表单中的附件：已有附件的缩略图，选择归属和类别后拖入文件或填写路径添加。
+    /// 附件添加后立即保存，不随表单的保存或放弃而改变
+    pub fn render_attachment_upload(&mut self, ui: &mut Ui) {
+        let household_id = self.ui_state.editing_household.filter(|_| self.ui_state.show_edit_dialog);
+        let members: Vec<(String, String)> = self.ui_state.edit_form.members.iter()
+            .filter(|m| !m.id_number.trim().is_empty())
+            .map(|m| {
+                let name = if m.name.trim().is_empty() { m.id_number.clone() } else { m.name.clone() };
+                (m.id_number.clone(), name)
+            })
+            .collect();
+        // 选中的成员已删除或改了证件号码时改回本户
+        let state = &mut self.ui_state.attachments;
+        if state.upload_member.as_ref().is_some_and(|id| !members.iter().any(|(n, _)| n == id)) {
+            state.upload_member = None;
+        }
+        if state.upload_member.is_none() && household_id.is_none() {
+            state.upload_member = members.first().map(|(id_number, _)| id_number.clone());
+        }
+        let owner = match &state.upload_member {
+            Some(id_number) => Some(AttachmentOwner::Member(id_number.clone())),
+            None => household_id.map(AttachmentOwner::Household),
+        };
+
+        let existing = self.household_manager.attachments(
+            &household_id.unwrap_or_default(),
+            members.iter().map(|(id_number, _)| id_number.as_str()),
+        );
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.heading(RichText::new("附件").size(16.0));
+                match &existing {
+                    Ok(attachments) if attachments.is_empty() => {
+                        ui.label(RichText::new("暂无附件").color(Color32::GRAY));
+                    }
+                    Ok(attachments) => {
+                        let attachments: Vec<&Attachment> = attachments.iter().collect();
+                        self.render_attachment_thumbnails(ui, &attachments);
+                    }
+                    Err(e) => {
+                        ui.colored_label(Color32::from_rgb(200, 80, 40), format!("附件无法读取: {}", e));
+                    }
+                }
+
+                let state = &mut self.ui_state.attachments;
+                ui.horizontal(|ui| {
+                    ui.label("添加到:");
+                    let selected = match &state.upload_member {
+                        Some(id_number) => members.iter()
+                            .find(|(n, _)| n == id_number)
+                            .map_or(id_number.clone(), |(_, name)| name.clone()),
+                        None if household_id.is_some() => "本户".to_string(),
+                        None => "（请先填写成员证件号码）".to_string(),
+                    };
+                    ComboBox::from_id_salt("attachment_owner").selected_text(selected).show_ui(ui, |ui| {
+                        if household_id.is_some() {
+                            ui.selectable_value(&mut state.upload_member, None, "本户");
+                        }
+                        for (id_number, name) in &members {
+                            ui.selectable_value(&mut state.upload_member, Some(id_number.clone()), name);
+                        }
+                    });
+                    ui.label("类别:");
+                    let response = ComboBox::from_id_salt("attachment_kind")
+                        .selected_text(state.upload_kind.to_string())
+                        .show_ui(ui, |ui| {
+                            for kind in AttachmentKind::ALL {
+                                ui.selectable_value(&mut state.upload_kind, *kind, kind.to_string());
+                            }
+                        })
+                        .response;
+                    keyboard::combo_keys(ui, &response, &mut state.upload_kind, &keyboard::code_options());
+                });
+                let mut add_path = false;
+                ui.horizontal(|ui| {
+                    ui.label("文件路径:");
+                    ui.text_edit_singleline(&mut state.upload_path);
+                    add_path = ui.add_enabled(owner.is_some(), Button::new("添加")).clicked();
+                });
+                let hint = if owner.is_none() {
+                    "新增户籍保存后才能添加本户的附件，成员填写证件号码后可添加其附件"
+                } else if ui.ctx().input(|i| !i.raw.hovered_files.is_empty()) {
+                    "松开鼠标即添加"
+                } else {
+                    "也可将 PNG、JPEG 图片或 PDF 文件拖到窗口中添加，附件添加后立即保存"
+                };
+                ui.label(RichText::new(hint).color(Color32::GRAY));
+
+                let Some(owner) = owner else {
+                    return;
+                };
+                let kind = state.upload_kind;
+                if add_path {
+                    let path = state.upload_path.trim().to_string();
+                    match std::fs::read(&path) {
+                        Ok(data) => {
+                            if self.add_attachment_file(owner.clone(), kind, &file_name(&path), &data) {
+                                self.ui_state.attachments.upload_path.clear();
+                            }
+                        }
+                        Err(e) => self.report_error("添加附件失败", &DataError::Io(format!("{}: {}", path, e))),
+                    }
+                }
+                for file in ui.ctx().input(|i| i.raw.dropped_files.clone()) {
+                    let data = match (&file.bytes, &file.path) {
+                        (Some(bytes), _) => Ok(bytes.to_vec()),
+                        (None, Some(path)) => std::fs::read(path)
+                            .map_err(|e| DataError::Io(format!("{}: {}", path.display(), e))),
+                        (None, None) => continue,
+                    };
+                    let name = match &file.path {
+                        Some(path) => file_name(&path.to_string_lossy()),
+                        None => file.name.clone(),
+                    };
+                    match data {
+                        Ok(data) => {
+                            self.add_attachment_file(owner.clone(), kind, &name, &data);
+                        }
+                        Err(e) => self.report_error("添加附件失败", &e),
+                    }
+                }
+            });
+        });
+    }
+
+    fn add_attachment_file(&mut self, owner: AttachmentOwner, kind: AttachmentKind, file_name: &str, data: &[u8]) -> bool {
+        let result = Attachment::new(owner, kind, file_name, data)
+            .and_then(|attachment| self.household_manager.add_attachment(&attachment, data));
+        match result {
+            Ok(()) => {
+                self.notify(format!("已添加附件 {}", file_name));
+                true
+            }
+            Err(e) => {
+                self.report_error("添加附件失败", &e);
+                false
+            }
+        }
+    }
+}
+
+// 解码 PNG 或 JPEG，并缩小到 `max_side` 以内
+fn decode_image(ctx: &Context, name: &str, data: &[u8], max_side: u32) -> Option<TextureHandle> {
+    let image = image::load_from_memory(data).ok()?.to_rgba8();
+    let longest = image.width().max(image.height());
+    let image = if longest > max_side {
+        let scale = |side: u32| ((side as u64 * max_side as u64 / longest as u64) as u32).max(1);
+        image::imageops::thumbnail(&image, scale(image.width()), scale(image.height()))
+    } else {
+        image
+    };
+    let size = [image.width() as usize, image.height() as usize];
+    let color_image = ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+    Some(ctx.load_texture(name, color_image, TextureOptions::LINEAR))
+}
+
+fn file_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .map_or_else(|| path.to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+// 用系统默认程序打开文件
+fn open_with_system(path: &str) -> Result<(), DataError> {
+    #[cfg(target_os = "windows")]
+    let result = std::process::Command::new("cmd").args(["/C", "start", "", path]).spawn();
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("open").arg(path).spawn();
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = std::process::Command::new("xdg-open").arg(path).spawn();
+    result.map(|_| ()).map_err(|e| DataError::Io(format!("无法启动系统程序: {}", e)))
+}
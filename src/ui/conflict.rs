@@ -0,0 +1,156 @@
+use crate::app::HouseholdApp;
+use crate::data::error::DataError;
+use crate::data::models::*;
+use eframe::egui::{self, *};
+
+pub(crate) const DIFFERENCE_COLOR: Color32 = Color32::from_rgb(200, 80, 40);
+
+/// 保存编辑时发现户籍在打开编辑后已被其他用户修改
+pub struct SaveConflict {
+    /// 本次要保存的内容
+    pub mine: Household,
+    /// 数据库中的当前版本，为空表示已被删除
+    pub theirs: Option<Household>,
+}
+
+enum Resolution {
+    KeepMine,
+    UseTheirs,
+    BackToEdit,
+}
+
+impl HouseholdApp {
+    /// 保存冲突时读取数据库中的当前版本，交由用户比较后决定
+    pub fn open_save_conflict(&mut self, mine: Household) {
+        match self.household_manager.get_household_by_id(&mine.id) {
+            Ok(theirs) => self.ui_state.save_conflict = Some(SaveConflict { mine, theirs }),
+            Err(e) => self.report_error("读取最新版本失败", &e),
+        }
+    }
+
+    pub fn render_save_conflict_dialog(&mut self, ctx: &egui::Context) {
+        let Some(conflict) = &self.ui_state.save_conflict else {
+            return;
+        };
+        let mut resolution = None;
+
+        egui::Window::new("保存冲突")
+            .id(egui::Id::new("save_conflict_dialog"))
+            .collapsible(false)
+            .resizable(true)
+            .default_size([700.0, 450.0])
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                match &conflict.theirs {
+                    Some(_) => ui.label("打开编辑后，该户籍已被其他用户修改。请对照两个版本（不同之处已标出）决定如何处理:"),
+                    None => ui.label("打开编辑后，该户籍已被其他用户删除。可以按您的修改重新登记，或放弃修改:"),
+                };
+                ui.add_space(5.0);
+
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    egui::Grid::new("save_conflict_grid").striped(true).num_columns(3).show(ui, |ui| {
+                        ui.label("");
+                        ui.strong("我的修改");
+                        ui.strong("对方的版本");
+                        ui.end_row();
+
+                        let mine = household_fields(&conflict.mine);
+                        let theirs = conflict.theirs.as_ref().map(household_fields).unwrap_or_default();
+                        for (i, (label, value)) in mine.iter().enumerate() {
+                            let other = theirs.get(i).map(|(_, v)| v.as_str()).unwrap_or("（已删除）");
+                            comparison_row(ui, label, value, other);
+                        }
+
+                        let theirs_members = conflict.theirs.as_ref().map_or(&[][..], |h| &h.members[..]);
+                        let count = conflict.mine.members.len().max(theirs_members.len());
+                        for i in 0..count {
+                            let summary = |members: &[Member]| members.get(i).map(member_summary).unwrap_or_default();
+                            comparison_row(ui, &format!("成员{}", i + 1), &summary(&conflict.mine.members), &summary(theirs_members));
+                        }
+                    });
+                });
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    let keep = if conflict.theirs.is_some() { "保留我的修改" } else { "重新登记" };
+                    if ui.button(keep).on_hover_text("以我的修改覆盖对方的版本").clicked() {
+                        resolution = Some(Resolution::KeepMine);
+                    }
+                    let discard = if conflict.theirs.is_some() { "使用对方的版本" } else { "放弃修改" };
+                    if ui.button(discard).on_hover_text("放弃我的修改并关闭编辑").clicked() {
+                        resolution = Some(Resolution::UseTheirs);
+                    }
+                    if conflict.theirs.is_some()
+                        && ui.button("返回编辑").on_hover_text("参照对方的版本继续修改后再保存").clicked()
+                    {
+                        resolution = Some(Resolution::BackToEdit);
+                    }
+                });
+            });
+
+        let Some(resolution) = resolution else {
+            return;
+        };
+        let Some(SaveConflict { mut mine, theirs }) = self.ui_state.save_conflict.take() else {
+            return;
+        };
+        match resolution {
+            Resolution::KeepMine => {
+                let result = match &theirs {
+                    Some(theirs) => {
+                        mine.revision = theirs.revision;
+                        self.update_household(mine.clone())
+                    }
+                    None => {
+                        mine.revision = 0;
+                        self.add_household(mine.clone())
+                    }
+                };
+                match result {
+                    Ok(()) => self.close_household_form(),
+                    // 比较期间又被修改，按最新版本重新比较
+                    Err(DataError::Conflict(_)) => self.open_save_conflict(mine),
+                    Err(e) => self.report_error("保存失败", &e),
+                }
+            }
+            Resolution::UseTheirs => {
+                self.close_household_form();
+                if let Err(e) = self.update_filtered_households() {
+                    self.report_error("刷新列表失败", &e);
+                }
+            }
+            Resolution::BackToEdit => {
+                if let Some(theirs) = theirs {
+                    self.ui_state.edit_form.revision = theirs.revision;
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn household_fields(household: &Household) -> Vec<(&'static str, String)> {
+    vec![
+        ("户主", household.head_name.clone()),
+        ("证件号码", household.id_number.clone()),
+        ("地址", household.address.to_string()),
+        ("电话", household.phone.clone()),
+        ("户别", household.household_type.to_string()),
+        ("成员数", household.members.len().to_string()),
+    ]
+}
+
+pub(crate) fn member_summary(member: &Member) -> String {
+    format!("{} {} {} {}", member.name, member.relationship, member.id_number, member.status)
+}
+
+fn comparison_row(ui: &mut Ui, label: &str, mine: &str, theirs: &str) {
+    ui.label(label);
+    if mine == theirs {
+        ui.label(mine);
+        ui.label(theirs);
+    } else {
+        ui.label(RichText::new(mine).color(DIFFERENCE_COLOR));
+        ui.label(RichText::new(theirs).color(DIFFERENCE_COLOR));
+    }
+    ui.end_row();
+}
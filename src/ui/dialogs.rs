@@ -1,51 +1,150 @@
 use crate::app::HouseholdApp;
+use crate::data::documents;
+use crate::data::error::DataError;
 use crate::data::models::*;
-use crate::data::validation::Validate;
+use crate::data::consistency::{self, Severity};
+use crate::data::validation::{Validate, ValidationErrors};
+use crate::ui::address::division_combo_boxes;
+use crate::ui::keyboard::{self, DialogKey};
 use eframe::egui::{self, *};
+use std::collections::HashSet;
+
+const FIELD_ERROR_COLOR: Color32 = Color32::from_rgb(200, 50, 50);
+
+/// 户籍表单的校验提示状态：字段修改过或点击过保存后才显示该字段的错误，
+/// 避免刚打开的空表单满屏报错
+#[derive(Debug, Default)]
+pub struct FormFeedback {
+    touched: HashSet<String>,
+    submitted: bool,
+}
+
+impl FormFeedback {
+    fn touch(&mut self, field: String) {
+        // 修改任一成员字段后同时显示成员列表整体的错误（如户主数量）
+        if field.starts_with("members[") {
+            self.touched.insert("members".to_string());
+        }
+        self.touched.insert(field);
+    }
+    
+    fn error<'a>(&self, errors: &'a ValidationErrors, field: &str) -> Option<&'a str> {
+        if self.submitted || self.touched.contains(field) {
+            errors.get(field)
+        } else {
+            None
+        }
+    }
+}
 
 impl HouseholdApp {
     pub fn render_dialogs(&mut self, ctx: &egui::Context) {
-        if self.ui_state.show_add_dialog {
-            self.render_add_dialog(ctx);
+        if !self.ui_state.pending_save_warnings.is_empty() {
+            self.render_duplicate_warning_dialog(ctx);
         }
         
-        if self.ui_state.show_edit_dialog {
-            self.render_edit_dialog(ctx);
+        if self.ui_state.show_duplicate_dialog {
+            self.render_duplicate_scan_dialog(ctx);
         }
+        
+        if self.ui_state.transfer_form.is_some() {
+            self.render_transfer_dialog(ctx);
+        }
+        
+        if self.ui_state.vital_event_form.is_some() {
+            self.render_vital_event_dialog(ctx);
+        }
+        
+        if self.ui_state.head_change_form.is_some() {
+            self.render_head_change_dialog(ctx);
+        }
+        
+        if self.ui_state.show_decode_errors {
+            self.render_decode_errors_dialog(ctx);
+        }
+        
+        if self.ui_state.show_address_review {
+            self.render_address_review_dialog(ctx);
+        }
+        
+        if self.ui_state.show_division_stats {
+            self.render_division_stats_dialog(ctx);
+        }
+        
+        if self.ui_state.dwelling_form.is_some() {
+            self.render_dwelling_dialog(ctx);
+        }
+        
+        if self.ui_state.show_occupancy_report {
+            self.render_occupancy_report(ctx);
+        }
+        
+        if self.ui_state.save_conflict.is_some() {
+            self.render_save_conflict_dialog(ctx);
+        }
+        
+        if self.ui_state.show_shortcuts {
+            self.render_shortcuts_dialog(ctx);
+        }
+        
+        if self.ui_state.confirm_discard {
+            self.render_discard_confirmation(ctx);
+        }
+        
+        if !self.ui_state.recovered_drafts.is_empty() {
+            self.render_draft_recovery_dialog(ctx);
+        }
+        
+        self.render_attachment_viewer(ctx);
+        self.render_audit_log(ctx);
     }
-
-    fn render_add_dialog(&mut self, ctx: &egui::Context) {
-        egui::Window::new("新增户籍")
-            .id(egui::Id::new("add_household_dialog"))
-            .collapsible(false)
-            .resizable(true)
-            .default_size([600.0, 500.0])
-            .show(ctx, |ui| {
-                self.render_household_form(ui, true);
-            });
-    }
-
-    fn render_edit_dialog(&mut self, ctx: &egui::Context) {
-        egui::Window::new("编辑户籍")
-            .id(egui::Id::new("edit_household_dialog"))
+    
+    // 列出加载时无法识别的记录，这些户不会显示，也不会被编辑覆盖
+    fn render_decode_errors_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        
+        egui::Window::new("无法读取的记录")
+            .id(egui::Id::new("decode_errors_dialog"))
+            .open(&mut open)
             .collapsible(false)
             .resizable(true)
-            .default_size([600.0, 500.0])
+            .default_size([600.0, 300.0])
             .show(ctx, |ui| {
-                self.render_household_form(ui, false);
+                ui.label("以下记录包含无法识别的数据，所在户籍已暂停加载，请修复数据库后重新启动:");
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for error in self.household_manager.decode_errors() {
+                        ui.label(error.to_string());
+                    }
+                });
             });
+        
+        self.ui_state.show_decode_errors = open;
     }
 
     pub fn render_household_form(&mut self, ui: &mut Ui, is_add: bool) {
+        // 每帧重新校验，输入时即时更新错误提示
+        let errors = self.ui_state.edit_form.validate().err().unwrap_or_default();
+        
         egui::ScrollArea::vertical().show(ui, |ui| {
             ui.vertical(|ui| {
                 // 基本信息表单
-                self.render_basic_info_form(ui);
+                self.render_basic_info_form(ui, &errors);
                 
                 ui.add_space(10.0);
                 
                 // 家庭成员表单
-                self.render_members_form(ui);
+                self.render_members_form(ui, &errors);
+                
+                ui.add_space(10.0);
+                
+                // 附件
+                self.render_attachment_upload(ui);
+                
+                ui.add_space(10.0);
+                
+                // 户内一致性检查清单
+                render_consistency_checklist(ui, &self.ui_state.edit_form);
                 
                 ui.add_space(10.0);
                 
@@ -55,53 +154,119 @@ impl HouseholdApp {
         });
     }
     
-    fn render_basic_info_form(&mut self, ui: &mut Ui) {
+    fn render_basic_info_form(&mut self, ui: &mut Ui, errors: &ValidationErrors) {
+        let dwellings = match self.household_manager.dwellings() {
+            Ok(dwellings) => dwellings,
+            Err(e) => {
+                self.report_error("读取门牌失败", &e);
+                Vec::new()
+            }
+        };
+        
         ui.group(|ui| {
             ui.vertical(|ui| {
                 ui.heading("基本信息");
                 
+                // 户主信息由成员列表中的户主带出
+                self.ui_state.edit_form.sync_head();
+                
                 ui.horizontal(|ui| {
                     ui.label("户主姓名:");
-                    ui.add(improved_text_edit_singleline(&mut self.ui_state.edit_form.head_name, "dialog_head_name")
-                        .desired_width(200.0));
+                    if self.ui_state.edit_form.head_name.is_empty() {
+                        ui.colored_label(egui::Color32::GRAY, "请在家庭成员中指定户主");
+                    } else {
+                        ui.label(&self.ui_state.edit_form.head_name);
+                    }
                 });
                 
                 ui.horizontal(|ui| {
-                    ui.label("身份证号:");
-                    ui.add(egui::TextEdit::singleline(&mut self.ui_state.edit_form.id_number)
-                        .id_source("dialog_id_number")
-                        .desired_width(200.0));
+                    let head_document_type = self.ui_state.edit_form.members.iter()
+                        .find(|m| m.relationship.is_head())
+                        .map(|m| m.document_type)
+                        .unwrap_or_default();
+                    ui.label(format!("{}:", head_document_type));
+                    ui.label(&self.ui_state.edit_form.id_number);
                 });
                 
                 ui.horizontal(|ui| {
                     ui.label("户口类型:");
-                    egui::ComboBox::from_id_salt("dialog_household_type")
+                    let response = egui::ComboBox::from_id_salt("dialog_household_type")
                         .selected_text(self.ui_state.edit_form.household_type.to_string())
                         .show_ui(ui, |ui| {
                             ui.selectable_value(&mut self.ui_state.edit_form.household_type, HouseholdType::Urban, "城镇户口");
                             ui.selectable_value(&mut self.ui_state.edit_form.household_type, HouseholdType::Rural, "农村户口");
-                        });
+                        })
+                        .response;
+                    keyboard::combo_keys(ui, &response, &mut self.ui_state.edit_form.household_type, &keyboard::code_options());
+                    field_error(ui, self.ui_state.form_feedback.error(errors, "household_type"));
                 });
                 
                 ui.horizontal(|ui| {
                     ui.label("联系电话:");
-                    ui.add(egui::TextEdit::singleline(&mut self.ui_state.edit_form.phone)
+                    let response = ui.add(egui::TextEdit::singleline(&mut self.ui_state.edit_form.phone)
                         .id_source("dialog_phone")
                         .desired_width(200.0));
+                    if response.changed() {
+                        self.ui_state.form_feedback.touch("phone".to_string());
+                    }
+                    field_error(ui, self.ui_state.form_feedback.error(errors, "phone"));
+                });
+                
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("行政区划:");
+                    if division_combo_boxes(ui, "dialog_address", &mut self.ui_state.edit_form.address) {
+                        self.ui_state.form_feedback.touch("address_division".to_string());
+                    }
+                    field_error(ui, self.ui_state.form_feedback.error(errors, "address_division"));
                 });
                 
                 ui.horizontal(|ui| {
-                    ui.label("家庭地址:");
-                    ui.add(egui::TextEdit::multiline(&mut self.ui_state.edit_form.address)
-                        .id_source("dialog_address")
+                    ui.label("详细地址:");
+                    let response = ui.add(egui::TextEdit::multiline(&mut self.ui_state.edit_form.address.detail)
+                        .id_source("dialog_address_detail")
                         .desired_width(300.0)
-                        .desired_rows(3));
+                        .desired_rows(2));
+                    keyboard::mark_multiline(ui, &response);
+                    if response.changed() {
+                        self.ui_state.form_feedback.touch("address_detail".to_string());
+                    }
+                    field_error(ui, self.ui_state.form_feedback.error(errors, "address_detail"));
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label("门牌:");
+                    let form = &mut self.ui_state.edit_form;
+                    let current = form.dwelling_id.and_then(|id| dwellings.iter().find(|d| d.id == id));
+                    let mut selected = form.dwelling_id;
+                    let response = egui::ComboBox::from_id_salt("dialog_dwelling")
+                        .selected_text(current.map(|d| d.to_string()).unwrap_or_else(|| "未指定".to_string()))
+                        .height(300.0)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut selected, None, "未指定");
+                            for dwelling in &dwellings {
+                                ui.selectable_value(&mut selected, Some(dwelling.id), dwelling.to_string());
+                            }
+                        })
+                        .response;
+                    let options: Vec<_> = std::iter::once((None, "未指定".to_string()))
+                        .chain(dwellings.iter().map(|d| (Some(d.id), d.to_string())))
+                        .collect();
+                    keyboard::combo_keys(ui, &response, &mut selected, &options);
+                    // 选择门牌后地址随门牌所在社区填写
+                    if selected != form.dwelling_id {
+                        form.dwelling_id = selected;
+                        if let Some(dwelling) = selected.and_then(|id| dwellings.iter().find(|d| d.id == id)) {
+                            let detail = if form.address.detail.trim().is_empty() { dwelling.short_name() } else { form.address.detail.clone() };
+                            form.address = Address::from_division(&dwelling.division, &detail);
+                            self.ui_state.form_feedback.touch("address_division".to_string());
+                        }
+                    }
                 });
             });
         });
     }
     
-    fn render_members_form(&mut self, ui: &mut Ui) {
+    fn render_members_form(&mut self, ui: &mut Ui, errors: &ValidationErrors) {
         ui.group(|ui| {
             ui.vertical(|ui| {
                 ui.horizontal(|ui| {
@@ -110,6 +275,8 @@ impl HouseholdApp {
                     if ui.button("添加成员").clicked() {
                         self.ui_state.edit_form.members.push(MemberForm::default());
                     }
+                    
+                    field_error(ui, self.ui_state.form_feedback.error(errors, "members"));
                 });
                 
                 let mut to_remove = None;
@@ -117,19 +284,19 @@ impl HouseholdApp {
                 
                 // 分离可变借用
                 let members = &mut self.ui_state.edit_form.members;
+                let feedback = &mut self.ui_state.form_feedback;
                 for (i, member) in members.iter_mut().enumerate() {
                     ui.group(|ui| {
                         ui.vertical(|ui| {
-                            ui.horizontal(|ui| {
-                                ui.heading(&format!("成员 {}", i + 1));
-                                
-                                if members_len > 1 && ui.button("删除").clicked() {
-                                    to_remove = Some(i);
-                                }
-                            });
+                            ui.heading(&format!("成员 {}", i + 1));
                             
                             // 直接在这里渲染成员表单，避免调用self方法
-                            render_member_form_inline(ui, member, i);
+                            render_member_form_inline(ui, member, i, errors, feedback);
+                            
+                            // 放在成员字段之后，按 Tab 依次填写时不会先落在删除按钮上
+                            if members_len > 1 && ui.button("删除成员").clicked() {
+                                to_remove = Some(i);
+                            }
                         });
                     });
                     ui.add_space(5.0);
@@ -145,109 +312,243 @@ impl HouseholdApp {
 
     
     fn render_form_buttons(&mut self, ui: &mut Ui, is_add: bool) {
+        // 保存冲突、疑似重复或放弃修改的确认框打开时不响应按键
+        let confirming = self.ui_state.save_conflict.is_some()
+            || !self.ui_state.pending_save_warnings.is_empty()
+            || self.ui_state.confirm_discard;
+        let key = if confirming { None } else { keyboard::dialog_key(ui) };
         ui.horizontal(|ui| {
-            if ui.button("保存").clicked() {
+            if ui.button("保存").on_hover_text("Enter").clicked() || key == Some(DialogKey::Submit) {
+                self.ui_state.form_feedback.submitted = true;
                 match self.ui_state.edit_form.validate() {
                     Ok(()) => {
-                        if is_add {
-                            if let Some(household) = self.ui_state.edit_form.to_household(None) {
-                                if let Err(e) = self.add_household(household) {
-                                    eprintln!("Failed to add household: {}", e);
-                                    self.ui_state.error_message = format!("添加失败: {}", e);
-                                    self.ui_state.show_error_dialog = true;
-                                } else {
-                                    self.ui_state.show_add_dialog = false;
-                                    self.ui_state.edit_form.clear();
-                                }
-                            } else {
-                                self.ui_state.error_message = "无法创建户籍对象".to_string();
-                                self.ui_state.show_error_dialog = true;
-                            }
-                        } else {
-                            if let Some(selected) = self.ui_state.selected_household {
-                                if let Ok(Some(existing_household)) = self.get_household(selected) {
-                                    if let Some(updated_household) = self.ui_state.edit_form.to_household(Some(existing_household.id)) {
-                                        if let Err(e) = self.update_household(updated_household) {
-                                            eprintln!("Failed to update household: {}", e);
-                                        } else {
-                                            self.ui_state.show_edit_dialog = false;
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                        self.check_duplicates_and_save(is_add);
                     }
-                    Err(error) => {
-                        // 显示错误对话框
-                        self.ui_state.error_message = error;
-                        self.ui_state.show_error_dialog = true;
+                    Err(errors) => {
+                        self.report_warning("保存失败", format!("表单中有 {} 处错误，请按提示修改", errors.len()));
                     }
                 }
             }
             
-            if ui.button("取消").clicked() {
-                if is_add {
-                    self.ui_state.show_add_dialog = false;
+            if ui.button("取消").on_hover_text("Esc").clicked() || key == Some(DialogKey::Cancel) {
+                self.cancel_household_form();
+            }
+        });
+    }
+    
+    // 保存前查重：身份证号重复直接拒绝，疑似重复需要用户确认
+    fn check_duplicates_and_save(&mut self, is_add: bool) {
+        let existing_id = if is_add {
+            None
+        } else {
+            match self.ui_state.editing_household {
+                Some(id) => Some(id),
+                None => return,
+            }
+        };
+        
+        let Some(household) = self.ui_state.edit_form.to_household(existing_id) else {
+            self.report_warning("保存失败", "无法创建户籍对象");
+            return;
+        };
+        
+        let matches = match self.household_manager.find_matches(&household, existing_id) {
+            Ok(matches) => matches,
+            Err(e) => {
+                self.report_error("查重失败", &e);
+                return;
+            }
+        };
+        
+        let exact: Vec<&str> = matches.iter()
+            .filter(|m| m.kind.is_exact())
+            .map(|m| m.message.as_str())
+            .collect();
+        if !exact.is_empty() {
+            let message = exact.join("\n");
+            self.report_warning("无法保存，存在重复登记", message);
+        } else if !matches.is_empty() {
+            self.ui_state.pending_save_warnings = matches;
+            self.ui_state.pending_save_is_add = is_add;
+        } else {
+            self.save_edit_form(is_add);
+        }
+    }
+    
+    fn save_edit_form(&mut self, is_add: bool) {
+        if is_add {
+            if let Some(household) = self.ui_state.edit_form.to_household(None) {
+                if let Err(e) = self.add_household(household) {
+                    self.report_error("添加失败", &e);
                 } else {
-                    self.ui_state.show_edit_dialog = false;
+                    self.close_household_form();
                 }
-                self.ui_state.edit_form.clear();
+            } else {
+                self.report_warning("保存失败", "无法创建户籍对象");
             }
-        });
+        } else if let Some(household_id) = self.ui_state.editing_household {
+            if let Some(updated_household) = self.ui_state.edit_form.to_household(Some(household_id)) {
+                match self.update_household(updated_household.clone()) {
+                    Ok(()) => self.close_household_form(),
+                    // 打开编辑后被其他用户修改或删除，比较两个版本后再决定
+                    Err(DataError::Conflict(_)) => self.open_save_conflict(updated_household),
+                    Err(e) => self.report_error("保存失败", &e),
+                }
+            }
+        }
+    }
+    
+    fn render_duplicate_warning_dialog(&mut self, ctx: &egui::Context) {
+        let mut decision = None;
+        
+        egui::Window::new("疑似重复")
+            .id(egui::Id::new("duplicate_warning_dialog"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("发现以下疑似重复的记录:");
+                ui.add_space(5.0);
+                for warning in &self.ui_state.pending_save_warnings {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(egui::Color32::from_rgb(255, 193, 7), "⚠");
+                        ui.label(&warning.message);
+                    });
+                }
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("仍然保存").clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button("返回修改").clicked() {
+                        decision = Some(false);
+                    }
+                });
+            });
+        
+        if let Some(save) = decision {
+            self.ui_state.pending_save_warnings.clear();
+            if save {
+                self.save_edit_form(self.ui_state.pending_save_is_add);
+            }
+        }
     }
 }
 
 // 独立的成员表单渲染函数，避免借用检查器问题
-fn render_member_form_inline(ui: &mut Ui, member: &mut MemberForm, index: usize) {
+fn render_member_form_inline(
+    ui: &mut Ui,
+    member: &mut MemberForm,
+    index: usize,
+    errors: &ValidationErrors,
+    feedback: &mut FormFeedback,
+) {
     use crate::data::models::*;
+    use chrono::Datelike;
+    
+    let field = |name: &str| format!("members[{}].{}", index, name);
     
     ui.horizontal(|ui| {
         ui.label("姓名:");
-        ui.add(egui::TextEdit::singleline(&mut member.name)
+        let response = ui.add(egui::TextEdit::singleline(&mut member.name)
             .id_source(format!("dialog_member_name_{}", index))
             .desired_width(120.0));
+        if response.changed() {
+            feedback.touch(field("name"));
+        }
         
         ui.label("关系:");
-        egui::ComboBox::from_id_salt(format!("dialog_member_relationship_{}", index))
-            .selected_text(member.relationship.to_string())
-            .show_ui(ui, |ui| {
-                ui.selectable_value(&mut member.relationship, Relationship::Head, "户主");
-                ui.selectable_value(&mut member.relationship, Relationship::Spouse, "配偶");
-                ui.selectable_value(&mut member.relationship, Relationship::Child, "子女");
-                ui.selectable_value(&mut member.relationship, Relationship::Parent, "父母");
-                ui.selectable_value(&mut member.relationship, Relationship::Other, "其他");
-            });
+        let previous = member.relationship;
+        relationship_combo_box(ui, format!("dialog_member_relationship_{}", index), &mut member.relationship);
+        if member.relationship != previous {
+            feedback.touch(field("relationship"));
+        }
+        
+        field_error(ui, feedback.error(errors, &field("name")));
     });
     
     ui.horizontal(|ui| {
-        ui.label("身份证号:");
-        ui.add(egui::TextEdit::singleline(&mut member.id_number)
+        ui.label("证件类型:");
+        if document_type_combo_box(ui, format!("dialog_member_document_type_{}", index), member) {
+            feedback.touch(field("document_type"));
+            feedback.touch(field("nationality"));
+        }
+        
+        if !member.document_type.is_chinese_citizen() {
+            ui.label("国籍:");
+            let response = ui.add(egui::TextEdit::singleline(&mut member.nationality)
+                .id_source(format!("dialog_member_nationality_{}", index))
+                .hint_text("如 USA")
+                .desired_width(60.0));
+            if response.changed() {
+                feedback.touch(field("nationality"));
+            }
+            if let Some(name) = documents::nationality_name(&member.nationality()) {
+                ui.label(name);
+            }
+        }
+        
+        field_error(ui, feedback.error(errors, &field("nationality")));
+    });
+    
+    ui.horizontal(|ui| {
+        ui.label("证件号码:");
+        let response = ui.add(egui::TextEdit::singleline(&mut member.id_number)
             .id_source(format!("dialog_member_id_number_{}", index))
             .desired_width(160.0));
+        if response.changed() {
+            feedback.touch(field("id_number"));
+        }
         
         ui.label("性别:");
-        egui::ComboBox::from_id_salt(format!("dialog_member_gender_{}", index))
+        let response = egui::ComboBox::from_id_salt(format!("dialog_member_gender_{}", index))
             .selected_text(member.gender.to_string())
             .show_ui(ui, |ui| {
                 ui.selectable_value(&mut member.gender, Gender::Male, "男");
                 ui.selectable_value(&mut member.gender, Gender::Female, "女");
-            });
+            })
+            .response;
+        keyboard::combo_keys(ui, &response, &mut member.gender, &keyboard::code_options());
+        
+        field_error(ui, feedback.error(errors, &field("id_number")));
     });
     
     ui.horizontal(|ui| {
         ui.label("出生年份:");
-        ui.add(egui::DragValue::new(&mut member.birth_year).range(1900..=2024));
+        let year = ui.add(egui::DragValue::new(&mut member.birth_year).range(1900..=chrono::Local::now().year()));
         
         ui.label("月:");
-        ui.add(egui::DragValue::new(&mut member.birth_month).range(1..=12));
+        let month = ui.add(egui::DragValue::new(&mut member.birth_month).range(1..=12));
         
         ui.label("日:");
-        ui.add(egui::DragValue::new(&mut member.birth_day).range(1..=31));
+        let day = ui.add(egui::DragValue::new(&mut member.birth_day).range(1..=31));
+        
+        if year.changed() || month.changed() || day.changed() {
+            feedback.touch(field("birth_date"));
+        }
+        field_error(ui, feedback.error(errors, &field("birth_date")));
+    });
+    
+    ui.horizontal(|ui| {
+        ui.label("婚姻状况:");
+        let response = egui::ComboBox::from_id_salt(format!("dialog_member_marital_status_{}", index))
+            .selected_text(member.marital_status.to_string())
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut member.marital_status, MaritalStatus::Unmarried, "未婚");
+                ui.selectable_value(&mut member.marital_status, MaritalStatus::Married, "已婚");
+                ui.selectable_value(&mut member.marital_status, MaritalStatus::Divorced, "离婚");
+                ui.selectable_value(&mut member.marital_status, MaritalStatus::Widowed, "丧偶");
+            })
+            .response;
+        keyboard::combo_keys(ui, &response, &mut member.marital_status, &keyboard::code_options());
+        
+        // 状态只能通过死亡注销、迁出等登记变更
+        ui.label(format!("状态: {}", member.status));
     });
     
     ui.horizontal(|ui| {
         ui.label("学历:");
-        egui::ComboBox::from_id_salt(format!("dialog_member_education_{}", index))
+        let response = egui::ComboBox::from_id_salt(format!("dialog_member_education_{}", index))
             .selected_text(member.education.to_string())
             .show_ui(ui, |ui| {
                 ui.selectable_value(&mut member.education, Education::Primary, "小学");
@@ -257,19 +558,116 @@ fn render_member_form_inline(ui: &mut Ui, member: &mut MemberForm, index: usize)
                 ui.selectable_value(&mut member.education, Education::University, "本科");
                 ui.selectable_value(&mut member.education, Education::Graduate, "研究生");
                 ui.selectable_value(&mut member.education, Education::Other, "其他");
-            });
+            })
+            .response;
+        keyboard::combo_keys(ui, &response, &mut member.education, &keyboard::code_options());
         
         ui.label("职业:");
-        ui.add(egui::TextEdit::singleline(&mut member.occupation)
+        let response = ui.add(egui::TextEdit::singleline(&mut member.occupation)
             .id_source(format!("dialog_member_occupation_{}", index))
             .desired_width(120.0));
+        if response.changed() {
+            feedback.touch(field("occupation"));
+        }
     });
+    
+    // 配置的校验规则可能涉及上方未单独显示错误的字段
+    for name in ["relationship", "gender", "education", "marital_status", "occupation"] {
+        if let Some(message) = feedback.error(errors, &field(name)) {
+            ui.horizontal(|ui| field_error(ui, Some(message)));
+        }
+    }
 }
 
-// 辅助函数：创建改进的单行文本输入框
-fn improved_text_edit_singleline(text: &mut String, id: impl std::hash::Hash) -> egui::TextEdit {
-    egui::TextEdit::singleline(text)
-        .id(egui::Id::new(id))
-        .clip_text(false)
-        .cursor_at_end(false)
+// 逐项显示户内一致性检查结果：错误需修改后才能保存，警告仅提示核实
+fn render_consistency_checklist(ui: &mut Ui, form: &HouseholdForm) {
+    let checks = consistency::check_household(form);
+    let warnings = checks.iter().filter(|c| c.severity() == Some(Severity::Warning)).count();
+    let failures = checks.iter().filter(|c| c.severity() == Some(Severity::Error)).count();
+    
+    let title = match (failures, warnings) {
+        (0, 0) => "一致性检查（全部通过）".to_string(),
+        _ => format!("一致性检查（{} 项错误，{} 项需核实）", failures, warnings),
+    };
+    
+    egui::CollapsingHeader::new(title)
+        .id_salt("consistency_checklist")
+        .default_open(true)
+        .show(ui, |ui| {
+            for check in &checks {
+                let (icon, color) = severity_style(check.severity());
+                ui.horizontal(|ui| {
+                    ui.colored_label(color, icon);
+                    ui.label(check.name);
+                });
+                for issue in &check.issues {
+                    ui.horizontal(|ui| {
+                        ui.add_space(20.0);
+                        ui.colored_label(severity_style(Some(issue.severity)).1, &issue.message);
+                    });
+                }
+            }
+        });
+}
+
+fn severity_style(severity: Option<Severity>) -> (&'static str, Color32) {
+    match severity {
+        None => ("✔", Color32::from_rgb(40, 150, 60)),
+        Some(Severity::Warning) => ("⚠", Color32::from_rgb(200, 140, 20)),
+        Some(Severity::Error) => ("✖", FIELD_ERROR_COLOR),
+    }
+}
+
+// 在输入控件旁显示字段错误
+fn field_error(ui: &mut Ui, message: Option<&str>) {
+    if let Some(message) = message {
+        ui.colored_label(FIELD_ERROR_COLOR, format!("⚠ {}", message));
+    }
+}
+
+// 与户主关系下拉框，按 GB/T 4761 类别分组显示，成员表单和迁移表单共用
+/// 证件类型下拉框，返回是否有改动。改为境外证件时清空默认的中国国籍
+pub(crate) fn document_type_combo_box(ui: &mut Ui, id: impl std::hash::Hash, member: &mut MemberForm) -> bool {
+    let previous = member.document_type;
+    let response = egui::ComboBox::from_id_salt(id)
+        .selected_text(member.document_type.to_string())
+        .show_ui(ui, |ui| {
+            for option in DocumentType::ALL {
+                ui.selectable_value(&mut member.document_type, option, option.to_string());
+            }
+        })
+        .response;
+    keyboard::combo_keys(ui, &response, &mut member.document_type, &keyboard::code_options());
+    if member.document_type == previous {
+        return false;
+    }
+    if member.document_type.is_chinese_citizen() || member.nationality() == documents::CHINA {
+        member.nationality.clear();
+    }
+    true
+}
+
+pub(crate) fn relationship_combo_box(ui: &mut Ui, id: impl std::hash::Hash, relationship: &mut Relationship) {
+    use crate::data::relationship::RELATIONSHIP_CATEGORIES;
+    
+    let response = egui::ComboBox::from_id_salt(id)
+        .selected_text(format!("{} {}", relationship.code(), relationship))
+        .height(400.0)
+        .show_ui(ui, |ui| {
+            for (i, category) in RELATIONSHIP_CATEGORIES.iter().enumerate() {
+                if i > 0 {
+                    ui.separator();
+                }
+                ui.label(RichText::new(category.to_string()).strong().color(Color32::GRAY));
+                for option in Relationship::in_category(*category) {
+                    ui.selectable_value(relationship, option, format!("  {} {}", option.code(), option));
+                }
+            }
+        })
+        .response;
+    let options: Vec<_> = RELATIONSHIP_CATEGORIES.iter()
+        .flat_map(|category| Relationship::in_category(*category))
+        .map(|option| (option, format!("{} {}", option.code(), option)))
+        .collect();
+    keyboard::combo_keys(ui, &response, relationship, &options);
 }
@@ -0,0 +1,264 @@
+use crate::app::HouseholdApp;
+use crate::data::drafts::Draft;
+use crate::data::error::DataError;
+use crate::data::models::HouseholdForm;
+use crate::ui::dialogs::FormFeedback;
+use crate::ui::keyboard::{self, DialogKey};
+use eframe::egui::{self, *};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// 表单有修改时自动保存草稿的间隔
+const DRAFT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 正在填写的户籍表单的初始内容及其草稿的保存情况
+#[derive(Debug, Default)]
+pub struct FormTracker {
+    /// 打开表单时的内容，与之不同即有未保存的修改
+    baseline: HouseholdForm,
+    draft_id: Uuid,
+    /// 最近一次保存为草稿的内容，尚未保存草稿时为空
+    saved: Option<HouseholdForm>,
+    last_save: Option<Instant>,
+    /// 自动保存失败时只提示一次，恢复正常后重置
+    save_failed: bool,
+}
+
+impl FormTracker {
+    /// `form` 与打开时的内容不同，即有未保存的修改
+    pub fn is_dirty(&self, form: &HouseholdForm) -> bool {
+        *form != self.baseline
+    }
+
+    fn new(baseline: HouseholdForm, draft_id: Uuid, saved: Option<HouseholdForm>) -> Self {
+        Self { baseline, draft_id, saved, last_save: Some(Instant::now()), save_failed: false }
+    }
+}
+
+enum DraftAction {
+    Restore(usize),
+    Discard(usize),
+}
+
+impl HouseholdApp {
+    /// 打开新增或编辑表单后调用，记录表单的初始内容
+    pub fn track_form(&mut self) {
+        self.ui_state.form_tracker = FormTracker::new(self.ui_state.edit_form.clone(), Uuid::new_v4(), None);
+    }
+
+    pub fn form_is_dirty(&self) -> bool {
+        self.ui_state.form_tracker.is_dirty(&self.ui_state.edit_form)
+    }
+
+    /// 取消表单，有未保存的修改时先请用户确认
+    pub fn cancel_household_form(&mut self) {
+        if self.form_is_dirty() {
+            self.ui_state.confirm_discard = true;
+        } else {
+            self.close_household_form();
+        }
+    }
+
+    /// 保存成功或放弃修改后关闭表单并删除其草稿，所在标签页回到查看或关闭
+    pub fn close_household_form(&mut self) {
+        self.ui_state.show_add_dialog = false;
+        self.ui_state.show_edit_dialog = false;
+        self.ui_state.editing_household = None;
+        self.ui_state.confirm_discard = false;
+        self.ui_state.edit_form.clear();
+        self.ui_state.form_feedback = FormFeedback::default();
+        let tracker = std::mem::take(&mut self.ui_state.form_tracker);
+        if tracker.saved.is_some() {
+            if let Err(e) = self.household_manager.discard_draft(&tracker.draft_id) {
+                self.report_error("删除草稿失败", &e);
+            }
+        }
+        self.finish_form_tab();
+    }
+
+    /// 表单有修改时定期保存为草稿，程序窗口关闭前立即保存
+    pub fn autosave_draft(&mut self, ctx: &egui::Context) {
+        if !self.draft_needs_saving() {
+            return;
+        }
+        let remaining = self.ui_state.form_tracker.last_save
+            .map(|t| DRAFT_AUTOSAVE_INTERVAL.saturating_sub(t.elapsed()))
+            .unwrap_or_default();
+        if !remaining.is_zero() && !ctx.input(|i| i.viewport().close_requested()) {
+            ctx.request_repaint_after(remaining);
+            return;
+        }
+        self.save_form_draft();
+    }
+
+    /// 切换标签页前调用，未保存的修改立即存为草稿
+    pub fn flush_draft(&mut self) {
+        if self.draft_needs_saving() {
+            self.save_form_draft();
+        }
+    }
+
+    // 表单是否有尚未存入草稿的修改；改回原样后不再需要草稿，顺带删除
+    fn draft_needs_saving(&mut self) -> bool {
+        if !self.ui_state.show_add_dialog && !self.ui_state.show_edit_dialog {
+            return false;
+        }
+        let tracker = &mut self.ui_state.form_tracker;
+        if self.ui_state.edit_form == tracker.baseline {
+            if tracker.saved.take().is_some() {
+                if let Err(e) = self.household_manager.discard_draft(&tracker.draft_id) {
+                    self.report_error("删除草稿失败", &e);
+                }
+            }
+            return false;
+        }
+        tracker.saved.as_ref() != Some(&self.ui_state.edit_form)
+    }
+
+    fn save_form_draft(&mut self) {
+        let draft = Draft {
+            id: self.ui_state.form_tracker.draft_id,
+            operator: self.settings.operator(),
+            household_id: self.ui_state.editing_household.filter(|_| self.ui_state.show_edit_dialog),
+            form: self.ui_state.edit_form.clone(),
+            updated_at: chrono::Local::now().naive_local(),
+        };
+        self.ui_state.form_tracker.last_save = Some(Instant::now());
+        match self.household_manager.save_draft(&draft) {
+            Ok(()) => {
+                let tracker = &mut self.ui_state.form_tracker;
+                tracker.saved = Some(draft.form);
+                tracker.save_failed = false;
+            }
+            Err(e) => {
+                if !std::mem::replace(&mut self.ui_state.form_tracker.save_failed, true) {
+                    self.report_error("草稿自动保存失败", &e);
+                }
+            }
+        }
+    }
+
+    /// 读取本操作员上次退出时未保存的表单，启动时调用
+    pub fn load_recovered_drafts(&mut self) {
+        match self.household_manager.drafts(&self.settings.operator()) {
+            Ok(drafts) => self.ui_state.recovered_drafts = drafts,
+            Err(e) => self.report_error("读取草稿失败", &e),
+        }
+    }
+
+    // 以草稿内容在标签页中打开表单，保存或放弃后删除该草稿；该户已在编辑时返回 false
+    fn restore_draft(&mut self, draft: &Draft) -> Result<bool, DataError> {
+        let baseline = match draft.household_id {
+            // 户籍已被删除时保存会提示冲突，可选择重新登记
+            Some(id) => match self.household_manager.get_household_by_id(&id)? {
+                Some(household) => self.household_manager.household_form(&household)?,
+                None => HouseholdForm::default(),
+            },
+            None => {
+                let mut form = HouseholdForm::default();
+                form.clear();
+                form
+            }
+        };
+        if !self.open_form_tab(draft.household_id, draft.form.clone()) {
+            return Ok(false);
+        }
+        self.ui_state.form_tracker = FormTracker::new(baseline, draft.id, Some(draft.form.clone()));
+        Ok(true)
+    }
+
+    pub fn render_draft_recovery_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut action = None;
+        // 查看员不能继续填写表单，只能丢弃
+        let locked = self.tabs_locked() || self.read_only();
+
+        egui::Window::new("恢复未保存的表单")
+            .id(egui::Id::new("draft_recovery_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("上次退出时以下表单尚未保存:");
+                ui.add_space(5.0);
+                egui::Grid::new("draft_recovery_grid").striped(true).num_columns(3).show(ui, |ui| {
+                    for (i, draft) in self.ui_state.recovered_drafts.iter().enumerate() {
+                        ui.label(draft.description());
+                        ui.label(draft.updated_at.format("%Y-%m-%d %H:%M").to_string());
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!locked, egui::Button::new("恢复"))
+                                .on_disabled_hover_text("请先处理正在打开的确认框")
+                                .clicked()
+                            {
+                                action = Some(DraftAction::Restore(i));
+                            }
+                            if ui.button("丢弃").clicked() {
+                                action = Some(DraftAction::Discard(i));
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(5.0);
+                ui.label(RichText::new("关闭此窗口后草稿仍会保留，下次启动时再次提示").color(Color32::GRAY));
+            });
+
+        match action {
+            Some(DraftAction::Restore(i)) => {
+                let draft = self.ui_state.recovered_drafts[i].clone();
+                match self.restore_draft(&draft) {
+                    Ok(true) => {
+                        self.ui_state.recovered_drafts.remove(i);
+                    }
+                    Ok(false) => self.report_warning("无法恢复草稿", "该户籍正在编辑，请先保存或关闭其标签页"),
+                    Err(e) => self.report_error("恢复草稿失败", &e),
+                }
+            }
+            Some(DraftAction::Discard(i)) => {
+                let draft_id = self.ui_state.recovered_drafts[i].id;
+                match self.household_manager.discard_draft(&draft_id) {
+                    Ok(()) => {
+                        self.ui_state.recovered_drafts.remove(i);
+                    }
+                    Err(e) => self.report_error("删除草稿失败", &e),
+                }
+            }
+            None => {}
+        }
+        if !open {
+            self.ui_state.recovered_drafts.clear();
+        }
+    }
+
+    pub fn render_discard_confirmation(&mut self, ctx: &egui::Context) {
+        let mut discard = None;
+
+        egui::Window::new("放弃修改")
+            .id(egui::Id::new("discard_confirmation_dialog"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("表单中有未保存的修改，关闭后将丢失。确定放弃吗？");
+                ui.add_space(10.0);
+                let key = keyboard::dialog_key(ui);
+                ui.horizontal(|ui| {
+                    if ui.button("放弃修改").clicked() {
+                        discard = Some(true);
+                    }
+                    if ui.button("继续编辑").on_hover_text("Esc").clicked() || key == Some(DialogKey::Cancel) {
+                        discard = Some(false);
+                    }
+                });
+            });
+
+        match discard {
+            Some(true) => self.close_household_form(),
+            Some(false) => {
+                self.ui_state.confirm_discard = false;
+                self.ui_state.close_tab_after_form = false;
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::app::HouseholdApp;
+use crate::data::duplicates::{DuplicateEntry, DuplicateKind, MemberRef};
+use eframe::egui::{self, *};
+use uuid::Uuid;
+
+// 查重窗口中用户选择的合并操作
+enum MergeAction {
+    KeepMember(MemberRef, Vec<MemberRef>),
+    MergeHouseholds(Uuid, Vec<Uuid>),
+}
+
+impl HouseholdApp {
+    pub fn open_duplicate_scan(&mut self) {
+        if !self.ensure_editable() {
+            return;
+        }
+        match self.household_manager.find_duplicates() {
+            Ok(groups) => {
+                self.ui_state.duplicate_groups = groups;
+                self.ui_state.show_duplicate_dialog = true;
+            }
+            Err(e) => {
+                self.report_error("查重失败", &e);
+            }
+        }
+    }
+
+    pub fn render_duplicate_scan_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut action = None;
+        let mut rescan = false;
+
+        egui::Window::new("查重")
+            .id(egui::Id::new("duplicate_scan_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("共发现 {} 组重复记录", self.ui_state.duplicate_groups.len()));
+                    if ui.button("重新扫描").clicked() {
+                        rescan = true;
+                    }
+                });
+                ui.separator();
+
+                if self.ui_state.duplicate_groups.is_empty() {
+                    ui.label("未发现重复记录");
+                    return;
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (group_index, group) in self.ui_state.duplicate_groups.iter().enumerate() {
+                        ui.group(|ui| {
+                            ui.vertical(|ui| {
+                                let color = if group.kind.is_exact() {
+                                    Color32::from_rgb(220, 53, 69)
+                                } else {
+                                    Color32::from_rgb(255, 193, 7)
+                                };
+                                ui.colored_label(color, format!("第{}组: {}", group_index + 1, group.kind));
+
+                                for (i, entry) in group.entries.iter().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        ui.label(entry.describe());
+                                        match group.kind {
+                                            DuplicateKind::SimilarHousehold => {
+                                                if ui.button("合并到此户").clicked() {
+                                                    let others = group.entries.iter()
+                                                        .enumerate()
+                                                        .filter(|(j, _)| *j != i)
+                                                        .map(|(_, e)| e.household_id())
+                                                        .collect();
+                                                    action = Some(MergeAction::MergeHouseholds(entry.household_id(), others));
+                                                }
+                                            }
+                                            _ => {
+                                                if let DuplicateEntry::Member { member, .. } = entry {
+                                                    if ui.button("保留此条").clicked() {
+                                                        let others = group.entries.iter()
+                                                            .enumerate()
+                                                            .filter(|(j, _)| *j != i)
+                                                            .filter_map(|(_, e)| match e {
+                                                                DuplicateEntry::Member { member, .. } => Some(member.clone()),
+                                                                DuplicateEntry::Household { .. } => None,
+                                                            })
+                                                            .collect();
+                                                        action = Some(MergeAction::KeepMember(member.clone(), others));
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    });
+                                }
+                            });
+                        });
+                        ui.add_space(5.0);
+                    }
+                });
+            });
+
+        if let Some(action) = action {
+            let result = match action {
+                MergeAction::KeepMember(keep, others) => {
+                    self.record("处理重复成员", |manager| manager.resolve_duplicate_member(&keep, &others))
+                }
+                MergeAction::MergeHouseholds(keep, others) => {
+                    self.record("合并重复户", |manager| manager.merge_duplicate_households(&keep, &others))
+                }
+            };
+
+            match result {
+                Ok(()) => {
+                    self.ui_state.selected_household = None;
+                    if let Err(e) = self.update_filtered_households() {
+                        self.report_error("刷新列表失败", &e);
+                    }
+                    rescan = true;
+                }
+                Err(e) => {
+                    self.report_error("合并失败", &e);
+                }
+            }
+        }
+
+        if rescan {
+            self.open_duplicate_scan();
+        }
+
+        if !open {
+            self.ui_state.show_duplicate_dialog = false;
+            self.ui_state.duplicate_groups.clear();
+        }
+    }
+}
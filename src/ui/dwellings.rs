@@ -0,0 +1,291 @@
+use crate::app::HouseholdApp;
+use crate::data::divisions;
+use crate::data::dwellings::{Occupancy, OccupancyStatus};
+use crate::data::models::*;
+use crate::ui::address::division_combo_boxes;
+use crate::ui::keyboard::{self, DialogKey};
+use eframe::egui::{self, *};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// 居住情况报表的筛选条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OccupancyFilter {
+    #[default]
+    All,
+    Empty,
+    Overcrowded,
+    /// 多户登记在同一门牌
+    Shared,
+}
+
+impl std::fmt::Display for OccupancyFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OccupancyFilter::All => write!(f, "全部"),
+            OccupancyFilter::Empty => write!(f, "空置"),
+            OccupancyFilter::Overcrowded => write!(f, "超员"),
+            OccupancyFilter::Shared => write!(f, "多户共住"),
+        }
+    }
+}
+
+impl OccupancyFilter {
+    fn matches(&self, occupancy: &Occupancy) -> bool {
+        match self {
+            OccupancyFilter::All => true,
+            OccupancyFilter::Empty => occupancy.status() == OccupancyStatus::Empty,
+            OccupancyFilter::Overcrowded => occupancy.status() == OccupancyStatus::Overcrowded,
+            OccupancyFilter::Shared => occupancy.households.len() > 1,
+        }
+    }
+}
+
+fn status_color(status: OccupancyStatus) -> Color32 {
+    match status {
+        OccupancyStatus::Empty => Color32::GRAY,
+        OccupancyStatus::Occupied => Color32::from_rgb(40, 167, 69),
+        OccupancyStatus::Overcrowded => Color32::from_rgb(220, 53, 69),
+    }
+}
+
+// 按相邻元素的键分组，输入已排序
+fn group_by<K: PartialEq>(items: &[Occupancy], key: impl Fn(&Occupancy) -> K) -> Vec<(K, &[Occupancy])> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    for i in 1..=items.len() {
+        if i == items.len() || key(&items[i]) != key(&items[start]) {
+            groups.push((key(&items[start]), &items[start..i]));
+            start = i;
+        }
+    }
+    groups
+}
+
+impl HouseholdApp {
+    /// 门牌树：社区 → 楼栋 → 单元 → 房号 → 户，与户籍列表二选一显示在左侧
+    pub fn render_dwelling_tree_panel(&mut self, ui: &mut Ui) {
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.heading(RichText::new("门牌").size(16.0));
+                if ui.add_enabled(!self.read_only(), Button::new("新增门牌")).clicked() {
+                    self.ui_state.dwelling_form = Some(DwellingForm::default());
+                }
+                if ui.button("居住情况").clicked() {
+                    self.ui_state.show_occupancy_report = true;
+                }
+            });
+            ui.separator();
+
+            let (occupancy, households) = match (self.household_manager.occupancy(), self.get_households()) {
+                (Ok(occupancy), Ok(households)) => (occupancy, households),
+                (Err(e), _) | (_, Err(e)) => {
+                    ui.colored_label(Color32::from_rgb(200, 80, 40), format!("门牌加载失败: {}", e));
+                    return;
+                }
+            };
+            // 列表下标与户籍列表一致，选中后右侧显示详情
+            let indices: HashMap<Uuid, usize> = households.iter().enumerate().map(|(i, h)| (h.id, i)).collect();
+
+            if occupancy.is_empty() {
+                ui.label("尚未登记门牌");
+                return;
+            }
+
+            let mut edit = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (division, by_division) in group_by(&occupancy, |o| o.dwelling.division.clone()) {
+                    egui::CollapsingHeader::new(divisions::full_name(&division))
+                        .id_salt(("dwelling_division", &division))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for (building, by_building) in group_by(by_division, |o| o.dwelling.building_label()) {
+                                egui::CollapsingHeader::new(&building)
+                                    .id_salt(("dwelling_building", &division, &building))
+                                    .show(ui, |ui| {
+                                        for (unit, by_unit) in group_by(by_building, |o| o.dwelling.unit_label()) {
+                                            if unit.is_empty() {
+                                                self.render_dwelling_rooms(ui, by_unit, &indices, &mut edit);
+                                                continue;
+                                            }
+                                            egui::CollapsingHeader::new(&unit)
+                                                .id_salt(("dwelling_unit", &division, &building, &unit))
+                                                .show(ui, |ui| {
+                                                    self.render_dwelling_rooms(ui, by_unit, &indices, &mut edit);
+                                                });
+                                        }
+                                    });
+                            }
+                        });
+                }
+            });
+
+            if let Some(dwelling) = edit {
+                self.ui_state.dwelling_form = Some(DwellingForm::from_dwelling(&dwelling));
+            }
+        });
+    }
+
+    fn render_dwelling_rooms(
+        &mut self,
+        ui: &mut Ui,
+        rooms: &[Occupancy],
+        indices: &HashMap<Uuid, usize>,
+        edit: &mut Option<Dwelling>,
+    ) {
+        let editable = !self.read_only();
+        for occupancy in rooms {
+            let status = occupancy.status();
+            ui.horizontal(|ui| {
+                ui.label(occupancy.dwelling.room_label());
+                ui.colored_label(
+                    status_color(status),
+                    format!("{}户 {}人{}", occupancy.households.len(), occupancy.members,
+                        if status == OccupancyStatus::Overcrowded { format!("（核定{}人）", occupancy.dwelling.capacity) } else { String::new() }),
+                );
+                if ui.add_enabled(editable, Button::new("编辑").small()).clicked() {
+                    *edit = Some(occupancy.dwelling.clone());
+                }
+            });
+            ui.indent(("dwelling_households", occupancy.dwelling.id), |ui| {
+                for (household_id, head_name) in &occupancy.households {
+                    let index = indices.get(household_id).copied();
+                    let selected = index.is_some() && self.ui_state.selected_household == index;
+                    if ui.selectable_label(selected, format!("户主: {}", head_name)).clicked() {
+                        self.ui_state.selected_household = index;
+                    }
+                }
+            });
+        }
+    }
+
+    pub fn render_dwelling_dialog(&mut self, ctx: &egui::Context) {
+        let Some(form) = self.ui_state.dwelling_form.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut delete = false;
+        let mut cancel = false;
+
+        egui::Window::new(if form.id.is_some() { "编辑门牌" } else { "新增门牌" })
+            .id(egui::Id::new("dwelling_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("所在社区:");
+                    division_combo_boxes(ui, "dwelling_address", &mut form.address);
+                });
+                egui::Grid::new("dwelling_form_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("楼栋:");
+                    ui.add(egui::TextEdit::singleline(&mut form.building).id_source("dwelling_building").hint_text("如 3 或 A座"));
+                    ui.end_row();
+                    ui.label("单元:");
+                    ui.add(egui::TextEdit::singleline(&mut form.unit).id_source("dwelling_unit").hint_text("无单元可不填"));
+                    ui.end_row();
+                    ui.label("房号:");
+                    ui.add(egui::TextEdit::singleline(&mut form.room).id_source("dwelling_room"));
+                    ui.end_row();
+                    ui.label("核定人数:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut form.capacity).range(0..=99));
+                        ui.label(RichText::new("0 表示未核定").color(Color32::GRAY));
+                    });
+                    ui.end_row();
+                });
+
+                ui.separator();
+                match keyboard::dialog_key(ui) {
+                    Some(DialogKey::Submit) => save = true,
+                    Some(DialogKey::Cancel) => cancel = true,
+                    None => {}
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("保存").clicked() {
+                        save = true;
+                    }
+                    if form.id.is_some() && ui.button("删除").clicked() {
+                        delete = true;
+                    }
+                    if ui.button("取消").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if save {
+            let dwelling = form.to_dwelling();
+            match self.household_manager.save_dwelling(dwelling) {
+                Ok(()) => self.ui_state.dwelling_form = None,
+                Err(e) => self.report_error("保存门牌失败", &e),
+            }
+        } else if delete {
+            if let Some(id) = form.id {
+                match self.household_manager.remove_dwelling(&id) {
+                    Ok(()) => self.ui_state.dwelling_form = None,
+                    Err(e) => self.report_error("删除门牌失败", &e),
+                }
+            }
+        } else if cancel || !open {
+            self.ui_state.dwelling_form = None;
+        }
+    }
+
+    pub fn render_occupancy_report(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut filter = self.ui_state.occupancy_filter;
+
+        egui::Window::new("居住情况")
+            .id(egui::Id::new("occupancy_report"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([650.0, 450.0])
+            .show(ctx, |ui| {
+                let occupancy = match self.household_manager.occupancy() {
+                    Ok(occupancy) => occupancy,
+                    Err(e) => {
+                        ui.colored_label(Color32::from_rgb(200, 80, 40), format!("统计失败: {}", e));
+                        return;
+                    }
+                };
+
+                ui.horizontal(|ui| {
+                    for option in [OccupancyFilter::All, OccupancyFilter::Empty, OccupancyFilter::Overcrowded, OccupancyFilter::Shared] {
+                        let count = occupancy.iter().filter(|o| option.matches(o)).count();
+                        ui.selectable_value(&mut filter, option, format!("{} ({})", option, count));
+                    }
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("occupancy_grid")
+                        .striped(true)
+                        .num_columns(5)
+                        .show(ui, |ui| {
+                            for header in ["门牌", "户数", "在册人数", "核定人数", "状态"] {
+                                ui.label(RichText::new(header).strong());
+                            }
+                            ui.end_row();
+
+                            for occupancy in occupancy.iter().filter(|o| filter.matches(o)) {
+                                let status = occupancy.status();
+                                ui.label(occupancy.dwelling.to_string());
+                                ui.label(occupancy.households.len().to_string());
+                                ui.label(occupancy.members.to_string());
+                                ui.label(if occupancy.dwelling.capacity > 0 { occupancy.dwelling.capacity.to_string() } else { "-".to_string() });
+                                ui.colored_label(status_color(status), status.to_string());
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        self.ui_state.occupancy_filter = filter;
+        if !open {
+            self.ui_state.show_occupancy_report = false;
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::app::HouseholdApp;
+use crate::data::models::*;
+use crate::ui::dialogs::relationship_combo_box;
+use crate::ui::keyboard::{self, DialogKey};
+use eframe::egui;
+
+impl HouseholdApp {
+    pub fn open_head_change_dialog(&mut self) {
+        if !self.ensure_editable() {
+            return;
+        }
+        if let Some(selected) = self.ui_state.selected_household {
+            if let Ok(Some(household)) = self.get_household(selected) {
+                self.ui_state.head_change_form = Some(HeadChangeForm::new(&household));
+            }
+        }
+    }
+
+    pub fn render_head_change_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut form) = self.ui_state.head_change_form.take() else {
+            return;
+        };
+
+        let Ok(Some(household)) = self.household_manager.get_household_by_id(&form.household_id) else {
+            return;
+        };
+
+        let mut open = true;
+        let mut submit = false;
+        let mut cancel = false;
+
+        egui::Window::new("变更户主")
+            .id(egui::Id::new("head_change_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([550.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("当前户主:");
+                    ui.label(&household.head_name);
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("新户主:");
+                    let selected_text = form.new_head
+                        .and_then(|i| household.members.get(i))
+                        .map(|m| m.name.clone())
+                        .unwrap_or_else(|| "请选择".to_string());
+                    let previous = form.new_head;
+                    let response = egui::ComboBox::from_id_salt("head_change_new_head")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for (i, member) in household.members.iter().enumerate()
+                                .filter(|(_, m)| m.is_active() && !m.relationship.is_head())
+                            {
+                                ui.selectable_value(&mut form.new_head, Some(i), format!("{} ({})", member.name, member.relationship));
+                            }
+                        })
+                        .response;
+                    let options: Vec<_> = household.members.iter().enumerate()
+                        .filter(|(_, m)| m.is_active() && !m.relationship.is_head())
+                        .map(|(i, m)| (Some(i), m.name.clone()))
+                        .collect();
+                    keyboard::combo_keys(ui, &response, &mut form.new_head, &options);
+                    if form.new_head != previous {
+                        form.preview(&household);
+                    }
+                });
+
+                if form.new_head.is_some() {
+                    ui.add_space(5.0);
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.label("关系调整预览（可手动修改）:");
+                            egui::Grid::new("head_change_preview").striped(true).show(ui, |ui| {
+                                ui.label("成员");
+                                ui.label("原关系");
+                                ui.label("新关系");
+                                ui.end_row();
+
+                                for (i, member) in household.members.iter().enumerate() {
+                                    ui.label(&member.name);
+                                    ui.label(member.relationship.to_string());
+                                    if Some(i) == form.new_head {
+                                        ui.label(Relationship::HEAD.to_string());
+                                    } else {
+                                        relationship_combo_box(ui, format!("head_change_relationship_{}", i), &mut form.relationships[i]);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("原因:");
+                    ui.add(egui::TextEdit::singleline(&mut form.reason)
+                        .id_source("head_change_reason")
+                        .desired_width(300.0));
+                });
+
+                ui.add_space(10.0);
+                match keyboard::dialog_key(ui) {
+                    Some(DialogKey::Submit) => submit = form.new_head.is_some(),
+                    Some(DialogKey::Cancel) => cancel = true,
+                    None => {}
+                }
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(form.new_head.is_some(), egui::Button::new("确认变更")).clicked() {
+                        submit = true;
+                    }
+                    if ui.button("取消").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if submit {
+            if let Some(new_head) = form.new_head {
+                let today = chrono::Local::now().date_naive();
+                let result = self.record(format!("变更户主 {}", household.head_name), |manager| {
+                    manager.change_head(&form.household_id, new_head, &form.relationships, today, &form.reason)
+                });
+                match result {
+                    Ok(()) => {
+                        if let Err(e) = self.update_filtered_households() {
+                            self.report_error("刷新列表失败", &e);
+                        }
+                        return;
+                    }
+                    Err(e) => {
+                        self.report_error("变更户主失败", &e);
+                    }
+                }
+            }
+        }
+
+        if open && !cancel {
+            self.ui_state.head_change_form = Some(form);
+        }
+    }
+}
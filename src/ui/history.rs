@@ -0,0 +1,101 @@
+use crate::app::HouseholdApp;
+use crate::data::error::DataError;
+use crate::data::history::Command;
+use crate::data::manager::HouseholdManager;
+use crate::ui::shortcuts::ShortcutAction;
+use eframe::egui::*;
+
+impl HouseholdApp {
+    /// 执行修改户籍的操作，成功后记入撤销历史
+    pub fn record<T>(
+        &mut self,
+        description: impl Into<String>,
+        operation: impl FnOnce(&mut HouseholdManager) -> Result<T, DataError>,
+    ) -> Result<T, DataError> {
+        self.household_manager.start_recording();
+        let result = operation(&mut self.household_manager);
+        let changes = self.household_manager.recorded_changes();
+        let value = result?;
+        match changes {
+            Ok(changes) => self.ui_state.history.push(Command { description: description.into(), changes }),
+            Err(e) => self.report_error("无法记录撤销信息", &e),
+        }
+        Ok(value)
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(command) = self.ui_state.history.take_undo() {
+            if let Some(command) = self.replay(command, true) {
+                self.ui_state.history.undone(command);
+            }
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(command) = self.ui_state.history.take_redo() {
+            if let Some(command) = self.replay(command, false) {
+                self.ui_state.history.redone(command);
+            }
+        }
+    }
+
+    // 成功时返回更新了版本号的操作；被其他用户修改过的无法再撤销或重做，直接丢弃
+    fn replay(&mut self, command: Command, undo: bool) -> Option<Command> {
+        let action = if undo { "撤销" } else { "重做" };
+        let selected_id = self.selected_household_id();
+        let result = self.household_manager.replay_changes(&command.changes, undo);
+        if let Err(e) = self.reload_households(selected_id) {
+            self.report_error("刷新列表失败", &e);
+        }
+        match result {
+            Ok(changes) => {
+                self.notify(format!("已{}: {}", action, command.description));
+                Some(Command { changes, ..command })
+            }
+            Err(DataError::Conflict(_)) => {
+                self.report_warning(
+                    &format!("无法{}", action),
+                    format!("“{}”涉及的户籍已被其他用户修改，不能再{}", command.description, action),
+                );
+                None
+            }
+            Err(e) => {
+                self.report_error(&format!("{}失败", action), &e);
+                // 数据库暂时不可用等，放回原处以便重试
+                if undo {
+                    self.ui_state.history.redone(command);
+                } else {
+                    self.ui_state.history.undone(command);
+                }
+                None
+            }
+        }
+    }
+
+    pub fn render_edit_menu(&mut self, ui: &mut Ui) {
+        ui.menu_button(RichText::new("编辑").size(14.0), |ui| {
+            let next_undo = self.ui_state.history.next_undo().map(|d| format!("撤销 {}", d));
+            let button = Button::new(next_undo.as_deref().unwrap_or("撤销"))
+                .shortcut_text(self.shortcut_text(ui.ctx(), ShortcutAction::Undo));
+            if ui.add_enabled(next_undo.is_some(), button).clicked() {
+                self.undo();
+                ui.close();
+            }
+
+            let next_redo = self.ui_state.history.next_redo().map(|d| format!("重做 {}", d));
+            let button = Button::new(next_redo.as_deref().unwrap_or("重做"))
+                .shortcut_text(self.shortcut_text(ui.ctx(), ShortcutAction::Redo));
+            if ui.add_enabled(next_redo.is_some(), button).clicked() {
+                self.redo();
+                ui.close();
+            }
+
+            ui.separator();
+            let button = Button::new("快捷键…").shortcut_text(self.shortcut_text(ui.ctx(), ShortcutAction::ShowShortcuts));
+            if ui.add(button).clicked() {
+                self.ui_state.show_shortcuts = true;
+                ui.close();
+            }
+        });
+    }
+}
@@ -0,0 +1,122 @@
+use crate::data::codes::StableCode;
+use eframe::egui::*;
+
+/// 连续输入的字符在此时间内合并匹配，超过后重新开始
+const TYPE_AHEAD_TIMEOUT: f64 = 1.0;
+
+/// 对话框中按下的 Enter 或 Esc
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogKey {
+    Submit,
+    Cancel,
+}
+
+// 标记多行输入框，其中的 Enter 用于换行
+#[derive(Clone, Copy)]
+struct MultilineText;
+
+/// 多行输入框须调用此函数，否则其中的 Enter 会保存对话框
+pub fn mark_multiline(ui: &Ui, response: &Response) {
+    ui.data_mut(|d| d.insert_temp(response.id, MultilineText));
+}
+
+/// 读取 `ui` 所在表单的 Enter（保存）和 Esc（取消）。有控件获得焦点时由该控件所在的表单响应，
+/// 否则由最前面的对话框响应，没有打开的对话框时才轮到主界面中的表单；
+/// 下拉框展开、按钮或多行输入框获得焦点时按键留给控件自身
+pub fn dialog_key(ui: &Ui) -> Option<DialogKey> {
+    let ctx = ui.ctx();
+    if ctx.is_popup_open() {
+        return None;
+    }
+    let focused = ctx.memory(|m| m.focused()).and_then(|id| ctx.read_response(id));
+    let owns_keys = match &focused {
+        Some(widget) => widget.layer_id == ui.layer_id() && ui.clip_rect().intersects(widget.rect),
+        None => top_window(ctx).unwrap_or_else(LayerId::background) == ui.layer_id(),
+    };
+    if !owns_keys {
+        return None;
+    }
+    if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape)) {
+        return Some(DialogKey::Cancel);
+    }
+    let enter_submits = match &focused {
+        None => true,
+        Some(widget) => {
+            TextEdit::load_state(ctx, widget.id).is_some()
+                && ctx.data(|d| d.get_temp::<MultilineText>(widget.id)).is_none()
+        }
+    };
+    if enter_submits && ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Enter)) {
+        return Some(DialogKey::Submit);
+    }
+    None
+}
+
+// 最前面的对话框；关闭的窗口仍留在层级顺序中，须跳过
+fn top_window(ctx: &Context) -> Option<LayerId> {
+    ctx.memory(|m| {
+        m.layer_ids()
+            .filter(|layer| layer.order == Order::Middle && m.areas().is_visible(layer))
+            .last()
+    })
+}
+
+/// 下拉框的选项及用于键盘查找的文字（代码和名称）
+pub fn code_options<T: StableCode + PartialEq + std::fmt::Display>() -> Vec<(T, String)> {
+    T::ALL.iter().map(|&v| (v, format!("{} {}", v.code(), v))).collect()
+}
+
+/// 在 `ComboBox::show_ui` 之后调用：下拉框获得焦点时按 ↑↓ 切换选项，
+/// 输入文字跳到第一个名称或代码以此开头的选项。返回是否改变了选择
+pub fn combo_keys<T: PartialEq + Clone>(ui: &Ui, response: &Response, value: &mut T, options: &[(T, String)]) -> bool {
+    if !response.has_focus() || options.is_empty() {
+        return false;
+    }
+    // ↑↓ 用于切换选项，不移动焦点
+    ui.memory_mut(|m| m.set_focus_lock_filter(response.id, EventFilter { vertical_arrows: true, ..Default::default() }));
+
+    let (up, down, typed, now) = ui.input_mut(|i| {
+        let typed: String = i.events.iter()
+            .filter_map(|e| match e {
+                Event::Text(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        (
+            i.consume_key(Modifiers::NONE, Key::ArrowUp),
+            i.consume_key(Modifiers::NONE, Key::ArrowDown),
+            typed,
+            i.time,
+        )
+    });
+
+    let current = options.iter().position(|(v, _)| v == value);
+    let target = if down {
+        current.map_or(0, |c| (c + 1).min(options.len() - 1))
+    } else if up {
+        current.map_or(0, |c| c.saturating_sub(1))
+    } else if !typed.trim().is_empty() {
+        let id = response.id.with("type_ahead");
+        let (mut buffer, last) = ui.data(|d| d.get_temp::<(String, f64)>(id)).unwrap_or_default();
+        if now - last > TYPE_AHEAD_TIMEOUT {
+            buffer.clear();
+        }
+        buffer.push_str(&typed.trim().to_lowercase());
+        ui.data_mut(|d| d.insert_temp(id, (buffer.clone(), now)));
+        let found = options.iter().position(|(_, text)| {
+            text.split_whitespace().any(|word| word.to_lowercase().starts_with(&buffer))
+        });
+        match found {
+            Some(found) => found,
+            None => return false,
+        }
+    } else {
+        return false;
+    };
+
+    if current == Some(target) {
+        return false;
+    }
+    *value = options[target].0.clone();
+    true
+}
@@ -1,6 +1,19 @@
+pub mod address;
+pub mod attachments;
 pub mod components;
+pub mod conflict;
 pub mod dialogs;
+pub mod drafts;
+pub mod duplicates;
+pub mod dwellings;
+pub mod head_change;
+pub mod history;
+pub mod keyboard;
+pub mod notifications;
 pub mod panels;
+pub mod privacy;
+pub mod shortcuts;
 pub mod styles;
-
-
+pub mod tabs;
+pub mod transfer;
+pub mod vital;
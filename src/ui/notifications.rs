@@ -0,0 +1,201 @@
+use crate::app::HouseholdApp;
+use crate::data::error::DataError;
+use chrono::NaiveDateTime;
+use eframe::egui::{self, Color32, RichText};
+use std::time::{Duration, Instant};
+
+// 提示在屏幕上停留的时间，错误停留更久
+const WARNING_DURATION: Duration = Duration::from_secs(5);
+const ERROR_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl NotificationLevel {
+    fn color(&self) -> Color32 {
+        match self {
+            NotificationLevel::Info => Color32::from_rgb(40, 120, 200),
+            NotificationLevel::Warning => Color32::from_rgb(200, 140, 20),
+            NotificationLevel::Error => Color32::from_rgb(200, 50, 50),
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        match self {
+            NotificationLevel::Error => ERROR_DURATION,
+            _ => WARNING_DURATION,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Toast {
+    level: NotificationLevel,
+    message: String,
+    shown_at: Instant,
+}
+
+/// 错误日志条目，保留到程序退出
+#[derive(Debug, Clone)]
+pub struct ErrorLogEntry {
+    pub time: NaiveDateTime,
+    pub level: NotificationLevel,
+    pub context: String,
+    pub message: String,
+}
+
+/// 右下角的非阻塞提示以及错误日志
+#[derive(Debug, Default)]
+pub struct Notifications {
+    toasts: Vec<Toast>,
+    log: Vec<ErrorLogEntry>,
+    pub show_log: bool,
+}
+
+impl Notifications {
+    pub fn push(&mut self, level: NotificationLevel, context: &str, message: String) {
+        let text = if context.is_empty() { message.clone() } else { format!("{}: {}", context, message) };
+
+        // 每帧重复出现的同一错误只保留一条提示
+        if let Some(toast) = self.toasts.iter_mut().find(|t| t.message == text) {
+            toast.shown_at = Instant::now();
+            return;
+        }
+        self.toasts.push(Toast { level, message: text, shown_at: Instant::now() });
+
+        if level != NotificationLevel::Info {
+            self.log.push(ErrorLogEntry {
+                time: chrono::Local::now().naive_local(),
+                level,
+                context: context.to_string(),
+                message,
+            });
+        }
+    }
+
+    pub fn log(&self) -> &[ErrorLogEntry] {
+        &self.log
+    }
+
+    pub fn clear_log(&mut self) {
+        self.log.clear();
+    }
+}
+
+impl HouseholdApp {
+    /// 报告数据层错误：用户可修正的错误作为警告，其余作为错误
+    pub fn report_error(&mut self, context: &str, error: &DataError) {
+        let level = if error.is_user_error() { NotificationLevel::Warning } else { NotificationLevel::Error };
+        self.ui_state.notifications.push(level, context, error.to_string());
+    }
+
+    pub fn report_warning(&mut self, context: &str, message: impl Into<String>) {
+        self.ui_state.notifications.push(NotificationLevel::Warning, context, message.into());
+    }
+
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.ui_state.notifications.push(NotificationLevel::Info, "", message.into());
+    }
+
+    pub fn render_notifications(&mut self, ctx: &egui::Context) {
+        let notifications = &mut self.ui_state.notifications;
+        notifications.toasts.retain(|t| t.shown_at.elapsed() < t.level.duration());
+
+        if !notifications.toasts.is_empty() {
+            let mut dismissed = None;
+            egui::Area::new(egui::Id::new("notifications"))
+                .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+                .order(egui::Order::Foreground)
+                .show(ctx, |ui| {
+                    ui.set_max_width(360.0);
+                    for (i, toast) in notifications.toasts.iter().enumerate() {
+                        let response = egui::Frame::popup(ui.style())
+                            .stroke(egui::Stroke::new(1.0, toast.level.color()))
+                            .show(ui, |ui| {
+                                ui.horizontal_wrapped(|ui| {
+                                    ui.colored_label(toast.level.color(), "●");
+                                    ui.label(&toast.message);
+                                });
+                            })
+                            .response
+                            .interact(egui::Sense::click());
+                        if response.on_hover_text("点击关闭").clicked() {
+                            dismissed = Some(i);
+                        }
+                    }
+                });
+            if let Some(i) = dismissed {
+                notifications.toasts.remove(i);
+            }
+            // 到期后需要重绘才能消失
+            ctx.request_repaint_after(Duration::from_millis(500));
+        }
+
+        if notifications.show_log {
+            let mut open = true;
+            let mut clear = false;
+            egui::Window::new("错误日志")
+                .id(egui::Id::new("error_log"))
+                .open(&mut open)
+                .resizable(true)
+                .default_size([600.0, 300.0])
+                .show(ctx, |ui| {
+                    if notifications.log.is_empty() {
+                        ui.label("暂无错误记录");
+                        return;
+                    }
+                    if ui.button("清空").clicked() {
+                        clear = true;
+                    }
+                    ui.separator();
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        egui::Grid::new("error_log_grid").striped(true).show(ui, |ui| {
+                            for entry in notifications.log.iter().rev() {
+                                ui.label(entry.time.format("%H:%M:%S").to_string());
+                                ui.label(RichText::new(&entry.context).color(entry.level.color()));
+                                ui.label(&entry.message);
+                                ui.end_row();
+                            }
+                        });
+                    });
+                });
+            if clear {
+                notifications.clear_log();
+            }
+            notifications.show_log = open;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_messages_show_once() {
+        let mut notifications = Notifications::default();
+        notifications.push(NotificationLevel::Error, "保存失败", "数据库错误".into());
+        notifications.push(NotificationLevel::Error, "保存失败", "数据库错误".into());
+        assert_eq!(notifications.toasts.len(), 1);
+        assert_eq!(notifications.toasts[0].message, "保存失败: 数据库错误");
+        assert_eq!(notifications.log().len(), 1);
+    }
+
+    #[test]
+    fn info_is_not_logged() {
+        let mut notifications = Notifications::default();
+        notifications.push(NotificationLevel::Info, "", "已保存".into());
+        notifications.push(NotificationLevel::Warning, "导入", "第3行身份证号无效".into());
+        assert_eq!(notifications.toasts.len(), 2);
+        assert_eq!(notifications.log().len(), 1);
+        assert_eq!(notifications.log()[0].context, "导入");
+        assert_eq!(notifications.log()[0].message, "第3行身份证号无效");
+
+        notifications.clear_log();
+        assert!(notifications.log().is_empty());
+    }
+}
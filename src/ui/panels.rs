@@ -1,5 +1,9 @@
 use crate::app::HouseholdApp;
+use crate::data::attachments::{Attachment, AttachmentOwner};
+use crate::data::documents;
+use crate::data::privacy::{self, SensitiveField};
 use crate::data::models::*;
+use crate::ui::shortcuts::ShortcutAction;
 use eframe::egui::{self, *};
 
 impl HouseholdApp {
@@ -9,45 +13,122 @@ impl HouseholdApp {
             
             ui.separator();
             
-            if ui.button(RichText::new("新增户籍").size(14.0)).clicked() {
-                self.ui_state.edit_form.clear();
-                self.ui_state.show_add_dialog = true;
-            }
+            self.render_edit_menu(ui);
+            
+            // 查看员只能查看
+            let editable = !self.read_only();
+            ui.add_enabled_ui(editable, |ui| {
+                let hint = self.shortcut_text(ui.ctx(), ShortcutAction::NewHousehold);
+                if ui.button(RichText::new("新增户籍").size(14.0)).on_hover_text(hint).clicked() {
+                    self.open_add_dialog();
+                }
             
-            if ui.button(RichText::new("编辑户籍").size(14.0)).clicked() {
-                if let Some(selected) = self.ui_state.selected_household {
-                    if let Ok(Some(household)) = self.get_household(selected) {
-                        self.ui_state.edit_form = HouseholdForm::from_household(&household);
-                        self.ui_state.show_edit_dialog = true;
+                let hint = self.shortcut_text(ui.ctx(), ShortcutAction::EditHousehold);
+                if ui.button(RichText::new("编辑户籍").size(14.0)).on_hover_text(hint).clicked() {
+                    self.open_edit_dialog();
+                }
+            
+                let hint = self.shortcut_text(ui.ctx(), ShortcutAction::DeleteHousehold);
+                if ui.button(RichText::new("删除户籍").size(14.0)).on_hover_text(hint).clicked() {
+                    self.delete_selected_household();
+                }
+            
+                if ui.button(RichText::new("变更户主").size(14.0)).clicked() {
+                    self.open_head_change_dialog();
+                }
+            
+                ui.menu_button(RichText::new("迁移办理").size(14.0), |ui| {
+                    for kind in [TransferKind::MoveMember, TransferKind::Split, TransferKind::Merge, TransferKind::MigrateOut] {
+                        if ui.button(kind.to_string()).clicked() {
+                            self.open_transfer_dialog(kind);
+                            ui.close();
+                        }
                     }
+                });
+            
+                ui.menu_button(RichText::new("户籍登记").size(14.0), |ui| {
+                    for kind in [EventType::Birth, EventType::Death, EventType::Marriage, EventType::Divorce] {
+                        if ui.button(kind.to_string()).clicked() {
+                            self.open_vital_event_dialog(kind);
+                            ui.close();
+                        }
+                    }
+                });
+            
+                if ui.button(RichText::new("查重").size(14.0)).clicked() {
+                    self.open_duplicate_scan();
                 }
+            });
+            
+            if ui.button(RichText::new("地区统计").size(14.0)).clicked() {
+                self.ui_state.show_division_stats = true;
             }
             
-            if ui.button(RichText::new("删除户籍").size(14.0)).clicked() {
-                if let Some(selected) = self.ui_state.selected_household {
-                    if let Ok(Some(household)) = self.get_household(selected) {
-                        if let Err(e) = self.remove_household(&household.id) {
-                            eprintln!("Failed to remove household: {}", e);
-                        }
+            match self.household_manager.address_review_count() {
+                Ok(0) => {}
+                Ok(count) => {
+                    let text = RichText::new(format!("地址待核对 ({})", count))
+                        .size(14.0)
+                        .color(Color32::from_rgb(200, 80, 40));
+                    if ui.add_enabled(editable, Button::new(text)).clicked() {
+                        self.open_address_review();
                     }
                 }
+                Err(e) => self.report_error("读取待核对地址失败", &e),
+            }
+            
+            let decode_error_count = self.household_manager.decode_errors().len();
+            if decode_error_count > 0 {
+                let text = RichText::new(format!("⚠ {} 条记录无法读取", decode_error_count))
+                    .size(14.0)
+                    .color(Color32::from_rgb(200, 80, 40));
+                if ui.button(text).clicked() {
+                    self.ui_state.show_decode_errors = true;
+                }
+            }
+            
+            let log_count = self.ui_state.notifications.log().len();
+            let log_label = if log_count > 0 { format!("错误日志 ({})", log_count) } else { "错误日志".to_string() };
+            if ui.button(RichText::new(log_label).size(14.0)).clicked() {
+                self.ui_state.notifications.show_log = true;
+            }
+            
+            if ui.button(RichText::new("操作记录").size(14.0)).clicked() {
+                self.open_audit_log();
+            }
+            
+            if !editable {
+                ui.label(RichText::new(format!("{}（只读）", self.settings.role)).size(14.0).color(Color32::GRAY));
             }
             
             ui.separator();
             
             ui.label("搜索:");
-            if ui.add(egui::TextEdit::singleline(&mut self.ui_state.search_query).id_source("search_box")).changed() {
+            let response = ui.add(egui::TextEdit::singleline(&mut self.ui_state.search_query).id_source("search_box"));
+            if std::mem::take(&mut self.ui_state.focus_search) {
+                response.request_focus();
+            }
+            if response.changed() {
                 if let Err(e) = self.update_filtered_households() {
-                    eprintln!("Failed to update filtered households: {}", e);
+                    self.report_error("刷新列表失败", &e);
                 }
             }
             
             ui.separator();
             
             // 显示统计信息
-            if let Ok(stats) = self.household_manager.get_statistics() {
-                ui.label(format!("总户数: {}", stats.total_households));
-                ui.label(format!("总人数: {}", stats.total_members));
+            match self.household_manager.get_statistics() {
+                Ok(stats) => {
+                    ui.label(format!("总户数: {}", stats.total_households));
+                    ui.label(format!("在册人数: {}", stats.total_members));
+                    if stats.inactive_members > 0 {
+                        ui.label(format!("注销/迁出: {}", stats.inactive_members));
+                    }
+                    if stats.non_mainland_members > 0 {
+                        ui.label(format!("境外证件: {}", stats.non_mainland_members));
+                    }
+                }
+                Err(e) => self.report_error("统计失败", &e),
             }
         });
     }
@@ -58,51 +139,84 @@ impl HouseholdApp {
             ui.separator();
             
             egui::ScrollArea::vertical().show(ui, |ui| {
-                if let Ok(households) = self.get_households() {
-                    for (i, household) in households.iter().enumerate() {
-                        if self.ui_state.filtered_households.contains(&i) {
-                            let is_selected = self.ui_state.selected_household == Some(i);
-                            
-                            let response = ui.selectable_label(
-                                is_selected,
-                                format!("{} - {}", household.head_name, household.household_type)
-                            );
-                            
-                            if response.clicked() {
-                                self.ui_state.selected_household = Some(i);
-                            }
-                            
-                            ui.separator();
+                let households = match self.get_households() {
+                    Ok(households) => households,
+                    Err(e) => {
+                        ui.colored_label(Color32::from_rgb(200, 80, 40), format!("户籍列表加载失败: {}", e));
+                        self.report_error("加载户籍失败", &e);
+                        return;
+                    }
+                };
+                for (i, household) in households.iter().enumerate() {
+                    if self.ui_state.filtered_households.contains(&i) {
+                        let is_selected = self.ui_state.selected_household == Some(i);
+                        
+                        let response = ui.selectable_label(
+                            is_selected,
+                            format!("{} - {}", household.head_name, household.household_type)
+                        );
+                        
+                        if response.clicked() {
+                            self.ui_state.selected_household = Some(i);
+                        }
+                        if is_selected && std::mem::take(&mut self.ui_state.scroll_to_selected) {
+                            response.scroll_to_me(None);
                         }
+                        
+                        ui.separator();
                     }
                 }
             });
         });
     }
 
-    pub fn render_household_details_panel(&mut self, ui: &mut Ui) {
-        if let Some(selected) = self.ui_state.selected_household {
-            if let Ok(Some(household)) = self.get_household(selected) {
-                ui.vertical(|ui| {
-                    ui.heading(RichText::new("户籍详细信息").size(18.0));
-                    ui.separator();
-                    
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        self.render_basic_info(ui, &household);
-                        ui.add_space(10.0);
-                        self.render_members_info(ui, &household);
+    /// 标签页中显示一户的详细信息
+    pub fn render_household_details(&mut self, ui: &mut Ui, household: &Household) {
+        let (events, events_error) = match self.household_manager.get_events(&household.id) {
+            Ok(events) => (events, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        };
+        let dwellings = self.household_manager.dwellings().unwrap_or_default();
+        let (attachments, attachments_error) = match self.household_manager.attachments(
+            &household.id,
+            household.members.iter().map(|m| m.id_number.as_str()),
+        ) {
+            Ok(attachments) => (attachments, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        };
+        ui.vertical(|ui| {
+            ui.heading(RichText::new("户籍详细信息").size(18.0));
+            ui.separator();
+            
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                self.render_basic_info(ui, household, &dwellings);
+                ui.add_space(10.0);
+                let household_attachments: Vec<&Attachment> = attachments.iter()
+                    .filter(|a| a.owner == AttachmentOwner::Household(household.id))
+                    .collect();
+                if !household_attachments.is_empty() {
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.heading(RichText::new("户籍材料").size(16.0));
+                            self.render_attachment_thumbnails(ui, &household_attachments);
+                        });
                     });
-                });
-            }
-        } else {
-            ui.vertical_centered(|ui| {
-                ui.add_space(200.0);
-                ui.heading(RichText::new("请选择一个户籍查看详细信息").size(16.0));
+                    ui.add_space(10.0);
+                }
+                if let Some(error) = &attachments_error {
+                    ui.colored_label(Color32::from_rgb(200, 80, 40), format!("附件无法读取: {}", error));
+                }
+                self.render_members_info(ui, household, &events, &attachments);
+                ui.add_space(10.0);
+                if let Some(error) = &events_error {
+                    ui.colored_label(Color32::from_rgb(200, 80, 40), format!("变动记录无法读取: {}", error));
+                }
+                self.render_events_info(ui, &events);
             });
-        }
+        });
     }
     
-    fn render_basic_info(&self, ui: &mut Ui, household: &Household) {
+    fn render_basic_info(&mut self, ui: &mut Ui, household: &Household, dwellings: &[Dwelling]) {
         ui.group(|ui| {
             ui.vertical(|ui| {
                 ui.heading(RichText::new("基本信息").size(16.0));
@@ -113,8 +227,8 @@ impl HouseholdApp {
                 });
                 
                 ui.horizontal(|ui| {
-                    ui.label(RichText::new("身份证号:").size(14.0));
-                    ui.label(RichText::new(&household.id_number).size(14.0));
+                    ui.label(RichText::new(format!("{}:", household.head_document_type)).size(14.0));
+                    self.render_sensitive_value(ui, household, "户主", SensitiveField::IdNumber, &household.id_number, 14.0);
                 });
                 
                 ui.horizontal(|ui| {
@@ -124,14 +238,21 @@ impl HouseholdApp {
                 
                 ui.horizontal(|ui| {
                     ui.label(RichText::new("联系电话:").size(14.0));
-                    ui.label(RichText::new(&household.phone).size(14.0));
+                    self.render_sensitive_value(ui, household, "户主", SensitiveField::Phone, &household.phone, 14.0);
                 });
                 
                 ui.horizontal(|ui| {
                     ui.label(RichText::new("家庭地址:").size(14.0));
-                    ui.label(RichText::new(&household.address).size(14.0));
+                    ui.label(RichText::new(household.address.to_string()).size(14.0));
                 });
                 
+                if let Some(dwelling) = household.dwelling_id.and_then(|id| dwellings.iter().find(|d| d.id == id)) {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new("门牌:").size(14.0));
+                        ui.label(RichText::new(dwelling.to_string()).size(14.0));
+                    });
+                }
+                
                 ui.horizontal(|ui| {
                     ui.label(RichText::new("登记日期:").size(14.0));
                     ui.label(RichText::new(&household.registration_date.format("%Y-%m-%d %H:%M:%S").to_string()).size(14.0));
@@ -140,7 +261,7 @@ impl HouseholdApp {
         });
     }
     
-    fn render_members_info(&self, ui: &mut Ui, household: &Household) {
+    fn render_members_info(&mut self, ui: &mut Ui, household: &Household, events: &[HouseholdEvent], attachments: &[Attachment]) {
         ui.group(|ui| {
             ui.vertical(|ui| {
                 ui.heading(RichText::new("家庭成员").size(16.0));
@@ -148,7 +269,12 @@ impl HouseholdApp {
                 for (i, member) in household.members.iter().enumerate() {
                     ui.group(|ui| {
                         ui.vertical(|ui| {
-                            ui.heading(RichText::new(&format!("成员 {}", i + 1)).size(14.0));
+                            ui.horizontal(|ui| {
+                                ui.heading(RichText::new(&format!("成员 {}", i + 1)).size(14.0));
+                                if !member.is_active() {
+                                    ui.colored_label(Color32::GRAY, RichText::new(member.status.to_string()).size(12.0));
+                                }
+                            });
                             
                             ui.horizontal(|ui| {
                                 ui.label(RichText::new("姓名:").size(12.0));
@@ -166,8 +292,15 @@ impl HouseholdApp {
                             });
                             
                             ui.horizontal(|ui| {
-                                ui.label(RichText::new("身份证号:").size(12.0));
-                                ui.label(RichText::new(&member.id_number).size(12.0));
+                                ui.label(RichText::new(format!("{}:", member.document_type)).size(12.0));
+                                let owner = format!("成员{}", member.name);
+                                self.render_sensitive_value(ui, household, &owner, SensitiveField::IdNumber, &member.id_number, 12.0);
+                                
+                                if member.nationality != documents::CHINA {
+                                    ui.separator();
+                                    ui.label(RichText::new("国籍:").size(12.0));
+                                    ui.label(RichText::new(documents::describe_nationality(&member.nationality)).size(12.0));
+                                }
                             });
                             
                             ui.horizontal(|ui| {
@@ -183,7 +316,43 @@ impl HouseholdApp {
                             ui.horizontal(|ui| {
                                 ui.label(RichText::new("职业:").size(12.0));
                                 ui.label(RichText::new(&member.occupation).size(12.0));
+                                
+                                ui.separator();
+                                
+                                ui.label(RichText::new("婚姻状况:").size(12.0));
+                                ui.label(RichText::new(member.marital_status.to_string()).size(12.0));
+                                
+                                if let Some(death_date) = member.death_date {
+                                    ui.separator();
+                                    ui.label(RichText::new("死亡日期:").size(12.0));
+                                    ui.label(RichText::new(death_date.format("%Y-%m-%d").to_string()).size(12.0));
+                                }
                             });
+                            
+                            // 证件照等成员附件
+                            let member_attachments: Vec<&Attachment> = attachments.iter()
+                                .filter(|a| !member.id_number.is_empty() && a.owner == AttachmentOwner::Member(member.id_number.clone()))
+                                .collect();
+                            if !member_attachments.is_empty() {
+                                self.render_attachment_thumbnails(ui, &member_attachments);
+                            }
+                            
+                            // 成员事件时间线
+                            let member_events: Vec<&HouseholdEvent> = events.iter()
+                                .filter(|e| e.member_id_number == member.id_number)
+                                .collect();
+                            if !member_events.is_empty() {
+                                ui.collapsing(RichText::new(format!("事件记录 ({})", member_events.len())).size(12.0), |ui| {
+                                    for event in member_events {
+                                        ui.label(RichText::new(format!(
+                                            "{}  {}{}",
+                                            event.event_date.format("%Y-%m-%d"),
+                                            event.event_type,
+                                            if event.reason.is_empty() { String::new() } else { format!(" - {}", event.reason) }
+                                        )).size(12.0));
+                                    }
+                                });
+                            }
                         });
                     });
                     ui.add_space(5.0);
@@ -191,4 +360,34 @@ impl HouseholdApp {
             });
         });
     }
+    
+    fn render_events_info(&self, ui: &mut Ui, events: &[HouseholdEvent]) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.heading(RichText::new("变动记录").size(16.0));
+                
+                if events.is_empty() {
+                    ui.label(RichText::new("暂无变动记录").size(12.0));
+                }
+                
+                for event in events {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(event.event_date.format("%Y-%m-%d").to_string()).size(12.0));
+                        ui.separator();
+                        ui.label(RichText::new(event.event_type.to_string()).size(12.0));
+                        ui.separator();
+                        ui.label(RichText::new(format!("{} ({})", event.member_name, privacy::mask(SensitiveField::IdNumber, &event.member_id_number))).size(12.0));
+                        if !event.destination.is_empty() {
+                            ui.separator();
+                            ui.label(RichText::new(format!("迁往: {}", event.destination)).size(12.0));
+                        }
+                        if !event.reason.is_empty() {
+                            ui.separator();
+                            ui.label(RichText::new(format!("原因: {}", event.reason)).size(12.0));
+                        }
+                    });
+                }
+            });
+        });
+    }
 }
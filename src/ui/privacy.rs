@@ -0,0 +1,117 @@
+use crate::app::HouseholdApp;
+use crate::data::audit::{AuditAction, AuditEntry};
+use crate::data::models::Household;
+use crate::data::privacy::{self, SensitiveField};
+use eframe::egui::{self, *};
+
+/// 操作记录窗口显示的条数
+const AUDIT_LOG_LIMIT: usize = 500;
+
+impl HouseholdApp {
+    /// 查看员不能修改数据
+    pub fn read_only(&self) -> bool {
+        !self.settings.role.can_edit()
+    }
+
+    /// 修改数据的入口先调用此检查，查看员时提示并返回 `false`
+    pub fn ensure_editable(&mut self) -> bool {
+        if self.read_only() {
+            self.report_warning("无法修改", format!("当前角色为{}，不能修改户籍数据", self.settings.role));
+            return false;
+        }
+        true
+    }
+
+    /// 记录一条操作，写入失败时提示并返回 `false`
+    pub fn record_audit(&mut self, action: AuditAction, household_id: Option<uuid::Uuid>, detail: String) -> bool {
+        let entry = AuditEntry::new(&self.settings.operator(), action, household_id, detail);
+        match self.household_manager.record_audit(&entry) {
+            Ok(()) => true,
+            Err(e) => {
+                self.report_error("无法写入操作记录", &e);
+                false
+            }
+        }
+    }
+
+    /// 显示遮蔽后的证件号码或电话。编辑员点击“显示”后本次运行期间显示完整内容，并记入操作记录；
+    /// 写入操作记录失败时不显示
+    pub fn render_sensitive_value(
+        &mut self,
+        ui: &mut Ui,
+        household: &Household,
+        owner: &str,
+        field: SensitiveField,
+        value: &str,
+        size: f32,
+    ) {
+        let masked = privacy::mask(field, value);
+        let key = (household.id, field, value.to_string());
+        let revealed = self.ui_state.revealed.contains(&key);
+        ui.label(RichText::new(if revealed { value } else { &masked }).size(size));
+        if masked.is_empty() {
+            return;
+        }
+        if revealed {
+            if ui.small_button("隐藏").clicked() {
+                self.ui_state.revealed.remove(&key);
+            }
+        } else if self.settings.role.can_reveal()
+            && ui.small_button("显示").on_hover_text("显示完整内容，将记入操作记录").clicked()
+        {
+            let detail = format!("{}户 {}的{}", household.head_name, owner, field);
+            if self.record_audit(AuditAction::Reveal, Some(household.id), detail) {
+                self.ui_state.revealed.insert(key);
+            }
+        }
+    }
+
+    pub fn open_audit_log(&mut self) {
+        match self.household_manager.audit_log(AUDIT_LOG_LIMIT) {
+            Ok(entries) => self.ui_state.audit_entries = Some(entries),
+            Err(e) => self.report_error("读取操作记录失败", &e),
+        }
+    }
+
+    pub fn render_audit_log(&mut self, ctx: &egui::Context) {
+        let Some(entries) = &self.ui_state.audit_entries else {
+            return;
+        };
+        let mut open = true;
+        let mut refresh = false;
+        egui::Window::new("操作记录")
+            .id(egui::Id::new("audit_log"))
+            .open(&mut open)
+            .resizable(true)
+            .default_size([700.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("最近 {} 条查看完整信息和导出的记录", entries.len()));
+                    if ui.button("刷新").clicked() {
+                        refresh = true;
+                    }
+                });
+                ui.separator();
+                if entries.is_empty() {
+                    ui.label("暂无记录");
+                    return;
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("audit_log_grid").striped(true).show(ui, |ui| {
+                        for entry in entries {
+                            ui.label(entry.recorded_at.format("%Y-%m-%d %H:%M:%S").to_string());
+                            ui.label(&entry.operator);
+                            ui.label(entry.action.to_string());
+                            ui.label(&entry.detail);
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        if !open {
+            self.ui_state.audit_entries = None;
+        } else if refresh {
+            self.open_audit_log();
+        }
+    }
+}
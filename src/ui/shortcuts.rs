@@ -0,0 +1,400 @@
+use crate::app::HouseholdApp;
+use crate::config::{self, Settings};
+use eframe::egui::{self, *};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 可设置快捷键的全局操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    NewHousehold,
+    EditHousehold,
+    DeleteHousehold,
+    FocusSearch,
+    NextHousehold,
+    PreviousHousehold,
+    Undo,
+    Redo,
+    ShowShortcuts,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 9] = [
+        ShortcutAction::NewHousehold,
+        ShortcutAction::EditHousehold,
+        ShortcutAction::DeleteHousehold,
+        ShortcutAction::FocusSearch,
+        ShortcutAction::NextHousehold,
+        ShortcutAction::PreviousHousehold,
+        ShortcutAction::Undo,
+        ShortcutAction::Redo,
+        ShortcutAction::ShowShortcuts,
+    ];
+
+    pub fn default_shortcut(self) -> KeyboardShortcut {
+        let command = |key| KeyboardShortcut::new(Modifiers::COMMAND, key);
+        match self {
+            ShortcutAction::NewHousehold => command(Key::N),
+            ShortcutAction::EditHousehold => command(Key::E),
+            ShortcutAction::DeleteHousehold => command(Key::Delete),
+            ShortcutAction::FocusSearch => command(Key::F),
+            ShortcutAction::NextHousehold => command(Key::ArrowDown),
+            ShortcutAction::PreviousHousehold => command(Key::ArrowUp),
+            ShortcutAction::Undo => command(Key::Z),
+            ShortcutAction::Redo => command(Key::Y),
+            ShortcutAction::ShowShortcuts => KeyboardShortcut::new(Modifiers::NONE, Key::F1),
+        }
+    }
+
+    // 输入框获得焦点时仍然生效的操作；撤销、删除和上下切换与输入框自身的按键冲突
+    fn works_while_typing(self) -> bool {
+        matches!(
+            self,
+            ShortcutAction::NewHousehold | ShortcutAction::EditHousehold | ShortcutAction::FocusSearch | ShortcutAction::ShowShortcuts
+        )
+    }
+}
+
+impl std::fmt::Display for ShortcutAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ShortcutAction::NewHousehold => "新增户籍",
+            ShortcutAction::EditHousehold => "编辑户籍",
+            ShortcutAction::DeleteHousehold => "删除户籍",
+            ShortcutAction::FocusSearch => "搜索",
+            ShortcutAction::NextHousehold => "下一户",
+            ShortcutAction::PreviousHousehold => "上一户",
+            ShortcutAction::Undo => "撤销",
+            ShortcutAction::Redo => "重做",
+            ShortcutAction::ShowShortcuts => "快捷键一览",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// 解析设置中如 `Ctrl+Shift+N` 的写法，Ctrl 在 macOS 上对应 Command 键
+pub fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = Key::from_name(parts.pop()?)?;
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" | "command" => modifiers.command = true,
+            "shift" => modifiers.shift = true,
+            "alt" | "option" => modifiers.alt = true,
+            _ => return None,
+        }
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+/// 保存到设置中的写法，与 `parse_shortcut` 对应
+pub fn shortcut_setting(shortcut: &KeyboardShortcut) -> String {
+    shortcut.format(&ModifierNames::NAMES, false)
+}
+
+/// 当前生效的快捷键
+#[derive(Debug, Clone)]
+pub struct Shortcuts {
+    bindings: BTreeMap<ShortcutAction, KeyboardShortcut>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self {
+            bindings: ShortcutAction::ALL.iter().map(|&a| (a, a.default_shortcut())).collect(),
+        }
+    }
+}
+
+impl Shortcuts {
+    /// 以设置覆盖默认按键，无法识别的写法沿用默认按键并返回说明
+    pub fn from_settings(settings: &Settings) -> (Self, Vec<String>) {
+        let mut shortcuts = Self::default();
+        let mut problems = Vec::new();
+        for (action, text) in &settings.shortcuts {
+            match parse_shortcut(text) {
+                Some(shortcut) => {
+                    shortcuts.bindings.insert(*action, shortcut);
+                }
+                None => problems.push(format!("“{}”的快捷键 {} 无法识别，已使用默认按键", action, text)),
+            }
+        }
+        (shortcuts, problems)
+    }
+
+    pub fn get(&self, action: ShortcutAction) -> KeyboardShortcut {
+        self.bindings.get(&action).copied().unwrap_or_else(|| action.default_shortcut())
+    }
+
+    /// 使用该按键的其他操作
+    fn used_by(&self, shortcut: &KeyboardShortcut, except: ShortcutAction) -> Option<ShortcutAction> {
+        self.bindings.iter()
+            .find(|(action, bound)| **action != except && *bound == shortcut)
+            .map(|(action, _)| *action)
+    }
+}
+
+// 快捷键一览中列出的固定按键
+const FIXED_KEYS: &[(&str, &str)] = &[
+    ("Enter", "保存当前对话框（多行输入框中为换行）"),
+    ("Esc", "取消并关闭当前对话框"),
+    ("Tab / Shift+Tab", "切换到下一个/上一个输入项"),
+    ("↑ / ↓", "在获得焦点的下拉框中切换选项"),
+    ("字母或数字", "在获得焦点的下拉框中跳到以此开头的选项（可输入代码）"),
+];
+
+impl HouseholdApp {
+    /// 按设置中的按键执行全局操作。输入框获得焦点时只响应带 Ctrl/Alt 的新增、编辑、搜索等操作，
+    /// 确认框打开时不响应新增、编辑和删除，当前标签页正在编辑时不响应删除
+    pub fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.ui_state.capturing_shortcut.is_some() {
+            return;
+        }
+        let typing = ctx.wants_keyboard_input();
+        let locked = self.tabs_locked();
+        let editing = self.ui_state.show_add_dialog || self.ui_state.show_edit_dialog;
+        for action in ShortcutAction::ALL {
+            let shortcut = self.ui_state.shortcuts.get(action);
+            if typing && !(action.works_while_typing() && (shortcut.modifiers.command || shortcut.modifiers.alt)) {
+                continue;
+            }
+            let changes_tabs = matches!(
+                action,
+                ShortcutAction::NewHousehold | ShortcutAction::EditHousehold | ShortcutAction::DeleteHousehold
+            );
+            if (locked && changes_tabs) || (editing && action == ShortcutAction::DeleteHousehold) {
+                continue;
+            }
+            if ctx.input_mut(|i| i.consume_shortcut(&shortcut)) {
+                self.run_shortcut(action);
+                break;
+            }
+        }
+    }
+
+    fn run_shortcut(&mut self, action: ShortcutAction) {
+        match action {
+            ShortcutAction::NewHousehold => self.open_add_dialog(),
+            ShortcutAction::EditHousehold => self.open_edit_dialog(),
+            ShortcutAction::DeleteHousehold => self.delete_selected_household(),
+            ShortcutAction::FocusSearch => {
+                self.ui_state.navigation = crate::app::NavigationMode::Households;
+                self.ui_state.focus_search = true;
+            }
+            ShortcutAction::NextHousehold => self.select_adjacent_household(true),
+            ShortcutAction::PreviousHousehold => self.select_adjacent_household(false),
+            ShortcutAction::Undo => self.undo(),
+            ShortcutAction::Redo => self.redo(),
+            ShortcutAction::ShowShortcuts => self.ui_state.show_shortcuts = true,
+        }
+    }
+
+    // 在列表显示的户籍中移动选中项，没有选中时从头或尾开始
+    fn select_adjacent_household(&mut self, forward: bool) {
+        let mut visible = self.ui_state.filtered_households.clone();
+        visible.sort_unstable();
+        let Some(&last) = visible.last() else {
+            return;
+        };
+        let position = self.ui_state.selected_household.and_then(|s| visible.iter().position(|&i| i == s));
+        let next = match (position, forward) {
+            (Some(p), true) => visible.get(p + 1).copied().unwrap_or(last),
+            (Some(p), false) => visible[p.saturating_sub(1)],
+            (None, true) => visible[0],
+            (None, false) => last,
+        };
+        self.ui_state.selected_household = Some(next);
+        self.ui_state.scroll_to_selected = true;
+    }
+
+    /// 菜单中显示的按键
+    pub fn shortcut_text(&self, ctx: &egui::Context, action: ShortcutAction) -> String {
+        ctx.format_shortcut(&self.ui_state.shortcuts.get(action))
+    }
+
+    pub fn render_shortcuts_dialog(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut capture = None;
+        let mut reset = None;
+
+        egui::Window::new("快捷键")
+            .id(egui::Id::new("shortcuts_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("快捷键保存在 {} 中，点击按键可重新设置。", config::SETTINGS_FILE));
+                ui.add_space(5.0);
+                egui::Grid::new("shortcuts_grid").striped(true).num_columns(3).show(ui, |ui| {
+                    for action in ShortcutAction::ALL {
+                        ui.label(action.to_string());
+                        let text = if self.ui_state.capturing_shortcut == Some(action) {
+                            "请按下新的按键…（Esc 取消）".to_string()
+                        } else {
+                            self.shortcut_text(ctx, action)
+                        };
+                        if ui.button(text).clicked() {
+                            capture = Some(action);
+                        }
+                        if self.ui_state.shortcuts.get(action) != action.default_shortcut()
+                            && ui.small_button("恢复默认").clicked()
+                        {
+                            reset = Some(action);
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                egui::Grid::new("fixed_keys_grid").striped(true).num_columns(2).show(ui, |ui| {
+                    for (keys, description) in FIXED_KEYS {
+                        ui.label(*keys);
+                        ui.label(*description);
+                        ui.end_row();
+                    }
+                });
+            });
+
+        // 用键盘点击按钮的 Enter 或空格不能当作新按键，所以只读取之前帧开始等待的
+        let mut captured = false;
+        if let Some(action) = self.ui_state.capturing_shortcut {
+            match captured_shortcut(ctx) {
+                Some(Captured::Cancel) => {
+                    self.ui_state.capturing_shortcut = None;
+                    captured = true;
+                }
+                Some(Captured::Shortcut(shortcut)) => {
+                    self.ui_state.capturing_shortcut = None;
+                    self.bind_shortcut(action, shortcut);
+                    captured = true;
+                }
+                None => {}
+            }
+        }
+        if let Some(action) = capture.filter(|_| !captured) {
+            self.ui_state.capturing_shortcut = Some(action);
+        }
+        if let Some(action) = reset {
+            self.bind_shortcut(action, action.default_shortcut());
+        }
+        if !open {
+            self.ui_state.show_shortcuts = false;
+            self.ui_state.capturing_shortcut = None;
+        }
+    }
+
+    // 设置新按键并写入设置文件；已被其他操作使用时不修改
+    fn bind_shortcut(&mut self, action: ShortcutAction, shortcut: KeyboardShortcut) {
+        if let Some(other) = self.ui_state.shortcuts.used_by(&shortcut, action) {
+            self.report_warning("快捷键未修改", format!("{} 已用于“{}”", shortcut_setting(&shortcut), other));
+            return;
+        }
+        self.ui_state.shortcuts.bindings.insert(action, shortcut);
+        if shortcut == action.default_shortcut() {
+            self.settings.shortcuts.remove(&action);
+        } else {
+            self.settings.shortcuts.insert(action, shortcut_setting(&shortcut));
+        }
+        if let Err(e) = self.settings.save(config::SETTINGS_FILE) {
+            self.report_warning("设置未保存", e);
+        }
+    }
+}
+
+enum Captured {
+    Shortcut(KeyboardShortcut),
+    Cancel,
+}
+
+// 读取本帧按下的按键组合，并阻止其触发其他操作
+fn captured_shortcut(ctx: &egui::Context) -> Option<Captured> {
+    ctx.input_mut(|input| {
+        let index = input.events.iter().position(|e| matches!(e, Event::Key { pressed: true, repeat: false, .. }))?;
+        let Event::Key { key, modifiers, .. } = input.events.remove(index) else {
+            return None;
+        };
+        if key == Key::Escape && modifiers.is_none() {
+            return Some(Captured::Cancel);
+        }
+        // Ctrl 与 macOS 的 Command 统一记为 Ctrl，与解析设置时一致
+        let modifiers = Modifiers { alt: modifiers.alt, shift: modifiers.shift, command: modifiers.command, ..Modifiers::NONE };
+        Some(Captured::Shortcut(KeyboardShortcut::new(modifiers, key)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_settings_text() {
+        assert_eq!(parse_shortcut("Ctrl+Shift+N"), Some(KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::N)));
+        assert_eq!(parse_shortcut("alt + F2"), Some(KeyboardShortcut::new(Modifiers::ALT, Key::F2)));
+        assert_eq!(parse_shortcut("Cmd+Delete"), Some(KeyboardShortcut::new(Modifiers::COMMAND, Key::Delete)));
+        assert_eq!(parse_shortcut("F1"), Some(KeyboardShortcut::new(Modifiers::NONE, Key::F1)));
+        assert_eq!(parse_shortcut("Super+N"), None);
+        assert_eq!(parse_shortcut("Ctrl+"), None);
+        assert_eq!(parse_shortcut(""), None);
+    }
+
+    #[test]
+    fn saved_text_parses_back() {
+        for action in ShortcutAction::ALL {
+            let shortcut = action.default_shortcut();
+            assert_eq!(parse_shortcut(&shortcut_setting(&shortcut)), Some(shortcut), "{}", action);
+        }
+        let shortcut = KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT | Modifiers::ALT, Key::S);
+        assert_eq!(parse_shortcut(&shortcut_setting(&shortcut)), Some(shortcut));
+    }
+
+    #[test]
+    fn settings_override_defaults() {
+        let settings: Settings = serde_json::from_str(
+            r#"{ "shortcuts": { "new_household": "Ctrl+Shift+N", "undo": "Hyper+Z" } }"#
+        ).unwrap();
+        let (shortcuts, problems) = Shortcuts::from_settings(&settings);
+
+        assert_eq!(shortcuts.get(ShortcutAction::NewHousehold), KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::N));
+        assert_eq!(shortcuts.get(ShortcutAction::Undo), ShortcutAction::Undo.default_shortcut());
+        assert_eq!(shortcuts.get(ShortcutAction::Redo), ShortcutAction::Redo.default_shortcut());
+        assert_eq!(problems, ["“撤销”的快捷键 Hyper+Z 无法识别，已使用默认按键"]);
+    }
+
+    #[test]
+    fn defaults_do_not_overlap() {
+        let shortcuts = Shortcuts::default();
+        for action in ShortcutAction::ALL {
+            assert_eq!(shortcuts.used_by(&action.default_shortcut(), action), None, "{}", action);
+        }
+        let ctrl_n = ShortcutAction::NewHousehold.default_shortcut();
+        assert_eq!(shortcuts.used_by(&ctrl_n, ShortcutAction::Undo), Some(ShortcutAction::NewHousehold));
+    }
+
+    // 在一帧中发送按键，返回此时读取到的按键组合
+    fn capture(key: Key, modifiers: Modifiers) -> Option<Captured> {
+        let ctx = egui::Context::default();
+        let input = RawInput {
+            events: vec![Event::Key { key, physical_key: None, pressed: true, repeat: false, modifiers }],
+            ..Default::default()
+        };
+        let mut captured = None;
+        let _ = ctx.run(input, |ctx| captured = captured_shortcut(ctx));
+        captured
+    }
+
+    #[test]
+    fn captures_pressed_keys() {
+        let ctrl = Modifiers { ctrl: true, command: true, ..Modifiers::NONE };
+        match capture(Key::K, ctrl | Modifiers::SHIFT) {
+            Some(Captured::Shortcut(shortcut)) => {
+                assert_eq!(shortcut, KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::K));
+                assert_eq!(shortcut_setting(&shortcut), "Ctrl+Shift+K");
+            }
+            _ => panic!("应读取到 Ctrl+Shift+K"),
+        }
+        assert!(matches!(capture(Key::Escape, Modifiers::NONE), Some(Captured::Cancel)));
+        assert!(matches!(capture(Key::Escape, Modifiers::SHIFT), Some(Captured::Shortcut(_))));
+    }
+}
@@ -0,0 +1,649 @@
+use crate::app::HouseholdApp;
+use crate::data::models::*;
+use crate::data::privacy;
+use crate::ui::conflict::{household_fields, member_summary, DIFFERENCE_COLOR};
+use crate::ui::dialogs::FormFeedback;
+use crate::ui::drafts::FormTracker;
+use eframe::egui::{self, *};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// 退出时保存标签页使用的键
+pub const TABS_STORAGE_KEY: &str = "household_tabs";
+
+/// 中央区域的标签页，查看或编辑一户
+#[derive(Debug, Default)]
+pub struct HouseholdTab {
+    /// 查看或编辑的户籍，新增户籍的标签页为空
+    pub household_id: Option<Uuid>,
+    pub editing: bool,
+    // 不在前台时暂存的表单，前台标签页的表单在 `UiState::edit_form` 等字段中
+    parked: Option<ParkedForm>,
+}
+
+#[derive(Debug)]
+struct ParkedForm {
+    form: HouseholdForm,
+    feedback: FormFeedback,
+    tracker: FormTracker,
+}
+
+/// 退出时保存的标签页。编辑中的标签页恢复为查看，未保存的修改由草稿恢复
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SavedTabs {
+    households: Vec<Uuid>,
+    active: Option<usize>,
+    compare: Option<usize>,
+}
+
+// 拖动标签页时携带的标签页序号
+struct DraggedTab(usize);
+
+impl HouseholdApp {
+    /// 保存冲突、疑似重复或放弃修改的确认框打开时不能切换或关闭标签页
+    pub fn tabs_locked(&self) -> bool {
+        self.ui_state.save_conflict.is_some()
+            || !self.ui_state.pending_save_warnings.is_empty()
+            || self.ui_state.confirm_discard
+    }
+
+    pub fn activate_tab(&mut self, index: usize) {
+        if self.ui_state.active_tab == Some(index) || index >= self.ui_state.tabs.len() || self.tabs_locked() {
+            return;
+        }
+        self.park_active_form();
+        self.ui_state.active_tab = Some(index);
+        if self.ui_state.compare_tab == Some(index) {
+            self.ui_state.compare_tab = None;
+        }
+        let tab = &mut self.ui_state.tabs[index];
+        let household_id = tab.household_id;
+        if let Some(parked) = tab.parked.take() {
+            self.ui_state.edit_form = parked.form;
+            self.ui_state.form_feedback = parked.feedback;
+            self.ui_state.form_tracker = parked.tracker;
+            self.ui_state.editing_household = household_id;
+            self.ui_state.show_add_dialog = household_id.is_none();
+            self.ui_state.show_edit_dialog = household_id.is_some();
+        }
+        if let Some(id) = household_id {
+            self.select_household(id);
+        }
+    }
+
+    // 当前标签页的表单移入标签页暂存，未保存的修改先存为草稿
+    fn park_active_form(&mut self) {
+        let Some(index) = self.ui_state.active_tab else {
+            return;
+        };
+        if !self.ui_state.tabs.get(index).is_some_and(|t| t.editing) {
+            return;
+        }
+        self.flush_draft();
+        self.ui_state.tabs[index].parked = Some(ParkedForm {
+            form: std::mem::take(&mut self.ui_state.edit_form),
+            feedback: std::mem::take(&mut self.ui_state.form_feedback),
+            tracker: std::mem::take(&mut self.ui_state.form_tracker),
+        });
+        self.ui_state.editing_household = None;
+        self.ui_state.show_add_dialog = false;
+        self.ui_state.show_edit_dialog = false;
+    }
+
+    // 在列表中选中该户，迁移、登记等操作以选中的户籍为准
+    fn select_household(&mut self, household_id: Uuid) {
+        if let Ok(households) = self.get_households() {
+            self.ui_state.selected_household = households.iter().position(|h| h.id == household_id);
+            self.ui_state.scroll_to_selected = true;
+        }
+        self.ui_state.shown_selection = Some(household_id);
+    }
+
+    /// 在标签页中查看一户：已打开的切换过去，否则替换当前的查看标签页，`new_tab` 时总是新开
+    pub fn open_household_tab(&mut self, household_id: Uuid, new_tab: bool) {
+        if self.tabs_locked() {
+            return;
+        }
+        if let Some(index) = self.ui_state.tabs.iter().position(|t| t.household_id == Some(household_id)) {
+            self.activate_tab(index);
+            return;
+        }
+        let replace = self.ui_state.active_tab.filter(|&i| !new_tab && !self.ui_state.tabs[i].editing);
+        match replace {
+            Some(index) => {
+                self.ui_state.tabs[index].household_id = Some(household_id);
+                self.select_household(household_id);
+            }
+            None => {
+                self.ui_state.tabs.push(HouseholdTab { household_id: Some(household_id), ..Default::default() });
+                self.activate_tab(self.ui_state.tabs.len() - 1);
+            }
+        }
+    }
+
+    /// 在标签页中打开表单，`household_id` 为空时新增户籍。该户已在编辑时切换到其标签页并返回 false，
+    /// 否则调用方随后记录表单的初始内容
+    pub fn open_form_tab(&mut self, household_id: Option<Uuid>, form: HouseholdForm) -> bool {
+        if self.tabs_locked() {
+            return false;
+        }
+        let existing = household_id
+            .and_then(|id| self.ui_state.tabs.iter().position(|t| t.household_id == Some(id)));
+        let index = match existing {
+            Some(index) => {
+                self.activate_tab(index);
+                if self.ui_state.tabs[index].editing {
+                    return false;
+                }
+                index
+            }
+            None => {
+                self.ui_state.tabs.push(HouseholdTab { household_id, ..Default::default() });
+                let index = self.ui_state.tabs.len() - 1;
+                self.activate_tab(index);
+                index
+            }
+        };
+        self.ui_state.tabs[index].editing = true;
+        self.ui_state.edit_form = form;
+        self.ui_state.form_feedback = FormFeedback::default();
+        self.ui_state.editing_household = household_id;
+        self.ui_state.show_add_dialog = household_id.is_none();
+        self.ui_state.show_edit_dialog = household_id.is_some();
+        true
+    }
+
+    /// 表单保存或放弃后调用：新增户籍的标签页关闭，编辑的标签页回到查看
+    pub(crate) fn finish_form_tab(&mut self) {
+        let Some(index) = self.ui_state.active_tab else {
+            return;
+        };
+        let close = std::mem::take(&mut self.ui_state.close_tab_after_form)
+            || self.ui_state.tabs[index].household_id.is_none();
+        self.ui_state.tabs[index].editing = false;
+        if close {
+            self.remove_tab(index);
+        }
+    }
+
+    /// 关闭标签页，正在编辑且有修改时先请用户确认
+    pub fn close_tab(&mut self, index: usize) {
+        if self.tabs_locked() {
+            return;
+        }
+        if self.ui_state.tabs[index].editing {
+            self.activate_tab(index);
+            self.ui_state.close_tab_after_form = true;
+            self.cancel_household_form();
+        } else {
+            self.remove_tab(index);
+        }
+    }
+
+    fn remove_tab(&mut self, index: usize) {
+        self.ui_state.tabs.remove(index);
+        let shift = |i: usize| if i > index { i - 1 } else { i };
+        self.ui_state.compare_tab = self.ui_state.compare_tab.filter(|&i| i != index).map(shift);
+        match self.ui_state.active_tab {
+            Some(active) if active == index => {
+                self.ui_state.active_tab = None;
+                if self.ui_state.tabs.is_empty() {
+                    self.ui_state.selected_household = None;
+                    self.ui_state.shown_selection = None;
+                } else {
+                    self.activate_tab(index.min(self.ui_state.tabs.len() - 1));
+                }
+            }
+            Some(active) => self.ui_state.active_tab = Some(shift(active)),
+            None => {}
+        }
+        if self.ui_state.compare_tab == self.ui_state.active_tab {
+            self.ui_state.compare_tab = None;
+        }
+    }
+
+    fn compare_with(&mut self, index: usize) {
+        if index < self.ui_state.tabs.len() && self.ui_state.active_tab.is_some_and(|active| active != index) {
+            self.ui_state.compare_tab = Some(index);
+        }
+    }
+
+    pub fn saved_tabs(&self) -> SavedTabs {
+        // 新增户籍的标签页不保存，序号按保存下来的标签页计算
+        let tabs = &self.ui_state.tabs;
+        let position = |index: Option<usize>| {
+            let index = index?;
+            tabs.get(index)?.household_id?;
+            Some(tabs[..index].iter().filter(|t| t.household_id.is_some()).count())
+        };
+        SavedTabs {
+            households: tabs.iter().filter_map(|t| t.household_id).collect(),
+            active: position(self.ui_state.active_tab),
+            compare: position(self.ui_state.compare_tab),
+        }
+    }
+
+    /// 启动时恢复上次打开的标签页，已被删除的户籍跳过
+    pub fn restore_tabs(&mut self, saved: SavedTabs) {
+        let Ok(households) = self.get_households() else {
+            return;
+        };
+        let mut restored = Vec::new();
+        let (mut active, mut compare) = (None, None);
+        for (i, id) in saved.households.into_iter().enumerate() {
+            if !households.iter().any(|h| h.id == id) {
+                continue;
+            }
+            if saved.active == Some(i) {
+                active = Some(restored.len());
+            }
+            if saved.compare == Some(i) {
+                compare = Some(restored.len());
+            }
+            restored.push(HouseholdTab { household_id: Some(id), ..Default::default() });
+        }
+        self.ui_state.tabs = restored;
+        let active = active.or(if self.ui_state.tabs.is_empty() { None } else { Some(0) });
+        if let Some(active) = active {
+            self.activate_tab(active);
+            if let Some(compare) = compare {
+                self.compare_with(compare);
+            }
+        }
+    }
+
+    // 列表、门牌树或快捷键改选户籍后在标签页中显示
+    fn sync_selection(&mut self) {
+        let selected = self.selected_household_id();
+        if selected != self.ui_state.shown_selection {
+            self.ui_state.shown_selection = selected;
+            if let Some(id) = selected {
+                self.open_household_tab(id, false);
+            }
+        }
+    }
+
+    /// 中央区域：标签栏及当前标签页，拖动标签页到右侧可与当前标签页并排比较
+    pub fn render_workspace(&mut self, ui: &mut Ui) {
+        self.sync_selection();
+        if self.ui_state.tabs.is_empty() {
+            ui.vertical_centered(|ui| {
+                ui.add_space(200.0);
+                ui.heading(RichText::new("请选择一个户籍查看详细信息").size(16.0));
+            });
+            return;
+        }
+
+        self.render_tab_bar(ui);
+        ui.separator();
+
+        let dragging = egui::DragAndDrop::has_payload_of_type::<DraggedTab>(ui.ctx());
+        match (self.ui_state.active_tab, self.ui_state.compare_tab) {
+            (Some(active), Some(other)) => self.render_comparison(ui, active, other),
+            (Some(_), None) if dragging => {
+                ui.columns(2, |columns| {
+                    self.render_active_tab(&mut columns[0]);
+                    self.render_split_drop_zone(&mut columns[1]);
+                });
+            }
+            _ => self.render_active_tab(ui),
+        }
+    }
+
+    fn render_tab_bar(&mut self, ui: &mut Ui) {
+        let mut activate = None;
+        let mut close = None;
+        let mut compare = None;
+        let mut open_new = None;
+
+        egui::ScrollArea::horizontal().id_salt("household_tab_bar").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for i in 0..self.ui_state.tabs.len() {
+                    let title = self.tab_title(i);
+                    let selected = self.ui_state.active_tab == Some(i) || self.ui_state.compare_tab == Some(i);
+                    let response = ui.dnd_drag_source(Id::new(("household_tab", i)), DraggedTab(i), |ui| {
+                        ui.selectable_label(selected, title)
+                    }).inner;
+                    let response = response.on_hover_text("拖到右侧与当前标签页并排比较，中键关闭");
+                    if response.clicked() {
+                        activate = Some(i);
+                    }
+                    if response.middle_clicked() {
+                        close = Some(i);
+                    }
+                    let is_active = self.ui_state.active_tab == Some(i);
+                    let household_id = self.ui_state.tabs[i].household_id;
+                    response.context_menu(|ui| {
+                        if ui.add_enabled(!is_active, egui::Button::new("与当前标签页并排比较")).clicked() {
+                            compare = Some(i);
+                            ui.close();
+                        }
+                        if let Some(id) = household_id {
+                            if ui.button("在新标签页中打开").clicked() {
+                                open_new = Some(id);
+                                ui.close();
+                            }
+                        }
+                        if ui.button("关闭").clicked() {
+                            close = Some(i);
+                            ui.close();
+                        }
+                    });
+                    if ui.small_button("×").on_hover_text("关闭").clicked() {
+                        close = Some(i);
+                    }
+                    ui.separator();
+                }
+            });
+        });
+
+        if let Some(i) = activate {
+            self.activate_tab(i);
+        }
+        if let Some(i) = compare {
+            self.compare_with(i);
+        }
+        if let Some(id) = open_new {
+            self.open_household_tab(id, true);
+        }
+        if let Some(i) = close {
+            self.close_tab(i);
+        }
+    }
+
+    // 标签页标题：户主姓名，编辑中的加“编辑:”前缀，有未保存的修改时加 *
+    fn tab_title(&mut self, index: usize) -> String {
+        let tab = &self.ui_state.tabs[index];
+        let form = match &tab.parked {
+            Some(parked) => Some((&parked.form, parked.tracker.is_dirty(&parked.form))),
+            None if tab.editing && self.ui_state.active_tab == Some(index) => {
+                Some((&self.ui_state.edit_form, self.form_is_dirty()))
+            }
+            None => None,
+        };
+        match (form, tab.household_id) {
+            (Some((form, dirty)), household_id) => {
+                let name = if form.head_name.trim().is_empty() { "未填写户主" } else { form.head_name.trim() };
+                let action = if household_id.is_some() { "编辑" } else { "新增" };
+                format!("{}: {}{}", action, name, if dirty { " *" } else { "" })
+            }
+            (None, Some(id)) => match self.household_manager.get_household_by_id(&id) {
+                Ok(Some(household)) => household.head_name,
+                _ => "(已删除)".to_string(),
+            },
+            (None, None) => "新增户籍".to_string(),
+        }
+    }
+
+    fn render_active_tab(&mut self, ui: &mut Ui) {
+        let Some(index) = self.ui_state.active_tab else {
+            return;
+        };
+        let tab = &self.ui_state.tabs[index];
+        if tab.editing {
+            let is_add = tab.household_id.is_none();
+            ui.heading(RichText::new(if is_add { "新增户籍" } else { "编辑户籍" }).size(18.0));
+            ui.separator();
+            self.render_household_form(ui, is_add);
+            return;
+        }
+        let Some(id) = tab.household_id else {
+            return;
+        };
+        match self.household_manager.get_household_by_id(&id) {
+            Ok(Some(household)) => self.render_household_details(ui, &household),
+            Ok(None) => {
+                ui.vertical_centered(|ui| {
+                    ui.add_space(200.0);
+                    ui.heading(RichText::new("该户籍已被删除").size(16.0));
+                });
+            }
+            Err(e) => {
+                ui.colored_label(DIFFERENCE_COLOR, format!("户籍加载失败: {}", e));
+            }
+        }
+    }
+
+    fn render_split_drop_zone(&mut self, ui: &mut Ui) {
+        let (_, dropped) = ui.dnd_drop_zone::<DraggedTab, ()>(Frame::group(ui.style()), |ui| {
+            ui.set_min_size(ui.available_size());
+            ui.centered_and_justified(|ui| {
+                ui.label(RichText::new("拖到此处与当前标签页并排比较").size(16.0));
+            });
+        });
+        if let Some(tab) = dropped {
+            self.compare_with(tab.0);
+        }
+    }
+
+    // 标签页当前的内容，编辑中的标签页按表单内容比较
+    fn tab_household(&mut self, index: usize) -> Option<Household> {
+        let tab = &self.ui_state.tabs[index];
+        let form = match &tab.parked {
+            Some(parked) => Some(&parked.form),
+            None if tab.editing && self.ui_state.active_tab == Some(index) => Some(&self.ui_state.edit_form),
+            None => None,
+        };
+        match form {
+            Some(form) => form.to_household(tab.household_id),
+            None => {
+                let id = tab.household_id?;
+                self.household_manager.get_household_by_id(&id).ok().flatten()
+            }
+        }
+    }
+
+    fn render_comparison(&mut self, ui: &mut Ui, active: usize, other: usize) {
+        let titles = [self.tab_title(active), self.tab_title(other)];
+        let households = [self.tab_household(active), self.tab_household(other)];
+        let fields: Vec<Vec<(&str, String)>> = households.iter()
+            .map(|h| h.as_ref().map(household_fields).unwrap_or_default())
+            .collect();
+        let members: Vec<Vec<String>> = households.iter()
+            .map(|h| h.as_ref().map(|h| h.members.iter().map(member_summary).collect()).unwrap_or_default())
+            .collect();
+        // 按完整内容比较，显示遮蔽后的证件号码和电话
+        let masked: Vec<Option<Household>> = households.iter().map(|h| h.as_ref().map(privacy::mask_household)).collect();
+        let shown_fields: Vec<Vec<(&str, String)>> = masked.iter()
+            .map(|h| h.as_ref().map(household_fields).unwrap_or_default())
+            .collect();
+        let shown_members: Vec<Vec<String>> = masked.iter()
+            .map(|h| h.as_ref().map(|h| h.members.iter().map(member_summary).collect()).unwrap_or_default())
+            .collect();
+
+        let mut close = false;
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("并排比较").size(16.0));
+            ui.label(RichText::new("不同之处以红色标出").color(Color32::GRAY));
+            if ui.button("取消并排").clicked() {
+                close = true;
+            }
+        });
+        ui.separator();
+
+        ui.columns(2, |columns| {
+            for (side, ui) in columns.iter_mut().enumerate() {
+                let opposite = 1 - side;
+                ui.heading(RichText::new(&titles[side]).size(16.0));
+                if households[side].is_none() {
+                    ui.label("该户籍已被删除或表单尚未填写完整");
+                    continue;
+                }
+                egui::ScrollArea::vertical().id_salt(("comparison", side)).show(ui, |ui| {
+                    egui::Grid::new(("comparison_grid", side)).striped(true).num_columns(2).show(ui, |ui| {
+                        for ((label, value), (_, shown)) in fields[side].iter().zip(&shown_fields[side]) {
+                            let differs = fields[opposite].iter().find(|(l, _)| l == label).map(|(_, v)| v) != Some(value);
+                            ui.label(*label);
+                            difference_label(ui, shown, differs);
+                            ui.end_row();
+                        }
+                        for (i, (member, shown)) in members[side].iter().zip(&shown_members[side]).enumerate() {
+                            ui.label(format!("成员{}", i + 1));
+                            difference_label(ui, shown, !members[opposite].contains(member));
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+        });
+
+        if close {
+            self.ui_state.compare_tab = None;
+        }
+    }
+}
+
+fn difference_label(ui: &mut Ui, text: &str, differs: bool) {
+    if differs {
+        ui.label(RichText::new(text).color(DIFFERENCE_COLOR));
+    } else {
+        ui.label(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::UiState;
+    use crate::config::Settings;
+    use crate::data::manager::HouseholdManager;
+
+    // 使用临时数据库的程序状态，测试结束时删除数据库文件
+    struct TestApp {
+        app: HouseholdApp,
+        path: String,
+    }
+
+    impl Drop for TestApp {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.path, suffix));
+            }
+        }
+    }
+
+    // 含三户的程序状态及这三户的ID
+    fn app() -> (TestApp, Vec<Uuid>) {
+        let path = std::env::temp_dir().join(format!("household-tabs-{}.db", Uuid::new_v4())).to_string_lossy().into_owned();
+        let mut app = HouseholdApp {
+            household_manager: HouseholdManager::open(&path).unwrap(),
+            ui_state: UiState::default(),
+            settings: Settings::default(),
+        };
+        let mut ids = Vec::new();
+        for (i, name) in ["张三", "李四", "王五"].iter().enumerate() {
+            let mut form = HouseholdForm::default();
+            form.clear();
+            form.members[0].name = name.to_string();
+            form.members[0].id_number = format!("11010119800101{:04}", i);
+            form.members[0].relationship = Relationship::HEAD;
+            let household = form.to_household(None).unwrap();
+            ids.push(household.id);
+            app.household_manager.add_household(household).unwrap();
+        }
+        (TestApp { app, path }, ids)
+    }
+
+    fn tab_ids(app: &HouseholdApp) -> Vec<Option<Uuid>> {
+        app.ui_state.tabs.iter().map(|t| t.household_id).collect()
+    }
+
+    #[test]
+    fn viewing_replaces_the_active_tab() {
+        let (mut test, ids) = app();
+        let app = &mut test.app;
+        app.open_household_tab(ids[0], false);
+        app.open_household_tab(ids[1], false);
+        assert_eq!(tab_ids(app), [Some(ids[1])]);
+
+        app.open_household_tab(ids[2], true);
+        assert_eq!(tab_ids(app), [Some(ids[1]), Some(ids[2])]);
+        assert_eq!(app.ui_state.active_tab, Some(1));
+
+        // 已打开的户切换过去
+        app.open_household_tab(ids[1], false);
+        assert_eq!(app.ui_state.active_tab, Some(0));
+        assert_eq!(app.ui_state.tabs.len(), 2);
+    }
+
+    #[test]
+    fn edit_forms_are_kept_per_tab() {
+        let (mut test, ids) = app();
+        let app = &mut test.app;
+        let household = app.household_manager.get_household_by_id(&ids[0]).unwrap().unwrap();
+        let form = HouseholdForm::from_household(&household);
+        assert!(app.open_form_tab(Some(ids[0]), form));
+        app.track_form();
+        app.ui_state.edit_form.phone = "13900139000".to_string();
+
+        // 查看其他户时另开标签页，编辑中的表单暂存并存为草稿
+        app.open_household_tab(ids[1], false);
+        assert_eq!(tab_ids(app), [Some(ids[0]), Some(ids[1])]);
+        assert!(!app.ui_state.show_edit_dialog);
+        let operator = app.settings.operator();
+        assert_eq!(app.household_manager.drafts(&operator).unwrap().len(), 1);
+
+        // 同一户不会打开第二个表单
+        assert!(!app.open_form_tab(Some(ids[0]), HouseholdForm::default()));
+        assert_eq!(app.ui_state.active_tab, Some(0));
+        assert!(app.ui_state.show_edit_dialog);
+        assert_eq!(app.ui_state.edit_form.phone, "13900139000");
+        assert!(app.form_is_dirty());
+
+        // 有修改时关闭标签页须先确认，确认期间不能切换
+        app.close_tab(0);
+        assert!(app.ui_state.confirm_discard);
+        app.activate_tab(1);
+        assert_eq!(app.ui_state.active_tab, Some(0));
+        app.close_household_form();
+        assert_eq!(tab_ids(app), [Some(ids[1])]);
+        assert!(app.household_manager.drafts(&operator).unwrap().is_empty());
+    }
+
+    #[test]
+    fn closing_tabs_keeps_comparison() {
+        let (mut test, ids) = app();
+        let app = &mut test.app;
+        for id in &ids {
+            app.open_household_tab(*id, true);
+        }
+        app.compare_with(0);
+        assert_eq!((app.ui_state.active_tab, app.ui_state.compare_tab), (Some(2), Some(0)));
+
+        app.close_tab(1);
+        assert_eq!(tab_ids(app), [Some(ids[0]), Some(ids[2])]);
+        assert_eq!((app.ui_state.active_tab, app.ui_state.compare_tab), (Some(1), Some(0)));
+
+        // 关闭当前标签页后切换到相邻的，不再与自身比较
+        app.close_tab(1);
+        assert_eq!((app.ui_state.active_tab, app.ui_state.compare_tab), (Some(0), None));
+        app.close_tab(0);
+        assert!(app.ui_state.tabs.is_empty());
+        assert_eq!(app.ui_state.selected_household, None);
+    }
+
+    #[test]
+    fn saved_tabs_skip_new_and_deleted_households() {
+        let (mut test, ids) = app();
+        let app = &mut test.app;
+        app.open_household_tab(ids[0], true);
+        let mut form = HouseholdForm::default();
+        form.clear();
+        assert!(app.open_form_tab(None, form));
+        app.open_household_tab(ids[1], true);
+        app.open_household_tab(ids[2], true);
+        app.compare_with(2);
+
+        let saved = app.saved_tabs();
+        assert_eq!(saved.households, ids);
+        assert_eq!((saved.active, saved.compare), (Some(2), Some(1)));
+
+        let saved: SavedTabs = serde_json::from_value(serde_json::to_value(&saved).unwrap()).unwrap();
+        let revision = app.household_manager.get_household_by_id(&ids[1]).unwrap().unwrap().revision;
+        app.household_manager.remove_household(&ids[1], revision).unwrap();
+        app.ui_state = UiState::default();
+        app.restore_tabs(saved);
+        assert_eq!(tab_ids(app), [Some(ids[0]), Some(ids[2])]);
+        assert_eq!((app.ui_state.active_tab, app.ui_state.compare_tab), (Some(1), None));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::app::HouseholdApp;
+use crate::data::models::*;
+use crate::ui::address::division_combo_boxes;
+use crate::ui::dialogs::relationship_combo_box;
+use crate::ui::keyboard::{self, DialogKey};
+use eframe::egui;
+
+impl HouseholdApp {
+    pub fn open_transfer_dialog(&mut self, kind: TransferKind) {
+        if !self.ensure_editable() {
+            return;
+        }
+        if let Some(selected) = self.ui_state.selected_household {
+            if let Ok(Some(household)) = self.get_household(selected) {
+                self.ui_state.transfer_form = Some(TransferForm::new(kind, &household));
+            }
+        }
+    }
+
+    pub fn render_transfer_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut form) = self.ui_state.transfer_form.take() else {
+            return;
+        };
+
+        let households = self.get_households().unwrap_or_default();
+        let Some(source) = form.source.and_then(|id| households.iter().find(|h| h.id == id)).cloned() else {
+            return;
+        };
+
+        let mut open = true;
+        let mut submit = false;
+        let mut cancel = false;
+
+        egui::Window::new(format!("迁移办理 - {}", form.kind))
+            .id(egui::Id::new("transfer_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([600.0, 450.0])
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("原户:");
+                        ui.label(format!("{} - {}", source.head_name, source.address));
+                    });
+
+                    // 迁入户 / 合并到的户
+                    if matches!(form.kind, TransferKind::MoveMember | TransferKind::Merge) {
+                        ui.horizontal(|ui| {
+                            ui.label(if form.kind == TransferKind::Merge { "合并到:" } else { "迁入户:" });
+                            let selected_text = form.target
+                                .and_then(|id| households.iter().find(|h| h.id == id))
+                                .map(|h| h.head_name.clone())
+                                .unwrap_or_else(|| "请选择".to_string());
+                            let response = egui::ComboBox::from_id_salt("transfer_target")
+                                .selected_text(selected_text)
+                                .show_ui(ui, |ui| {
+                                    for household in households.iter().filter(|h| h.id != source.id) {
+                                        ui.selectable_value(
+                                            &mut form.target,
+                                            Some(household.id),
+                                            format!("{} - {}", household.head_name, household.address),
+                                        );
+                                    }
+                                })
+                                .response;
+                            let options: Vec<_> = households.iter()
+                                .filter(|h| h.id != source.id)
+                                .map(|h| (Some(h.id), format!("{} {}", h.head_name, h.address)))
+                                .collect();
+                            keyboard::combo_keys(ui, &response, &mut form.target, &options);
+                        });
+                    }
+
+                    ui.add_space(5.0);
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.label(match form.kind {
+                                TransferKind::Merge => "原户成员在新户中的关系:",
+                                TransferKind::MigrateOut => "选择迁出的成员:",
+                                _ => "选择迁移的成员及其在新户中的关系:",
+                            });
+
+                            for (i, member) in source.members.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    if form.kind == TransferKind::Merge {
+                                        ui.label(&member.name);
+                                    } else {
+                                        ui.checkbox(&mut form.selected_members[i], &member.name);
+                                    }
+                                    ui.label(format!("(原关系: {})", member.relationship));
+
+                                    let involved = form.kind == TransferKind::Merge || form.selected_members[i];
+                                    if involved && form.kind != TransferKind::MigrateOut {
+                                        ui.label("新关系:");
+                                        relationship_combo_box(ui, format!("transfer_relationship_{}", i), &mut form.relationships[i]);
+                                    }
+                                    if form.kind == TransferKind::Split && form.selected_members[i] {
+                                        ui.radio_value(&mut form.new_head, Some(i), "新户户主");
+                                    }
+                                });
+                            }
+                        });
+                    });
+
+                    if form.kind == TransferKind::Split {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label("新户地址:");
+                            division_combo_boxes(ui, "transfer_address", &mut form.address);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("详细地址:");
+                            ui.add(egui::TextEdit::singleline(&mut form.address.detail)
+                                .id_source("transfer_address_detail")
+                                .desired_width(300.0));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("联系电话:");
+                            ui.add(egui::TextEdit::singleline(&mut form.phone)
+                                .id_source("transfer_phone")
+                                .desired_width(200.0));
+                        });
+                    }
+
+                    if form.kind == TransferKind::MigrateOut {
+                        ui.horizontal(|ui| {
+                            ui.label("迁往地:");
+                            ui.add(egui::TextEdit::singleline(&mut form.destination)
+                                .id_source("transfer_destination")
+                                .desired_width(300.0));
+                        });
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("办理日期:");
+                        ui.add(egui::DragValue::new(&mut form.year).range(1900..=2100));
+                        ui.label("年");
+                        ui.add(egui::DragValue::new(&mut form.month).range(1..=12));
+                        ui.label("月");
+                        ui.add(egui::DragValue::new(&mut form.day).range(1..=31));
+                        ui.label("日");
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("原因:");
+                        ui.add(egui::TextEdit::singleline(&mut form.reason)
+                            .id_source("transfer_reason")
+                            .desired_width(300.0));
+                    });
+
+                    ui.add_space(10.0);
+                    match keyboard::dialog_key(ui) {
+                        Some(DialogKey::Submit) => submit = true,
+                        Some(DialogKey::Cancel) => cancel = true,
+                        None => {}
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("确定").clicked() {
+                            submit = true;
+                        }
+                        if ui.button("取消").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+            });
+
+        if submit {
+            match self.record(form.kind.to_string(), |manager| manager.execute_transfer(&form)) {
+                Ok(()) => {
+                    self.ui_state.selected_household = None;
+                    if let Err(e) = self.update_filtered_households() {
+                        self.report_error("刷新列表失败", &e);
+                    }
+                    return;
+                }
+                Err(e) => {
+                    self.report_error(&format!("{}失败", form.kind), &e);
+                }
+            }
+        }
+
+        if open && !cancel {
+            self.ui_state.transfer_form = Some(form);
+        }
+    }
+}
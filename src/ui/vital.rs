@@ -0,0 +1,218 @@
+use crate::app::HouseholdApp;
+use crate::data::models::*;
+use crate::ui::dialogs::{document_type_combo_box, relationship_combo_box};
+use crate::ui::keyboard::{self, DialogKey};
+use eframe::egui;
+
+impl HouseholdApp {
+    pub fn open_vital_event_dialog(&mut self, kind: EventType) {
+        if !self.ensure_editable() {
+            return;
+        }
+        if let Some(selected) = self.ui_state.selected_household {
+            if let Ok(Some(household)) = self.get_household(selected) {
+                self.ui_state.vital_event_form = Some(VitalEventForm::new(kind, &household));
+            }
+        }
+    }
+
+    pub fn render_vital_event_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut form) = self.ui_state.vital_event_form.take() else {
+            return;
+        };
+
+        let Ok(Some(household)) = self.household_manager.get_household_by_id(&form.household_id) else {
+            return;
+        };
+
+        let mut open = true;
+        let mut submit = false;
+        let mut cancel = false;
+
+        egui::Window::new(form.kind.to_string())
+            .id(egui::Id::new("vital_event_dialog"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([550.0, 400.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("户主:");
+                    ui.label(format!("{} - {}", household.head_name, household.address));
+                });
+                ui.separator();
+
+                match form.kind {
+                    EventType::Birth => {
+                        ui.label("新生儿信息:");
+                        render_new_member_fields(ui, &mut form.new_member, "birth");
+                    }
+                    EventType::Death => {
+                        member_combo_box(ui, "死亡人员:", "vital_member", &household, &mut form.member_index);
+                    }
+                    EventType::Marriage => {
+                        member_combo_box(ui, "登记人:", "vital_member", &household, &mut form.member_index);
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut form.partner_joins, true, "配偶随结婚迁入本户");
+                            ui.radio_value(&mut form.partner_joins, false, "配偶为本户成员");
+                        });
+                        if form.partner_joins {
+                            ui.label("配偶信息:");
+                            render_new_member_fields(ui, &mut form.new_member, "spouse");
+                        } else {
+                            member_combo_box(ui, "配偶:", "vital_partner", &household, &mut form.partner_index);
+                        }
+                    }
+                    EventType::Divorce => {
+                        member_combo_box(ui, "登记人:", "vital_member", &household, &mut form.member_index);
+                        member_combo_box(ui, "配偶:", "vital_partner", &household, &mut form.partner_index);
+                        if form.partner_index.is_some() {
+                            ui.checkbox(&mut form.partner_leaves, "配偶同时迁出本户");
+                            if form.partner_leaves {
+                                ui.horizontal(|ui| {
+                                    ui.label("迁往地:");
+                                    ui.add(egui::TextEdit::singleline(&mut form.destination)
+                                        .id_source("vital_destination")
+                                        .desired_width(300.0));
+                                });
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+
+                // 出生登记以出生日期为准
+                if form.kind != EventType::Birth {
+                    ui.horizontal(|ui| {
+                        ui.label(if form.kind == EventType::Death { "死亡日期:" } else { "登记日期:" });
+                        ui.add(egui::DragValue::new(&mut form.year).range(1900..=2100));
+                        ui.label("年");
+                        ui.add(egui::DragValue::new(&mut form.month).range(1..=12));
+                        ui.label("月");
+                        ui.add(egui::DragValue::new(&mut form.day).range(1..=31));
+                        ui.label("日");
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("备注:");
+                    ui.add(egui::TextEdit::singleline(&mut form.reason)
+                        .id_source("vital_reason")
+                        .desired_width(300.0));
+                });
+
+                ui.add_space(10.0);
+                match keyboard::dialog_key(ui) {
+                    Some(DialogKey::Submit) => submit = true,
+                    Some(DialogKey::Cancel) => cancel = true,
+                    None => {}
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("确定").clicked() {
+                        submit = true;
+                    }
+                    if ui.button("取消").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if submit {
+            match self.record(form.kind.to_string(), |manager| manager.execute_vital_event(&form)) {
+                Ok(()) => {
+                    if let Err(e) = self.update_filtered_households() {
+                        self.report_error("刷新列表失败", &e);
+                    }
+                    return;
+                }
+                Err(e) => {
+                    self.report_error(&format!("{}失败", form.kind), &e);
+                }
+            }
+        }
+
+        if open && !cancel {
+            self.ui_state.vital_event_form = Some(form);
+        }
+    }
+}
+
+// 选择户内在册成员的下拉框
+fn member_combo_box(ui: &mut egui::Ui, label: &str, id: &str, household: &Household, selected: &mut Option<usize>) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let selected_text = selected
+            .and_then(|i| household.members.get(i))
+            .map(|m| m.name.clone())
+            .unwrap_or_else(|| "请选择".to_string());
+        let response = egui::ComboBox::from_id_salt(id)
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                for (i, member) in household.members.iter().enumerate().filter(|(_, m)| m.is_active()) {
+                    ui.selectable_value(
+                        selected,
+                        Some(i),
+                        format!("{} ({}, {})", member.name, member.relationship, member.marital_status),
+                    );
+                }
+            })
+            .response;
+        let options: Vec<_> = household.members.iter().enumerate()
+            .filter(|(_, m)| m.is_active())
+            .map(|(i, m)| (Some(i), m.name.clone()))
+            .collect();
+        keyboard::combo_keys(ui, &response, selected, &options);
+    });
+}
+
+fn render_new_member_fields(ui: &mut egui::Ui, member: &mut MemberForm, id: &str) {
+    ui.horizontal(|ui| {
+        ui.label("姓名:");
+        ui.add(egui::TextEdit::singleline(&mut member.name)
+            .id_source(format!("vital_{}_name", id))
+            .desired_width(120.0));
+
+        ui.label("关系:");
+        relationship_combo_box(ui, format!("vital_{}_relationship", id), &mut member.relationship);
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("证件类型:");
+        document_type_combo_box(ui, format!("vital_{}_document_type", id), member);
+
+        if !member.document_type.is_chinese_citizen() {
+            ui.label("国籍:");
+            ui.add(egui::TextEdit::singleline(&mut member.nationality)
+                .id_source(format!("vital_{}_nationality", id))
+                .hint_text("如 USA")
+                .desired_width(60.0));
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("证件号码:");
+        ui.add(egui::TextEdit::singleline(&mut member.id_number)
+            .id_source(format!("vital_{}_id_number", id))
+            .desired_width(160.0));
+
+        ui.label("性别:");
+        let response = egui::ComboBox::from_id_salt(format!("vital_{}_gender", id))
+            .selected_text(member.gender.to_string())
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut member.gender, Gender::Male, "男");
+                ui.selectable_value(&mut member.gender, Gender::Female, "女");
+            })
+            .response;
+        keyboard::combo_keys(ui, &response, &mut member.gender, &keyboard::code_options());
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("出生日期:");
+        ui.add(egui::DragValue::new(&mut member.birth_year).range(1900..=2100));
+        ui.label("年");
+        ui.add(egui::DragValue::new(&mut member.birth_month).range(1..=12));
+        ui.label("月");
+        ui.add(egui::DragValue::new(&mut member.birth_day).range(1..=31));
+        ui.label("日");
+    });
+}
@@ -0,0 +1,175 @@
+use crate::data::codes::StableCode;
+use crate::data::models::{Address, Household, Member};
+use chrono::{NaiveDate, NaiveDateTime};
+use std::fs;
+use uuid::Uuid;
+
+/// 可导入的CSV格式：每行一名成员，所在户的信息逐行重复，枚举字段写存储代码
+const HEADER: [&str; 18] = [
+    "户籍ID", "户口类型", "联系电话", "行政区划代码", "详细地址", "登记日期",
+    "姓名", "证件类型", "证件号码", "国籍", "与户主关系", "性别", "出生日期",
+    "学历", "职业", "婚姻状况", "状态", "死亡日期",
+];
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 导出为可重新导入的CSV文件
+pub fn write_households(households: &[Household], file_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut content = String::new();
+    push_row(&mut content, HEADER.iter().map(|s| s.to_string()));
+
+    for household in households {
+        for member in &household.members {
+            push_row(&mut content, [
+                household.id.to_string(),
+                household.household_type.code().to_string(),
+                household.phone.clone(),
+                household.address.division().to_string(),
+                household.address.detail.clone(),
+                household.registration_date.format(DATETIME_FORMAT).to_string(),
+                member.name.clone(),
+                member.document_type.code().to_string(),
+                member.id_number.clone(),
+                member.nationality.clone(),
+                member.relationship.code().to_string(),
+                member.gender.code().to_string(),
+                member.birth_date.format(DATE_FORMAT).to_string(),
+                member.education.code().to_string(),
+                member.occupation.clone(),
+                member.marital_status.code().to_string(),
+                member.status.code().to_string(),
+                member.death_date.map(|d| d.format(DATE_FORMAT).to_string()).unwrap_or_default(),
+            ]);
+        }
+    }
+
+    fs::write(file_path, content)?;
+    Ok(())
+}
+
+/// 读取 `write_households` 格式的CSV文件，同一户籍ID的行合并为一户
+pub fn read_households(file_path: &str) -> Result<Vec<Household>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(file_path)?;
+    let mut rows = parse(content.trim_start_matches('\u{feff}'))?.into_iter();
+
+    match rows.next() {
+        Some(header) if header.iter().map(String::as_str).eq(HEADER) => {}
+        _ => return Err(format!("{} 不是可导入的户籍CSV文件，表头应为: {}", file_path, HEADER.join(",")).into()),
+    }
+
+    let mut households: Vec<Household> = Vec::new();
+    for (i, row) in rows.enumerate() {
+        // 表头为第1行
+        let line = i + 2;
+        if row.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+        if row.len() != HEADER.len() {
+            return Err(format!("第{}行有{}列，应为{}列", line, row.len(), HEADER.len()).into());
+        }
+        let (household, member) = parse_row(&row).map_err(|e| format!("第{}行: {}", line, e))?;
+        match households.iter_mut().find(|h| h.id == household.id) {
+            Some(existing) => existing.members.push(member),
+            None => {
+                let mut household = household;
+                household.members.push(member);
+                households.push(household);
+            }
+        }
+    }
+
+    for household in &mut households {
+        household.sync_head();
+    }
+    Ok(households)
+}
+
+fn parse_row(row: &[String]) -> Result<(Household, Member), String> {
+    let date = |s: &str| NaiveDate::parse_from_str(s, DATE_FORMAT).map_err(|_| format!("无效的日期: {}", s));
+
+    let household = Household {
+        id: Uuid::parse_str(&row[0]).map_err(|_| format!("无效的户籍ID: {}", row[0]))?,
+        head_name: String::new(),
+        id_number: String::new(),
+        head_document_type: Default::default(),
+        household_type: row[1].parse().map_err(|e| format!("{}", e))?,
+        phone: row[2].clone(),
+        address: Address::from_division(&row[3], &row[4]),
+        dwelling_id: None,
+        registration_date: NaiveDateTime::parse_from_str(&row[5], DATETIME_FORMAT)
+            .map_err(|_| format!("无效的登记日期: {}", row[5]))?,
+        members: Vec::new(),
+        revision: 0,
+    };
+    let member = Member {
+        name: row[6].clone(),
+        document_type: row[7].parse().map_err(|e| format!("{}", e))?,
+        id_number: row[8].clone(),
+        nationality: row[9].clone(),
+        relationship: row[10].parse().map_err(|e| format!("{}", e))?,
+        gender: row[11].parse().map_err(|e| format!("{}", e))?,
+        birth_date: date(&row[12])?,
+        education: row[13].parse().map_err(|e| format!("{}", e))?,
+        occupation: row[14].clone(),
+        marital_status: row[15].parse().map_err(|e| format!("{}", e))?,
+        status: row[16].parse().map_err(|e| format!("{}", e))?,
+        death_date: if row[17].is_empty() { None } else { Some(date(&row[17])?) },
+    };
+    Ok((household, member))
+}
+
+fn push_row(content: &mut String, fields: impl IntoIterator<Item = String>) {
+    let fields: Vec<String> = fields.into_iter().map(|field| quote(&field)).collect();
+    content.push_str(&fields.join(","));
+    content.push('\n');
+}
+
+// 含逗号、引号或换行的字段加引号，引号写两次
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn parse(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("引号未闭合".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
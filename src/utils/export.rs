@@ -1,7 +1,11 @@
-use crate::data::models::Household;
+use crate::data::attachments::content_hash;
+use crate::data::divisions::{self, DivisionLevel};
+use crate::data::models::{DocumentType, Household, Member};
 use chrono::Datelike;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
+use uuid::Uuid;
 
 pub struct ExportUtils;
 
@@ -11,18 +15,20 @@ impl ExportUtils {
         let mut file = File::create(file_path)?;
         
         // 写入CSV头部
-        writeln!(file, "户主姓名,身份证号,户口类型,联系电话,家庭地址,登记日期,成员数量")?;
+        writeln!(file, "户主姓名,证件类型,证件号码,户口类型,联系电话,行政区划代码,家庭地址,登记日期,成员数量")?;
         
         // 写入数据
         for household in households {
             writeln!(
                 file,
-                "{},{},{},{},{},{},{}",
+                "{},{},{},{},{},{},{},{},{}",
                 household.head_name,
+                household.head_document_type,
                 household.id_number,
                 household.household_type,
                 household.phone,
-                household.address.replace(',', "，"), // 替换逗号避免CSV格式问题
+                household.address.division(),
+                household.address.to_string().replace(',', "，"), // 替换逗号避免CSV格式问题
                 household.registration_date.format("%Y-%m-%d"),
                 household.members.len()
             )?;
@@ -36,22 +42,26 @@ impl ExportUtils {
         let mut file = File::create(file_path)?;
         
         // 写入CSV头部
-        writeln!(file, "户主姓名,成员姓名,身份证号,关系,性别,出生日期,学历,职业")?;
+        writeln!(file, "户主姓名,成员姓名,证件类型,证件号码,国籍,关系,性别,出生日期,学历,职业,婚姻状况,状态")?;
         
         // 写入数据
         for household in households {
             for member in &household.members {
                 writeln!(
                     file,
-                    "{},{},{},{},{},{},{},{}",
+                    "{},{},{},{},{},{},{},{},{},{},{},{}",
                     household.head_name,
                     member.name,
+                    member.document_type,
                     member.id_number,
+                    member.nationality,
                     member.relationship,
                     member.gender,
                     member.birth_date.format("%Y-%m-%d"),
                     member.education,
-                    member.occupation.replace(',', "，")
+                    member.occupation.replace(',', "，"),
+                    member.marital_status,
+                    member.status
                 )?;
             }
         }
@@ -71,7 +81,10 @@ impl ExportUtils {
             .filter(|h| matches!(h.household_type, crate::data::models::HouseholdType::Urban))
             .count();
         let rural_count = total_households - urban_count;
-        let total_members: usize = households.iter().map(|h| h.members.len()).sum();
+        // 只统计在册人口
+        let total_members: usize = households.iter()
+            .map(|h| h.members.iter().filter(|m| m.is_active()).count())
+            .sum();
         
         writeln!(file, "户籍管理系统统计报告")?;
         writeln!(file, "========================")?;
@@ -81,16 +94,44 @@ impl ExportUtils {
         writeln!(file, "  总户数: {}", total_households)?;
         writeln!(file, "  城镇户口: {}", urban_count)?;
         writeln!(file, "  农村户口: {}", rural_count)?;
-        writeln!(file, "  总人数: {}", total_members)?;
+        writeln!(file, "  在册人数: {}", total_members)?;
         writeln!(file, "  平均每户人数: {:.2}", if total_households > 0 { total_members as f64 / total_households as f64 } else { 0.0 })?;
         writeln!(file)?;
         
+        // 证件类型分布
+        writeln!(file, "证件类型:")?;
+        for document_type in DocumentType::ALL {
+            let count: usize = households.iter()
+                .map(|h| h.members.iter().filter(|m| m.is_active() && m.document_type == document_type).count())
+                .sum();
+            if count > 0 {
+                writeln!(file, "  {}: {}", document_type, count)?;
+            }
+        }
+        writeln!(file)?;
+        
+        // 按县区、乡镇街道分组
+        for level in [DivisionLevel::County, DivisionLevel::Township] {
+            let mut counts: std::collections::BTreeMap<&str, (usize, usize)> = std::collections::BTreeMap::new();
+            for household in households {
+                let entry = counts.entry(household.address.code(level)).or_default();
+                entry.0 += 1;
+                entry.1 += household.members.iter().filter(|m| m.is_active()).count();
+            }
+            writeln!(file, "按{}统计:", level)?;
+            for (code, (household_count, member_count)) in counts {
+                let name = if code.is_empty() { "未指定".to_string() } else { divisions::full_name(code) };
+                writeln!(file, "  {}: {}户 {}人", name, household_count, member_count)?;
+            }
+            writeln!(file)?;
+        }
+        
         // 年龄分布统计
         let mut age_groups = [0; 7]; // 0-10, 11-20, 21-30, 31-40, 41-50, 51-60, 60+
         let today = chrono::Utc::now().naive_utc().date();
         
         for household in households {
-            for member in &household.members {
+            for member in household.members.iter().filter(|m| m.is_active()) {
                 let age = today.year() - member.birth_date.year();
                 let group_index = match age {
                     0..=10 => 0,
@@ -116,4 +157,365 @@ impl ExportUtils {
         
         Ok(())
     }
+    
+    /// 导出供统计部门使用的匿名化成员数据，每行一名在册成员。
+    /// 姓名换为化名，证件号码只保留地区和出生年，出生日期和地址按 `options` 概括；
+    /// 写入前按准标识符（截取后的证件号码、出生、性别、地区、学历、户内人数）分组，不足 k 条的组整组隐去，隐去的记录列在报告中。
+    /// 户编号每次导出随机生成，不能跨期关联同一户，但本次导出中同户成员仍可相互关联
+    pub fn export_anonymized(
+        households: &[Household],
+        file_path: &str,
+        options: &AnonymizationOptions,
+    ) -> Result<AnonymizationReport, Box<dyn std::error::Error>> {
+        // 未指定密钥时人员化名只在本次导出内一致；户编号总是只在本次导出内一致
+        let key = if options.key.is_empty() { Uuid::new_v4().to_string() } else { options.key.clone() };
+        let household_key = Uuid::new_v4().to_string();
+        
+        let mut records = Vec::new();
+        for household in households {
+            let active: Vec<&Member> = household.members.iter().filter(|m| m.is_active()).collect();
+            for member in &active {
+                records.push(AnonymizedRecord {
+                    household: pseudonym(&household_key, "H", &household.id.to_string()),
+                    person: pseudonym(&key, "P", &member_identity(household, member)),
+                    id_number: truncate_id_number(member, options),
+                    relationship: member.relationship.to_string(),
+                    gender: member.gender.to_string(),
+                    birth: options.birth_granularity.generalize(member.birth_date.year()),
+                    education: member.education.to_string(),
+                    marital_status: member.marital_status.to_string(),
+                    household_type: household.household_type.to_string(),
+                    region: household.address.code(options.region_level).to_string(),
+                    household_size: active.len().to_string(),
+                });
+            }
+        }
+        
+        // k-匿名检查：统计每种准标识符组合的记录数
+        let mut group_sizes: HashMap<[&str; 6], usize> = HashMap::new();
+        for record in &records {
+            *group_sizes.entry(record.quasi_identifiers()).or_default() += 1;
+        }
+        let mut report = AnonymizationReport {
+            k: options.k,
+            region_level: options.region_level,
+            birth_granularity: options.birth_granularity,
+            exported: 0,
+            suppressed: Vec::new(),
+        };
+        let mut kept = Vec::new();
+        for record in &records {
+            let group_size = group_sizes[&record.quasi_identifiers()];
+            if group_size < options.k {
+                report.suppressed.push(SuppressedRecord {
+                    person: record.person.clone(),
+                    household: record.household.clone(),
+                    quasi_identifiers: record.quasi_identifiers().join(" / "),
+                    group_size,
+                });
+            } else {
+                kept.push(record);
+            }
+        }
+        report.exported = kept.len();
+        
+        let mut file = File::create(file_path)?;
+        writeln!(file, "户编号,人员编号,证件号码（地区/出生年）,与户主关系,性别,出生年份,学历,婚姻状况,户口类型,地区代码,地区,户内在册人数")?;
+        for record in kept {
+            let region_name = if record.region.is_empty() { "未指定".to_string() } else { divisions::full_name(&record.region) };
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{},{},{},{},{},{}",
+                record.household,
+                record.person,
+                record.id_number,
+                record.relationship,
+                record.gender,
+                record.birth,
+                record.education,
+                record.marital_status,
+                record.household_type,
+                record.region,
+                region_name.replace(',', "，"),
+                record.household_size
+            )?;
+        }
+        
+        Ok(report)
+    }
+}
+
+/// 匿名化导出时出生日期的概括程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BirthGranularity {
+    /// 只保留出生年份
+    Year,
+    /// 按 5 年分段，如 1990-1994
+    #[default]
+    FiveYears,
+    /// 按 10 年分段，如 1990-1999
+    TenYears,
+}
+
+impl BirthGranularity {
+    pub fn generalize(&self, year: i32) -> String {
+        let span = match self {
+            BirthGranularity::Year => return year.to_string(),
+            BirthGranularity::FiveYears => 5,
+            BirthGranularity::TenYears => 10,
+        };
+        let start = year - year.rem_euclid(span);
+        format!("{}-{}", start, start + span - 1)
+    }
+}
+
+impl std::fmt::Display for BirthGranularity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BirthGranularity::Year => write!(f, "出生年份"),
+            BirthGranularity::FiveYears => write!(f, "5年分段"),
+            BirthGranularity::TenYears => write!(f, "10年分段"),
+        }
+    }
+}
+
+/// 匿名化导出的选项
+#[derive(Debug, Clone)]
+pub struct AnonymizationOptions {
+    /// 地址和证件号码中的地区保留到的级别；证件号码最多保留到县区
+    pub region_level: DivisionLevel,
+    pub birth_granularity: BirthGranularity,
+    /// 每种准标识符组合至少要有的记录数，不足的不导出
+    pub k: usize,
+    /// 生成人员化名的密钥，多次导出使用同一密钥时同一人的化名相同；为空时每次导出随机生成。户编号不使用此密钥
+    pub key: String,
+}
+
+impl Default for AnonymizationOptions {
+    fn default() -> Self {
+        Self {
+            region_level: DivisionLevel::County,
+            birth_granularity: BirthGranularity::default(),
+            k: 5,
+            key: String::new(),
+        }
+    }
+}
+
+/// 因准标识符组合不足 k 条而未导出的记录
+#[derive(Debug, Clone)]
+pub struct SuppressedRecord {
+    pub person: String,
+    pub household: String,
+    pub quasi_identifiers: String,
+    /// 该组合的记录数
+    pub group_size: usize,
+}
+
+/// 匿名化导出的结果报告。隐去的记录虽只列化名，其准标识符组合仍可能指向具体的人，报告只供本单位核对，不随数据交付
+#[derive(Debug, Clone)]
+pub struct AnonymizationReport {
+    pub k: usize,
+    pub region_level: DivisionLevel,
+    pub birth_granularity: BirthGranularity,
+    pub exported: usize,
+    pub suppressed: Vec<SuppressedRecord>,
+}
+
+impl std::fmt::Display for AnonymizationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "匿名化导出报告")?;
+        writeln!(f, "========================")?;
+        writeln!(f, "k = {}，出生按{}，地区到{}", self.k, self.birth_granularity, self.region_level)?;
+        writeln!(f, "导出: {} 条", self.exported)?;
+        writeln!(f, "隐去: {} 条", self.suppressed.len())?;
+        writeln!(f, "注意: k-匿名按单条记录计算。同一户编号的成员可相互关联，结合与户主关系、婚姻状况等未参与分组的字段，")?;
+        writeln!(f, "      同户成员的组合可能少于 {} 户，实际保护程度低于 k 所示", self.k)?;
+        if !self.suppressed.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "隐去的记录（证件号码 / 出生 / 性别 / 地区 / 学历 / 户内人数 组合不足 {} 条）:", self.k)?;
+            for record in &self.suppressed {
+                writeln!(f, "  {}\t{}\t{}\t{}条", record.person, record.household, record.quasi_identifiers, record.group_size)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 匿名化后的一名成员
+struct AnonymizedRecord {
+    household: String,
+    person: String,
+    id_number: String,
+    relationship: String,
+    gender: String,
+    birth: String,
+    education: String,
+    marital_status: String,
+    household_type: String,
+    region: String,
+    household_size: String,
+}
+
+impl AnonymizedRecord {
+    fn quasi_identifiers(&self) -> [&str; 6] {
+        [&self.id_number, &self.birth, &self.gender, &self.region, &self.education, &self.household_size]
+    }
+}
+
+// 密钥与原值的 SHA-256 前 10 位，不知道密钥时无法由证件号码推算
+fn pseudonym(key: &str, prefix: &str, value: &str) -> String {
+    format!("{}{}", prefix, &content_hash(format!("{}\n{}", key, value).as_bytes())[..10])
+}
+
+// 成员以证件号码区分，迁往他户后化名不变；没有证件号码的以所在户和姓名区分
+fn member_identity(household: &Household, member: &Member) -> String {
+    let id_number = member.id_number.trim().to_uppercase();
+    if id_number.is_empty() {
+        format!("{}/{}", household.id, member.name.trim())
+    } else {
+        id_number
+    }
+}
+
+// 居民身份证和港澳台居民居住证保留地区码和概括后的出生年，如 110101/1990-1994；其他证件不含地区信息，留空
+fn truncate_id_number(member: &Member, options: &AnonymizationOptions) -> String {
+    let id_number = member.id_number.trim();
+    let has_region = matches!(member.document_type, DocumentType::ResidentId | DocumentType::HmtResidencePermit)
+        && id_number.len() == 18
+        && id_number.is_ascii();
+    if !has_region {
+        return String::new();
+    }
+    let digits = options.region_level.code_len().min(6);
+    format!("{}/{}", &id_number[..digits], options.birth_granularity.generalize(member.birth_date.year()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::models::*;
+    use chrono::NaiveDate;
+
+    fn member(id_number: &str, relationship: Relationship, birth_year: i32) -> Member {
+        Member {
+            name: format!("成员{}", &id_number[14..]),
+            document_type: DocumentType::ResidentId,
+            id_number: id_number.to_string(),
+            nationality: "CHN".to_string(),
+            relationship,
+            birth_date: NaiveDate::from_ymd_opt(birth_year, 3, 1).unwrap(),
+            gender: Gender::Male,
+            education: Education::University,
+            occupation: String::new(),
+            status: MemberStatus::Active,
+            marital_status: MaritalStatus::Unmarried,
+            death_date: None,
+        }
+    }
+
+    fn household(members: Vec<Member>) -> Household {
+        let mut household = Household {
+            id: Uuid::new_v4(),
+            head_name: String::new(),
+            id_number: String::new(),
+            head_document_type: DocumentType::ResidentId,
+            address: Address::from_division("110101001", "XXX路XXX号"),
+            dwelling_id: None,
+            phone: String::new(),
+            household_type: HouseholdType::Urban,
+            registration_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            members,
+            revision: 0,
+        };
+        household.sync_head();
+        household
+    }
+
+    // 一人户，户主 1990 年出生
+    fn single(sequence: u32) -> Household {
+        household(vec![member(&format!("11010119900301{:04}", sequence), Relationship::HEAD, 1990)])
+    }
+
+    // 导出到临时文件，返回报告和数据行（不含表头）
+    fn export(households: &[Household], options: &AnonymizationOptions) -> (AnonymizationReport, Vec<Vec<String>>) {
+        let path = std::env::temp_dir().join(format!("{}.csv", Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+        let report = ExportUtils::export_anonymized(households, path, options).unwrap();
+        let content = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let rows = content.lines().skip(1).map(|line| line.split(',').map(str::to_string).collect()).collect();
+        (report, rows)
+    }
+
+    #[test]
+    fn birth_granularity() {
+        assert_eq!(BirthGranularity::Year.generalize(1992), "1992");
+        assert_eq!(BirthGranularity::FiveYears.generalize(1992), "1990-1994");
+        assert_eq!(BirthGranularity::FiveYears.generalize(1995), "1995-1999");
+        assert_eq!(BirthGranularity::TenYears.generalize(1999), "1990-1999");
+    }
+
+    #[test]
+    fn generalizes_identifying_fields() {
+        let households: Vec<Household> = (1..=5).map(single).collect();
+        let (report, rows) = export(&households, &AnonymizationOptions::default());
+        assert_eq!(report.exported, 5);
+        for row in &rows {
+            assert!(row[0].starts_with('H') && row[1].starts_with('P'));
+            assert_eq!(row[2], "110101/1990-1994");
+            assert_eq!(row[5], "1990-1994");
+            assert_eq!(row[9], "110101");
+            assert_eq!(row[11], "1");
+        }
+        let content = rows.concat().join(",");
+        assert!(!content.contains("成员") && !content.contains("11010119900301"));
+    }
+
+    #[test]
+    fn suppresses_groups_smaller_than_k() {
+        let mut households: Vec<Household> = (1..=5).map(single).collect();
+        households.push(household(vec![member("110101198003010001", Relationship::HEAD, 1980)]));
+        let (report, rows) = export(&households, &AnonymizationOptions::default());
+        assert_eq!(report.exported, 5);
+        assert_eq!(rows.len(), 5);
+        assert_eq!(report.suppressed.len(), 1);
+        assert_eq!(report.suppressed[0].group_size, 1);
+        assert!(report.suppressed[0].quasi_identifiers.contains("1980-1984"));
+
+        // 分段放宽到 10 年后仍不足 5 人，k 为 1 时全部导出
+        let options = AnonymizationOptions { birth_granularity: BirthGranularity::TenYears, ..Default::default() };
+        assert_eq!(export(&households, &options).0.suppressed.len(), 1);
+        let options = AnonymizationOptions { k: 1, ..Default::default() };
+        assert_eq!(export(&households, &options).0.exported, 6);
+    }
+
+    #[test]
+    fn household_size_is_quasi_identifier() {
+        // 5 人其他字段相同，其中 1 人在两人户中，分成 4 人和 1 人两组，均不足 5 人
+        let mut households: Vec<Household> = (1..=4).map(single).collect();
+        households.push(household(vec![
+            member("110101199003010005", Relationship::HEAD, 1990),
+            member("110101201503010006", Relationship::SON, 2015),
+        ]));
+        let (report, rows) = export(&households, &AnonymizationOptions::default());
+        assert!(rows.is_empty());
+        assert_eq!(report.suppressed.len(), 6);
+        assert!(report.to_string().contains("同户成员"));
+    }
+
+    #[test]
+    fn person_pseudonyms_follow_key_and_household_pseudonyms_do_not() {
+        let households: Vec<Household> = (1..=5).map(single).collect();
+        let options = AnonymizationOptions { key: "密钥".to_string(), ..Default::default() };
+        let (_, first) = export(&households, &options);
+        let (_, second) = export(&households, &options);
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a[1], b[1]);
+            assert_ne!(a[0], b[0]);
+        }
+
+        let (_, other_key) = export(&households, &AnonymizationOptions { key: "其他".to_string(), ..Default::default() });
+        assert!(first.iter().zip(&other_key).all(|(a, b)| a[1] != b[1]));
+    }
 }
@@ -1,36 +1,60 @@
-/// 格式化身份证号，隐藏中间部分
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// 格式化身份证号，隐藏中间部分；不是 18 位时原样返回
 pub fn format_id_number_masked(id_number: &str) -> String {
-    if id_number.len() != 18 {
-        return id_number.to_string();
-    }
-    
-    format!(
-        "{}****{}",
-        &id_number[0..6],
-        &id_number[14..18]
-    )
+    mask_middle(id_number, 18, 6, 4)
 }
 
-/// 格式化手机号，隐藏中间部分
+/// 格式化手机号，隐藏中间部分；不是 11 位时原样返回
 pub fn format_phone_masked(phone: &str) -> String {
-    if phone.len() != 11 {
-        return phone.to_string();
+    mask_middle(phone, 11, 3, 4)
+}
+
+// 按字符计数，长度为 `length` 时保留前 `keep_start` 位和后 `keep_end` 位；`keep_start + keep_end` 不超过 `length`
+fn mask_middle(text: &str, length: usize, keep_start: usize, keep_end: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() != length {
+        return text.to_string();
     }
-    
-    format!(
-        "{}****{}",
-        &phone[0..3],
-        &phone[7..11]
-    )
+    let start: String = chars[..keep_start].iter().collect();
+    let end: String = chars[length - keep_end..].iter().collect();
+    format!("{}****{}", start, end)
 }
 
-/// 格式化地址，如果太长则截断
-pub fn format_address_truncated(address: &str, max_length: usize) -> String {
-    if address.len() <= max_length {
-        address.to_string()
-    } else {
-        format!("{}...", &address[0..max_length])
+/// 文本的显示宽度，汉字等全角字符占 2 列
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// 截断到不超过 `max_width` 列，按字形簇截断，不会拆开组合字符或表情；
+/// 截断时以“...”结尾，省略号计入宽度
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    const ELLIPSIS: &str = "...";
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    let budget = max_width.saturating_sub(ELLIPSIS.len());
+    let mut truncated = String::new();
+    let mut width = 0;
+    for grapheme in text.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if width + grapheme_width > budget {
+            break;
+        }
+        truncated.push_str(grapheme);
+        width += grapheme_width;
+    }
+    // 宽度连省略号都放不下时只保留能放下的部分
+    if max_width >= ELLIPSIS.len() {
+        truncated.push_str(ELLIPSIS);
     }
+    truncated
+}
+
+/// 格式化地址，如果超过 `max_width` 列则截断
+pub fn format_address_truncated(address: &str, max_width: usize) -> String {
+    truncate_to_width(address, max_width)
 }
 
 /// 格式化姓名，确保正确显示
@@ -38,20 +62,206 @@ pub fn format_name(name: &str) -> String {
     name.trim().to_string()
 }
 
+/// 中文数字的写法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumeralStyle {
+    /// 小写，如“一百零五”“十二万”
+    Lower,
+    /// 大写，如“壹佰零伍”“壹拾贰万”，用于正式文书
+    Upper,
+}
+
+impl NumeralStyle {
+    fn digits(&self) -> [&'static str; 10] {
+        match self {
+            NumeralStyle::Lower => ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"],
+            NumeralStyle::Upper => ["零", "壹", "贰", "叁", "肆", "伍", "陆", "柒", "捌", "玖"],
+        }
+    }
+
+    // 千、百、十
+    fn units(&self) -> [&'static str; 3] {
+        match self {
+            NumeralStyle::Lower => ["千", "百", "十"],
+            NumeralStyle::Upper => ["仟", "佰", "拾"],
+        }
+    }
+}
+
+/// 格式化为中文数字，按万、亿分节，如 100010 为“十万零一十”，大写为“壹拾万零壹拾”；
+/// 亿以上的部分仍以亿计，如“一万零一亿”
+pub fn format_chinese_numeral(number: u64, style: NumeralStyle) -> String {
+    let text = chinese_numeral(number, style);
+    // 小写时开头的“一十”读作“十”
+    match style {
+        NumeralStyle::Lower if text.starts_with("一十") => text["一".len()..].to_string(),
+        _ => text,
+    }
+}
+
+fn chinese_numeral(number: u64, style: NumeralStyle) -> String {
+    for (unit_value, unit) in [(100_000_000, "亿"), (10_000, "万")] {
+        if number >= unit_value {
+            let (high, low) = (number / unit_value, number % unit_value);
+            let mut text = chinese_numeral(high, style) + unit;
+            if low > 0 {
+                // 下一节不满最高位时补“零”，如一万零五百
+                if low < unit_value / 10 {
+                    text.push_str(style.digits()[0]);
+                }
+                text.push_str(&chinese_numeral(low, style));
+            }
+            return text;
+        }
+    }
+
+    let digits = style.digits();
+    let units = style.units();
+    let mut text = String::new();
+    let mut pending_zero = false;
+    for (place, unit) in [(1000, units[0]), (100, units[1]), (10, units[2]), (1, "")] {
+        let digit = (number / place % 10) as usize;
+        if digit == 0 {
+            pending_zero = !text.is_empty();
+            continue;
+        }
+        if pending_zero {
+            text.push_str(digits[0]);
+            pending_zero = false;
+        }
+        text.push_str(digits[digit]);
+        text.push_str(unit);
+    }
+    if text.is_empty() {
+        text.push_str(digits[0]);
+    }
+    text
+}
+
 /// 格式化数字为中文计数
 pub fn format_count_chinese(count: usize) -> String {
-    match count {
-        0 => "零".to_string(),
-        1 => "一".to_string(),
-        2 => "二".to_string(),
-        3 => "三".to_string(),
-        4 => "四".to_string(),
-        5 => "五".to_string(),
-        6 => "六".to_string(),
-        7 => "七".to_string(),
-        8 => "八".to_string(),
-        9 => "九".to_string(),
-        10 => "十".to_string(),
-        _ => count.to_string(),
+    format_chinese_numeral(count as u64, NumeralStyle::Lower)
+}
+
+/// 格式化文件大小，如“1.5 MB”
+pub fn format_file_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    let size = bytes as f64;
+    if size < KB {
+        format!("{} B", bytes)
+    } else if size < KB * KB {
+        format!("{:.1} KB", size / KB)
+    } else {
+        format!("{:.1} MB", size / KB / KB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // 把中文数字读回数值，用于检查格式化结果
+    fn parse_chinese_numeral(text: &str) -> u64 {
+        for (unit, unit_value) in [("亿", 100_000_000), ("万", 10_000)] {
+            if let Some(position) = text.rfind(unit) {
+                let (high, low) = (&text[..position], &text[position + unit.len()..]);
+                return parse_chinese_numeral(high) * unit_value + parse_chinese_numeral(low);
+            }
+        }
+        let mut total = 0;
+        let mut digit = None;
+        for c in text.chars() {
+            let lower = "零一二三四五六七八九".chars().position(|d| d == c);
+            let upper = "零壹贰叁肆伍陆柒捌玖".chars().position(|d| d == c);
+            if let Some(value) = lower.or(upper) {
+                digit = Some(value as u64);
+                continue;
+            }
+            let place = match c {
+                '千' | '仟' => 1000,
+                '百' | '佰' => 100,
+                '十' | '拾' => 10,
+                _ => panic!("无法识别的字符: {}", c),
+            };
+            total += digit.take().unwrap_or(1) * place;
+        }
+        total + digit.unwrap_or(0)
+    }
+
+    #[test]
+    fn chinese_numeral_fixed_values() {
+        let cases = [
+            (0, "零", "零"),
+            (10, "十", "壹拾"),
+            (15, "十五", "壹拾伍"),
+            (105, "一百零五", "壹佰零伍"),
+            (1010, "一千零一十", "壹仟零壹拾"),
+            (10_500, "一万零五百", "壹万零伍佰"),
+            (100_010, "十万零一十", "壹拾万零壹拾"),
+            (120_000, "十二万", "壹拾贰万"),
+            (100_000_000, "一亿", "壹亿"),
+            (1_000_100_000_000, "一万零一亿", "壹万零壹亿"),
+        ];
+        for (number, lower, upper) in cases {
+            assert_eq!(format_chinese_numeral(number, NumeralStyle::Lower), lower);
+            assert_eq!(format_chinese_numeral(number, NumeralStyle::Upper), upper);
+            assert_eq!(parse_chinese_numeral(lower), number);
+            assert_eq!(parse_chinese_numeral(upper), number);
+        }
+        let max = format_chinese_numeral(u64::MAX, NumeralStyle::Lower);
+        assert_eq!(parse_chinese_numeral(&max), u64::MAX);
+        assert_eq!(format_count_chinese(3), "三");
+    }
+
+    #[test]
+    fn masks_by_character() {
+        assert_eq!(format_id_number_masked("110101199001011234"), "110101****1234");
+        assert_eq!(format_id_number_masked("１１０１０１１９９００１０１１２３４"), "１１０１０１****１２３４");
+        assert_eq!(format_id_number_masked("11010119900101"), "11010119900101");
+        assert_eq!(format_phone_masked("13800138000"), "138****8000");
+    }
+
+    #[test]
+    fn truncates_by_display_width() {
+        assert_eq!(display_width("北京市abc"), 9);
+        assert_eq!(truncate_to_width("北京市朝阳区建外街道XXX路XXX号", 12), "北京市朝...");
+        assert_eq!(truncate_to_width("北京市", 6), "北京市");
+        assert_eq!(truncate_to_width("北京市", 2), "");
+        // 组合字符与其基字符一起保留或舍去
+        assert_eq!(truncate_to_width(&"e\u{301}".repeat(6), 5), "e\u{301}e\u{301}...");
+    }
+
+    proptest! {
+        #[test]
+        fn truncate_never_exceeds_width(text in any::<String>(), max_width in 0usize..40) {
+            let truncated = truncate_to_width(&text, max_width);
+            prop_assert!(display_width(&truncated) <= max_width);
+            if display_width(&text) <= max_width {
+                prop_assert_eq!(&truncated, &text);
+            } else if max_width >= 3 {
+                prop_assert!(truncated.ends_with("..."));
+                prop_assert!(text.starts_with(truncated.trim_end_matches("...")));
+            }
+        }
+
+        #[test]
+        fn mask_never_panics(text in any::<String>(), length in 0usize..24, keep_start in 0usize..12, keep_end in 0usize..12) {
+            prop_assume!(keep_start + keep_end <= length);
+            let masked = mask_middle(&text, length, keep_start, keep_end);
+            if text.chars().count() == length {
+                prop_assert_eq!(masked.chars().count(), keep_start + 4 + keep_end);
+            } else {
+                prop_assert_eq!(&masked, &text);
+            }
+            format_id_number_masked(&text);
+            format_phone_masked(&text);
+        }
+
+        #[test]
+        fn chinese_numeral_round_trips(number in any::<u64>()) {
+            prop_assert_eq!(parse_chinese_numeral(&format_chinese_numeral(number, NumeralStyle::Lower)), number);
+            prop_assert_eq!(parse_chinese_numeral(&format_chinese_numeral(number, NumeralStyle::Upper)), number);
+        }
     }
 }
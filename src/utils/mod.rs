@@ -1,6 +1,7 @@
 pub mod date;
 pub mod format;
 pub mod export;
+pub mod csv;
 
 pub use date::*;
 pub use format::*;
@@ -0,0 +1,59 @@
+//! 集成测试共用的测试数据
+
+#![allow(dead_code)]
+
+use chrono::NaiveDate;
+use household_management::data::models::*;
+use uuid::Uuid;
+
+/// 随机的 18 位身份证号，共用数据库中多次运行也不重复
+pub fn id_number() -> String {
+    let random = Uuid::new_v4().as_u128() % 100_000_000;
+    format!("1101011990{:08}", random)
+}
+
+pub fn member(name: &str, relationship: Relationship) -> Member {
+    Member {
+        name: name.to_string(),
+        document_type: DocumentType::ResidentId,
+        id_number: id_number(),
+        nationality: "CHN".to_string(),
+        relationship,
+        birth_date: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
+        gender: Gender::Male,
+        education: Education::University,
+        occupation: String::new(),
+        status: MemberStatus::Active,
+        marital_status: MaritalStatus::Unmarried,
+        death_date: None,
+    }
+}
+
+/// 户主和其余成员组成的一户，户主信息已同步
+pub fn household(head: &str, others: &[(&str, Relationship)]) -> Household {
+    let mut members = vec![member(head, Relationship::HEAD)];
+    members.extend(others.iter().map(|(name, relationship)| member(name, *relationship)));
+    let mut household = Household {
+        id: Uuid::new_v4(),
+        head_name: String::new(),
+        id_number: String::new(),
+        head_document_type: DocumentType::ResidentId,
+        address: Address::from_division("110105001", "XXX路XXX号"),
+        dwelling_id: None,
+        phone: "13800138000".to_string(),
+        household_type: HouseholdType::Urban,
+        registration_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+        members,
+        revision: 0,
+    };
+    household.sync_head();
+    household
+}
+
+/// 临时目录中不重复的文件路径
+pub fn temp_path(extension: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("household-test-{}.{}", Uuid::new_v4(), extension))
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,353 @@
+//! 经由 `HouseholdManager` 对各存储后端执行相同的操作，检查行为一致。
+//!
+//! PostgreSQL 后端需启用 `postgres` 功能，并在环境变量 `HOUSEHOLD_TEST_POSTGRES_URL` 中指定测试数据库，
+//! 如 `postgres://用户:密码@127.0.0.1/household_test`；未设置时跳过。测试数据使用随机证件号码，结束时删除。
+
+mod common;
+
+use chrono::NaiveDate;
+use common::{household, temp_path};
+use household_management::data::attachments::{Attachment, AttachmentKind, AttachmentOwner};
+use household_management::data::audit::{AuditAction, AuditEntry};
+use household_management::data::database::Database;
+use household_management::data::drafts::Draft;
+use household_management::data::error::DataError;
+use household_management::data::manager::HouseholdManager;
+use household_management::data::memory::MemoryRepository;
+use household_management::data::models::*;
+use household_management::data::repository::HouseholdRepository;
+use uuid::Uuid;
+
+fn with_memory(scenario: impl FnOnce(&mut HouseholdManager<MemoryRepository>)) {
+    scenario(&mut HouseholdManager::with_repository(MemoryRepository::new()));
+}
+
+fn with_sqlite(scenario: impl FnOnce(&mut HouseholdManager<Database>)) {
+    let path = temp_path("db");
+    scenario(&mut HouseholdManager::open(&path).unwrap());
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn with_postgres(scenario: impl FnOnce(&mut HouseholdManager<household_management::data::postgres::PostgresRepository>)) {
+    let Ok(url) = std::env::var("HOUSEHOLD_TEST_POSTGRES_URL") else {
+        eprintln!("未设置 HOUSEHOLD_TEST_POSTGRES_URL，跳过 PostgreSQL 测试");
+        return;
+    };
+    let repository = household_management::data::postgres::PostgresRepository::connect(&url).unwrap();
+    scenario(&mut HouseholdManager::with_repository(repository));
+}
+
+// 每个场景对每个后端生成一个测试
+macro_rules! scenarios {
+    ($($scenario:ident),* $(,)?) => {
+        mod memory {
+            $(#[test] fn $scenario() { super::with_memory(super::$scenario) })*
+        }
+        mod sqlite {
+            $(#[test] fn $scenario() { super::with_sqlite(super::$scenario) })*
+        }
+        #[cfg(feature = "postgres")]
+        mod postgres {
+            $(#[test] fn $scenario() { super::with_postgres(super::$scenario) })*
+        }
+    };
+}
+
+scenarios!(
+    households_round_trip,
+    stale_update_is_rejected,
+    id_number_is_unique_across_households,
+    blank_id_numbers_do_not_conflict,
+    transfer_records_events,
+    undo_and_redo,
+    dwellings_in_use_are_kept,
+    audit_log_is_newest_first,
+    drafts_are_kept_per_operator,
+    attachments_share_content,
+);
+
+fn stored<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, id: &Uuid) -> Household {
+    manager.get_household_by_id(id).unwrap().expect("户籍应存在")
+}
+
+// 按当前版本删除测试数据
+fn remove<R: HouseholdRepository>(manager: &mut HouseholdManager<R>, id: &Uuid) {
+    let revision = stored(manager, id).revision;
+    manager.remove_household(id, revision).unwrap();
+}
+
+fn households_round_trip<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    let original = household("张三", &[("李四", Relationship::WIFE)]);
+    manager.add_household(original.clone()).unwrap();
+
+    let loaded = stored(manager, &original.id);
+    assert_eq!(loaded.head_name, "张三");
+    assert_eq!(loaded.id_number, original.members[0].id_number);
+    assert_eq!(loaded.address, original.address);
+    assert_eq!(loaded.members.len(), 2);
+    assert_eq!(loaded.members[1].relationship, Relationship::WIFE);
+    assert_eq!(loaded.members[1].id_number, original.members[1].id_number);
+
+    let mut changed = loaded.clone();
+    changed.phone = "13900139000".to_string();
+    changed.members[1].occupation = "教师".to_string();
+    manager.update_household(changed).unwrap();
+    let reloaded = stored(manager, &original.id);
+    assert_eq!(reloaded.phone, "13900139000");
+    assert_eq!(reloaded.members[1].occupation, "教师");
+    assert!(reloaded.revision > loaded.revision);
+
+    remove(manager, &original.id);
+    assert!(manager.get_household_by_id(&original.id).unwrap().is_none());
+}
+
+fn stale_update_is_rejected<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    let original = household("张三", &[]);
+    manager.add_household(original.clone()).unwrap();
+    let first = stored(manager, &original.id);
+    let mut second = first.clone();
+
+    let mut changed = first;
+    changed.phone = "13900139000".to_string();
+    manager.update_household(changed).unwrap();
+
+    second.phone = "13700137000".to_string();
+    let result = manager.update_household(second);
+    assert!(matches!(result, Err(DataError::Conflict(id)) if id == original.id), "{:?}", result);
+    assert_eq!(stored(manager, &original.id).phone, "13900139000");
+
+    // 按修改前的版本删除同样冲突，成员保留
+    let result = manager.remove_household(&original.id, original.revision);
+    assert!(matches!(result, Err(DataError::Conflict(id)) if id == original.id), "{:?}", result);
+    assert_eq!(stored(manager, &original.id).members.len(), 1);
+
+    remove(manager, &original.id);
+    let result = manager.remove_household(&original.id, original.revision + 1);
+    assert!(matches!(result, Err(DataError::Conflict(_))), "{:?}", result);
+}
+
+// 两个程序打开同一数据库文件，各自按读取时的版本写入
+#[test]
+fn sqlite_rejects_stale_writes_from_another_manager() {
+    let path = temp_path("db");
+    let mut first = HouseholdManager::open(&path).unwrap();
+    let mut second = HouseholdManager::open(&path).unwrap();
+    let original = household("张三", &[("李四", Relationship::WIFE)]);
+    first.add_household(original.clone()).unwrap();
+    let stale = stored(&mut second, &original.id);
+
+    let mut changed = stored(&mut first, &original.id);
+    changed.phone = "13900139000".to_string();
+    first.update_household(changed).unwrap();
+
+    let mut update = stale.clone();
+    update.phone = "13700137000".to_string();
+    let result = second.update_household(update);
+    assert!(matches!(result, Err(DataError::Conflict(id)) if id == original.id), "{:?}", result);
+    let result = second.remove_household(&stale.id, stale.revision);
+    assert!(matches!(result, Err(DataError::Conflict(id)) if id == original.id), "{:?}", result);
+    let current = stored(&mut second, &original.id);
+    assert_eq!(current.phone, "13900139000");
+    assert_eq!(current.members.len(), 2);
+
+    // 删除后另一方的修改和删除也视为冲突
+    second.remove_household(&current.id, current.revision).unwrap();
+    let mut update = current.clone();
+    update.phone = "13700137000".to_string();
+    assert!(matches!(first.update_household(update), Err(DataError::Conflict(_))));
+    assert!(matches!(first.remove_household(&current.id, current.revision), Err(DataError::Conflict(_))));
+    assert!(first.get_household_by_id(&original.id).unwrap().is_none());
+
+    drop((first, second));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+    }
+}
+
+fn id_number_is_unique_across_households<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    let first = household("张三", &[("张小", Relationship::SON)]);
+    manager.add_household(first.clone()).unwrap();
+
+    let mut second = household("王五", &[("王小", Relationship::SON)]);
+    second.members[1].id_number = first.members[1].id_number.clone();
+    let result = manager.add_household(second.clone());
+    assert!(matches!(result, Err(DataError::Constraint(_))), "{:?}", result);
+    assert!(manager.get_household_by_id(&second.id).unwrap().is_none());
+
+    // 注销后证件号码不再占用
+    let mut changed = stored(manager, &first.id);
+    changed.members[1].status = MemberStatus::Cancelled;
+    manager.update_household(changed).unwrap();
+    manager.add_household(second.clone()).unwrap();
+
+    remove(manager, &first.id);
+    remove(manager, &second.id);
+}
+
+fn blank_id_numbers_do_not_conflict<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    // 如尚无证件的草稿户，户主和成员的证件号码都未填写
+    let mut first = household("张三", &[("张小", Relationship::SON)]);
+    let mut second = household("王五", &[("王小", Relationship::SON)]);
+    for member in first.members.iter_mut().chain(second.members.iter_mut()) {
+        member.id_number = " ".to_string();
+    }
+    manager.add_household(first.clone()).unwrap();
+    manager.add_household(second.clone()).unwrap();
+
+    remove(manager, &first.id);
+    remove(manager, &second.id);
+}
+
+fn transfer_records_events<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    let source = household("张三", &[("张小", Relationship::SON)]);
+    let target = household("王五", &[]);
+    manager.add_household(source.clone()).unwrap();
+    manager.add_household(target.clone()).unwrap();
+
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    manager.transfer_members(&source.id, &[1], &target.id, &[Relationship::OTHER_RELATIVE], date, "投亲").unwrap();
+
+    assert_eq!(stored(manager, &source.id).members.len(), 1);
+    let moved = stored(manager, &target.id);
+    assert_eq!(moved.members.len(), 2);
+    assert_eq!(moved.members[1].name, "张小");
+    assert_eq!(moved.members[1].relationship, Relationship::OTHER_RELATIVE);
+
+    let out = manager.get_events(&source.id).unwrap();
+    assert!(out.iter().any(|e| e.event_type == EventType::TransferOut && e.related_household_id == Some(target.id)));
+    let into = manager.get_events(&target.id).unwrap();
+    assert!(into.iter().any(|e| e.event_type == EventType::TransferIn && e.member_name == "张小" && e.event_date == date));
+
+    remove(manager, &source.id);
+    remove(manager, &target.id);
+}
+
+fn undo_and_redo<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    let original = household("张三", &[]);
+    manager.add_household(original.clone()).unwrap();
+
+    manager.start_recording();
+    let mut changed = stored(manager, &original.id);
+    changed.phone = "13900139000".to_string();
+    manager.update_household(changed).unwrap();
+    let changes = manager.recorded_changes().unwrap();
+    assert_eq!(changes.len(), 1);
+
+    let undone = manager.replay_changes(&changes, true).unwrap();
+    assert_eq!(stored(manager, &original.id).phone, "13800138000");
+    manager.replay_changes(&undone, false).unwrap();
+    assert_eq!(stored(manager, &original.id).phone, "13900139000");
+
+    // 其间被修改过时不能再撤销
+    let mut other = stored(manager, &original.id);
+    other.phone = "13700137000".to_string();
+    manager.update_household(other).unwrap();
+    assert!(matches!(manager.replay_changes(&undone, true), Err(DataError::Conflict(_))));
+
+    remove(manager, &original.id);
+}
+
+fn dwellings_in_use_are_kept<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    // 随机楼栋号，避免与共用数据库中的其他门牌重复
+    let building = (Uuid::new_v4().as_u128() % 100_000).to_string();
+    let dwelling = Dwelling {
+        id: Uuid::new_v4(),
+        division: "110105001001".to_string(),
+        building: building.clone(),
+        unit: "1".to_string(),
+        room: "101".to_string(),
+        capacity: 4,
+    };
+    manager.save_dwelling(dwelling.clone()).unwrap();
+
+    let duplicate = Dwelling { id: Uuid::new_v4(), building: format!(" {}", building), ..dwelling.clone() };
+    assert!(manager.save_dwelling(duplicate).is_err());
+
+    let mut resident = household("张三", &[]);
+    resident.dwelling_id = Some(dwelling.id);
+    manager.add_household(resident.clone()).unwrap();
+    let occupancy = manager.occupancy().unwrap();
+    let entry = occupancy.iter().find(|o| o.dwelling.id == dwelling.id).unwrap();
+    assert_eq!(entry.households, [(resident.id, "张三".to_string())]);
+
+    let result = manager.remove_dwelling(&dwelling.id);
+    assert!(matches!(result, Err(DataError::Constraint(_))), "{:?}", result);
+    remove(manager, &resident.id);
+    manager.remove_dwelling(&dwelling.id).unwrap();
+    assert!(manager.dwellings().unwrap().iter().all(|d| d.id != dwelling.id));
+}
+
+fn audit_log_is_newest_first<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    let household_id = Uuid::new_v4();
+    let first = AuditEntry::new("测试员", AuditAction::Reveal, Some(household_id), format!("查看 {}", Uuid::new_v4()));
+    let second = AuditEntry::new("测试员", AuditAction::MaskedExport, None, format!("导出 {}", Uuid::new_v4()));
+    manager.record_audit(&first).unwrap();
+    manager.record_audit(&second).unwrap();
+
+    let log = manager.audit_log(2).unwrap();
+    assert_eq!(log.len(), 2);
+    assert_eq!((log[0].id, log[0].action, log[0].household_id), (second.id, AuditAction::MaskedExport, None));
+    assert_eq!((log[1].id, log[1].household_id), (first.id, Some(household_id)));
+    assert_eq!(log[1].detail, first.detail);
+}
+
+fn drafts_are_kept_per_operator<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    // 随机操作员，共用数据库中只列出本次的草稿
+    let operator = format!("测试员{}", Uuid::new_v4());
+    let saved_at = |hour| NaiveDate::from_ymd_opt(2024, 6, 1).unwrap().and_hms_opt(hour, 0, 0).unwrap();
+    let mut form = HouseholdForm::from_household(&household("张三", &[]));
+    let editing = Draft { id: Uuid::new_v4(), operator: operator.clone(), household_id: Some(Uuid::new_v4()), form: form.clone(), updated_at: saved_at(8) };
+    form.head_name = "李四".to_string();
+    let adding = Draft { id: Uuid::new_v4(), operator: operator.clone(), household_id: None, form, updated_at: saved_at(9) };
+    let others = Draft { id: Uuid::new_v4(), operator: format!("{}乙", operator), ..adding.clone() };
+    for draft in [&editing, &adding, &others] {
+        manager.save_draft(draft).unwrap();
+    }
+
+    let drafts = manager.drafts(&operator).unwrap();
+    assert_eq!(drafts.iter().map(|d| d.id).collect::<Vec<_>>(), [adding.id, editing.id]);
+    assert_eq!(drafts[0].form, adding.form);
+    assert_eq!(drafts[1].household_id, editing.household_id);
+
+    // 同一草稿再次保存时更新
+    let mut edited = editing.clone();
+    edited.form.phone = "13900139000".to_string();
+    edited.updated_at = saved_at(10);
+    manager.save_draft(&edited).unwrap();
+    let drafts = manager.drafts(&operator).unwrap();
+    assert_eq!(drafts.len(), 2);
+    assert_eq!((drafts[0].id, drafts[0].form.phone.as_str()), (edited.id, "13900139000"));
+
+    for draft in [&editing, &adding, &others] {
+        manager.discard_draft(&draft.id).unwrap();
+    }
+    assert!(manager.drafts(&operator).unwrap().is_empty());
+}
+
+fn attachments_share_content<R: HouseholdRepository>(manager: &mut HouseholdManager<R>) {
+    let owner = household("张三", &[]);
+    manager.add_household(owner.clone()).unwrap();
+    let id_number = owner.members[0].id_number.clone();
+
+    let data = format!("%PDF-1.4 {}", Uuid::new_v4()).into_bytes();
+    let household_file = Attachment::new(AttachmentOwner::Household(owner.id), AttachmentKind::Other, "户口本.pdf", &data).unwrap();
+    let member_file = Attachment::new(AttachmentOwner::Member(id_number.clone()), AttachmentKind::Photo, "照片.pdf", &data).unwrap();
+    manager.add_attachment(&household_file, &data).unwrap();
+    manager.add_attachment(&member_file, &data).unwrap();
+
+    let attachments = manager.attachments(&owner.id, [id_number.as_str()]).unwrap();
+    assert_eq!(attachments.len(), 2);
+
+    // 内容相同只存一份，删除其中一个附件后另一个仍可读取
+    manager.remove_attachment(&household_file.id).unwrap();
+    assert_eq!(manager.attachment_data(&member_file).unwrap(), data);
+    let remaining = manager.attachments(&owner.id, [id_number.as_str()]).unwrap();
+    assert_eq!(remaining.iter().map(|a| a.id).collect::<Vec<_>>(), [member_file.id]);
+
+    manager.remove_attachment(&member_file.id).unwrap();
+    assert!(manager.attachments(&owner.id, [id_number.as_str()]).unwrap().is_empty());
+    remove(manager, &owner.id);
+}
@@ -0,0 +1,300 @@
+//! 在本机随机端口启动接口服务，连接临时 SQLite 数据库，按 HTTP 请求检查各接口
+
+#![cfg(feature = "server")]
+
+mod common;
+
+use common::{household, member, temp_path};
+use household_management::data::audit::AuditAction;
+use household_management::data::manager::HouseholdManager;
+use household_management::data::models::*;
+use household_management::server::Server;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+const TOKEN: &str = "test-token";
+
+/// 在后台线程中运行的接口服务，测试进程结束时随之退出
+struct TestServer {
+    addr: SocketAddr,
+    db_path: String,
+}
+
+impl TestServer {
+    fn start() -> Self {
+        let db_path = temp_path("db");
+        let path = db_path.clone();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut manager = HouseholdManager::open(&path).unwrap();
+            let server = Server::bind(&mut manager, "127.0.0.1:0", TOKEN).unwrap();
+            sender.send(server.local_addr().unwrap()).unwrap();
+            server.run();
+        });
+        TestServer { addr: receiver.recv().unwrap(), db_path }
+    }
+
+    /// 发送请求，返回状态码和 JSON 响应体（无响应体时为 `Value::Null`）
+    fn request_with_token(&self, method: &str, path: &str, token: Option<&str>, body: Option<&Value>) -> (u16, Value) {
+        let authorization = token.map(|token| format!("Bearer {}", token));
+        let headers: Vec<(&str, &str)> = authorization.iter().map(|value| ("Authorization", value.as_str())).collect();
+        self.request_with_headers(method, path, &headers, body)
+    }
+
+    fn request_with_headers(&self, method: &str, path: &str, headers: &[(&str, &str)], body: Option<&Value>) -> (u16, Value) {
+        let body = body.map(Value::to_string).unwrap_or_default();
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+            method, path, self.addr, body.len()
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        request.push_str(&body);
+
+        let mut stream = TcpStream::connect(self.addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let (head, content) = response.split_once("\r\n\r\n").expect("响应应有头部");
+        let status = head.split(' ').nth(1).and_then(|s| s.parse().ok()).expect("响应应有状态码");
+        let value = if content.is_empty() { Value::Null } else { serde_json::from_str(content).unwrap() };
+        (status, value)
+    }
+
+    fn request(&self, method: &str, path: &str, body: Option<&Value>) -> (u16, Value) {
+        self.request_with_token(method, path, Some(TOKEN), body)
+    }
+
+    /// 以 `If-Match` 请求头提供户籍版本
+    fn request_if_match(&self, method: &str, path: &str, revision: &Value, body: Option<&Value>) -> (u16, Value) {
+        let authorization = format!("Bearer {}", TOKEN);
+        let if_match = format!("\"{}\"", revision);
+        self.request_with_headers(method, path, &[("Authorization", &authorization), ("If-Match", &if_match)], body)
+    }
+
+    fn create(&self, household: &Household) -> Value {
+        let (status, body) = self.request("POST", "/api/households?unmasked=true", Some(&json!(household)));
+        assert_eq!(status, 201, "{}", body);
+        body["household"].clone()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        // 服务线程仍打开着数据库，删除失败时留在临时目录中
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.db_path, suffix));
+        }
+    }
+}
+
+#[test]
+fn requires_token() {
+    let server = TestServer::start();
+    assert_eq!(server.request_with_token("GET", "/api/households", None, None).0, 401);
+    assert_eq!(server.request_with_token("GET", "/api/households", Some("wrong"), None).0, 401);
+    assert_eq!(server.request_with_token("GET", "/api/statistics", Some(""), None).0, 401);
+    assert_eq!(server.request("GET", "/api/households", None).0, 200);
+
+    // 接口描述无需令牌
+    let (status, doc) = server.request_with_token("GET", "/api/openapi.json", None, None);
+    assert_eq!(status, 200);
+    assert!(doc["paths"].is_object());
+}
+
+#[test]
+fn pagination() {
+    let server = TestServer::start();
+    for name in ["张三", "李四", "王五"] {
+        server.create(&household(name, &[]));
+    }
+
+    let (status, page) = server.request("GET", "/api/households?page=2&per_page=2", None);
+    assert_eq!(status, 200);
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+    let (_, beyond) = server.request("GET", "/api/households?page=5&per_page=2", None);
+    assert!(beyond["items"].as_array().unwrap().is_empty());
+
+    let (_, found) = server.request("GET", "/api/households?q=%E6%9D%8E%E5%9B%9B", None);
+    assert_eq!(found["total"], 1);
+    assert_eq!(found["items"][0]["head_name"], "李四");
+
+    for query in ["page=0", "per_page=0", "per_page=501", "page=-1", "per_page=abc"] {
+        let (status, body) = server.request("GET", &format!("/api/households?{}", query), None);
+        assert_eq!(status, 400, "{}: {}", query, body);
+        assert!(body["error"].is_string());
+    }
+    assert_eq!(server.request("GET", "/api/households?per_page=500", None).0, 200);
+}
+
+#[test]
+fn create_conflicts() {
+    let server = TestServer::start();
+    let mut original = household("张三", &[("李四", Relationship::WIFE)]);
+    original.members[1].gender = Gender::Female;
+    server.create(&original);
+
+    // 户籍ID重复
+    let (status, _) = server.request("POST", "/api/households", Some(&json!(original)));
+    assert_eq!(status, 409);
+
+    // 证件号码已登记在其他户
+    let mut duplicate = household("王五", &[("赵六", Relationship::WIFE)]);
+    duplicate.members[1].gender = Gender::Female;
+    duplicate.members[1].id_number = original.members[1].id_number.clone();
+    let (status, body) = server.request("POST", "/api/households", Some(&json!(duplicate)));
+    assert_eq!(status, 409, "{}", body);
+
+    // 校验未通过
+    let mut invalid = household("王五", &[]);
+    invalid.members[0].name = String::new();
+    let (status, body) = server.request("POST", "/api/households", Some(&json!(invalid)));
+    assert_eq!(status, 422, "{}", body);
+
+    let (status, _) = server.request("POST", "/api/households", Some(&json!({ "members": "张三" })));
+    assert_eq!(status, 400);
+    assert_eq!(server.request("POST", "/api/households", None).0, 400);
+}
+
+#[test]
+fn update_conflicts() {
+    let server = TestServer::start();
+    let created = server.create(&household("张三", &[]));
+    let path = format!("/api/households/{}", created["id"].as_str().unwrap());
+
+    let mut first = created.clone();
+    first["phone"] = json!("13900139000");
+    let (status, updated) = server.request("PUT", &format!("{}?unmasked=true", path), Some(&first));
+    assert_eq!(status, 200, "{}", updated);
+    assert_eq!(updated["household"]["phone"], "13900139000");
+
+    // 仍带着读取时的版本，其间已被修改
+    let mut stale = created.clone();
+    stale["phone"] = json!("13700137000");
+    assert_eq!(server.request("PUT", &path, Some(&stale)).0, 409);
+
+    // 必须提供版本，不能直接覆盖
+    stale.as_object_mut().unwrap().remove("revision");
+    let (status, body) = server.request("PUT", &path, Some(&stale));
+    assert_eq!(status, 428, "{}", body);
+    assert_eq!(server.request("GET", &format!("{}?unmasked=true", path), None).1["phone"], "13900139000");
+
+    // 也可由 If-Match 提供版本
+    let stale_revision = &created["revision"];
+    assert_eq!(server.request_if_match("PUT", &path, stale_revision, Some(&stale)).0, 409);
+    let authorization = format!("Bearer {}", TOKEN);
+    let any = [("Authorization", authorization.as_str()), ("If-Match", "*")];
+    assert_eq!(server.request_with_headers("PUT", &path, &any, Some(&stale)).0, 400);
+    let current_revision = &updated["household"]["revision"];
+    let (status, body) = server.request_if_match("PUT", &format!("{}?unmasked=true", path), current_revision, Some(&stale));
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(body["household"]["phone"], "13700137000");
+
+    // 请求体和 If-Match 中的版本不一致
+    let latest = body["household"].clone();
+    let (status, body) = server.request_if_match("PUT", &path, stale_revision, Some(&latest));
+    assert_eq!(status, 400, "{}", body);
+
+    let mut invalid = latest.clone();
+    invalid["members"] = json!([]);
+    assert_eq!(server.request("PUT", &path, Some(&invalid)).0, 422);
+
+    // 删除时版本已过期
+    assert_eq!(server.request_if_match("DELETE", &path, current_revision, None).0, 409);
+    assert_eq!(server.request_if_match("DELETE", &path, &latest["revision"], None).0, 204);
+    assert_eq!(server.request("GET", &path, None).0, 404);
+    assert_eq!(server.request("PUT", &path, Some(&stale)).0, 404);
+    assert_eq!(server.request("GET", "/api/households/not-a-uuid", None).0, 404);
+    assert_eq!(server.request("PATCH", &path, None).0, 405);
+}
+
+#[test]
+fn member_routes() {
+    let server = TestServer::start();
+    let created = server.create(&household("张三", &[]));
+    let members = format!("/api/households/{}/members", created["id"].as_str().unwrap());
+
+    let (status, list) = server.request("GET", &members, None);
+    assert_eq!(status, 200);
+    assert_eq!(list.as_array().unwrap().len(), 1);
+
+    let mut child = member("张小", Relationship::SON);
+    child.birth_date = chrono::NaiveDate::from_ymd_opt(2018, 6, 1).unwrap();
+    let (status, body) = server.request("POST", &members, Some(&json!(child)));
+    assert_eq!(status, 201, "{}", body);
+    assert_eq!(body["household"]["members"].as_array().unwrap().len(), 2);
+
+    let (status, fetched) = server.request("GET", &format!("{}/1?unmasked=true", members), None);
+    assert_eq!(status, 200);
+    assert_eq!(fetched["name"], "张小");
+
+    let mut renamed = fetched.clone();
+    renamed["name"] = json!("张晓");
+    let (status, body) = server.request("PUT", &format!("{}/1", members), Some(&renamed));
+    assert_eq!(status, 200, "{}", body);
+    assert_eq!(body["household"]["members"][1]["name"], "张晓");
+
+    for index in ["2", "abc", "-1"] {
+        assert_eq!(server.request("GET", &format!("{}/{}", members, index), None).0, 404, "{}", index);
+    }
+    assert_eq!(server.request("PUT", &format!("{}/5", members), Some(&renamed)).0, 404);
+
+    let (status, body) = server.request("DELETE", &format!("{}/1", members), None);
+    assert_eq!(status, 200);
+    assert_eq!(body["household"]["members"].as_array().unwrap().len(), 1);
+    // 删除唯一的成员后校验不通过
+    assert_eq!(server.request("DELETE", &format!("{}/0", members), None).0, 422);
+}
+
+#[test]
+fn responses_are_masked_unless_requested() {
+    let server = TestServer::start();
+    let mut original = household("张三", &[]);
+    original.phone = "13800138000".to_string();
+    let created = server.create(&original);
+    let id = created["id"].as_str().unwrap();
+    let path = format!("/api/households/{}", id);
+
+    let (status, masked) = server.request("GET", &path, None);
+    assert_eq!(status, 200);
+    assert_ne!(masked["phone"], "13800138000");
+    assert!(masked["members"][0]["id_number"].as_str().unwrap().contains('*'));
+    let (_, list) = server.request("GET", "/api/households", None);
+    assert!(list["items"][0]["phone"].as_str().unwrap().contains('*'));
+    let (_, member) = server.request("GET", &format!("{}/members/0", path), None);
+    assert!(member["id_number"].as_str().unwrap().contains('*'));
+
+    // 遮蔽后的内容不能写回
+    let (status, body) = server.request("PUT", &path, Some(&masked));
+    assert_eq!(status, 422, "{}", body);
+
+    let (status, full) = server.request("GET", &format!("{}?unmasked=true", path), None);
+    assert_eq!(status, 200);
+    assert_eq!(full["phone"], "13800138000");
+    assert_eq!(full["members"][0]["id_number"], json!(original.members[0].id_number));
+    assert_eq!(server.request("GET", &format!("{}?unmasked=yes", path), None).0, 400);
+
+    // 创建和查看完整内容各记一条，遮蔽的读取不记
+    let mut manager = HouseholdManager::open(&server.db_path).unwrap();
+    let entries: Vec<_> = manager.audit_log(100).unwrap().into_iter().filter(|e| e.action == AuditAction::Export).collect();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.household_id.map(|h| h.to_string()) == Some(id.to_string())));
+}
+
+#[test]
+fn statistics() {
+    let server = TestServer::start();
+    let mut couple = household("张三", &[("李四", Relationship::WIFE)]);
+    couple.members[1].gender = Gender::Female;
+    server.create(&couple);
+
+    let (status, stats) = server.request("GET", "/api/statistics?by=county", None);
+    assert_eq!(status, 200, "{}", stats);
+    assert_eq!(server.request("GET", "/api/statistics?by=planet", None).0, 400);
+    assert_eq!(server.request("GET", "/api/unknown", None).0, 404);
+}